@@ -0,0 +1,195 @@
+use crate::cargo_config::cargo_home;
+use crate::cli::Args;
+use crate::project::Project;
+use crate::remote::git;
+use crate::report::{Finding, Kind, Severity};
+use rustsec::database::Query;
+use rustsec::{Collection, Database, Lockfile};
+use std::path::Path;
+
+/// Where the RustSec advisory database is cloned from.
+const ADVISORY_DB: &str = "https://github.com/rustsec/advisory-db";
+
+/// Reports locked crates.io packages that a RustSec advisory covers: vulnerabilities, and
+/// unmaintained or unsound crates at a lower severity. The database is the checkout
+/// cargo-audit keeps in `$CARGO_HOME/advisory-db`, cloned or updated there (at most once
+/// per cache TTL) unless offline, or the one `--advisory-db` names, used as it is.
+pub fn analyze(args: &Args, project: &Project, offline: bool) -> Vec<Finding> {
+    let path = match &args.advisory_db {
+        Some(path) => path.clone(),
+        None => {
+            let path = cargo_home().join("advisory-db");
+            if !offline {
+                if let Err(err) = update(project, &path) {
+                    tracing::warn!("couldn't update the advisory database: {}", err);
+                }
+            }
+            path
+        }
+    };
+    if !path.is_dir() {
+        tracing::warn!(
+            "no advisory database at {}, so advisories aren't checked; run without --offline \
+             or pass --advisory-db",
+            path.display()
+        );
+        return Vec::new();
+    }
+    let database = match Database::open(&path) {
+        Ok(database) => database,
+        Err(err) => {
+            tracing::warn!(
+                "couldn't read the advisory database at {}: {}",
+                path.display(),
+                err
+            );
+            return Vec::new();
+        }
+    };
+    let lockfile_path = project.root().join("Cargo.lock");
+    let Ok(lockfile) = Lockfile::load(&lockfile_path) else {
+        tracing::debug!("no readable Cargo.lock, so advisories aren't checked");
+        return Vec::new();
+    };
+
+    check(project, &database, &lockfile, &lockfile_path)
+}
+
+/// The findings for the packages of `lockfile` that `database` has advisories about.
+fn check(
+    project: &Project,
+    database: &Database,
+    lockfile: &Lockfile,
+    lockfile_path: &Path,
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    // Informational advisories (unmaintained, unsound) included, which the default scope
+    // leaves out.
+    let query = Query::new().collection(Collection::Crates).withdrawn(false);
+    for vulnerability in database.query_vulnerabilities(lockfile, &query) {
+        let advisory = &vulnerability.advisory;
+        let package = &vulnerability.package;
+        let kind = advisory
+            .informational
+            .as_ref()
+            .map_or("vulnerability".to_string(), |informational| {
+                informational.to_string()
+            });
+        let mut finding = Finding::new(Kind::Advisory, package.name.as_str())
+            .file(project, lockfile_path)
+            .note(format!(
+                "{} {} is covered by {} ({}): {}",
+                package.name, package.version, advisory.id, kind, advisory.title
+            ))
+            .note(format!("https://rustsec.org/advisories/{}", advisory.id));
+        finding = match vulnerability.versions.patched() {
+            [] => finding.note("No release fixes it; replace the crate or drop what pulls it in"),
+            patched => finding.note(format!(
+                "Fixed in {}; update to one of them",
+                patched
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        };
+        if advisory.informational.is_some() {
+            finding.severity = Severity::Warning;
+        }
+        findings.push(finding);
+    }
+    findings
+}
+
+/// Clones the database to `path`, or fast-forwards the clone there, unless that was done
+/// within the cache TTL.
+fn update(project: &Project, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let key = path.display().to_string();
+    project.cache.fetch(&["advisory-db", &key], || {
+        if path.join(".git").is_dir() {
+            git(path, &["pull", "--quiet", "--ff-only"])?;
+        } else {
+            let parent = path.parent().ok_or("the advisory database has no parent")?;
+            std::fs::create_dir_all(parent)?;
+            let target = path.display().to_string();
+            git(
+                parent,
+                &["clone", "--quiet", "--depth", "1", ADVISORY_DB, &target],
+            )?;
+        }
+        Ok(String::new())
+    })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::fixture;
+
+    const LOCKFILE: &str = r#"version = 3
+
+[[package]]
+name = "itoa"
+version = "1.0.18"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "0000000000000000000000000000000000000000000000000000000000000000"
+
+[[package]]
+name = "memchr"
+version = "2.7.4"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "0000000000000000000000000000000000000000000000000000000000000000"
+"#;
+
+    fn advisory(id: &str, package: &str, extra: &str, patched: &str) -> String {
+        format!(
+            "```toml\n[advisory]\nid = \"{}\"\npackage = \"{}\"\ndate = \"2024-01-01\"\n{}\n\n\
+             [versions]\npatched = [{}]\n```\n\n# {} advisory\n\nDetails.\n",
+            id, package, extra, patched, package
+        )
+    }
+
+    #[test]
+    fn reports_the_locked_versions_advisories_cover() {
+        let fixed = advisory("RUSTSEC-2024-0001", "itoa", "", "\">= 1.0.19\"");
+        let old = advisory("RUSTSEC-2024-0002", "itoa", "", "\">= 1.0.0\"");
+        let unmaintained = advisory(
+            "RUSTSEC-2024-0003",
+            "memchr",
+            "informational = \"unmaintained\"",
+            "",
+        );
+        let (dir, project) = fixture(&[
+            (
+                "Cargo.toml",
+                "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+            ),
+            ("src/lib.rs", ""),
+            ("db/crates/itoa/RUSTSEC-2024-0001.md", &fixed),
+            ("db/crates/itoa/RUSTSEC-2024-0002.md", &old),
+            ("db/crates/memchr/RUSTSEC-2024-0003.md", &unmaintained),
+        ]);
+        let database = Database::open(&dir.path().join("db")).unwrap();
+        let lockfile: Lockfile = LOCKFILE.parse().unwrap();
+
+        let mut findings = check(
+            &project,
+            &database,
+            &lockfile,
+            &dir.path().join("Cargo.lock"),
+        );
+        findings.sort_by(|a, b| a.notes.cmp(&b.notes));
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].krate, "itoa");
+        assert_eq!(findings[0].severity, Severity::Error);
+        assert!(findings[0].notes[0].contains("RUSTSEC-2024-0001 (vulnerability)"));
+        assert_eq!(
+            findings[0].notes[2],
+            "Fixed in >=1.0.19; update to one of them"
+        );
+        assert_eq!(findings[1].krate, "memchr");
+        assert_eq!(findings[1].severity, Severity::Warning);
+        assert!(findings[1].notes[0].contains("(unmaintained)"));
+    }
+}
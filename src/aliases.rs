@@ -0,0 +1,77 @@
+use crate::ident;
+use regex::Regex;
+use std::collections::HashMap;
+
+/// The names a file imports under an alias, mapped to the crate (or `crate`, `self`,
+/// `super`) their path starts from: `use foo as bar;`, `use foo::Item as Other;`,
+/// `use foo::{self as f, Item as I};` and `extern crate foo as bar;`. Later `bar::...`
+/// paths then count for `foo`, not for a dependency that happens to be called `bar`.
+pub fn table(content: &str) -> HashMap<String, String> {
+    let ident = ident::PATTERN;
+    let single = Regex::new(&format!(
+        r"(?:use|extern\s+crate)\s+(?:::)?({ident})(?:\s*::\s*{ident})*\s+as\s+({ident})\s*;"
+    ))
+    .unwrap();
+    let group = Regex::new(&format!(
+        r"use\s+(?:::)?({ident})\s*::[^;{{]*\{{([^;]*)\}}\s*;"
+    ))
+    .unwrap();
+    let renamed = Regex::new(&format!(r"({ident})\s+as\s+({ident})")).unwrap();
+
+    let mut aliases = HashMap::new();
+    for cap in single.captures_iter(content) {
+        aliases.insert(
+            ident::unraw(&cap[2]).to_string(),
+            ident::unraw(&cap[1]).to_string(),
+        );
+    }
+    for cap in group.captures_iter(content) {
+        for rename in renamed.captures_iter(&cap[2]) {
+            aliases.insert(
+                ident::unraw(&rename[2]).to_string(),
+                ident::unraw(&cap[1]).to_string(),
+            );
+        }
+    }
+    // `use foo as _;` only imports for side effects.
+    aliases.remove("_");
+    aliases
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renamed_imports_map_to_their_crate() {
+        let content = "use foo as bar;\n\
+                       use foo::Item as Other;\n\
+                       use baz::{self as b, Thing as T, Plain};\n\
+                       use crate::inner as i;\n\
+                       extern crate r#quux as q;\n";
+        let aliases = table(content);
+        let expected = [
+            ("bar", "foo"),
+            ("Other", "foo"),
+            ("b", "baz"),
+            ("T", "baz"),
+            ("i", "crate"),
+            ("q", "quux"),
+        ];
+        assert_eq!(aliases.len(), expected.len(), "{:?}", aliases);
+        for (alias, krate) in expected {
+            assert_eq!(
+                aliases.get(alias).map(String::as_str),
+                Some(krate),
+                "{}",
+                alias
+            );
+        }
+    }
+
+    #[test]
+    fn plain_and_underscore_imports_are_not_aliases() {
+        let content = "use side as _;\nuse plain::Item;\nuse other::{A, B};\nextern crate core;\n";
+        assert!(table(content).is_empty());
+    }
+}
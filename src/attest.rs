@@ -0,0 +1,101 @@
+use crate::project::Project;
+use crate::report::{Finding, Severity};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+pub const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+pub const PREDICATE_TYPE: &str =
+    "https://github.com/nick1udwig/cargo-dep-analysis/attestation/dependencies/v1";
+
+/// `--format attest`: an in-toto statement whose predicate is the dependency report, the
+/// resolved packages (name, version, source, checksum and purl, as an SBOM lists them)
+/// and the findings. Its subjects are the files given with `--subject`, such as release
+/// artifacts, or Cargo.lock when there are none, each identified by its SHA-256. The
+/// statement is ready to be signed, e.g. with `cosign attest-blob`.
+pub fn render(
+    project: &Project,
+    findings: &[Finding],
+    subjects: &[PathBuf],
+) -> Result<String, Box<dyn std::error::Error>> {
+    let lockfile = project.root().join("Cargo.lock");
+    let subjects: Vec<PathBuf> = if subjects.is_empty() {
+        vec![lockfile.clone()]
+    } else {
+        subjects.to_vec()
+    };
+    let subject = subjects
+        .iter()
+        .map(|path| {
+            let digest = crate::checksums::sha256(path)
+                .ok_or_else(|| format!("could not read subject {}", path.display()))?;
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| project.display_path(path));
+            Ok(json!({ "name": name, "digest": { "sha256": digest } }))
+        })
+        .collect::<Result<Vec<Value>, Box<dyn std::error::Error>>>()?;
+
+    let locked = project.lockfile().unwrap_or_default();
+    let checksums: HashMap<(&str, &str), &str> = locked
+        .get("package")
+        .and_then(|p| p.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|pkg| {
+            Some((
+                (pkg.get("name")?.as_str()?, pkg.get("version")?.as_str()?),
+                pkg.get("checksum")?.as_str()?,
+            ))
+        })
+        .collect();
+    let mut packages: Vec<&cargo_metadata::Package> = project.metadata.packages.iter().collect();
+    packages.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+    let packages: Vec<Value> = packages
+        .into_iter()
+        .map(|pkg| {
+            let version = pkg.version.to_string();
+            let mut entry = json!({
+                "name": pkg.name,
+                "version": version,
+                "purl": format!("pkg:cargo/{}@{}", pkg.name, version),
+                "member": project.metadata.workspace_members.contains(&pkg.id),
+            });
+            if let Some(source) = &pkg.source {
+                entry["source"] = json!(source.repr);
+            }
+            if let Some(checksum) = checksums.get(&(pkg.name.as_str(), version.as_str())) {
+                entry["checksum"] = json!({ "sha256": checksum });
+            }
+            entry
+        })
+        .collect();
+
+    let count = |severity| findings.iter().filter(|f| f.severity == severity).count();
+    let statement = json!({
+        "_type": STATEMENT_TYPE,
+        "subject": subject,
+        "predicateType": PREDICATE_TYPE,
+        "predicate": {
+            "analyzer": {
+                "name": env!("CARGO_PKG_NAME"),
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "generatedAt": humantime::format_rfc3339_seconds(std::time::SystemTime::now())
+                .to_string(),
+            "lockfile": {
+                "sha256": crate::checksums::sha256(&lockfile),
+            },
+            "packages": packages,
+            "findings": findings,
+            "summary": {
+                "errors": count(Severity::Error),
+                "warnings": count(Severity::Warning),
+                "info": count(Severity::Info),
+                "help": count(Severity::Help),
+            },
+        },
+    });
+    Ok(serde_json::to_string_pretty(&statement)?)
+}
@@ -0,0 +1,54 @@
+use crate::history::Snapshot;
+use std::path::Path;
+
+/// Roughly the advance of one character of 11px Verdana, the font shields.io badges use.
+const CHAR_WIDTH: usize = 7;
+const PADDING: usize = 10;
+
+/// `badge`: writes a shields.io-style SVG summarizing the dependency counts, green when
+/// nothing is flagged as unused.
+pub fn write(snapshot: &Snapshot, output: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let message = format!("{} / unused: {}", snapshot.direct_deps, snapshot.unused);
+    let color = if snapshot.unused == 0 {
+        "#4c1"
+    } else {
+        "#dfb317"
+    };
+    std::fs::write(output, svg("deps", &message, color))?;
+    tracing::info!("Wrote badge to {}", output.display());
+    Ok(())
+}
+
+fn svg(label: &str, message: &str, color: &str) -> String {
+    let label_width = label.chars().count() * CHAR_WIDTH + PADDING;
+    let message_width = message.chars().count() * CHAR_WIDTH + PADDING;
+    let width = label_width + message_width;
+    // Text is drawn at 10x scale, as shields.io does, for finer positioning.
+    let label_x = label_width * 5;
+    let message_x = (label_width + message_width / 2) * 10;
+    let label_length = (label_width - PADDING) * 10;
+    let message_length = (message_width - PADDING) * 10;
+
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {message}">
+  <title>{label}: {message}</title>
+  <linearGradient id="s" x2="0" y2="100%">
+    <stop offset="0" stop-color="#bbb" stop-opacity=".1"/>
+    <stop offset="1" stop-opacity=".1"/>
+  </linearGradient>
+  <clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>
+  <g clip-path="url(#r)">
+    <rect width="{label_width}" height="20" fill="#555"/>
+    <rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/>
+    <rect width="{width}" height="20" fill="url(#s)"/>
+  </g>
+  <g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" text-rendering="geometricPrecision" font-size="110">
+    <text aria-hidden="true" x="{label_x}" y="150" fill="#010101" fill-opacity=".3" transform="scale(.1)" textLength="{label_length}">{label}</text>
+    <text x="{label_x}" y="140" transform="scale(.1)" textLength="{label_length}">{label}</text>
+    <text aria-hidden="true" x="{message_x}" y="150" fill="#010101" fill-opacity=".3" transform="scale(.1)" textLength="{message_length}">{message}</text>
+    <text x="{message_x}" y="140" transform="scale(.1)" textLength="{message_length}">{message}</text>
+  </g>
+</svg>
+"##
+    )
+}
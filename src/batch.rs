@@ -0,0 +1,207 @@
+use crate::cli::{Args, Format};
+use crate::history::Snapshot;
+use crate::report::{Finding, Severity};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// How one project of a batch fared.
+struct Outcome {
+    project: String,
+    result: Result<Row, String>,
+}
+
+struct Row {
+    snapshot: Snapshot,
+    errors: usize,
+    warnings: usize,
+    /// Why the project fails the run, from --max-warnings and --fail-on.
+    failing: Option<String>,
+}
+
+/// Batch mode (several paths, or --recursive): analyzes each project in turn, printing
+/// its report, then a table summing them up. A project that fails to analyze doesn't
+/// stop the others; the run fails once all are done if any failed or any exceeded the
+/// thresholds.
+pub fn run(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let mut manifests = Vec::new();
+    for path in &args.paths {
+        manifests.push(manifest(path)?);
+    }
+    if let Some(dir) = &args.recursive {
+        let found = discover(dir);
+        if found.is_empty() {
+            return Err(format!("no Cargo.toml under {}", dir.display()).into());
+        }
+        manifests.extend(found);
+    }
+    if let [manifest] = &manifests[..] {
+        if !crate::analyze(args, Some(manifest))? {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let text = args.format == Format::Text && args.template.is_none();
+    let mut outcomes = Vec::new();
+    for manifest in &manifests {
+        let project = match manifest.parent() {
+            Some(dir) if dir != Path::new("") => dir.display().to_string(),
+            _ => ".".to_string(),
+        };
+        tracing::info!("Analyzing {}", project);
+        if text {
+            println!("\n{}", project);
+            println!("{}", "=".repeat(project.chars().count()));
+        }
+        let result = crate::analyze_and_report(args, Some(manifest))
+            .map(|(analyzed, findings)| row(args, &analyzed, &findings))
+            .map_err(|err| err.to_string());
+        if let Err(err) = &result {
+            tracing::error!("{}: {}", project, err);
+        }
+        outcomes.push(Outcome { project, result });
+    }
+
+    let table = summary(&outcomes);
+    if text {
+        print!("{}", table);
+    } else {
+        // stdout carries the reports, which machine formats keep parseable.
+        for line in table.lines().filter(|line| !line.is_empty()) {
+            tracing::info!("{}", line);
+        }
+    }
+    let failed = outcomes.iter().filter(|outcome| match &outcome.result {
+        Ok(row) => row.failing.is_some(),
+        Err(_) => true,
+    });
+    let failed: Vec<&str> = failed.map(|outcome| outcome.project.as_str()).collect();
+    if !failed.is_empty() {
+        tracing::error!(
+            "{} of {} projects failed: {}",
+            failed.len(),
+            outcomes.len(),
+            failed.join(", ")
+        );
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// The manifest of a project given as a directory or as the manifest itself.
+pub fn manifest(path: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let manifest = if path.is_dir() {
+        path.join("Cargo.toml")
+    } else {
+        path.to_path_buf()
+    };
+    if !manifest.is_file() {
+        return Err(format!("{} is not a Cargo project", path.display()).into());
+    }
+    Ok(manifest)
+}
+
+/// The root manifests of the projects under `dir`: every workspace, and every package
+/// not inside one. Members of a workspace found higher up are analyzed as part of it;
+/// `target` and hidden directories, and vendored crates, are skipped.
+fn discover(dir: &Path) -> Vec<PathBuf> {
+    let mut manifests: Vec<PathBuf> = WalkDir::new(dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            let skipped = entry.depth() > 0 && (name.starts_with('.') || name == "target");
+            // `cargo vendor` leaves a checksum file in each crate.
+            !skipped && !entry.path().join(".cargo-checksum.json").exists()
+        })
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file() && entry.file_name() == "Cargo.toml")
+        .map(|entry| entry.into_path())
+        .collect();
+    // Parents before children, so workspaces are known before their members.
+    manifests.sort_by_key(|path| path.components().count());
+    let mut workspaces: Vec<PathBuf> = Vec::new();
+    let mut projects = Vec::new();
+    for manifest in manifests {
+        let root = manifest.parent().unwrap_or(Path::new(".")).to_path_buf();
+        if workspaces
+            .iter()
+            .any(|workspace| root.starts_with(workspace))
+        {
+            continue;
+        }
+        let is_workspace =
+            crate::project::read_toml(&manifest).is_ok_and(|table| table.contains_key("workspace"));
+        if is_workspace {
+            workspaces.push(root);
+        }
+        projects.push(manifest);
+    }
+    projects.sort();
+    projects
+}
+
+fn row(args: &Args, project: &crate::project::Project, findings: &[Finding]) -> Row {
+    let count = |severity: Severity| findings.iter().filter(|f| f.severity == severity).count();
+    Row {
+        snapshot: Snapshot::take(project, findings),
+        errors: count(Severity::Error),
+        warnings: count(Severity::Warning),
+        failing: crate::report::threshold_exceeded(findings, args.max_warnings, &args.fail_on),
+    }
+}
+
+/// The combined summary: a line per project and their totals.
+fn summary(outcomes: &[Outcome]) -> String {
+    let width = outcomes
+        .iter()
+        .map(|outcome| outcome.project.chars().count())
+        .chain(["Project".len(), "Total".len()])
+        .max()
+        .unwrap_or_default();
+    let mut out = String::from("\nBatch Summary:\n==========================\n");
+    out.push_str(&format!(
+        "{:<width$}  {:>6}  {:>6}  {:>6}  {:>10}  {:>6}  {:>8}\n",
+        "Project", "Direct", "Crates", "Unused", "Duplicates", "Errors", "Warnings"
+    ));
+    let mut totals = [0; 6];
+    for outcome in outcomes {
+        match &outcome.result {
+            Ok(row) => {
+                let values = [
+                    row.snapshot.direct_deps,
+                    row.snapshot.total_crates,
+                    row.snapshot.unused,
+                    row.snapshot.duplicates,
+                    row.errors,
+                    row.warnings,
+                ];
+                for (total, value) in totals.iter_mut().zip(values) {
+                    *total += value;
+                }
+                out.push_str(&line(&outcome.project, values, width));
+                if let Some(reason) = &row.failing {
+                    out.push_str(&format!("{:<width$}  fails: {}\n", "", reason));
+                }
+            }
+            Err(err) => out.push_str(&format!(
+                "{:<width$}  analysis failed: {}\n",
+                outcome.project,
+                err.lines().next().unwrap_or_default()
+            )),
+        }
+    }
+    out.push_str(&line("Total", totals, width));
+    out
+}
+
+fn line(
+    label: &str,
+    [direct, crates, unused, duplicates, errors, warnings]: [usize; 6],
+    width: usize,
+) -> String {
+    format!(
+        "{:<width$}  {:>6}  {:>6}  {:>6}  {:>10}  {:>6}  {:>8}\n",
+        label, direct, crates, unused, duplicates, errors, warnings
+    )
+}
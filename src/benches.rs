@@ -0,0 +1,65 @@
+use crate::project::Project;
+use crate::report::{Finding, Kind};
+use crate::targets;
+
+/// Flags bench targets that use criterion, iai or divan but keep the default libtest
+/// harness, which then fails to build or ignores the framework's own `main`.
+pub fn analyze(project: &Project) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for member in project.members() {
+        let manifest = project.package_manifest(member).unwrap_or_default();
+        let declared = manifest.get("bench").and_then(|b| b.as_array());
+        for target in member
+            .targets
+            .iter()
+            .filter(|t| t.kind.iter().any(|k| k == "bench"))
+        {
+            let harness = declared
+                .into_iter()
+                .flatten()
+                .find(|bench| bench.get("name").and_then(|n| n.as_str()) == Some(&target.name))
+                .and_then(|bench| bench.get("harness"))
+                .and_then(|harness| harness.as_bool())
+                .unwrap_or(true);
+            if !harness {
+                continue;
+            }
+            let framework = targets::files(target).into_iter().find_map(|file| {
+                let content = std::fs::read_to_string(file).ok()?;
+                framework(&content)
+            });
+            if let Some(framework) = framework {
+                findings.push(
+                    Finding::new(Kind::BenchHarness, framework)
+                        .member(project, member)
+                        .note(format!(
+                            "Bench `{}` uses {} but keeps the default libtest harness",
+                            target.name, framework
+                        ))
+                        .note(format!(
+                            "Add a [[bench]] section with name = \"{}\" and harness = false",
+                            target.name
+                        )),
+                );
+            }
+        }
+    }
+    findings
+}
+
+/// The bench framework whose `main` a bench source defines, if any.
+fn framework(content: &str) -> Option<&'static str> {
+    if content.contains("criterion_main!") {
+        Some("criterion")
+    } else if content.contains("iai_callgrind::main!")
+        || content.contains("library_benchmark_group!")
+    {
+        Some("iai-callgrind")
+    } else if content.contains("iai::main!") {
+        Some("iai")
+    } else if content.contains("divan::main") {
+        Some("divan")
+    } else {
+        None
+    }
+}
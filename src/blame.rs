@@ -0,0 +1,127 @@
+use crate::report::{Finding, Kind};
+use cargo_metadata::Package;
+use regex::Regex;
+use std::path::Path;
+use std::process::Command;
+
+/// Adds "who added this" to findings about a package's own dependency declarations, by
+/// blaming the declaring line of its manifest, so follow-ups reach the right owner.
+pub fn annotate(package: &Package, findings: &mut [Finding]) {
+    let manifest = package.manifest_path.as_std_path();
+    let Ok(content) = std::fs::read_to_string(manifest) else {
+        return;
+    };
+
+    for finding in findings.iter_mut().filter(|f| f.kind == Kind::Unused) {
+        let Some(line) = declaration_line(&content, &finding.krate) else {
+            continue;
+        };
+        if let Some(attribution) = blame(manifest, line) {
+            finding.notes.push(attribution);
+        }
+    }
+}
+
+/// The 1-based line declaring `name` in a dependency table (`[dependencies]`,
+/// `[target.'cfg(unix)'.dev-dependencies]`, `[workspace.dependencies]`, ...), either as
+/// `name = ...`/`name.x = ...` or as a `[dependencies.name]` header. Keys of other tables,
+/// such as a `name = ["dep:name"]` feature, don't count.
+pub fn declaration_line(content: &str, name: &str) -> Option<usize> {
+    let name = regex::escape(name);
+    let key = Regex::new(&format!(r#"^\s*(?:"{name}"|{name})\s*[=.]"#, name = name)).ok()?;
+    let header = Regex::new(&format!(
+        r#"^\s*\[(?:.*\.)?(?:dev-|build-)?dependencies\.(?:"{name}"|{name})\]"#,
+        name = name
+    ))
+    .ok()?;
+    let mut in_dependencies = false;
+    for (index, line) in content.lines().enumerate() {
+        if header.is_match(line) {
+            return Some(index + 1);
+        }
+        let trimmed = line.trim();
+        if let Some(table) = trimmed.strip_prefix('[') {
+            // The comment after a header, if any, isn't part of it.
+            let table = table.split('#').next().unwrap_or("").trim_end();
+            in_dependencies = !table.starts_with('[')
+                && table
+                    .trim_end_matches(']')
+                    .trim()
+                    .trim_matches(['"', '\''])
+                    .ends_with("dependencies");
+            continue;
+        }
+        if in_dependencies && key.is_match(line) {
+            return Some(index + 1);
+        }
+    }
+    None
+}
+
+fn blame(manifest: &Path, line: usize) -> Option<String> {
+    let range = format!("{},{}", line, line);
+    let output = Command::new("git")
+        .args(["blame", "--porcelain", "-L", &range, "--"])
+        .arg(manifest.file_name()?)
+        .current_dir(manifest.parent()?)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let porcelain = String::from_utf8_lossy(&output.stdout);
+    let commit = porcelain.split_whitespace().next()?;
+    if commit.chars().all(|c| c == '0') {
+        return Some("Added in uncommitted changes".to_string());
+    }
+    let field = |key: &str| {
+        porcelain
+            .lines()
+            .find_map(|l| l.strip_prefix(key).map(str::to_string))
+    };
+    let author = field("author ").unwrap_or_default();
+    let summary = field("summary ").unwrap_or_default();
+    let date = field("author-time ")
+        .and_then(|t| t.parse::<u64>().ok())
+        .map(|t| std::time::UNIX_EPOCH + std::time::Duration::from_secs(t))
+        .map(|t| humantime::format_rfc3339_seconds(t).to_string()[..10].to_string())
+        .unwrap_or_default();
+
+    let pr = Regex::new(r"(?:\(#(\d+)\)|pull request #(\d+))")
+        .ok()?
+        .captures(&summary)
+        .and_then(|c| c.get(1).or_else(|| c.get(2)))
+        .map(|m| format!(" (PR #{})", m.as_str()))
+        .unwrap_or_default();
+
+    Some(format!(
+        "Added by {} in {} on {}{}: {}",
+        author,
+        &commit[..commit.len().min(10)],
+        date,
+        pr,
+        summary
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::declaration_line;
+
+    #[test]
+    fn skips_keys_outside_dependency_tables() {
+        let manifest = "[package]\nname = \"app\"\n\n[features]\nserde = [\"dep:serde\"]\n\n\
+                        [dependencies]\nserde = { version = \"1\", optional = true }\n";
+        assert_eq!(declaration_line(manifest, "serde"), Some(8));
+    }
+
+    #[test]
+    fn finds_platform_tables_and_headers() {
+        let manifest = "[target.'cfg(unix)'.dependencies]\nlibc = \"0.2\"\n\n\
+                        [dev-dependencies.tempfile]\nversion = \"3\"\n";
+        assert_eq!(declaration_line(manifest, "libc"), Some(2));
+        assert_eq!(declaration_line(manifest, "tempfile"), Some(4));
+        assert_eq!(declaration_line(manifest, "version"), None);
+    }
+}
@@ -0,0 +1,150 @@
+use crate::diff;
+use crate::project::Project;
+use crate::report::{Finding, Kind};
+use std::collections::BTreeSet;
+
+/// What a lockfile records of the graph: its packages, by name and version, and the
+/// crates the workspace packages (those without a source) depend on directly.
+struct Counts {
+    crates: BTreeSet<(String, String)>,
+    direct: BTreeSet<String>,
+}
+
+impl Counts {
+    fn of(lockfile: &toml::Table) -> Counts {
+        let packages: Vec<&toml::Table> = lockfile
+            .get("package")
+            .and_then(|p| p.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|p| p.as_table())
+            .collect();
+        let field = |pkg: &toml::Table, key: &str| {
+            pkg.get(key)
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        let local: BTreeSet<String> = packages
+            .iter()
+            .filter(|pkg| !pkg.contains_key("source"))
+            .map(|pkg| field(pkg, "name"))
+            .collect();
+        let direct = packages
+            .iter()
+            .filter(|pkg| !pkg.contains_key("source"))
+            .filter_map(|pkg| pkg.get("dependencies")?.as_array())
+            .flatten()
+            // Entries read `name`, `name version` or `name version (source)`.
+            .filter_map(|dep| dep.as_str()?.split(' ').next())
+            .filter(|name| !local.contains(*name))
+            .map(str::to_string)
+            .collect();
+        Counts {
+            crates: packages
+                .iter()
+                .map(|pkg| (field(pkg, "name"), field(pkg, "version")))
+                .collect(),
+            direct,
+        }
+    }
+}
+
+/// Checks the lockfile against the budgets of the configuration. With `base`, each
+/// finding also says how much the count grew since that revision.
+pub fn analyze(project: &Project, base: Option<&str>) -> Vec<Finding> {
+    let budget = &project.config.budget;
+    if budget.max_direct_deps.is_none()
+        && budget.max_total_crates.is_none()
+        && budget.max_added_crates.is_none()
+    {
+        return Vec::new();
+    }
+    let Some(lockfile) = project.lockfile() else {
+        tracing::warn!("no Cargo.lock to check the dependency budgets against");
+        return Vec::new();
+    };
+    let now = Counts::of(&lockfile);
+    let before = base.and_then(|base| match baseline(project, base) {
+        Ok(counts) => Some(counts),
+        Err(error) => {
+            tracing::warn!("could not read the lockfile at {}: {}", base, error);
+            None
+        }
+    });
+    let delta = |now: usize, count: fn(&Counts) -> usize| {
+        let (Some(base), Some(counts)) = (base, &before) else {
+            return None;
+        };
+        let change = now as i64 - count(counts) as i64;
+        Some(format!("{:+} since {}", change, base))
+    };
+
+    let mut findings = Vec::new();
+    let mut check = |count: usize, max: Option<usize>, what: &str, key: &str, change| {
+        let Some(max) = max.filter(|max| count > *max) else {
+            return;
+        };
+        let mut finding = Finding::new(Kind::BudgetExceeded, "workspace").note(format!(
+            "{} {}, over the budget of {} ({})",
+            count, what, max, key
+        ));
+        if let Some(change) = change {
+            finding = finding.note(change);
+        }
+        findings.push(finding);
+    };
+    check(
+        now.direct.len(),
+        budget.max_direct_deps,
+        "direct dependencies",
+        "max-direct-deps",
+        delta(now.direct.len(), |c| c.direct.len()),
+    );
+    check(
+        now.crates.len(),
+        budget.max_total_crates,
+        "crates in the lockfile",
+        "max-total-crates",
+        delta(now.crates.len(), |c| c.crates.len()),
+    );
+
+    if let Some(max) = budget.max_added_crates {
+        match (base, &before) {
+            (Some(base), Some(before)) => {
+                let added: Vec<String> = now
+                    .crates
+                    .difference(&before.crates)
+                    .map(|(name, version)| format!("{} {}", name, version))
+                    .collect();
+                if added.len() > max {
+                    findings.push(
+                        Finding::new(Kind::BudgetExceeded, "workspace")
+                            .note(format!(
+                                "{} crates added since {}, over the budget of {} \
+                                 (max-added-crates)",
+                                added.len(),
+                                base,
+                                max
+                            ))
+                            .note(format!("Added: {}", added.join(", "))),
+                    );
+                }
+            }
+            (None, _) => {
+                tracing::warn!("max-added-crates needs --base to compare the lockfile against")
+            }
+            (Some(_), None) => {}
+        }
+    }
+    findings
+}
+
+/// The lockfile as committed at `base`.
+fn baseline(project: &Project, base: &str) -> Result<Counts, Box<dyn std::error::Error>> {
+    let content = diff::git(
+        &project.root(),
+        &["show", &format!("{}:./Cargo.lock", base)],
+    )?;
+    Ok(Counts::of(&content.parse()?))
+}
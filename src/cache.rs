@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+/// Default lifetime of cached network responses.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// On-disk cache for network responses (registry index files, `git ls-remote`),
+/// shared by every rule that goes to the network. Lives in
+/// `$XDG_CACHE_HOME/cargo-dep-analysis` (or `~/.cache/cargo-dep-analysis`). Clones
+/// share what was fetched during the run, so each response is fetched at most once
+/// even with `refresh` set or without a cache directory.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: Option<PathBuf>,
+    fetched: Arc<Mutex<HashMap<Vec<String>, String>>>,
+    pub ttl: Duration,
+    /// Ignore cached entries and fetch everything again (still updating the cache).
+    pub refresh: bool,
+}
+
+impl Cache {
+    pub fn new(ttl: Duration) -> Self {
+        let base = std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")));
+        Cache {
+            dir: base.map(|base| base.join("cargo-dep-analysis")),
+            fetched: Arc::default(),
+            ttl,
+            refresh: false,
+        }
+    }
+
+    /// Returns the cached value for `key` if it's younger than the TTL, otherwise calls
+    /// `fetch` and stores the result. Failing to read or write the cache never fails the
+    /// lookup itself.
+    pub fn fetch(
+        &self,
+        key: &[&str],
+        fetch: impl FnOnce() -> Result<String, Box<dyn std::error::Error>>,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let id: Vec<String> = key.iter().map(|part| part.to_string()).collect();
+        if let Some(value) = self.fetched.lock().ok().and_then(|f| f.get(&id).cloned()) {
+            return Ok(value);
+        }
+        let Some(path) = self.path(key) else {
+            let value = fetch()?;
+            self.remember(id, &value);
+            return Ok(value);
+        };
+
+        if !self.refresh {
+            let fresh = std::fs::metadata(&path)
+                .and_then(|meta| meta.modified())
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                .is_some_and(|age| age < self.ttl);
+            if fresh {
+                if let Ok(cached) = std::fs::read_to_string(&path) {
+                    tracing::trace!(path = %path.display(), "cache hit");
+                    return Ok(cached);
+                }
+            }
+        }
+
+        let value = fetch()?;
+        self.remember(id, &value);
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let _ = std::fs::write(&path, &value);
+        Ok(value)
+    }
+
+    fn remember(&self, id: Vec<String>, value: &str) {
+        if let Ok(mut fetched) = self.fetched.lock() {
+            fetched.insert(id, value.to_string());
+        }
+    }
+
+    fn path(&self, key: &[&str]) -> Option<PathBuf> {
+        let mut path = self.dir.clone()?;
+        for part in key {
+            let sanitized: String = part
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '-' || c == '.' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            path.push(sanitized);
+        }
+        Some(path)
+    }
+}
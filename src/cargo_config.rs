@@ -0,0 +1,151 @@
+use std::path::{Path, PathBuf};
+
+/// Cargo's own configuration as cargo would see it from a directory: every
+/// `.cargo/config.toml` from that directory up to the filesystem root, then
+/// `$CARGO_HOME/config.toml`, with nearer files taking precedence.
+#[derive(Debug, Default)]
+pub struct CargoConfig {
+    table: toml::Table,
+    /// A CA certificate bundle from `--cacert`, taking precedence over `http.cainfo`.
+    pub cacert: Option<PathBuf>,
+}
+
+impl CargoConfig {
+    pub fn load(dir: &Path) -> Self {
+        let mut files: Vec<PathBuf> = dir
+            .ancestors()
+            .map(|ancestor| ancestor.join(".cargo"))
+            .collect();
+        files.push(cargo_home());
+
+        let mut table = toml::Table::new();
+        for cargo_dir in files {
+            let file = ["config.toml", "config"]
+                .iter()
+                .map(|name| cargo_dir.join(name))
+                .find(|path| path.is_file());
+            if let Some(mut config) = file.and_then(|path| crate::project::read_toml(&path).ok()) {
+                if let Some(base) = cargo_dir.parent() {
+                    absolutize_paths(&mut config, base);
+                }
+                merge(&mut table, config);
+            }
+        }
+        CargoConfig {
+            table,
+            cacert: None,
+        }
+    }
+
+    /// The value at a dotted key path such as `["registries", "my-registry", "index"]`.
+    pub fn get(&self, path: &[&str]) -> Option<&toml::Value> {
+        let (first, rest) = path.split_first()?;
+        rest.iter()
+            .try_fold(self.table.get(*first)?, |value, key| value.get(*key))
+    }
+
+    pub fn get_str(&self, path: &[&str]) -> Option<&str> {
+        self.get(path)?.as_str()
+    }
+
+    /// The proxy for HTTPS requests, as cargo picks it: the `CARGO_HTTP_PROXY` variable,
+    /// `http.proxy`, then the conventional `HTTPS_PROXY` and `ALL_PROXY` variables.
+    pub fn proxy(&self) -> Option<String> {
+        std::env::var("CARGO_HTTP_PROXY")
+            .ok()
+            .or_else(|| self.get_str(&["http", "proxy"]).map(str::to_string))
+            .or_else(|| {
+                ["https_proxy", "HTTPS_PROXY", "all_proxy", "ALL_PROXY"]
+                    .iter()
+                    .find_map(|var| std::env::var(var).ok())
+            })
+            .filter(|proxy| !proxy.is_empty())
+    }
+
+    /// Extra CA certificates to trust: `--cacert`, the `CARGO_HTTP_CAINFO` variable, or
+    /// `http.cainfo`.
+    pub fn cainfo(&self) -> Option<PathBuf> {
+        self.cacert
+            .clone()
+            .or_else(|| std::env::var_os("CARGO_HTTP_CAINFO").map(PathBuf::from))
+            .or_else(|| self.get_str(&["http", "cainfo"]).map(PathBuf::from))
+    }
+
+    /// `net.offline`, or the `CARGO_NET_OFFLINE` variable that overrides it.
+    pub fn offline(&self) -> bool {
+        match std::env::var("CARGO_NET_OFFLINE") {
+            Ok(value) => value == "true",
+            Err(_) => self.get(&["net", "offline"]).and_then(|o| o.as_bool()) == Some(true),
+        }
+    }
+}
+
+pub fn cargo_home() -> PathBuf {
+    match std::env::var_os("CARGO_HOME") {
+        Some(home) => PathBuf::from(home),
+        None => PathBuf::from(std::env::var_os("HOME").unwrap_or_default()).join(".cargo"),
+    }
+}
+
+/// Whether requests to `host` skip the proxy, per the `NO_PROXY` variable: `*`, or a
+/// comma-separated list of hosts matching themselves and their subdomains.
+pub fn bypasses_proxy(host: &str) -> bool {
+    let Some(no_proxy) = ["no_proxy", "NO_PROXY"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok())
+    else {
+        return false;
+    };
+    no_proxy
+        .split(',')
+        .map(|entry| entry.trim().trim_start_matches('.'))
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| {
+            entry == "*"
+                || host == entry
+                || host
+                    .strip_suffix(entry)
+                    .is_some_and(|prefix| prefix.ends_with('.'))
+        })
+}
+
+/// Cargo resolves the `directory` and `local-registry` paths of a `[source]`, and
+/// `http.cainfo`, relative to the directory containing `.cargo/`; rewrite them before
+/// merging loses that context.
+fn absolutize_paths(config: &mut toml::Table, base: &Path) {
+    if let Some(toml::Value::String(path)) = config
+        .get_mut("http")
+        .and_then(|http| http.get_mut("cainfo"))
+    {
+        *path = base.join(path.as_str()).to_string_lossy().into_owned();
+    }
+    let Some(sources) = config.get_mut("source").and_then(|s| s.as_table_mut()) else {
+        return;
+    };
+    for (_, source) in sources.iter_mut() {
+        for key in ["directory", "local-registry"] {
+            if let Some(toml::Value::String(path)) = source.get_mut(key) {
+                *path = base.join(path.as_str()).to_string_lossy().into_owned();
+            }
+        }
+    }
+}
+
+/// Merges a lower-precedence config into `into`: tables merge recursively, arrays are
+/// concatenated, and existing scalar values win.
+fn merge(into: &mut toml::Table, from: toml::Table) {
+    for (key, value) in from {
+        match (into.get_mut(&key), value) {
+            (Some(toml::Value::Table(existing)), toml::Value::Table(table)) => {
+                merge(existing, table)
+            }
+            (Some(toml::Value::Array(existing)), toml::Value::Array(array)) => {
+                existing.extend(array)
+            }
+            (Some(_), _) => {}
+            (None, value) => {
+                into.insert(key, value);
+            }
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use crate::cfg_eval::Cfg;
+use crate::{ident, macros};
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Code behind a `#[cfg(...)]` attribute: the item following an outer attribute, or
+/// the rest of the file after an inner `#![cfg(...)]`.
+pub struct Region {
+    pub predicate: String,
+    pub text: String,
+}
+
+/// Splits `content` into the code compiled unconditionally and the regions gated by a
+/// `cfg` predicate. Nested gates end up in their outermost region.
+pub fn split(content: &str) -> (String, Vec<Region>) {
+    let start = Regex::new(r"#(!?)\[\s*cfg\s*\(").unwrap();
+    let mut unconditional = String::new();
+    let mut regions = Vec::new();
+    let mut copied = 0;
+    for cap in start.captures_iter(content) {
+        let m = cap.get(0).unwrap();
+        if m.start() < copied {
+            continue;
+        }
+        let Some(close) = macros::matching_delimiter(content, m.end() - 1) else {
+            continue;
+        };
+        let Some(attribute_end) = content[close..].find(']').map(|i| close + i + 1) else {
+            continue;
+        };
+        let end = if &cap[1] == "!" {
+            content.len()
+        } else {
+            item_end(content, attribute_end)
+        };
+        unconditional.push_str(&content[copied..m.start()]);
+        regions.push(Region {
+            predicate: content[m.end()..close].trim().to_string(),
+            text: content[attribute_end..end].to_string(),
+        });
+        copied = end;
+    }
+    unconditional.push_str(&content[copied..]);
+    (unconditional, regions)
+}
+
+/// Whether a predicate only holds when compiling tests: it requires `test`, alone or
+/// as one of the conditions of an `all(...)` (`all(test, not(miri))`).
+pub fn is_test(predicate: &str) -> bool {
+    fn requires_test(cfg: &Cfg) -> bool {
+        match cfg {
+            Cfg::Name(name) => name == "test",
+            Cfg::All(all) => all.iter().any(requires_test),
+            _ => false,
+        }
+    }
+    Cfg::parse(predicate).is_some_and(|cfg| requires_test(&cfg))
+}
+
+/// The files of out-of-line modules (`mod name;`) declared under a `cfg` gate, directly
+/// or through a gated parent module, with the predicate gating them.
+pub fn gated_files(files: &[(PathBuf, String)]) -> HashMap<PathBuf, String> {
+    let mut gates = HashMap::new();
+    let mut children: HashMap<&Path, Vec<PathBuf>> = HashMap::new();
+    let mut pending = Vec::new();
+    for (path, content) in files {
+        let (unconditional, regions) = split(content);
+        for name in modules(&unconditional) {
+            children
+                .entry(path)
+                .or_default()
+                .extend(module_files(path, &name));
+        }
+        for region in regions {
+            for name in modules(&region.text) {
+                for file in module_files(path, &name) {
+                    pending.push((file, region.predicate.clone()));
+                }
+            }
+        }
+    }
+    while let Some((file, predicate)) = pending.pop() {
+        if gates.contains_key(&file) {
+            continue;
+        }
+        for child in children.get(file.as_path()).into_iter().flatten() {
+            pending.push((child.clone(), predicate.clone()));
+        }
+        gates.insert(file, predicate);
+    }
+    gates
+}
+
+/// The modules `code` declares out of line, with `mod name;`.
+pub fn modules(code: &str) -> Vec<String> {
+    let re = Regex::new(&format!(
+        r"(?m)^\s*(?:pub(?:\([^)]*\))?\s+)?mod\s+({})\s*;",
+        ident::PATTERN
+    ))
+    .unwrap();
+    re.captures_iter(code)
+        .map(|cap| ident::unraw(&cap[1]).to_string())
+        .collect()
+}
+
+/// The files an out-of-line `mod name;` in `parent` may live in.
+pub fn module_files(parent: &Path, name: &str) -> [PathBuf; 2] {
+    let dir = parent.parent().unwrap_or(Path::new(""));
+    let dir = match parent.file_stem().and_then(|stem| stem.to_str()) {
+        Some("lib" | "main" | "mod") | None => dir.to_path_buf(),
+        Some(stem) => dir.join(stem),
+    };
+    [
+        dir.join(format!("{}.rs", name)),
+        dir.join(name).join("mod.rs"),
+    ]
+}
+
+/// Where the item starting at `start` ends: after its `;`, or after the braces of its
+/// body, whichever comes first outside nested delimiters. Further attributes on the item
+/// are part of it.
+fn item_end(content: &str, start: usize) -> usize {
+    let bytes = content.as_bytes();
+    let mut depth = 0usize;
+    let mut i = start;
+    while i < bytes.len() {
+        if let Some(end) = macros::skip_literal(content, i) {
+            i = end;
+            continue;
+        }
+        match bytes[i] {
+            b'{' if depth == 0 => {
+                return macros::matching_delimiter(content, i).map_or(content.len(), |c| c + 1)
+            }
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' => {
+                if depth == 0 {
+                    // The enclosing block ended: the attribute was on a trailing expression.
+                    return i;
+                }
+                depth -= 1;
+            }
+            b';' | b',' if depth == 0 => return i + 1,
+            _ => {}
+        }
+        i += 1;
+    }
+    content.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_ends_regions_at_the_item_despite_quotes_in_comments() {
+        let content = r#"#[cfg(test)]
+mod tests {
+    // a 3" pipe
+    const C: char = '\'';
+}
+
+pub fn f() {
+    util::x();
+}
+"#;
+        let (unconditional, regions) = split(content);
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].predicate, "test");
+        assert!(regions[0].text.trim_end().ends_with("'\\'';\n}"));
+        assert!(unconditional.contains("util::x()"));
+    }
+
+    #[test]
+    fn test_regions_require_test() {
+        assert!(is_test("test"));
+        assert!(is_test("all(test, not(miri))"));
+        assert!(is_test(r#"all(feature = "std", all(unix, test))"#));
+        assert!(!is_test(r#"feature = "test-util""#));
+        assert!(!is_test("not(test)"));
+        assert!(!is_test("any(test, debug_assertions)"));
+        assert!(!is_test("testing"));
+        assert!(!is_test("all(test"));
+    }
+}
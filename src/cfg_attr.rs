@@ -0,0 +1,197 @@
+use crate::ident;
+use regex::Regex;
+use std::collections::HashMap;
+
+/// A crate named by an attribute inside `#[cfg_attr(<predicate>, ...)]`.
+pub struct GatedAttribute {
+    pub krate: String,
+    /// The features the predicate mentions; empty when it isn't about features (e.g.
+    /// `cfg_attr(docsrs, ...)`), in which case the usage is unconditional as far as
+    /// dependencies are concerned.
+    pub features: Vec<String>,
+}
+
+/// Finds the crates used by `cfg_attr` attributes and returns them with the content
+/// stripped of those attributes, so the usage patterns don't count them unconditionally.
+///
+/// `derive(serde::Serialize)` and helper attributes such as `serde(rename_all = "...")`
+/// name their crate directly; a bare `derive(Serialize)` is resolved through the file's
+/// `use` declarations.
+pub fn extract(content: &str) -> (String, Vec<GatedAttribute>) {
+    let start = Regex::new(r"#!?\[\s*cfg_attr\s*\(").unwrap();
+    let feature = Regex::new(r#"feature\s*=\s*"([^"]+)""#).unwrap();
+    let imports = imports(content);
+
+    let mut stripped = String::new();
+    let mut attributes = Vec::new();
+    let mut copied = 0;
+    for m in start.find_iter(content) {
+        if m.start() < copied {
+            continue;
+        }
+        let Some(close) = closing_paren(content, m.end() - 1) else {
+            continue;
+        };
+        let mut parts = split_top_level(&content[m.end()..close]).into_iter();
+        let predicate = parts.next().unwrap_or_default();
+        let features = if predicate.contains("not(") {
+            Vec::new()
+        } else {
+            feature
+                .captures_iter(predicate)
+                .map(|cap| cap[1].to_string())
+                .collect()
+        };
+        for attribute in parts {
+            for krate in crates(attribute, &imports) {
+                attributes.push(GatedAttribute {
+                    krate,
+                    features: features.clone(),
+                });
+            }
+        }
+        stripped.push_str(&content[copied..m.start()]);
+        copied = content[close..].find(']').map_or(close, |i| close + i + 1);
+    }
+    stripped.push_str(&content[copied..]);
+    (stripped, attributes)
+}
+
+/// The crates one attribute (`derive(...)`, `serde(...)`, `path::attr`) refers to.
+fn crates(attribute: &str, imports: &HashMap<String, String>) -> Vec<String> {
+    let attribute = attribute.trim();
+    let name_end = attribute
+        .find(|c: char| c == '(' || c == '=' || c.is_whitespace())
+        .unwrap_or(attribute.len());
+    let name = &attribute[..name_end];
+    if name != "derive" {
+        let root = name
+            .trim_start_matches("::")
+            .split("::")
+            .next()
+            .unwrap_or("");
+        return vec![root.to_string()];
+    }
+    let Some(args) = attribute
+        .strip_prefix("derive")
+        .map(str::trim)
+        .and_then(|rest| rest.strip_prefix('('))
+        .and_then(|rest| rest.strip_suffix(')'))
+    else {
+        return Vec::new();
+    };
+    args.split(',')
+        .map(str::trim)
+        .filter(|derive| !derive.is_empty())
+        .filter_map(
+            |derive| match derive.trim_start_matches("::").split_once("::") {
+                Some((krate, _)) => Some(krate.to_string()),
+                None => imports.get(derive).cloned(),
+            },
+        )
+        .collect()
+}
+
+/// Maps the names a file imports from other crates to the crate they come from, for
+/// `use serde::Serialize;` and `use serde::{Deserialize, Serialize};`.
+fn imports(content: &str) -> HashMap<String, String> {
+    let ident = ident::PATTERN;
+    let re = Regex::new(&format!(
+        r"use\s+(?:::)?({ident})::(?:\{{([^}}]*)\}}|({ident}))"
+    ))
+    .unwrap();
+    let mut imports = HashMap::new();
+    for cap in re.captures_iter(content) {
+        let names = cap.get(2).or(cap.get(3)).map_or("", |m| m.as_str());
+        for name in names.split(',') {
+            let name = name.split(" as ").last().unwrap_or("").trim();
+            if !name.is_empty() {
+                imports.insert(
+                    ident::unraw(name).to_string(),
+                    ident::unraw(&cap[1]).to_string(),
+                );
+            }
+        }
+    }
+    imports
+}
+
+fn closing_paren(content: &str, open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    for (i, c) in content[open..].char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Splits at commas outside parentheses and strings.
+fn split_top_level(s: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => depth = depth.saturating_sub(1),
+            ',' if !in_string && depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gated_derives_are_extracted_and_stripped() {
+        let content = "use serde::{Deserialize, Serialize};\n\
+                       #[cfg_attr(feature = \"serde\", derive(Serialize, serde_json::Value))]\n\
+                       struct A;\n";
+        let (stripped, attributes) = extract(content);
+        assert!(!stripped.contains("cfg_attr"), "{}", stripped);
+        assert!(stripped.contains("struct A;"));
+        let crates: Vec<&str> = attributes.iter().map(|a| a.krate.as_str()).collect();
+        assert_eq!(crates, ["serde", "serde_json"]);
+        assert!(attributes.iter().all(|a| a.features == ["serde"]));
+    }
+
+    #[test]
+    fn predicates_without_features_are_unconditional() {
+        let content = "#![cfg_attr(not(feature = \"std\"), no_std)]\n\
+                       #[cfg_attr(docsrs, doc(cfg(feature = \"x\")))]\nfn f() {}\n";
+        let (_, attributes) = extract(content);
+        let crates: Vec<&str> = attributes.iter().map(|a| a.krate.as_str()).collect();
+        assert_eq!(crates, ["no_std", "doc"]);
+        assert!(attributes.iter().all(|a| a.features.is_empty()));
+    }
+
+    #[test]
+    fn unresolved_derives_and_plain_attributes_are_ignored() {
+        let (_, attributes) = extract("#[cfg_attr(test, derive(Debug))]\nstruct A;\n");
+        assert!(attributes.is_empty());
+
+        let content = "#[derive(serde::Serialize)]\n#[cfg(feature = \"x\")]\nstruct A;\n";
+        let (stripped, attributes) = extract(content);
+        assert_eq!(stripped, content);
+        assert!(attributes.is_empty());
+    }
+}
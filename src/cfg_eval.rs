@@ -0,0 +1,331 @@
+use std::collections::{BTreeSet, HashSet};
+use std::process::Command;
+
+/// A parsed `cfg` predicate.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cfg {
+    Any(Vec<Cfg>),
+    All(Vec<Cfg>),
+    Not(Box<Cfg>),
+    /// `unix`, `test`, `debug_assertions`, ...
+    Name(String),
+    /// `feature = "serde"`, `target_os = "linux"`, ...
+    KeyValue(String, String),
+}
+
+/// What a predicate is evaluated against. Anything it doesn't know evaluates to `None`.
+#[derive(Clone, Copy)]
+pub struct Context<'a> {
+    /// The enabled features; `None` enables them all.
+    pub features: Option<&'a BTreeSet<String>>,
+    /// The cfg values of the target being analyzed for, when one was given.
+    pub target: Option<&'a TargetCfg>,
+}
+
+/// The cfg values rustc sets for a target, as printed by `rustc --print cfg`.
+pub struct TargetCfg {
+    names: HashSet<String>,
+    values: HashSet<(String, String)>,
+}
+
+impl TargetCfg {
+    /// Asks rustc (`$RUSTC`, or the one on the path) for the cfg values of `triple`.
+    pub fn load(triple: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let output = Command::new(std::env::var("RUSTC").unwrap_or_else(|_| "rustc".into()))
+            .args(["--print", "cfg", "--target", triple])
+            .output()?;
+        if !output.status.success() {
+            return Err(format!(
+                "rustc doesn't know the target `{}`: {}",
+                triple,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+            .into());
+        }
+        let mut cfg = TargetCfg {
+            names: HashSet::new(),
+            values: HashSet::new(),
+        };
+        for line in String::from_utf8(output.stdout)?.lines() {
+            match line.split_once('=') {
+                Some((key, value)) => {
+                    cfg.values
+                        .insert((key.to_string(), value.trim_matches('"').to_string()));
+                }
+                None => {
+                    cfg.names.insert(line.to_string());
+                }
+            }
+        }
+        Ok(cfg)
+    }
+}
+
+impl Cfg {
+    /// Parses the inside of a `cfg(...)` attribute; `None` when it isn't a valid predicate.
+    pub fn parse(predicate: &str) -> Option<Cfg> {
+        let tokens = tokenize(predicate)?;
+        let mut pos = 0;
+        let cfg = parse_predicate(&tokens, &mut pos)?;
+        // A trailing comma is allowed, as in `cfg(unix,)`.
+        if tokens.get(pos) == Some(&Token::Comma) {
+            pos += 1;
+        }
+        (pos == tokens.len()).then_some(cfg)
+    }
+
+    /// Whether the predicate holds in `context`: `Some` when it can be decided, `None`
+    /// when it depends on something the context doesn't know, such as the platform when
+    /// no target was given. Code under `test` is never part of a normal build.
+    pub fn eval(&self, context: &Context) -> Option<bool> {
+        match self {
+            Cfg::Any(predicates) => {
+                let results: Vec<_> = predicates.iter().map(|p| p.eval(context)).collect();
+                if results.contains(&Some(true)) {
+                    Some(true)
+                } else if results.iter().all(|r| *r == Some(false)) {
+                    Some(false)
+                } else {
+                    None
+                }
+            }
+            Cfg::All(predicates) => {
+                let results: Vec<_> = predicates.iter().map(|p| p.eval(context)).collect();
+                if results.contains(&Some(false)) {
+                    Some(false)
+                } else if results.iter().all(|r| *r == Some(true)) {
+                    Some(true)
+                } else {
+                    None
+                }
+            }
+            Cfg::Not(predicate) => predicate.eval(context).map(|holds| !holds),
+            Cfg::KeyValue(key, feature) if key == "feature" => Some(
+                context
+                    .features
+                    .is_none_or(|enabled| enabled.contains(feature)),
+            ),
+            Cfg::Name(name) if matches!(name.as_str(), "test" | "doc" | "doctest" | "miri") => {
+                Some(false)
+            }
+            Cfg::Name(name) => context.target.map(|target| target.names.contains(name)),
+            Cfg::KeyValue(key, value) => context
+                .target
+                .map(|target| target.values.contains(&(key.clone(), value.clone()))),
+        }
+    }
+
+    /// The features the predicate asks to be enabled (those not under a `not`).
+    pub fn features(&self) -> Vec<String> {
+        match self {
+            Cfg::Any(predicates) | Cfg::All(predicates) => {
+                predicates.iter().flat_map(Cfg::features).collect()
+            }
+            Cfg::KeyValue(key, feature) if key == "feature" => vec![feature.clone()],
+            Cfg::Not(_) | Cfg::Name(_) | Cfg::KeyValue(..) => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Equals,
+    Open,
+    Close,
+    Comma,
+}
+
+fn tokenize(input: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '=' | '(' | ')' | ',' => {
+                chars.next();
+                tokens.push(match c {
+                    '=' => Token::Equals,
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    _ => Token::Comma,
+                });
+            }
+            '"' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => value.push(chars.next()?),
+                        c => value.push(c),
+                    }
+                }
+                tokens.push(Token::Str(value));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(&c) = chars.peek().filter(|c| c.is_alphanumeric() || **c == '_') {
+                    ident.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            _ => return None,
+        }
+    }
+    Some(tokens)
+}
+
+fn parse_predicate(tokens: &[Token], pos: &mut usize) -> Option<Cfg> {
+    let Some(Token::Ident(name)) = tokens.get(*pos) else {
+        return None;
+    };
+    *pos += 1;
+    match tokens.get(*pos) {
+        Some(Token::Equals) => {
+            let Some(Token::Str(value)) = tokens.get(*pos + 1) else {
+                return None;
+            };
+            *pos += 2;
+            Some(Cfg::KeyValue(name.clone(), value.clone()))
+        }
+        Some(Token::Open) => {
+            *pos += 1;
+            let mut predicates = Vec::new();
+            while tokens.get(*pos) != Some(&Token::Close) {
+                predicates.push(parse_predicate(tokens, pos)?);
+                match tokens.get(*pos) {
+                    Some(Token::Comma) => *pos += 1,
+                    Some(Token::Close) => {}
+                    _ => return None,
+                }
+            }
+            *pos += 1;
+            match name.as_str() {
+                "any" => Some(Cfg::Any(predicates)),
+                "all" => Some(Cfg::All(predicates)),
+                "not" if predicates.len() == 1 => {
+                    Some(Cfg::Not(Box::new(predicates.pop().unwrap())))
+                }
+                _ => None,
+            }
+        }
+        _ => Some(Cfg::Name(name.clone())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn linux() -> TargetCfg {
+        TargetCfg {
+            names: HashSet::from(["unix".to_string()]),
+            values: HashSet::from([
+                ("target_os".to_string(), "linux".to_string()),
+                ("target_pointer_width".to_string(), "64".to_string()),
+            ]),
+        }
+    }
+
+    fn eval(predicate: &str, features: &[&str], target: Option<&TargetCfg>) -> Option<bool> {
+        let features: BTreeSet<String> = features.iter().map(|f| f.to_string()).collect();
+        let context = Context {
+            features: Some(&features),
+            target,
+        };
+        Cfg::parse(predicate)
+            .expect("the predicate parses")
+            .eval(&context)
+    }
+
+    #[test]
+    fn parses_nested_predicates() {
+        assert_eq!(
+            Cfg::parse(r#"all(unix, not(any(feature = "a", target_os = "macos")))"#),
+            Some(Cfg::All(vec![
+                Cfg::Name("unix".into()),
+                Cfg::Not(Box::new(Cfg::Any(vec![
+                    Cfg::KeyValue("feature".into(), "a".into()),
+                    Cfg::KeyValue("target_os".into(), "macos".into()),
+                ]))),
+            ]))
+        );
+    }
+
+    #[test]
+    fn accepts_trailing_commas() {
+        assert_eq!(Cfg::parse("unix,"), Some(Cfg::Name("unix".into())));
+        assert_eq!(
+            Cfg::parse(r#"any(feature = "a", feature = "b",)"#),
+            Some(Cfg::Any(vec![
+                Cfg::KeyValue("feature".into(), "a".into()),
+                Cfg::KeyValue("feature".into(), "b".into()),
+            ]))
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_predicates() {
+        assert_eq!(Cfg::parse("feature ="), None);
+        assert_eq!(Cfg::parse("not(unix, windows)"), None);
+        assert_eq!(Cfg::parse("nope(unix)"), None);
+        assert_eq!(Cfg::parse("all(unix"), None);
+        assert_eq!(Cfg::parse("unix windows"), None);
+    }
+
+    #[test]
+    fn evaluates_key_value_pairs() {
+        assert_eq!(eval(r#"feature = "a""#, &["a"], None), Some(true));
+        assert_eq!(eval(r#"feature = "b""#, &["a"], None), Some(false));
+        assert_eq!(
+            eval(r#"target_os = "linux""#, &[], Some(&linux())),
+            Some(true)
+        );
+        assert_eq!(
+            eval(r#"target_os = "windows""#, &[], Some(&linux())),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn evaluates_nested_any_all_not() {
+        let target = linux();
+        let target = Some(&target);
+        let predicate = r#"all(unix, not(any(feature = "a", target_os = "macos")))"#;
+        assert_eq!(eval(predicate, &[], target), Some(true));
+        assert_eq!(eval(predicate, &["a"], target), Some(false));
+        assert_eq!(eval("any()", &[], target), Some(false));
+        assert_eq!(eval("all()", &[], target), Some(true));
+        assert_eq!(eval("not(test)", &[], None), Some(true));
+    }
+
+    #[test]
+    fn unknown_predicates_are_undecided() {
+        // Without a target, platform predicates depend on where it's built.
+        assert_eq!(eval("unix", &[], None), None);
+        assert_eq!(eval(r#"target_os = "linux""#, &[], None), None);
+        assert_eq!(eval("not(windows)", &[], None), None);
+        // An undecided operand decides `any` and `all` only when another one does.
+        assert_eq!(eval(r#"all(unix, feature = "a")"#, &[], None), Some(false));
+        assert_eq!(eval(r#"all(unix, feature = "a")"#, &["a"], None), None);
+        assert_eq!(
+            eval(r#"any(unix, feature = "a")"#, &["a"], None),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn all_features_enables_every_feature() {
+        let context = Context {
+            features: None,
+            target: None,
+        };
+        let cfg = Cfg::parse(r#"feature = "anything""#).unwrap();
+        assert_eq!(cfg.eval(&context), Some(true));
+    }
+}
@@ -0,0 +1,152 @@
+use crate::cli::Args;
+use crate::project::Project;
+use cargo_metadata::diagnostic::DiagnosticLevel;
+use cargo_metadata::{Message, Package};
+use std::io::BufReader;
+use std::process::{Command, Stdio};
+
+/// Feature sets beyond this size make `--powerset` refuse to run: 2^n builds.
+const MAX_POWERSET_FEATURES: usize = 12;
+
+/// `check-features`: runs `cargo check` on each analyzed package with no default
+/// features, then with each feature on its own (and with `powerset`, every combination),
+/// printing the combinations that fail to build. Exits with 1 when one does.
+pub fn run(args: &Args, powerset: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let project = Project::load(args.manifest_path.as_deref())?;
+    let mut failed = 0;
+    let mut checked = 0;
+    for package in crate::selected_packages(args, &project)? {
+        let combinations = combinations(package, powerset)?;
+        println!(
+            "\nChecking {} feature combination{} of {}:",
+            combinations.len(),
+            if combinations.len() == 1 { "" } else { "s" },
+            package.name
+        );
+        for features in combinations {
+            checked += 1;
+            let label = if features.is_empty() {
+                "--no-default-features".to_string()
+            } else {
+                format!("--no-default-features --features {}", features.join(","))
+            };
+            match check(args, &project, package, &features)? {
+                None => println!("  ok    {}", label),
+                Some(error) => {
+                    failed += 1;
+                    println!("  FAIL  {}", label);
+                    println!("        {}", error);
+                }
+            }
+        }
+    }
+    println!("\n{} of {} combinations failed to build.", failed, checked);
+    if failed > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// The feature sets to build with: none, each feature alone, and with `powerset` every
+/// other subset. `default` is left out: the other sets are built without it.
+fn combinations(
+    package: &Package,
+    powerset: bool,
+) -> Result<Vec<Vec<String>>, Box<dyn std::error::Error>> {
+    let features: Vec<&String> = package
+        .features
+        .keys()
+        .filter(|feature| *feature != "default")
+        .collect();
+    if !powerset {
+        return Ok(std::iter::once(Vec::new())
+            .chain(features.iter().map(|feature| vec![feature.to_string()]))
+            .collect());
+    }
+    if features.len() > MAX_POWERSET_FEATURES {
+        return Err(format!(
+            "{} has {} features, {} combinations; --powerset supports up to {} features",
+            package.name,
+            features.len(),
+            1u64 << features.len(),
+            MAX_POWERSET_FEATURES
+        )
+        .into());
+    }
+    Ok((0..1usize << features.len())
+        .map(|mask| {
+            features
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| mask & (1 << i) != 0)
+                .map(|(_, feature)| feature.to_string())
+                .collect()
+        })
+        .collect())
+}
+
+/// Checks `package` with exactly `features`; the first error rustc reports about the
+/// package when the build fails (or a generic message when the failure is elsewhere).
+fn check(
+    args: &Args,
+    project: &Project,
+    package: &Package,
+    features: &[String],
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let mut command = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".into()));
+    command
+        .args(["check", "--message-format=json", "--package"])
+        .arg(format!("{}@{}", package.name, package.version))
+        .arg("--manifest-path")
+        .arg(package.manifest_path.as_std_path())
+        // The same separate target directory as --verify-with-rustc, so the user's build
+        // cache keeps its features.
+        .arg("--target-dir")
+        .arg(
+            project
+                .metadata
+                .target_directory
+                .join("dep-analysis")
+                .as_std_path(),
+        )
+        .arg("--no-default-features")
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    if !features.is_empty() {
+        command.arg("--features").arg(features.join(","));
+    }
+    if let Some(target) = &args.target {
+        command.arg("--target").arg(target);
+    }
+    if args.offline || project.offline() {
+        command.arg("--offline");
+    }
+    tracing::debug!(?features, "checking {}", package.name);
+    let mut child = command.spawn()?;
+
+    let mut error = None;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    for message in Message::parse_stream(BufReader::new(stdout)) {
+        if let Message::CompilerMessage(msg) = message? {
+            if error.is_none()
+                && msg.package_id == package.id
+                && msg.message.level == DiagnosticLevel::Error
+            {
+                let place = msg
+                    .message
+                    .spans
+                    .iter()
+                    .find(|span| span.is_primary)
+                    .map(|span| format!(" ({}:{})", span.file_name, span.line_start))
+                    .unwrap_or_default();
+                error = Some(format!("{}{}", msg.message.message, place));
+            }
+        }
+    }
+    if child.wait()?.success() {
+        return Ok(None);
+    }
+    Ok(Some(error.unwrap_or_else(|| {
+        "the build failed outside the package (a dependency or the manifest)".to_string()
+    })))
+}
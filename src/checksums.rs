@@ -0,0 +1,191 @@
+use crate::project::Project;
+use crate::registry::Registry;
+use crate::report::{Finding, Kind};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Mismatched files listed per vendored package; the rest are counted.
+const MAX_FILES_LISTED: usize = 5;
+
+/// `.cargo-checksum.json`, as `cargo vendor` writes it next to each vendored package.
+#[derive(serde::Deserialize)]
+struct VendorChecksums {
+    files: BTreeMap<String, String>,
+    /// The checksum of the `.crate` file; absent for git sources.
+    package: Option<String>,
+}
+
+/// Checks the checksums Cargo.lock records: against the index of the registry each
+/// package came from (unless offline), and against the vendored sources when
+/// dependencies are vendored, whose files must also match the hashes `cargo vendor`
+/// recorded for them.
+pub fn analyze(project: &Project, offline: bool) -> Vec<Finding> {
+    let Some(lockfile) = project.lockfile() else {
+        return Vec::new();
+    };
+    let locked: HashMap<(&str, &str), &str> = lockfile
+        .get("package")
+        .and_then(|p| p.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|pkg| {
+            Some((
+                (pkg.get("name")?.as_str()?, pkg.get("version")?.as_str()?),
+                pkg.get("checksum")?.as_str()?,
+            ))
+        })
+        .collect();
+    let lockfile_path = project.root().join("Cargo.lock");
+
+    let mut findings = Vec::new();
+    if !offline {
+        findings.extend(against_registries(project, &locked, &lockfile_path));
+    }
+    for (pkg, dir) in vendored(project) {
+        let version = pkg.version.to_string();
+        let recorded = locked.get(&(pkg.name.as_str(), version.as_str())).copied();
+        let Some(notes) = against_vendored(&dir, recorded) else {
+            continue;
+        };
+        let mut finding =
+            Finding::new(Kind::ChecksumMismatch, &pkg.name).file(project, &lockfile_path);
+        for note in notes {
+            finding = finding.note(note);
+        }
+        findings.push(finding);
+    }
+    findings
+}
+
+fn against_registries(
+    project: &Project,
+    locked: &HashMap<(&str, &str), &str>,
+    lockfile: &Path,
+) -> Vec<Finding> {
+    let mut registries: HashMap<&str, Option<Registry>> = HashMap::new();
+    let mut findings = Vec::new();
+    let progress = project.progress.bar(
+        "Verifying checksums",
+        Some(project.metadata.packages.len() as u64),
+    );
+    for pkg in &project.metadata.packages {
+        progress.inc();
+        let Some(source) = pkg.source.as_ref().map(|s| s.repr.as_str()) else {
+            continue;
+        };
+        if !source.starts_with("registry+") && !source.starts_with("sparse+") {
+            continue;
+        }
+        let version = pkg.version.to_string();
+        let Some(recorded) = locked.get(&(pkg.name.as_str(), version.as_str())) else {
+            continue;
+        };
+        let registry = registries.entry(source).or_insert_with(|| {
+            Registry::for_source(source, &project.cargo_config, &project.cache).unwrap_or_else(
+                |err| {
+                    tracing::warn!("skipping registry {}: {}", source, err);
+                    None
+                },
+            )
+        });
+        let Some(registry) = registry else {
+            continue;
+        };
+        let Ok(versions) = registry.versions(&pkg.name) else {
+            continue;
+        };
+        let Some(entry) = versions.iter().find(|entry| entry.vers == version) else {
+            continue;
+        };
+        if !entry.cksum.is_empty() && entry.cksum != *recorded {
+            findings.push(
+                Finding::new(Kind::ChecksumMismatch, &pkg.name)
+                    .file(project, lockfile)
+                    .note(format!(
+                        "Cargo.lock records {} for {} {}, but {} lists {}",
+                        recorded, pkg.name, pkg.version, registry.name, entry.cksum
+                    ))
+                    .note("Either the lockfile or the registry's index was altered"),
+            );
+        }
+    }
+    findings
+}
+
+/// The packages resolved from the vendor directory, with their directories.
+fn vendored(project: &Project) -> Vec<(&cargo_metadata::Package, PathBuf)> {
+    let Some(vendor) = &project.vendor else {
+        return Vec::new();
+    };
+    let directory = project.root().join(&vendor.directory);
+    project
+        .metadata
+        .packages
+        .iter()
+        .filter_map(|pkg| {
+            let dir = pkg.manifest_path.parent()?.as_std_path();
+            dir.starts_with(&directory)
+                .then(|| (pkg, dir.to_path_buf()))
+        })
+        .collect()
+}
+
+/// What doesn't match in a vendored package: its recorded package checksum against
+/// the lockfile's, and its files against their recorded hashes. `None` when all do.
+fn against_vendored(dir: &Path, locked: Option<&str>) -> Option<Vec<String>> {
+    let checksums = match std::fs::read_to_string(dir.join(".cargo-checksum.json")) {
+        Ok(content) => serde_json::from_str::<VendorChecksums>(&content).ok()?,
+        Err(_) => {
+            return Some(vec![format!(
+                "{} has no .cargo-checksum.json, so its sources can't be verified",
+                dir.display()
+            )])
+        }
+    };
+    let mut notes = Vec::new();
+    if let (Some(vendored), Some(locked)) = (&checksums.package, locked) {
+        if vendored != locked {
+            notes.push(format!(
+                "The vendored package was made from a .crate with checksum {}, but \
+                 Cargo.lock records {}",
+                vendored, locked
+            ));
+        }
+    }
+    let modified: Vec<&String> = checksums
+        .files
+        .iter()
+        .filter(|(file, hash)| sha256(&dir.join(file)).as_ref() != Some(*hash))
+        .map(|(file, _)| file)
+        .collect();
+    if !modified.is_empty() {
+        let mut listed = modified
+            .iter()
+            .take(MAX_FILES_LISTED)
+            .map(|f| f.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        if modified.len() > MAX_FILES_LISTED {
+            listed.push_str(&format!(" and {} more", modified.len() - MAX_FILES_LISTED));
+        }
+        notes.push(format!(
+            "Vendored files changed or missing since `cargo vendor`: {}",
+            listed
+        ));
+    }
+    (!notes.is_empty()).then_some(notes)
+}
+
+/// The SHA-256 of a file, in lowercase hex; `None` when it can't be read.
+pub fn sha256(path: &Path) -> Option<String> {
+    Some(sha256_hex(&std::fs::read(path).ok()?))
+}
+
+/// The SHA-256 of `bytes`, in lowercase hex as lockfiles and indexes record it.
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
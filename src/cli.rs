@@ -0,0 +1,481 @@
+use crate::report::Kind;
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+/// Call attention to potentially-unused crates and other dependency hygiene issues.
+#[derive(Parser, Debug)]
+#[command(name = "cargo-dep-analysis", version, about)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Projects to analyze, each a directory or a Cargo.toml; with more than one, each
+    /// gets its report and a combined summary follows
+    #[arg(value_name = "PATH", conflicts_with_all = ["manifest_path", "git"])]
+    pub paths: Vec<PathBuf>,
+
+    /// Analyze every project under this directory: each workspace, and each package
+    /// outside one
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["manifest_path", "git"])]
+    pub recursive: Option<PathBuf>,
+
+    /// Path to the Cargo.toml of the project to analyze
+    #[arg(long, global = true, value_name = "PATH")]
+    pub manifest_path: Option<PathBuf>,
+
+    /// Shallow-clone a git repository into a temporary directory and analyze it;
+    /// --manifest-path is then taken relative to the repository root
+    #[arg(long, value_name = "URL")]
+    pub git: Option<String>,
+
+    /// Branch, tag or commit to check out with --git (defaults to the remote HEAD)
+    #[arg(long, value_name = "REV", requires = "git")]
+    pub rev: Option<String>,
+
+    /// How to render the report
+    #[arg(long, alias = "message-format", global = true, value_enum, default_value_t = Format::Text)]
+    pub format: Format,
+
+    /// Render the report with a Handlebars template instead of a built-in format
+    #[arg(long, global = true, value_name = "FILE", conflicts_with = "format")]
+    pub template: Option<PathBuf>,
+
+    /// Organize the text and pr-comment reports into sections
+    #[arg(long, global = true, value_enum, value_name = "KEY")]
+    pub group_by: Option<GroupBy>,
+
+    /// Print only the aggregate numbers instead of the findings
+    #[arg(long, conflicts_with_all = ["format", "template"])]
+    pub summary: bool,
+
+    /// With --format pr-comment, the branch to compare the findings against; also the
+    /// revision whose lockfile the dependency budgets measure growth from
+    #[arg(long, value_name = "REF")]
+    pub base: Option<String>,
+
+    /// With --format attest, a file the statement is about, such as a release artifact
+    /// (repeatable; default: Cargo.lock)
+    #[arg(long, value_name = "FILE")]
+    pub subject: Vec<PathBuf>,
+
+    /// Sign the report and print it wrapped in a DSSE envelope: KEY is an Ed25519 private
+    /// key (PKCS#8 PEM), or `keyless` to sign with sigstore through `cosign`
+    #[arg(long, value_name = "KEY", conflicts_with = "summary")]
+    pub sign: Option<PathBuf>,
+
+    /// When to color the output; `auto` colors terminals unless NO_COLOR is set
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// Fail when there are more than this many warnings (any error always fails)
+    #[arg(long, value_name = "N")]
+    pub max_warnings: Option<usize>,
+
+    /// Fail when a finding matches RULE (repeatable): a rule id such as `unused`, or
+    /// `outdated-major`, `in-range-update-patch`, ... for updates of that size
+    #[arg(long, value_name = "RULE", value_parser = parse_rule)]
+    pub fail_on: Vec<String>,
+
+    /// Scan only the Rust files listed in this file (`-` for stdin), one per line, instead
+    /// of walking src/; dependencies used only in other files are then reported as unused
+    #[arg(long, value_name = "PATH")]
+    pub files_from: Option<PathBuf>,
+
+    /// Scan only this workspace member's sources (repeatable); by default the root
+    /// package's, or every member's in a virtual workspace
+    #[arg(short, long, value_name = "NAME")]
+    pub package: Vec<String>,
+
+    /// Scan only the library's sources (combines with the other target flags); by default
+    /// every target is scanned
+    #[arg(long)]
+    pub lib: bool,
+
+    /// Scan only the binaries' sources
+    #[arg(long)]
+    pub bins: bool,
+
+    /// Scan only the test targets (and `#[cfg(test)]` code), evaluating only
+    /// [dev-dependencies]
+    #[arg(long)]
+    pub tests: bool,
+
+    /// Scan only the benches, evaluating only [dev-dependencies]
+    #[arg(long)]
+    pub benches: bool,
+
+    /// Scan only the examples, evaluating only [dev-dependencies]
+    #[arg(long)]
+    pub examples: bool,
+
+    /// Scan every target (the default)
+    #[arg(long)]
+    pub all_targets: bool,
+
+    /// Skip source paths matching this glob (relative to the package root, e.g.
+    /// `src/generated/**`) in the usage scan; repeatable
+    #[arg(long, value_name = "GLOB")]
+    pub exclude: Vec<String>,
+
+    /// Follow symbolic links to files and directories in the usage scan (a link that
+    /// leads back into a directory being scanned is skipped). By default links are not
+    /// followed, so symlinked source trees aren't scanned
+    #[arg(long)]
+    pub follow_symlinks: bool,
+
+    /// Count references from generated files (`@generated` markers, bindgen and protobuf
+    /// output) as usage; by default only hand-written code does
+    #[arg(long)]
+    pub include_generated: bool,
+
+    /// Features to treat as enabled when deciding whether code under
+    /// `#[cfg(feature = ...)]` uses a dependency (comma or space separated); repeatable
+    #[arg(short = 'F', long, value_delimiter = ',', value_name = "FEATURES")]
+    pub features: Vec<String>,
+
+    /// Treat every feature as enabled
+    #[arg(long)]
+    pub all_features: bool,
+
+    /// Don't treat the `default` feature as enabled
+    #[arg(long)]
+    pub no_default_features: bool,
+
+    /// Evaluate `#[cfg(...)]` predicates for this target triple (e.g.
+    /// `x86_64-pc-windows-msvc`): code for other platforms doesn't count as usage, and
+    /// code for this one counts unconditionally
+    #[arg(long, value_name = "TRIPLE")]
+    pub target: Option<String>,
+
+    /// Confirm the unused-dependency findings with rustc: runs `cargo check` with the
+    /// `unused_crate_dependencies` lint and labels each finding with its evidence
+    #[arg(long)]
+    pub verify_with_rustc: bool,
+
+    /// Report the default features and listed features of registry dependencies the
+    /// workspace builds without: each is tried in a scratch copy with `cargo check
+    /// --all-targets`, and only those that compile are reported
+    #[arg(long)]
+    pub verify_features: bool,
+
+    /// Also analyze path dependencies that aren't workspace members, and theirs, up to
+    /// DEPTH levels away from the members (1, direct path dependencies only, by default)
+    #[arg(
+        long,
+        value_name = "DEPTH",
+        num_args = 0..=1,
+        default_missing_value = "1"
+    )]
+    pub follow_path_deps: Option<usize>,
+
+    /// The files read from --files-from, made absolute.
+    #[arg(skip)]
+    pub files: Option<Vec<PathBuf>>,
+
+    /// Show more detail, such as who added each unused dependency (git blame); repeat
+    /// for debug (-vv) and trace (-vvv) logging
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Log only warnings (-q) or errors (-qq); RUST_LOG takes precedence over both
+    #[arg(short, long, global = true, action = ArgAction::Count, conflicts_with = "verbose")]
+    pub quiet: u8,
+
+    /// Apply the suggested manifest rewrites where a fix is available
+    #[arg(long, global = true)]
+    pub fix: bool,
+
+    /// Skip checks that need network access (git remotes, registry lookups)
+    #[arg(long, global = true)]
+    pub offline: bool,
+
+    /// Append a snapshot of the report's metrics to .dep-analysis/history.jsonl
+    #[arg(long)]
+    pub record: bool,
+
+    /// Ignore cached registry and git responses and fetch them again
+    #[arg(long, global = true)]
+    pub refresh: bool,
+
+    /// Run at most N tasks at once in each phase: packages scanned in parallel (default:
+    /// one per core) and registry requests in flight (default: 16)
+    #[arg(short, long, global = true, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub jobs: Option<u16>,
+
+    /// The RustSec advisory database to check the lockfile against, used as it is
+    /// (default: $CARGO_HOME/advisory-db, cloned or updated there unless offline)
+    #[arg(long, global = true, value_name = "DIR")]
+    pub advisory_db: Option<PathBuf>,
+
+    /// Also trust the CA certificates in this PEM file for registry requests, e.g. a
+    /// proxy's that intercepts TLS (default: cargo's http.cainfo)
+    #[arg(long, global = true, value_name = "FILE")]
+    pub cacert: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// The full, human-readable report
+    Text,
+    /// A compact, collapsible markdown summary to post as a single CI bot comment
+    PrComment,
+    /// Gauges in the Prometheus text exposition format
+    Metrics,
+    /// One JSON object per finding and line, printed as soon as each pass finishes
+    Jsonl,
+    /// A GitLab Code Quality report, for the merge request widget
+    Gitlab,
+    /// A self-contained HTML page drawing the dependency graph, zoomable and filterable
+    HtmlGraph,
+    /// An in-toto statement carrying the resolved packages and the findings, to sign and
+    /// attach to release artifacts as provenance
+    Attest,
+    /// One cargo `compiler-message` per line, in the shape of rustc's JSON diagnostics,
+    /// for tools that read `cargo check --message-format json`
+    JsonDiagnostic,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupBy {
+    Crate,
+    /// The file to change (a member's manifest, the root manifest, the lockfile)
+    File,
+    Severity,
+    Rule,
+    Member,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    /// Whether to color a stream; `auto` follows https://no-color.org.
+    pub fn enabled(self, is_terminal: bool) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                is_terminal && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+            }
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Download a crate from crates.io and analyze its published sources
+    Remote {
+        /// The crate to analyze, as `name` (latest release) or `name@version`
+        spec: String,
+    },
+    /// Host an interactive dashboard on localhost, analyzing again when sources change
+    Serve {
+        /// The port to listen on
+        #[arg(long, default_value_t = 7878)]
+        port: u16,
+    },
+    /// Print a completion script for a shell, e.g. `completions bash > /etc/bash_completion.d/cargo-dep-analysis`
+    Completions { shell: clap_complete::Shell },
+    /// Describe a finding kind, why it matters and how to resolve it; lists them all
+    /// when no rule is given
+    Explain {
+        /// The rule id (e.g. `unused`) or report label (e.g. "POTENTIALLY UNUSED")
+        rule: Option<String>,
+    },
+    /// Order the actionable findings into a step-by-step cleanup plan, surest and most
+    /// rewarding (crates taken out of the build) first
+    Plan,
+    /// Build each analyzed package with no default features and with each feature alone
+    /// (`cargo check`), reporting the combinations that fail
+    CheckFeatures {
+        /// Build every combination of features instead of each one alone
+        #[arg(long)]
+        powerset: bool,
+    },
+    /// Show, for each feature, the dependencies code under it uses and the optional
+    /// dependencies it enables
+    FeatureMatrix,
+    /// Resolve the direct dependencies to the lowest versions their requirements allow
+    /// and build, reporting lower bounds too low to compile
+    MinimalVersions,
+    /// Show which targets (the library, each binary, test, example and bench) use each
+    /// dependency
+    Targets,
+    /// Show which external crates the workspace members share and with which features,
+    /// the members declaring a shared crate differently, and the crates only one uses
+    Overlap,
+    /// Show the workspace's internal graph: which members depend on which, with dev and
+    /// build edges, layered from the members depending on no other up
+    Members {
+        /// Print the graph in Graphviz's DOT language
+        #[arg(long)]
+        dot: bool,
+    },
+    /// Show the transitive crates only a direct dependency's default features bring
+    /// in, and how many crates turning each one's default features off would drop
+    DefaultFeatures,
+    /// Show the crates compiled twice, for the target and for build scripts and
+    /// proc-macros on the host, and the direct dependencies causing it
+    HostTarget,
+    /// Show the metrics recorded with --record over time
+    History,
+    /// Write an SVG badge showing the dependency and unused-dependency counts
+    Badge {
+        /// Where to write the SVG
+        #[arg(short, long, value_name = "PATH", default_value = "dep-analysis.svg")]
+        output: PathBuf,
+    },
+    /// Compare the findings of two git revisions, or of one revision and the working tree
+    Diff {
+        /// The revision to compare from
+        base: Option<String>,
+        /// The revision to compare to (defaults to the working tree)
+        head: Option<String>,
+        /// The revision to compare the working tree against
+        #[arg(long, value_name = "REF", conflicts_with_all = ["base", "head"])]
+        against: Option<String>,
+    },
+    /// Compare this project's direct and resolved dependencies (requirements, versions,
+    /// features) with another project's
+    Compare {
+        /// The other project, a directory or its Cargo.toml
+        other: PathBuf,
+    },
+    /// Combine reports produced with --format jsonl or attest, e.g. one per repository,
+    /// into an organization-wide view: distinct crates, the most common unused ones and
+    /// the advisories several reports share
+    Merge {
+        /// The reports to combine
+        #[arg(required = true)]
+        reports: Vec<PathBuf>,
+        /// Print the rollup as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Run a language server on stdin and stdout, publishing the findings as diagnostics
+    /// on the manifests with code actions to remove or ignore unused dependencies
+    Lsp,
+    /// Keep the project loaded and answer queries (run the analysis, describe a
+    /// dependency, show why it is in the graph) over a Unix socket, re-analyzing only
+    /// when sources change
+    Daemon {
+        /// The socket to listen on (default: dep-analysis.sock in the target directory)
+        #[arg(long, value_name = "PATH")]
+        socket: Option<PathBuf>,
+    },
+    /// Check the signature of a report signed with --sign and print the report
+    Verify {
+        /// The signed report (a DSSE envelope)
+        envelope: PathBuf,
+        /// The signer's Ed25519 public key (SPKI PEM)
+        #[arg(
+            long,
+            value_name = "PATH",
+            required_unless_present = "certificate_identity"
+        )]
+        key: Option<PathBuf>,
+        /// For keyless signatures, the identity (e.g. an email or workflow URL) the
+        /// signing certificate must have been issued to
+        #[arg(long, conflicts_with = "key", requires = "certificate_oidc_issuer")]
+        certificate_identity: Option<String>,
+        /// For keyless signatures, the OIDC issuer that must have vouched for the identity
+        #[arg(long, requires = "certificate_identity")]
+        certificate_oidc_issuer: Option<String>,
+    },
+}
+
+/// The target kinds whose sources are scanned, from --lib, --bins, --tests, --benches,
+/// --examples and --all-targets.
+#[derive(Clone, Copy, Debug)]
+pub struct Targets {
+    pub lib: bool,
+    pub bins: bool,
+    pub tests: bool,
+    pub benches: bool,
+    pub examples: bool,
+}
+
+impl Targets {
+    /// Whether [dependencies] and [build-dependencies] are evaluated: they serve the
+    /// library and binaries.
+    pub fn normal(self) -> bool {
+        self.lib || self.bins
+    }
+
+    /// Whether [dev-dependencies] are evaluated.
+    pub fn dev(self) -> bool {
+        self.tests || self.benches || self.examples
+    }
+}
+
+impl Args {
+    /// The selected targets; all of them unless a target flag narrows them down.
+    pub fn targets(&self) -> Targets {
+        let any = self.lib || self.bins || self.tests || self.benches || self.examples;
+        let all = self.all_targets || !any;
+        Targets {
+            lib: all || self.lib,
+            bins: all || self.bins,
+            tests: all || self.tests,
+            benches: all || self.benches,
+            examples: all || self.examples,
+        }
+    }
+
+    /// Parses the command line, dropping the extra `dep-analysis` argument cargo passes
+    /// when the tool is run as `cargo dep-analysis`.
+    pub fn from_env() -> Self {
+        let mut args: Vec<OsString> = std::env::args_os().collect();
+        if args.get(1).is_some_and(|arg| arg == "dep-analysis") {
+            args.remove(1);
+        }
+        Args::parse_from(args)
+    }
+
+    /// Reads the --files-from list up front, since stdin can only be read once and some
+    /// commands analyze more than once.
+    pub fn read_files_from(&mut self) -> std::io::Result<()> {
+        let Some(source) = &self.files_from else {
+            return Ok(());
+        };
+        let list = if source.as_os_str() == "-" {
+            std::io::read_to_string(std::io::stdin())?
+        } else {
+            std::fs::read_to_string(source)?
+        };
+        let cwd = std::env::current_dir()?;
+        self.files = Some(
+            list.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(|line| cwd.join(line))
+                .collect(),
+        );
+        Ok(())
+    }
+}
+
+/// A `--fail-on` rule: a finding kind's id, or `<id>-<size>` for the kinds suggesting
+/// updates.
+fn parse_rule(rule: &str) -> Result<String, String> {
+    let known = Kind::ALL.iter().any(|kind| {
+        kind.id() == rule
+            || matches!(kind, Kind::Outdated | Kind::InRangeUpdate)
+                && ["major", "minor", "patch"]
+                    .iter()
+                    .any(|size| rule == format!("{}-{}", kind.id(), size))
+    });
+    if known {
+        Ok(rule.to_string())
+    } else {
+        Err(format!(
+            "unknown rule `{}`; see `cargo dep-analysis explain` for the rule ids",
+            rule
+        ))
+    }
+}
@@ -0,0 +1,187 @@
+use crate::project::Project;
+use cargo_metadata::DependencyKind;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+/// How a project declares a direct dependency, across its members.
+#[derive(Default, PartialEq)]
+struct Declared {
+    reqs: BTreeSet<String>,
+    kinds: BTreeSet<&'static str>,
+    /// Features enabled explicitly, plus `default` where default features are kept.
+    features: BTreeSet<String>,
+}
+
+/// How a crate ended up in a project's resolved graph.
+#[derive(Default, PartialEq)]
+struct Resolved {
+    versions: BTreeSet<String>,
+    features: BTreeSet<String>,
+}
+
+/// `compare <other>`: diffs the dependencies of this project and another, the direct
+/// ones its members declare (requirements, kinds, features) and the resolved graph
+/// (versions, features), for consolidating services or splitting a crate out of a
+/// monolith. `-` lines are only in this project, `+` lines only in the other.
+pub fn run(manifest_path: Option<&Path>, other: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let this = Project::load(manifest_path)?;
+    let other = Project::load(Some(&crate::batch::manifest(other)?))?;
+
+    println!(
+        "\nDependency Comparison ({} vs {}):",
+        this.root().display(),
+        other.root().display()
+    );
+    println!("==========================");
+
+    let (before, after) = (direct(&this), direct(&other));
+    section("Direct dependencies", &before, &after, |name, declared| {
+        format!(
+            "{} {} ({})",
+            name,
+            join(&declared.reqs),
+            join(&declared.kinds)
+        )
+    });
+    for (name, old, new) in changed(&before, &after) {
+        if old.reqs != new.reqs {
+            println!("~ {} {} -> {}", name, join(&old.reqs), join(&new.reqs));
+        }
+        if old.kinds != new.kinds {
+            println!("~ {} as {} -> {}", name, join(&old.kinds), join(&new.kinds));
+        }
+        features(name, &old.features, &new.features);
+    }
+
+    let (before, after) = (resolved(&this), resolved(&other));
+    section("Resolved crates", &before, &after, |name, resolved| {
+        format!("{} {}", name, join(&resolved.versions))
+    });
+    for (name, old, new) in changed(&before, &after) {
+        if old.versions != new.versions {
+            println!(
+                "~ {} {} -> {}",
+                name,
+                join(&old.versions),
+                join(&new.versions)
+            );
+        }
+        features(name, &old.features, &new.features);
+    }
+    Ok(())
+}
+
+/// Prints a section's heading, its counts and the entries only one side has.
+fn section<T: PartialEq>(
+    title: &str,
+    before: &BTreeMap<String, T>,
+    after: &BTreeMap<String, T>,
+    describe: impl Fn(&str, &T) -> String,
+) {
+    let shared = before
+        .keys()
+        .filter(|name| after.contains_key(*name))
+        .count();
+    let same = before
+        .iter()
+        .filter(|(name, entry)| after.get(*name) == Some(entry))
+        .count();
+    println!(
+        "\n{}: {} shared ({} identical), {} only here, {} only in the other",
+        title,
+        shared,
+        same,
+        before.len() - shared,
+        after.len() - shared
+    );
+    for (name, entry) in before.iter().filter(|(name, _)| !after.contains_key(*name)) {
+        println!("- {}", describe(name, entry));
+    }
+    for (name, entry) in after.iter().filter(|(name, _)| !before.contains_key(*name)) {
+        println!("+ {}", describe(name, entry));
+    }
+}
+
+/// The entries both sides have but that differ.
+fn changed<'a, T: PartialEq>(
+    before: &'a BTreeMap<String, T>,
+    after: &'a BTreeMap<String, T>,
+) -> Vec<(&'a str, &'a T, &'a T)> {
+    before
+        .iter()
+        .filter_map(|(name, old)| {
+            let new = after.get(name)?;
+            (old != new).then_some((name.as_str(), old, new))
+        })
+        .collect()
+}
+
+fn features(name: &str, before: &BTreeSet<String>, after: &BTreeSet<String>) {
+    if before == after {
+        return;
+    }
+    let removed = before.difference(after).map(|f| format!("-{}", f));
+    let added = after.difference(before).map(|f| format!("+{}", f));
+    println!(
+        "~ {} features: {}",
+        name,
+        removed.chain(added).collect::<Vec<_>>().join(" ")
+    );
+}
+
+fn join<T: AsRef<str>>(items: &BTreeSet<T>) -> String {
+    items
+        .iter()
+        .map(AsRef::as_ref)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The dependencies the members declare, other than on each other.
+fn direct(project: &Project) -> BTreeMap<String, Declared> {
+    let members: BTreeSet<&str> = project.members().map(|pkg| pkg.name.as_str()).collect();
+    let mut direct: BTreeMap<String, Declared> = BTreeMap::new();
+    for dep in project.members().flat_map(|member| &member.dependencies) {
+        if members.contains(dep.name.as_str()) {
+            continue;
+        }
+        let declared = direct.entry(dep.name.clone()).or_default();
+        declared.reqs.insert(dep.req.to_string());
+        declared.kinds.insert(match dep.kind {
+            DependencyKind::Development => "dev",
+            DependencyKind::Build => "build",
+            _ => "normal",
+        });
+        declared.features.extend(dep.features.iter().cloned());
+        if dep.uses_default_features {
+            declared.features.insert("default".to_string());
+        }
+    }
+    direct
+}
+
+/// Every crate in the resolved graph other than the members, with its versions and the
+/// features cargo enabled on it.
+fn resolved(project: &Project) -> BTreeMap<String, Resolved> {
+    let features: BTreeMap<_, _> = project
+        .metadata
+        .resolve
+        .iter()
+        .flat_map(|resolve| &resolve.nodes)
+        .map(|node| (&node.id, &node.features))
+        .collect();
+    let mut resolved: BTreeMap<String, Resolved> = BTreeMap::new();
+    for pkg in &project.metadata.packages {
+        if project.metadata.workspace_members.contains(&pkg.id) {
+            continue;
+        }
+        let entry = resolved.entry(pkg.name.clone()).or_default();
+        entry.versions.insert(pkg.version.to_string());
+        if let Some(enabled) = features.get(&pkg.id) {
+            entry
+                .features
+                .extend(enabled.iter().map(|feature| feature.to_string()));
+        }
+    }
+    resolved
+}
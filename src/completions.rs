@@ -0,0 +1,42 @@
+use crate::cli::Args;
+use crate::project::{dependency_tables, read_toml};
+use clap::builder::PossibleValuesParser;
+use clap::CommandFactory;
+use clap_complete::Shell;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// `completions <shell>`: prints a completion script covering every flag and subcommand,
+/// offering the dependencies of the manifest at hand wherever a crate name is expected.
+pub fn print(shell: Shell, manifest_path: Option<&Path>) {
+    let names = dependency_names(manifest_path.unwrap_or(Path::new("Cargo.toml")));
+    let mut command = Args::command();
+    if !names.is_empty() {
+        command = command.mut_subcommand("remote", |remote| {
+            remote.mut_arg("spec", |spec| {
+                spec.value_parser(PossibleValuesParser::new(names))
+            })
+        });
+    }
+    let name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, name, &mut std::io::stdout());
+}
+
+/// Every dependency the manifest declares, including `[workspace.dependencies]`.
+fn dependency_names(manifest_path: &Path) -> BTreeSet<String> {
+    let Ok(manifest) = read_toml(manifest_path) else {
+        return BTreeSet::new();
+    };
+    let mut names: BTreeSet<String> = dependency_tables(&manifest)
+        .iter()
+        .flat_map(|table| table.entries.keys().cloned())
+        .collect();
+    if let Some(workspace) = manifest
+        .get("workspace")
+        .and_then(|w| w.get("dependencies"))
+        .and_then(|d| d.as_table())
+    {
+        names.extend(workspace.keys().cloned());
+    }
+    names
+}
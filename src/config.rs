@@ -0,0 +1,60 @@
+use crate::report::{Kind, Severity};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Settings read from `[workspace.metadata.dep-analysis]` in the root manifest, or
+/// `[package.metadata.dep-analysis]` for single-crate projects.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    pub layers: Vec<LayerRule>,
+    /// Seconds that cached registry and git responses stay fresh.
+    pub cache_ttl: Option<u64>,
+    /// Overrides of the default severity per finding kind, e.g. `unused = "error"`.
+    pub severity: BTreeMap<Kind, Severity>,
+    /// Globs, relative to the package root, of source paths the usage scan skips.
+    pub exclude: Vec<String>,
+    /// Dependencies never to report as unused, in any member.
+    pub ignore: Vec<String>,
+    /// Limits on the size of the dependency graph, reported as errors when exceeded.
+    pub budget: Budget,
+}
+
+/// `[workspace.metadata.dep-analysis.budget]`. Crates are counted in the lockfile.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Budget {
+    /// Distinct crates the workspace members depend on directly, of any kind.
+    pub max_direct_deps: Option<usize>,
+    /// Packages in the lockfile, the workspace members included.
+    pub max_total_crates: Option<usize>,
+    /// Packages in the lockfile that the one at `--base` doesn't have.
+    pub max_added_crates: Option<usize>,
+}
+
+/// An architecture constraint: members matching `members` (a member name or a glob over
+/// member directories relative to the workspace root, e.g. `core/*`) may not depend,
+/// directly or transitively, on any crate matching `forbid`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LayerRule {
+    pub members: String,
+    pub forbid: Vec<String>,
+    pub reason: Option<String>,
+}
+
+impl Config {
+    pub fn load(manifest: &toml::Table) -> Result<Self, Box<dyn std::error::Error>> {
+        let table = ["workspace", "package"].iter().find_map(|section| {
+            manifest
+                .get(*section)?
+                .get("metadata")?
+                .get("dep-analysis")
+                .cloned()
+        });
+        match table {
+            Some(table) => Ok(table.try_into()?),
+            None => Ok(Config::default()),
+        }
+    }
+}
@@ -0,0 +1,322 @@
+use crate::cli::Args;
+use crate::project::Project;
+use crate::report::Finding;
+use crate::serve::{fingerprint, Fingerprint};
+use cargo_metadata::{DependencyKind, PackageId};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// What the daemon keeps between requests.
+struct State {
+    /// The workspace's root manifest.
+    manifest: PathBuf,
+    /// `cargo metadata`'s view and the caches hanging off it, reloaded when a manifest,
+    /// the lockfile, a source or cargo's configuration changes; `None` while the
+    /// manifests fail to load.
+    project: Option<Project>,
+    /// The sources as they were when `project` was loaded.
+    fingerprint: Fingerprint,
+    /// When the registry responses in `project.cache` were first fetched; they are
+    /// dropped once older than the cache TTL, as the on-disk cache would drop them.
+    warmed: Instant,
+    /// The findings for `project`, once a request needed them.
+    findings: Option<Vec<Finding>>,
+}
+
+/// `daemon`: loads the project once and answers requests over a Unix socket, one JSON
+/// object per line each way. Requests are `{"method": "analyze"}`, `{"method":
+/// "dependency", "crate": NAME}`, `{"method": "why", "crate": NAME}` and `{"method":
+/// "shutdown"}`; an `id` in a request is echoed in its response, which carries either a
+/// `result` or an `error`. Findings are computed at most once per change to the sources.
+pub fn run(args: &Args, socket: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    let project = Project::load(args.manifest_path.as_deref())?;
+    let socket = socket.map(Path::to_path_buf).unwrap_or_else(|| {
+        project
+            .metadata
+            .target_directory
+            .join("dep-analysis.sock")
+            .into_std_path_buf()
+    });
+    let state = Mutex::new(State {
+        manifest: project.root().join("Cargo.toml"),
+        fingerprint: fingerprint(&project.root()),
+        project: Some(project),
+        warmed: Instant::now(),
+        findings: None,
+    });
+    listen(args, &socket, &state)
+}
+
+#[cfg(unix)]
+fn listen(
+    args: &Args,
+    socket: &Path,
+    state: &Mutex<State>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    if socket.exists() {
+        if UnixStream::connect(socket).is_ok() {
+            return Err(format!("a daemon is already listening on {}", socket.display()).into());
+        }
+        // Left behind by a daemon that didn't shut down cleanly.
+        std::fs::remove_file(socket)?;
+    }
+    if let Some(parent) = socket.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let listener = UnixListener::bind(socket)?;
+    tracing::info!("Listening on {}", socket.display());
+
+    std::thread::scope(|scope| {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    tracing::warn!("failed to accept a connection: {}", err);
+                    continue;
+                }
+            };
+            // Each client gets a thread, so an editor holding its connection open
+            // doesn't keep a CI job waiting; requests still run one at a time.
+            scope.spawn(move || {
+                let Ok(mut writer) = stream.try_clone() else {
+                    return;
+                };
+                for line in BufReader::new(stream).lines() {
+                    let Ok(line) = line else {
+                        break;
+                    };
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    let (response, shutdown) = respond(args, state, &line);
+                    if writeln!(writer, "{}", response)
+                        .and_then(|_| writer.flush())
+                        .is_err()
+                    {
+                        break;
+                    }
+                    if shutdown {
+                        tracing::info!("Shutting down");
+                        let _ = std::fs::remove_file(socket);
+                        std::process::exit(0);
+                    }
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn listen(_: &Args, _: &Path, _: &Mutex<State>) -> Result<(), Box<dyn std::error::Error>> {
+    Err("the daemon listens on a Unix socket, which this platform doesn't have".into())
+}
+
+/// Answers one request line; the flag is set when the daemon should exit.
+fn respond(args: &Args, state: &Mutex<State>, line: &str) -> (Value, bool) {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(err) => {
+            return (
+                json!({ "error": format!("invalid request: {}", err) }),
+                false,
+            )
+        }
+    };
+    let method = request["method"].as_str().unwrap_or_default();
+    let krate = request["crate"].as_str();
+    let result = match (method, krate) {
+        ("shutdown", _) => Ok(Value::Null),
+        ("analyze", _) => with_findings(args, state, |_, findings| json!({ "findings": findings })),
+        ("dependency", Some(krate)) => with_findings(args, state, |project, findings| {
+            dependency(project, findings, krate)
+        }),
+        ("why", Some(krate)) => with_project(state, |project| why(project, krate)),
+        ("dependency" | "why", None) => Err(format!("`{}` needs a `crate`", method).into()),
+        _ => Err(format!("unknown method `{}`", method).into()),
+    };
+    let mut response = match result {
+        Ok(result) => json!({ "result": result }),
+        Err(err) => json!({ "error": err.to_string() }),
+    };
+    if let Some(id) = request.get("id") {
+        response["id"] = id.clone();
+    }
+    (response, method == "shutdown")
+}
+
+/// Calls `f` with the project, reloaded first if the sources changed since it was.
+fn with_project(
+    state: &Mutex<State>,
+    f: impl FnOnce(&Project) -> Value,
+) -> Result<Value, Box<dyn std::error::Error>> {
+    let mut state = state.lock().map_err(|_| "the daemon's state is poisoned")?;
+    refresh(&mut state)?;
+    let project = state.project.as_ref().ok_or("the project failed to load")?;
+    Ok(f(project))
+}
+
+/// Calls `f` with the project and its findings, analyzing it first if they aren't known.
+fn with_findings(
+    args: &Args,
+    state: &Mutex<State>,
+    f: impl FnOnce(&Project, &[Finding]) -> Value,
+) -> Result<Value, Box<dyn std::error::Error>> {
+    let mut state = state.lock().map_err(|_| "the daemon's state is poisoned")?;
+    refresh(&mut state)?;
+    if state.findings.is_none() {
+        let project = state.project.take().ok_or("the project failed to load")?;
+        let started = Instant::now();
+        let (project, findings) = crate::collect_project(args, project, &mut |_| {})?;
+        tracing::info!("Analyzed in {:.1?}", started.elapsed());
+        state.project = Some(project);
+        state.findings = Some(findings);
+    }
+    match (&state.project, &state.findings) {
+        (Some(project), Some(findings)) => Ok(f(project, findings)),
+        _ => Err("the project failed to load".into()),
+    }
+}
+
+/// Reloads the project when the sources changed (or it failed to load last time),
+/// keeping the registry responses fetched so far unless they have outlived the cache TTL.
+fn refresh(state: &mut State) -> Result<(), Box<dyn std::error::Error>> {
+    let root = state.manifest.parent().unwrap_or(Path::new("."));
+    let sources = fingerprint(root);
+    if state.project.is_some() && sources == state.fingerprint {
+        return Ok(());
+    }
+    tracing::info!("Sources changed, reloading the project");
+    let old = state.project.take();
+    state.findings = None;
+    let mut project = Project::load(Some(&state.manifest))?;
+    match old {
+        Some(old) if state.warmed.elapsed() < project.cache.ttl => project.cache = old.cache,
+        _ => state.warmed = Instant::now(),
+    }
+    state.project = Some(project);
+    state.fingerprint = sources;
+    Ok(())
+}
+
+/// The `dependency` query: the resolved versions of `krate`, the members declaring it
+/// and how, the packages depending on it, and the findings about it.
+fn dependency(project: &Project, findings: &[Finding], krate: &str) -> Value {
+    let nodes = project.metadata.resolve.iter().flat_map(|r| &r.nodes);
+    let packages: Vec<Value> = project
+        .metadata
+        .packages
+        .iter()
+        .filter(|pkg| pkg.name == krate)
+        .map(|pkg| {
+            let features = nodes
+                .clone()
+                .find(|node| node.id == pkg.id)
+                .map(|node| node.features.clone())
+                .unwrap_or_default();
+            let dependents: Vec<String> = nodes
+                .clone()
+                .filter(|node| node.deps.iter().any(|dep| dep.pkg == pkg.id))
+                .filter_map(|node| project.package(&node.id))
+                .map(|dependent| format!("{} {}", dependent.name, dependent.version))
+                .collect();
+            json!({
+                "version": pkg.version.to_string(),
+                "source": pkg.source.as_ref().map(|source| source.repr.clone()),
+                "features": features,
+                "dependents": dependents,
+            })
+        })
+        .collect();
+    let declarations: Vec<Value> = project
+        .members()
+        .flat_map(|member| {
+            member
+                .dependencies
+                .iter()
+                .filter(|dep| dep.name == krate)
+                .map(move |dep| {
+                    json!({
+                        "member": member.name,
+                        "kind": match dep.kind {
+                            DependencyKind::Development => "dev",
+                            DependencyKind::Build => "build",
+                            _ => "normal",
+                        },
+                        "target": dep.target.as_ref().map(|target| target.to_string()),
+                        "req": dep.req.to_string(),
+                        "optional": dep.optional,
+                        "default_features": dep.uses_default_features,
+                        "features": dep.features,
+                    })
+                })
+        })
+        .collect();
+    let findings: Vec<&Finding> = findings.iter().filter(|f| f.krate == krate).collect();
+    json!({
+        "crate": krate,
+        "packages": packages,
+        "declarations": declarations,
+        "findings": findings,
+    })
+}
+
+/// The `why` query: for each member that reaches `krate`, the shortest chain of
+/// dependencies leading to each of its resolved versions.
+fn why(project: &Project, krate: &str) -> Value {
+    let Some(resolve) = &project.metadata.resolve else {
+        return json!({ "crate": krate, "paths": [] });
+    };
+    let nodes: HashMap<&PackageId, _> = resolve.nodes.iter().map(|n| (&n.id, n)).collect();
+    let mut paths = Vec::new();
+    for member in project.members() {
+        let mut parents: HashMap<&PackageId, &PackageId> = HashMap::new();
+        let mut queue = VecDeque::from([&member.id]);
+        while let Some(id) = queue.pop_front() {
+            let Some(node) = nodes.get(id) else {
+                continue;
+            };
+            for dep in &node.deps {
+                // Dev-dependencies of dependencies aren't built; only the member's are.
+                let dev_only = dep
+                    .dep_kinds
+                    .iter()
+                    .all(|k| k.kind == DependencyKind::Development);
+                if dep.pkg == member.id
+                    || parents.contains_key(&dep.pkg)
+                    || dev_only && id != &member.id
+                {
+                    continue;
+                }
+                parents.insert(&dep.pkg, id);
+                if project
+                    .package(&dep.pkg)
+                    .is_some_and(|pkg| pkg.name == krate)
+                {
+                    let mut path = vec![&dep.pkg];
+                    let mut current = &dep.pkg;
+                    while current != &member.id {
+                        current = parents[current];
+                        path.push(current);
+                    }
+                    paths.push(
+                        path.iter()
+                            .rev()
+                            .filter_map(|id| project.package(id))
+                            .map(|pkg| format!("{} {}", pkg.name, pkg.version))
+                            .collect::<Vec<_>>(),
+                    );
+                } else {
+                    queue.push_back(&dep.pkg);
+                }
+            }
+        }
+    }
+    json!({ "crate": krate, "paths": paths })
+}
@@ -0,0 +1,282 @@
+use crate::project::{declared_in, kind_table, Project};
+use crate::report::{Finding, Kind};
+use cargo_metadata::{Dependency, DependencyKind};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Flags crates declared in several tables of the same manifest (normal, dev, build,
+/// target-specific) whose version requirements or feature sets disagree.
+pub fn analyze(project: &Project) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    for member in project.members() {
+        let mut by_crate: BTreeMap<&str, Vec<&Dependency>> = BTreeMap::new();
+        for dep in &member.dependencies {
+            by_crate.entry(&dep.name).or_default().push(dep);
+        }
+
+        for (name, decls) in by_crate {
+            if decls.len() < 2 || !conflicting(&decls) {
+                continue;
+            }
+
+            let mut finding = Finding::new(Kind::DuplicateDeclaration, name)
+                .member(project, member)
+                .note(format!(
+                    "Declared in {} tables of {}'s manifest:",
+                    decls.len(),
+                    member.name
+                ));
+            for dep in &decls {
+                finding = finding.note(format!(
+                    "  [{}]: {}, features {:?}, default-features {}",
+                    declared_in(dep),
+                    dep.req,
+                    dep.features,
+                    on_off(dep.uses_default_features)
+                ));
+            }
+            findings.push(merged(project, finding, name, &decls));
+        }
+    }
+
+    findings
+}
+
+fn conflicting(decls: &[&Dependency]) -> bool {
+    let first = decls[0];
+    let features = |dep: &Dependency| dep.features.iter().cloned().collect::<BTreeSet<_>>();
+    decls.iter().any(|dep| {
+        dep.req != first.req
+            || dep.uses_default_features != first.uses_default_features
+            || features(dep) != features(first)
+    })
+}
+
+/// Describes what cargo will actually build. Resolver 1 unifies every declaration into
+/// one set of features. From resolver 2 on, build scripts get their own build for the host,
+/// dev-dependencies only join in tests, examples and benches, and a target-specific table
+/// only applies on its platform, so each of those builds is described on its own.
+fn merged(project: &Project, finding: Finding, name: &str, decls: &[&Dependency]) -> Finding {
+    let kinds: BTreeSet<&str> = decls.iter().map(|dep| kind_table(dep.kind)).collect();
+    let (resolver, _) = crate::resolver::effective_resolver(project);
+    let mut finding = if resolver == "1" {
+        finding.note(format!(
+            "Effective (resolver 1 unifies every table): {}",
+            effective(project, name, decls)
+        ))
+    } else {
+        let builds: [(&str, &[DependencyKind]); 3] = [
+            ("normal builds", &[DependencyKind::Normal]),
+            (
+                "tests, examples and benches",
+                &[DependencyKind::Normal, DependencyKind::Development],
+            ),
+            ("build scripts", &[DependencyKind::Build]),
+        ];
+        let mut finding = finding;
+        for (build, included) in builds {
+            let in_build: Vec<&Dependency> = decls
+                .iter()
+                .copied()
+                .filter(|dep| included.contains(&dep.kind))
+                .collect();
+            // Tests only differ from normal builds through dev-dependencies.
+            if in_build.is_empty()
+                || (included.contains(&DependencyKind::Development)
+                    && in_build
+                        .iter()
+                        .all(|dep| dep.kind != DependencyKind::Development))
+            {
+                continue;
+            }
+            let targets: BTreeSet<String> = in_build
+                .iter()
+                .filter_map(|dep| dep.target.as_ref().map(|target| target.to_string()))
+                .collect();
+            let everywhere: Vec<&Dependency> = in_build
+                .iter()
+                .copied()
+                .filter(|dep| dep.target.is_none())
+                .collect();
+            if !everywhere.is_empty() {
+                let platforms = if targets.is_empty() {
+                    String::new()
+                } else {
+                    " on other platforms".to_string()
+                };
+                finding = finding.note(format!(
+                    "Effective in {}{}: {}",
+                    build,
+                    platforms,
+                    effective(project, name, &everywhere)
+                ));
+            }
+            for target in targets {
+                let on_target: Vec<&Dependency> = in_build
+                    .iter()
+                    .copied()
+                    .filter(|dep| {
+                        dep.target
+                            .as_ref()
+                            .is_none_or(|platform| platform.to_string() == target)
+                    })
+                    .collect();
+                finding = finding.note(format!(
+                    "Effective in {} for {}: {}",
+                    build,
+                    target,
+                    effective(project, name, &on_target)
+                ));
+            }
+        }
+        finding
+    };
+    if kinds.len() == 1 {
+        finding =
+            finding.note("Cargo unifies these declarations; consider declaring the crate once");
+    } else if decls.iter().any(|dep| dep.req != decls[0].req) {
+        finding = finding.note(format!(
+            "The tables ({}) can't be merged, but their version requirements can agree",
+            kinds.into_iter().collect::<Vec<_>>().join(", ")
+        ));
+    }
+    finding
+}
+
+/// The version, features and default features a build with `decls` gets: one version
+/// satisfying every requirement and the union of the requested features.
+fn effective(project: &Project, name: &str, decls: &[&Dependency]) -> String {
+    let features: Vec<&String> = decls
+        .iter()
+        .flat_map(|dep| &dep.features)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let default_features = decls.iter().any(|dep| dep.uses_default_features);
+    let resolved: Vec<String> = project
+        .metadata
+        .packages
+        .iter()
+        .filter(|pkg| pkg.name == name && decls.iter().any(|dep| dep.req.matches(&pkg.version)))
+        .map(|pkg| pkg.version.to_string())
+        .collect();
+    let version = if resolved.is_empty() {
+        "no resolved version".to_string()
+    } else {
+        resolved.join(", ")
+    };
+    format!(
+        "{} {}, features {:?}, default-features {}",
+        name,
+        version,
+        features,
+        on_off(default_features)
+    )
+}
+
+fn on_off(enabled: bool) -> &'static str {
+    if enabled {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::fixture;
+
+    fn notes(edition: &str, tables: &str) -> Vec<String> {
+        let manifest = format!(
+            "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"{}\"\n\n{}",
+            edition, tables
+        );
+        let (_dir, project) = fixture(&[
+            ("Cargo.toml", &manifest),
+            ("src/lib.rs", ""),
+            (
+                "util/Cargo.toml",
+                "[package]\nname = \"util\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n\
+                 [features]\na = []\nb = []\n",
+            ),
+            ("util/src/lib.rs", ""),
+        ]);
+        let mut findings = analyze(&project);
+        assert_eq!(findings.len(), 1);
+        findings.remove(0).notes
+    }
+
+    #[test]
+    fn describes_each_build_separately() {
+        let notes = notes(
+            "2021",
+            r#"[dependencies]
+util = { path = "util", features = ["a"] }
+
+[dev-dependencies]
+util = { path = "util", features = ["b"] }
+
+[build-dependencies]
+util = { path = "util" }
+"#,
+        );
+        let effective: Vec<&str> = notes
+            .iter()
+            .filter(|note| note.starts_with("Effective"))
+            .map(String::as_str)
+            .collect();
+        assert_eq!(
+            effective,
+            [
+                r#"Effective in normal builds: util 0.1.0, features ["a"], default-features on"#,
+                r#"Effective in tests, examples and benches: util 0.1.0, features ["a", "b"], default-features on"#,
+                r#"Effective in build scripts: util 0.1.0, features [], default-features on"#,
+            ]
+        );
+        assert!(!notes
+            .iter()
+            .any(|note| note.contains("declaring the crate once")));
+    }
+
+    #[test]
+    fn describes_target_tables_per_platform() {
+        let notes = notes(
+            "2021",
+            r#"[dependencies]
+util = { path = "util", features = ["a"] }
+
+[target.'cfg(windows)'.dependencies]
+util = { path = "util", features = ["b"] }
+"#,
+        );
+        assert!(notes.contains(
+            &r#"Effective in normal builds on other platforms: util 0.1.0, features ["a"], default-features on"#
+                .to_string()
+        ));
+        assert!(notes.contains(
+            &r#"Effective in normal builds for cfg(windows): util 0.1.0, features ["a", "b"], default-features on"#
+                .to_string()
+        ));
+        assert!(notes
+            .iter()
+            .any(|note| note.contains("declaring the crate once")));
+    }
+
+    #[test]
+    fn resolver_1_unifies_every_table() {
+        let notes = notes(
+            "2018",
+            r#"[dependencies]
+util = { path = "util", features = ["a"] }
+
+[build-dependencies]
+util = { path = "util", features = ["b"] }
+"#,
+        );
+        assert!(notes.contains(
+            &r#"Effective (resolver 1 unifies every table): util 0.1.0, features ["a", "b"], default-features on"#
+                .to_string()
+        ));
+    }
+}
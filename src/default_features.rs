@@ -0,0 +1,305 @@
+use crate::cli::Args;
+use crate::features;
+use crate::project::Project;
+use cargo_metadata::{Node, Package, PackageId};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// What the default features of one direct dependency bring into the build.
+struct Blame<'a> {
+    package: &'a Package,
+    /// The members declaring it with default features.
+    members: BTreeSet<&'a str>,
+    /// Other packages in the graph keeping its default features on regardless.
+    kept_by: BTreeSet<String>,
+    /// The crates that leave the build with its default features off, each with the
+    /// default features that bring it in.
+    dropped: BTreeMap<&'a PackageId, BTreeSet<String>>,
+}
+
+/// `default-features`: for each transitive crate present only because a direct
+/// dependency's default features enable an optional dependency, which dependency and
+/// feature that is; and for each direct dependency, how many crates turning off its
+/// default features would drop. Features the members (or other dependents) ask for
+/// explicitly stay on, so only what the defaults alone add is counted.
+pub fn run(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let project = Project::load(args.manifest_path.as_deref())?;
+    let nodes: BTreeMap<&PackageId, &Node> = project
+        .metadata
+        .resolve
+        .iter()
+        .flat_map(|resolve| &resolve.nodes)
+        .map(|node| (&node.id, node))
+        .collect();
+
+    let mut blames: BTreeMap<&PackageId, Blame> = BTreeMap::new();
+    for member in project.members() {
+        let Some(node) = nodes.get(&member.id) else {
+            continue;
+        };
+        for dep in member
+            .dependencies
+            .iter()
+            .filter(|d| d.uses_default_features)
+        {
+            let resolved = node
+                .deps
+                .iter()
+                .filter_map(|d| project.package(&d.pkg))
+                .find(|pkg| pkg.name == dep.name && dep.req.matches(&pkg.version));
+            let Some(package) = resolved else {
+                continue;
+            };
+            if project.metadata.workspace_members.contains(&package.id) {
+                continue;
+            }
+            blames
+                .entry(&package.id)
+                .or_insert_with(|| Blame {
+                    package,
+                    members: BTreeSet::new(),
+                    kept_by: BTreeSet::new(),
+                    dropped: BTreeMap::new(),
+                })
+                .members
+                .insert(&member.name);
+        }
+    }
+    for blame in blames.values_mut() {
+        blame_defaults(&project, &nodes, blame);
+    }
+
+    println!("\nCrates present only because of a default feature:");
+    let mut crates: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for blame in blames.values() {
+        for (id, via) in &blame.dropped {
+            let Some(pkg) = project.package(id) else {
+                continue;
+            };
+            let via: Vec<String> = via.iter().map(|f| format!("`{}`", f)).collect();
+            let name = format!("{} {}", pkg.name, pkg.version);
+            crates.entry(name).or_default().push(format!(
+                "{} {} ({})",
+                blame.package.name,
+                blame.package.version,
+                via.join(", ")
+            ));
+        }
+    }
+    if crates.is_empty() {
+        println!("  (none)");
+    }
+    let width = crates.keys().map(|k| k.chars().count()).max().unwrap_or(0);
+    for (krate, via) in &crates {
+        println!(
+            "  {:<width$}  default features of {}",
+            krate,
+            via.join("; "),
+            width = width
+        );
+    }
+
+    let mut ranked: Vec<&Blame> = blames.values().filter(|b| !b.dropped.is_empty()).collect();
+    ranked.sort_by(|a, b| {
+        b.dropped
+            .len()
+            .cmp(&a.dropped.len())
+            .then(a.package.name.cmp(&b.package.name))
+    });
+    if !ranked.is_empty() {
+        println!("\nDirect dependencies whose default features add crates:");
+    }
+    for blame in ranked {
+        let names: Vec<String> = blame
+            .dropped
+            .keys()
+            .filter_map(|id| project.package(id))
+            .map(|pkg| format!("{} {}", pkg.name, pkg.version))
+            .collect();
+        println!(
+            "  Turn off default features of {} {} ({}) to drop {} crate{}: {}",
+            blame.package.name,
+            blame.package.version,
+            blame.members.iter().copied().collect::<Vec<_>>().join(", "),
+            names.len(),
+            if names.len() == 1 { "" } else { "s" },
+            names.join(", ")
+        );
+    }
+
+    let kept: Vec<&Blame> = blames.values().filter(|b| !b.kept_by.is_empty()).collect();
+    if !kept.is_empty() {
+        println!("\nDefault features other dependents keep on anyway:");
+    }
+    for blame in kept {
+        println!(
+            "  {} {}: {}",
+            blame.package.name,
+            blame.package.version,
+            blame.kept_by.iter().cloned().collect::<Vec<_>>().join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Works out what turning off the default features of `blame.package` in the members
+/// declaring it would drop, unless another package keeps them on.
+fn blame_defaults<'a>(
+    project: &'a Project,
+    nodes: &BTreeMap<&'a PackageId, &'a Node>,
+    blame: &mut Blame<'a>,
+) {
+    let package = blame.package;
+    let Some(node) = nodes.get(&package.id) else {
+        return;
+    };
+    if package.features.get("default").is_none_or(Vec::is_empty) {
+        return;
+    }
+
+    // The features every dependent asks for, on its entry or by forwarding its own
+    // enabled features, and whether it keeps the defaults.
+    let mut requested: Vec<String> = Vec::new();
+    for dependent in nodes.values() {
+        if !dependent.deps.iter().any(|d| d.pkg == package.id) {
+            continue;
+        }
+        let Some(pkg) = project.package(&dependent.id) else {
+            continue;
+        };
+        let member = project.metadata.workspace_members.contains(&pkg.id);
+        for dep in pkg
+            .dependencies
+            .iter()
+            .filter(|dep| dep.name == package.name && dep.req.matches(&package.version))
+        {
+            requested.extend(dep.features.iter().cloned());
+            if dep.uses_default_features && !member {
+                blame
+                    .kept_by
+                    .insert(format!("{} {}", pkg.name, pkg.version));
+            }
+            let key = dep.rename.as_deref().unwrap_or(&dep.name);
+            for enabled in &dependent.features {
+                for value in pkg.features.get(enabled.as_str()).into_iter().flatten() {
+                    if let Some((name, feature)) = value.split_once('/') {
+                        if name.trim_end_matches('?') == key {
+                            requested.push(feature.to_string());
+                        }
+                    }
+                }
+            }
+        }
+    }
+    if !blame.kept_by.is_empty() {
+        return;
+    }
+
+    let kept = features::enabled(package, &requested, false, true);
+    let defaults = features::enabled(package, &[], false, false);
+    let enabled: BTreeSet<&str> = node.features.iter().map(|f| f.as_str()).collect();
+    let lost: Vec<&String> = defaults
+        .iter()
+        .filter(|f| enabled.contains(f.as_str()) && !kept.contains(*f))
+        .collect();
+    let still_optional: BTreeSet<&str> =
+        kept.iter().flat_map(|f| enabled_deps(package, f)).collect();
+
+    // The edges to optional dependencies only the lost features enable, by feature.
+    let mut edges: BTreeMap<(&PackageId, &PackageId), BTreeSet<String>> = BTreeMap::new();
+    for feature in lost {
+        for key in enabled_deps(package, feature) {
+            if still_optional.contains(key) {
+                continue;
+            }
+            let declared: Vec<_> = package
+                .dependencies
+                .iter()
+                .filter(|d| d.rename.as_deref().unwrap_or(&d.name) == key)
+                .collect();
+            // Also a required dependency of another kind: it stays either way.
+            if declared.is_empty() || declared.iter().any(|d| !d.optional) {
+                continue;
+            }
+            for dep in &node.deps {
+                let Some(pkg) = project.package(&dep.pkg) else {
+                    continue;
+                };
+                if declared
+                    .iter()
+                    .any(|d| d.name == pkg.name && d.req.matches(&pkg.version))
+                {
+                    edges
+                        .entry((&package.id, &dep.pkg))
+                        .or_default()
+                        .insert(feature.clone());
+                }
+            }
+        }
+    }
+    if edges.is_empty() {
+        return;
+    }
+
+    let removed: BTreeSet<(&PackageId, &PackageId)> = edges.keys().copied().collect();
+    let dropped: BTreeSet<&PackageId> = crate::impact::dropped_without(project, &removed)
+        .into_iter()
+        .map(|pkg| &pkg.id)
+        .collect();
+    for ((_, to), features) in &edges {
+        for id in reachable_from(nodes, to) {
+            if let Some(&id) = dropped.get(id) {
+                blame
+                    .dropped
+                    .entry(id)
+                    .or_default()
+                    .extend(features.iter().cloned());
+            }
+        }
+    }
+}
+
+/// The optional dependencies (by key) the values of `feature` enable directly.
+fn enabled_deps<'a>(package: &'a Package, feature: &str) -> Vec<&'a str> {
+    package
+        .features
+        .get(feature)
+        .into_iter()
+        .flatten()
+        .filter_map(|value| {
+            if let Some(key) = value.strip_prefix("dep:") {
+                return Some(key);
+            }
+            match value.split_once('/') {
+                Some((key, _)) if !key.ends_with('?') => Some(key),
+                Some(_) => None,
+                // Through its implicit feature, which is blamed instead.
+                None if package.features.contains_key(value) => None,
+                None => package
+                    .dependencies
+                    .iter()
+                    .any(|d| d.optional && d.rename.as_deref().unwrap_or(&d.name) == value)
+                    .then_some(value.as_str()),
+            }
+        })
+        .collect()
+}
+
+fn reachable_from<'a>(
+    nodes: &BTreeMap<&'a PackageId, &'a Node>,
+    from: &'a PackageId,
+) -> BTreeSet<&'a PackageId> {
+    let mut seen = BTreeSet::new();
+    let mut pending = vec![from];
+    while let Some(id) = pending.pop() {
+        if !seen.insert(id) {
+            continue;
+        }
+        pending.extend(
+            nodes
+                .get(id)
+                .into_iter()
+                .flat_map(|n| n.deps.iter().map(|d| &d.pkg)),
+        );
+    }
+    seen
+}
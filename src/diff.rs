@@ -0,0 +1,172 @@
+use crate::cli::Args;
+use crate::project::Project;
+use crate::report::{Finding, Kind};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// `diff <base> [<head>]` / `diff --against <ref>`: analyzes each revision in a temporary
+/// worktree (the working tree itself when `head` is omitted) and prints the findings that
+/// were added, removed or changed between them.
+pub fn run(
+    args: &Args,
+    base: Option<&str>,
+    head: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let base =
+        base.ok_or("`diff` needs a base revision, e.g. `diff main` or `diff --against main`")?;
+
+    let project = Project::load(args.manifest_path.as_deref())?;
+    let before = findings_at(args, &project, base)?;
+    let after = match head {
+        Some(head) => findings_at(args, &project, head)?,
+        None => crate::collect(args, Some(&project.root().join("Cargo.toml")))?.1,
+    };
+
+    print(base, head.unwrap_or("working tree"), &before, &after);
+    Ok(())
+}
+
+/// The findings of `project` as of git revision `rev`.
+pub fn findings_at(
+    args: &Args,
+    project: &Project,
+    rev: &str,
+) -> Result<Vec<Finding>, Box<dyn std::error::Error>> {
+    let toplevel = PathBuf::from(git(&project.root(), &["rev-parse", "--show-toplevel"])?);
+    let manifest = project.root().join("Cargo.toml");
+    let manifest = manifest.strip_prefix(&toplevel).unwrap_or(&manifest);
+    at_revision(args, &toplevel, manifest, rev)
+}
+
+/// Checks `rev` out into a temporary worktree, so the user's checkout is never touched,
+/// and collects its findings.
+fn at_revision(
+    args: &Args,
+    toplevel: &Path,
+    manifest: &Path,
+    rev: &str,
+) -> Result<Vec<Finding>, Box<dyn std::error::Error>> {
+    let dir = tempfile::Builder::new()
+        .prefix("cargo-dep-analysis-")
+        .tempdir()?;
+    let tree = dir.path().join("tree");
+    let tree_arg = tree.to_string_lossy();
+    git(
+        toplevel,
+        &["worktree", "add", "--quiet", "--detach", &tree_arg, rev],
+    )?;
+
+    let findings = crate::collect(args, Some(&tree.join(manifest)));
+    git(toplevel, &["worktree", "remove", "--force", &tree_arg])?;
+    Ok(findings?.1)
+}
+
+pub fn git(dir: &Path, args: &[&str]) -> Result<String, Box<dyn std::error::Error>> {
+    tracing::debug!("running git {}", args.join(" "));
+    let output = Command::new("git").args(args).current_dir(dir).output()?;
+    if !output.status.success() {
+        return Err(format!(
+            "`git {}` failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A finding that differs between two runs, with its notes on either side.
+pub struct Change {
+    pub status: Status,
+    pub kind: Kind,
+    pub krate: String,
+    pub before: Vec<String>,
+    pub after: Vec<String>,
+}
+
+/// Findings are matched by kind and crate; a match whose details differ is "changed".
+pub fn compare(before: &[Finding], after: &[Finding]) -> Vec<Change> {
+    let group = |findings: &[Finding]| {
+        let mut grouped: BTreeMap<(Kind, String), Vec<String>> = BTreeMap::new();
+        for finding in findings {
+            grouped
+                .entry((finding.kind, finding.krate.clone()))
+                .or_default()
+                .extend(finding.notes.iter().cloned());
+        }
+        grouped
+    };
+    let mut before = group(before);
+    let after = group(after);
+
+    let mut changes = Vec::new();
+    for ((kind, krate), notes) in after {
+        let (status, old) = match before.remove(&(kind, krate.clone())) {
+            None => (Status::Added, Vec::new()),
+            Some(old) if old != notes => (Status::Changed, old),
+            Some(_) => continue,
+        };
+        changes.push(Change {
+            status,
+            kind,
+            krate,
+            before: old,
+            after: notes,
+        });
+    }
+    for ((kind, krate), notes) in before {
+        changes.push(Change {
+            status: Status::Removed,
+            kind,
+            krate,
+            before: notes,
+            after: Vec::new(),
+        });
+    }
+    changes
+}
+
+fn print(base: &str, head: &str, before: &[Finding], after: &[Finding]) {
+    let changes = compare(before, after);
+
+    println!("\nDependency Diff ({}..{}):", base, head);
+    println!("==========================");
+
+    for change in &changes {
+        match change.status {
+            Status::Added => {
+                println!("\n+ {} ({})", change.krate, change.kind.label());
+                for note in &change.after {
+                    println!("    {}", note);
+                }
+            }
+            Status::Removed => {
+                println!("\n- {} ({})", change.krate, change.kind.label());
+                for note in &change.before {
+                    println!("    {}", note);
+                }
+            }
+            Status::Changed => {
+                println!("\n~ {} ({})", change.krate, change.kind.label());
+                for note in change.before.iter().filter(|n| !change.after.contains(n)) {
+                    println!("  - {}", note);
+                }
+                for note in change.after.iter().filter(|n| !change.before.contains(n)) {
+                    println!("  + {}", note);
+                }
+            }
+        }
+    }
+
+    if changes.is_empty() {
+        println!("\nNo findings changed");
+    }
+}
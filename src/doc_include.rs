@@ -0,0 +1,95 @@
+use regex::Regex;
+use std::path::Path;
+
+/// The Rust code blocks of the markdown files a source includes as documentation with
+/// `#[doc = include_str!("../README.md")]` (or `#![doc = ...]`). They compile as doc
+/// tests, so the crates they use are dependencies too.
+pub fn code_blocks(source: &Path, content: &str) -> Vec<String> {
+    let include =
+        Regex::new(r#"#!?\[\s*doc\s*=\s*include_str!\s*\(\s*"([^"]+)"\s*\)\s*\]"#).unwrap();
+    let dir = source.parent().unwrap_or(Path::new(""));
+    include
+        .captures_iter(content)
+        .filter_map(|cap| {
+            let path = dir.join(&cap[1]);
+            match std::fs::read_to_string(&path) {
+                Ok(markdown) => Some(rust_fences(&markdown)),
+                Err(err) => {
+                    tracing::debug!("can't read {}: {}", path.display(), err);
+                    None
+                }
+            }
+        })
+        .flatten()
+        .collect()
+}
+
+/// The fenced code blocks rustdoc compiles: untagged ones and those tagged `rust`,
+/// `no_run`, `should_panic`, `compile_fail` or `editionYYYY`, but not `ignore`, `text` or
+/// other languages.
+fn rust_fences(markdown: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Option<(String, bool)> = None;
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        let fence = trimmed.starts_with("```") || trimmed.starts_with("~~~");
+        match &mut current {
+            None if fence => {
+                let info = trimmed.trim_start_matches(['`', '~']).trim();
+                let compiled = info.split([',', ' ']).filter(|t| !t.is_empty()).all(|tag| {
+                    matches!(tag, "rust" | "no_run" | "should_panic" | "compile_fail")
+                        || tag.starts_with("edition")
+                });
+                current = Some((String::new(), compiled));
+            }
+            Some((code, compiled)) if fence => {
+                if *compiled {
+                    blocks.push(std::mem::take(code));
+                }
+                current = None;
+            }
+            Some((code, _)) => {
+                // Hidden lines (`# use foo::Bar;`) compile like the others.
+                let line = trimmed.strip_prefix("# ").unwrap_or(line);
+                code.push_str(line);
+                code.push('\n');
+            }
+            None => {}
+        }
+    }
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const README: &str = "# Title\n\n\
+                          ```rust\nuse a::X;\n```\n\n\
+                          ```text\nb::c\n```\n\n\
+                          ```ignore\nuse c::Z;\n```\n\n\
+                          ```\n# use d::Y;\nd::go();\n```\n\n\
+                          ~~~no_run, edition2021\ne::run();\n~~~\n";
+
+    #[test]
+    fn compiled_fences_of_included_readmes_are_returned() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("README.md"), README).unwrap();
+        std::fs::create_dir(dir.path().join("src")).unwrap();
+        let source = dir.path().join("src/lib.rs");
+
+        let blocks = code_blocks(&source, "#![doc = include_str!(\"../README.md\")]\n");
+        assert_eq!(
+            blocks,
+            ["use a::X;\n", "use d::Y;\nd::go();\n", "e::run();\n"]
+        );
+    }
+
+    #[test]
+    fn sources_without_readable_includes_have_no_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("src/lib.rs");
+        assert!(code_blocks(&source, "//! Docs.\nfn f() {}\n").is_empty());
+        assert!(code_blocks(&source, "#![doc = include_str!(\"../MISSING.md\")]\n").is_empty());
+    }
+}
@@ -0,0 +1,471 @@
+use crate::report::Kind;
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// The long-form documentation of one finding kind.
+struct Explanation {
+    summary: &'static str,
+    why: &'static str,
+    false_positives: &'static str,
+    resolve: &'static str,
+}
+
+/// `explain [<rule>]`: documents a finding kind, found by rule id (`unused`) or report
+/// label (`POTENTIALLY UNUSED`), or lists every rule.
+pub fn run(rule: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut out = String::new();
+    match rule {
+        None => {
+            out.push_str("Rules (run `cargo dep-analysis explain <rule>` for details):\n\n");
+            for kind in Kind::ALL {
+                writeln!(
+                    out,
+                    "  {:<30} {:<8} {}",
+                    kind.id(),
+                    kind.default_severity(),
+                    kind.label()
+                )?;
+            }
+        }
+        Some(rule) => {
+            let kind = Kind::ALL
+                .into_iter()
+                .find(|kind| kind.id() == rule || kind.label().eq_ignore_ascii_case(rule))
+                .ok_or_else(|| {
+                    format!(
+                        "unknown rule `{}`; run `cargo dep-analysis explain` to list them",
+                        rule
+                    )
+                })?;
+            writeln!(out, "{} ({})", kind.id(), kind.label())?;
+            writeln!(out, "Default severity: {}\n", kind.default_severity())?;
+            writeln!(out, "{}", text(kind))?;
+            writeln!(
+                out,
+                "\nTo change how much it matters, set its severity in the configuration:\n\n    [workspace.metadata.dep-analysis.severity]\n    {} = \"info\"",
+                kind.id()
+            )?;
+        }
+    }
+
+    // Piped into `head` or a pager that quits early, the rest has nowhere to go.
+    let mut stdout = std::io::stdout().lock();
+    match stdout
+        .write_all(out.as_bytes())
+        .and_then(|()| stdout.flush())
+    {
+        Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+/// The documentation of `kind`, as `explain` prints it.
+pub fn text(kind: Kind) -> String {
+    let explanation = explanation(kind);
+    format!(
+        "{}\n\nWhy it matters:\n{}\n\nCommon false positives:\n{}\n\nResolving or silencing it:\n{}",
+        explanation.summary, explanation.why, explanation.false_positives, explanation.resolve
+    )
+}
+
+/// How to resolve a finding of `kind`, for the removal plan.
+pub fn resolution(kind: Kind) -> &'static str {
+    explanation(kind).resolve
+}
+
+fn explanation(kind: Kind) -> Explanation {
+    match kind {
+        Kind::Unused => Explanation {
+            summary: "A dependency of a workspace member that none of the member's code \
+                      references: no `use`, path, macro call, derive or `extern crate` \
+                      names it. [dependencies] entries are looked for in the library and \
+                      binaries (everything under `src` and their entry points elsewhere), \
+                      [dev-dependencies] entries in the tests, examples and benches too. \
+                      Entries used only by `#[cfg(test)]` code, examples, benches, or code \
+                      behind a feature or platform have their own rules (test-only, \
+                      example-only, bench-only, feature-gated, platform-only).",
+            why: "Unused dependencies still download, compile and link, slowing every clean \
+                  build and widening the supply-chain surface for no benefit.",
+            false_positives: "The scan is textual. Dependencies used only from build.rs, \
+                              through re-exports or generated code, or purely for their side \
+                              effects (e.g. linking a native library, enabling a feature of \
+                              another crate) are reported.",
+            resolve: "Remove the dependency from Cargo.toml, or move it to the table it \
+                      belongs in ([build-dependencies] for build.rs). To keep one, list it \
+                      in `[package.metadata.cargo-udeps.ignore]` or \
+                      `[package.metadata.cargo-machete] ignored`.",
+        },
+        Kind::UndeclaredDependency => Explanation {
+            summary: "A member's code names a crate its manifest doesn't declare, one that \
+                      is only in the graph as a dependency of another package or as another \
+                      member.",
+            why: "Cargo only puts declared dependencies in scope, so that code builds only \
+                  while it stays behind a feature or platform nobody builds, and fails the \
+                  day it is enabled; whichever package brings the crate in decides its \
+                  version, and can drop it in any update.",
+            false_positives: "Local modules or imports named like a crate in the graph that \
+                              the scan doesn't see, such as ones a macro generates.",
+            resolve: "Declare the crate in the member's manifest, with the version the graph \
+                      resolves to; `--fix` does.",
+        },
+        Kind::FeatureGated => Explanation {
+            summary: "A dependency of a workspace member is only used by code behind \
+                      features this analysis doesn't enable, such as `#[cfg(feature = \"serde\")]` \
+                      items or `#[cfg_attr(feature = \"serde\", derive(Serialize))]`; the \
+                      finding names the features and the ones enabling the dependency.",
+            why: "Unless the dependency is optional and enabled by those features, every build \
+                  compiles it even though only builds with the features use it.",
+            false_positives: "Optional dependencies enabled by the gating feature aren't \
+                              reported. Uses the scan can't tie to a crate (a derive imported \
+                              through a glob) may leave a dependency looking feature-only.",
+            resolve: "Make the dependency optional and enable it from the feature \
+                      (`serde = [\"dep:serde\"]`), or analyze with `--features` to count \
+                      the gated code.",
+        },
+        Kind::FeatureUnusedDependency => Explanation {
+            summary: "A feature enables an optional dependency, but no code under that \
+                      feature (or the features it enables) uses it; the dependency is used \
+                      under other features.",
+            why: "Turning the feature on builds a crate it doesn't need, usually a leftover \
+                  of code that moved behind another feature.",
+            false_positives: "Features enabling a dependency only to turn on its features \
+                              for another one, and uses the textual scan misses.",
+            resolve: "Remove the dependency from the feature's list, or enable it from the \
+                      features whose code uses it.",
+        },
+        Kind::FeatureMissingDependency => Explanation {
+            summary: "Code under `#[cfg(feature = \"...\")]` uses an optional dependency \
+                      the feature doesn't enable.",
+            why: "Builds enabling only that feature fail to compile; it works today only \
+                  because the feature is always combined with one that enables the \
+                  dependency.",
+            false_positives: "Gates combined with features that do enable it in ways the \
+                              scan reads as alternatives.",
+            resolve: "Add `dep:<name>` (or a feature enabling it) to the feature. \
+                      `check-features` confirms the build.",
+        },
+        Kind::UnknownFeature => Explanation {
+            summary: "A `cfg(feature = \"...\")` predicate, in `#[cfg]`, `#[cfg_attr]` or \
+                      `cfg!`, names a feature the package's [features] table doesn't have.",
+            why: "An unknown feature is simply never enabled: a typo compiles without a \
+                  word and turns the code under it off for every build, tests included.",
+            false_positives: "Features passed to rustc by hand (`--cfg 'feature=\"x\"'`) \
+                              instead of through cargo.",
+            resolve: "Fix the name (the finding suggests the closest feature), or add the \
+                      feature to [features].",
+        },
+        Kind::FeatureTrim => Explanation {
+            summary: "A member enables features of a registry dependency, its defaults or \
+                      ones it lists, that the workspace builds without: with \
+                      `--verify-features`, each is removed in a scratch copy and \
+                      `cargo check --all-targets` still passes.",
+            why: "Every enabled feature is compiled, often with optional dependencies of \
+                  its own, in every build of the member and of the crates depending on it.",
+            false_positives: "Features only needed on other platforms, with other feature \
+                              flags of the member, or for behavior rather than for \
+                              compiling (a runtime backend, `std` for error types); the \
+                              check proves it builds, not that it still works.",
+            resolve: "Set `default-features = false` on the entry, or remove the feature \
+                      from its `features` list, then run the tests.",
+        },
+        Kind::RedundantFeature => Explanation {
+            summary: "A feature's list enables something it already enables: a value \
+                      listed twice, or one another value of the list enables through the \
+                      features it leads to (`x = [\"y\", \"z\"]` where `y = [\"z\"]`).",
+            why: "The extra entries change nothing, but they make the feature graph harder \
+                  to read and keep enabling the value after the feature implying it no \
+                  longer does.",
+            false_positives: "An entry kept on purpose, so the feature still enables it if \
+                              the other one stops doing so.",
+            resolve: "Replace the list with the minimized one the finding gives; `--fix` \
+                      does.",
+        },
+        Kind::GateableProcMacro => Explanation {
+            summary: "A library member always builds a proc-macro, its own dependency or \
+                      one a feature on a dependency turns on (`serde/derive`), but uses it \
+                      in only a few of its source files.",
+            why: "Proc-macros and what they need (syn, quote, proc-macro2) are compiled for \
+                  the host before anything using them, in every consumer's build. Behind an \
+                  optional feature, consumers that don't need the derived impls skip them.",
+            false_positives: "Derives every consumer needs anyway, and crates whose other \
+                              dependencies build the same macros regardless; the savings \
+                              are estimated from source size, not measured.",
+            resolve: "Add a feature enabling the macros (`serde-derive = \
+                      [\"serde/derive\"]`, or `dep:` for an optional proc-macro dependency) \
+                      and gate the uses: `#[cfg_attr(feature = \"serde-derive\", \
+                      derive(Serialize))]`.",
+        },
+        Kind::PlatformOnly => Explanation {
+            summary: "A [dependencies] entry is only used by code compiled for some \
+                      platforms, behind `#[cfg(windows)]`, `#[cfg(unix)]`, \
+                      `#[cfg(target_os = \"...\")]` and the like.",
+            why: "Normal dependencies are built on every platform; a platform-specific \
+                  table keeps them out of the other platforms' builds and lockfile checks.",
+            false_positives: "Uses the textual scan misses in unconditional code.",
+            resolve: "Move the entry to the `[target.'cfg(...)'.dependencies]` table the \
+                      finding names; `--fix` does.",
+        },
+        Kind::TestOnly => Explanation {
+            summary: "A [dependencies] entry of a workspace member is only used inside \
+                      `#[cfg(test)]` code (unit test modules under src), never by the \
+                      library or binaries themselves.",
+            why: "Normal dependencies are built by everyone depending on the package; test \
+                  helpers belong in [dev-dependencies], which only the package's own tests \
+                  build.",
+            false_positives: "Uses the textual scan misses outside test code, such as macro \
+                              expansions the crate's users trigger.",
+            resolve: "Run with `--fix` to move the entry to [dev-dependencies].",
+        },
+        Kind::ExampleOnly => Explanation {
+            summary: "A [dependencies] entry of a workspace member is only used by its \
+                      examples, never by the library or binaries.",
+            why: "Everyone depending on the package builds its normal dependencies, even \
+                  though only the examples need this one.",
+            false_positives: "Uses the textual scan misses in the library itself.",
+            resolve: "Move it to [dev-dependencies], which examples can use, or make it \
+                      optional behind a feature and list that feature in the examples' \
+                      `required-features`.",
+        },
+        Kind::BenchOnly => Explanation {
+            summary: "A [dependencies] entry of a workspace member is only used by its \
+                      benches, typically a framework such as criterion or iai.",
+            why: "Everyone depending on the package builds its normal dependencies, and bench \
+                  frameworks are large.",
+            false_positives: "Uses the textual scan misses in the library itself.",
+            resolve: "Move it to [dev-dependencies], which benches can use.",
+        },
+        Kind::BenchHarness => Explanation {
+            summary: "A bench target uses criterion, iai or divan but keeps the default \
+                      libtest harness.",
+            why: "These frameworks define `main` themselves; with the harness on, `cargo \
+                  bench` fails to build the target or runs libtest instead of the framework.",
+            false_positives: "None known.",
+            resolve: "Declare the target with `[[bench]] name = \"...\"` and \
+                      `harness = false`.",
+        },
+        Kind::RedundantExternCrate => Explanation {
+            summary: "A package on edition 2018 or later still declares a dependency with \
+                      `extern crate`.",
+            why: "Since edition 2018 every dependency is in scope through the extern prelude; \
+                  the declaration is leftover noise from a migration.",
+            false_positives: "`#[macro_use]` and other attributed declarations, renames \
+                              such as `extern crate self as name`, `pub` re-exports, sysroot \
+                              crates (`alloc`, `proc_macro`) and declarations the same file \
+                              reaches as `crate::name::...` or `self::name::...` aren't \
+                              reported. Other modules of the crate can still use such a path \
+                              to a declaration at the crate root.",
+            resolve: "Delete the declaration; --fix does.",
+        },
+        Kind::StalePatch => Explanation {
+            summary: "An entry of [patch.<registry>] doesn't apply: the crate isn't in the \
+                      graph, or the patch provides a version no requirement accepts.",
+            why: "Cargo silently ignores patches that don't match, so the fix or fork the \
+                  patch was meant to bring in is not actually being built.",
+            false_positives: "Patches kept on purpose for a dependency that is only enabled \
+                              by a feature or on another platform.",
+            resolve: "Remove the entry, or update the patched source to a version matching \
+                      the requirements listed in the finding.",
+        },
+        Kind::ActivePatch => Explanation {
+            summary: "A [patch] entry is in effect; the finding lists the packages that \
+                      build against the patched crate.",
+            why: "Patches are easy to forget. Reviewing them regularly keeps temporary forks \
+                  from outliving the upstream release that made them unnecessary.",
+            false_positives: "None; this is informational.",
+            resolve: "Remove the entry once upstream ships the change.",
+        },
+        Kind::StaleReplace => Explanation {
+            summary: "A [replace] entry matches no package in the dependency graph.",
+            why: "It has no effect but suggests an override is in place.",
+            false_positives: "Entries for dependencies only enabled by a feature.",
+            resolve: "Remove the entry.",
+        },
+        Kind::LegacyReplace => Explanation {
+            summary: "A [replace] entry is in effect.",
+            why: "[replace] is deprecated in favor of [patch], which is more flexible and \
+                  better supported.",
+            false_positives: "None.",
+            resolve: "Rewrite it as the [patch] snippet shown in the finding.",
+        },
+        Kind::StaleProfileOverride => Explanation {
+            summary: "A [profile.<name>.package.<spec>] override matches no package in the \
+                      dependency graph.",
+            why: "It has no effect, so whatever tuning it was meant to apply isn't happening.",
+            false_positives: "Overrides for dependencies only enabled by a feature.",
+            resolve: "Remove the override or fix the package spec.",
+        },
+        Kind::DuplicateDeclaration => Explanation {
+            summary: "A crate is declared in several dependency tables of the same manifest \
+                      (normal, dev, build, target-specific) with different requirements or \
+                      features.",
+            why: "Cargo merges the declarations that end up in the same build, so the \
+                  effective requirement and feature set (shown in the finding per build: \
+                  normal, tests, build scripts and platform) may not be what any single \
+                  table says. Resolver 1 merges all of them.",
+            false_positives: "Deliberately different features per table, e.g. a test-only \
+                              feature in [dev-dependencies].",
+            resolve: "Make the declarations agree. Declarations in the same kind of table, \
+                      differing only by platform, can often be merged into one.",
+        },
+        Kind::UnusedWorkspaceDependency => Explanation {
+            summary: "An entry of [workspace.dependencies] is not inherited by any member.",
+            why: "Dead entries suggest a version is managed centrally when it isn't.",
+            false_positives: "Entries kept for members excluded from the workspace.",
+            resolve: "Remove the entry, or inherit it with `<name>.workspace = true`.",
+        },
+        Kind::InheritableDependency => Explanation {
+            summary: "Several members declare the same dependency identically; it could live \
+                      in [workspace.dependencies].",
+            why: "Declaring it once keeps the members from drifting apart.",
+            false_positives: "None, though centralizing is a matter of taste.",
+            resolve: "Run with `--fix` to move it into [workspace.dependencies].",
+        },
+        Kind::VersionSkew => Explanation {
+            summary: "Members request different versions of the same crate.",
+            why: "Today's requirements may resolve to one version, but incompatible \
+                  requirements compile several copies, increasing build times and causing \
+                  type mismatches between members.",
+            false_positives: "Intentional use of two major versions during a migration.",
+            resolve: "Align the requirements, ideally through [workspace.dependencies].",
+        },
+        Kind::LayerViolation => Explanation {
+            summary: "A member reaches a crate its layering rule forbids, directly or \
+                      transitively; the finding shows the shortest path.",
+            why: "The rule encodes an architectural decision (e.g. core stays runtime-\
+                  agnostic) that this dependency breaks.",
+            false_positives: "Paths through optional dependencies that are never enabled \
+                              together with this member.",
+            resolve: "Break the path shown, or update the `layers` rule.",
+        },
+        Kind::ResolverV1 => Explanation {
+            summary: "The workspace uses feature resolver 1, and features requested only by \
+                      dev, build or target-specific dependencies leak into normal builds.",
+            why: "Unified features make release builds larger and can enable code (e.g. std) \
+                  on targets that can't support it.",
+            false_positives: "Projects that depend on the unified behavior.",
+            resolve: "Run with `--fix` to set `resolver = \"2\"`.",
+        },
+        Kind::UnpublishablePathDependency => Explanation {
+            summary: "A publishable package depends on another by path without a version \
+                      requirement.",
+            why: "`cargo publish` rejects it, so the problem only surfaces at release time.",
+            false_positives: "Packages that are never published but lack `publish = false`.",
+            resolve: "Add `version = \"...\"` next to `path`, or set `publish = false`.",
+        },
+        Kind::ExternalPathDependency => Explanation {
+            summary: "A path dependency points outside the workspace.",
+            why: "The build then depends on a checkout layout other contributors and CI may \
+                  not share.",
+            false_positives: "Monorepos that deliberately reference sibling workspaces.",
+            resolve: "Use a git or registry dependency, or move the crate into the workspace.",
+        },
+        Kind::UnpinnedGitDependency => Explanation {
+            summary: "A git dependency follows a branch or the default branch instead of a \
+                      tag or commit.",
+            why: "`cargo update` can then pull in arbitrary upstream commits.",
+            false_positives: "Dependencies on repositories you control and update in lockstep.",
+            resolve: "Pin it with `rev = \"...\"` or `tag = \"...\"`.",
+        },
+        Kind::GitDrift => Explanation {
+            summary: "The branch a git dependency follows has moved past the locked commit.",
+            why: "You're missing upstream fixes, and the next `cargo update` will bring in \
+                  all of them at once.",
+            false_positives: "None; this is informational.",
+            resolve: "Run `cargo update -p <crate>` when ready, or pin the dependency.",
+        },
+        Kind::GitReleaseAvailable => Explanation {
+            summary: "A crate taken from git has a compatible release on crates.io.",
+            why: "Registry releases are immutable, checksummed and faster to fetch.",
+            false_positives: "The git source may carry changes the release doesn't.",
+            resolve: "Switch the dependency to the released version.",
+        },
+        Kind::Outdated => Explanation {
+            summary: "A newer release of a dependency is out that its version requirement \
+                      doesn't allow.",
+            why: "Fixes and security patches land in new releases first, and the further \
+                  behind a dependency falls the larger the eventual upgrade.",
+            false_positives: "Staying on an older series can be deliberate, e.g. to keep a \
+                              minimum supported Rust version.",
+            resolve: "Read the linked changelog and release notes for the jump, then raise \
+                      the requirement and fix what breaks.",
+        },
+        Kind::InRangeUpdate => Explanation {
+            summary: "Refreshing the lockfile would move a package to a newer version its \
+                      requirements already allow.",
+            why: "Compatible releases carry fixes at no API cost, and a lockfile that lags \
+                  far behind makes the eventual `cargo update` a large, hard-to-review change.",
+            false_positives: "The lockfile may pin an older version on purpose, e.g. while a \
+                              regression in the newer one is fixed.",
+            resolve: "Run the `cargo update -p` command shown in the finding, or `cargo \
+                      update` for all of them.",
+        },
+        Kind::YankedVersion => Explanation {
+            summary: "The lockfile pins a version that has been yanked from its registry.",
+            why: "Versions are usually yanked for serious bugs or security issues, and fresh \
+                  resolutions can no longer select them.",
+            false_positives: "None.",
+            resolve: "Run the `cargo update -p` command shown in the finding.",
+        },
+        Kind::Advisory => Explanation {
+            summary: "A RustSec advisory covers a locked crates.io package: a vulnerability, \
+                      or (at warning level) a crate found unmaintained or unsound. The \
+                      database is cargo-audit's checkout in $CARGO_HOME/advisory-db, kept up \
+                      to date unless offline, or the one --advisory-db names.",
+            why: "A vulnerable version ends up in every build the lockfile drives, including \
+                  the binaries shipped from it.",
+            false_positives: "Advisories about code paths the workspace never calls; the \
+                              advisory names the affected functions when it's known.",
+            resolve: "Update to a fixed version with `cargo update -p <crate>`, or replace the \
+                      crate when none exists. To accept the risk, set the kind's severity in \
+                      the configuration.",
+        },
+        Kind::ChecksumMismatch => Explanation {
+            summary: "A package's checksum in Cargo.lock differs from its registry's index, or \
+                      vendored sources don't match the hashes recorded for them.",
+            why: "Checksums are what ties a build to the exact sources that were reviewed and \
+                  published. A mismatch means the lockfile, the registry or the vendored files \
+                  were changed, which is how supply-chain attacks get in.",
+            false_positives: "Vendored sources patched by hand on purpose, without updating \
+                              `.cargo-checksum.json`.",
+            resolve: "Don't build until the difference is explained. Restore the lockfile \
+                      entry or the vendored files (`cargo vendor` again), and report a \
+                      registry whose index changed a published checksum.",
+        },
+        Kind::VendorDrift => Explanation {
+            summary: "The vendor directory and Cargo.lock disagree: a locked package isn't \
+                      vendored, or a vendored one is no longer locked.",
+            why: "Vendored builds resolve only against the vendor directory, so a missing \
+                  package breaks every `--offline` build. Stale packages don't break the \
+                  build but ship unused sources and hide which ones are actually in use.",
+            false_positives: "A vendor directory shared by several workspaces, whose \
+                              packages are locked by one of the others.",
+            resolve: "Run `cargo vendor` again after changing dependencies, and commit the \
+                      vendor directory together with Cargo.lock.",
+        },
+        Kind::LinksConflict => Explanation {
+            summary: "Several crates in the graph link the same native library: they \
+                      declare the same `links` key (an error), or are known to bind the \
+                      same C library under different keys, such as openssl-sys and \
+                      boring-sys (a warning).",
+            why: "Cargo refuses to build a graph with two packages declaring one `links` \
+                  key. Under different keys it builds, but the binary gets two copies of \
+                  the library's symbols: linking fails on the duplicates, or one copy \
+                  silently serves both crates' bindings, which expect different versions.",
+            false_positives: "Crates that prefix their symbols, and dependencies of \
+                              binaries that are never linked together.",
+            resolve: "Settle on one implementation: turn off the features that select the \
+                      other (`native-tls` vs `rustls`, `vendored`), or align the crates \
+                      depending on them. The path on each crate shows where it comes from.",
+        },
+        Kind::BudgetExceeded => Explanation {
+            summary: "The dependency graph is larger than a budget set in the configuration.",
+            why: "Budgets (`budget` in the dep-analysis metadata) cap the direct \
+                  dependencies, the crates in the lockfile, or the crates a change may add, \
+                  so growth is a decision rather than an accident.",
+            false_positives: "None: the counts come from the lockfile. Raise the budget when \
+                              the growth is intended.",
+            resolve: "Remove dependencies until the count is within the budget, or raise \
+                      the budget in the same change.",
+        },
+    }
+}
@@ -0,0 +1,149 @@
+use crate::ident;
+use crate::project::Project;
+use crate::report::{Finding, Kind};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+/// Sysroot crates, which still need `extern crate` in some setups (`alloc` in `no_std`
+/// crates, `test` and `proc_macro` before they joined the extern prelude).
+const SYSROOT: [&str; 5] = ["std", "core", "alloc", "proc_macro", "test"];
+
+/// An `extern crate` declaration that edition 2018 and later make unnecessary.
+pub struct Declaration {
+    pub krate: String,
+    /// The 1-based line it starts on.
+    pub line: usize,
+    /// Its byte range, including the line break after it.
+    start: usize,
+    end: usize,
+}
+
+/// The `extern crate` declarations in `content` that only bring a dependency into scope,
+/// which the extern prelude already does. Attributed ones (`#[macro_use]`, `#[cfg]`),
+/// renames (`as`, including `extern crate self as name`), `pub` re-exports and sysroot
+/// crates are left alone, and so are declarations the file reaches through a module path
+/// (`crate::name::...`, `self::name::...`), which only resolve because of them.
+pub fn redundant(content: &str) -> Vec<Declaration> {
+    let ident = ident::PATTERN;
+    let declaration = Regex::new(&format!(
+        r"(?m)^([ \t]*)(#\[[^\]]*\]\s*)*(pub(?:\([^)]*\))?\s+)?extern\s+crate\s+({ident})(\s+as\s+{ident})?\s*;[ \t]*\n?"
+    ))
+    .unwrap();
+    declaration
+        .captures_iter(content)
+        .filter(|cap| cap.get(2).is_none() && cap.get(3).is_none() && cap.get(5).is_none())
+        .filter(|cap| &cap[4] != "self" && !SYSROOT.contains(&&cap[4]))
+        .filter(|cap| !used_as_item(content, ident::unraw(&cap[4])))
+        .map(|cap| {
+            let m = cap.get(0).unwrap();
+            Declaration {
+                krate: ident::unraw(&cap[4]).to_string(),
+                line: content[..m.start()].lines().count() + 1,
+                start: m.start(),
+                end: m.end(),
+            }
+        })
+        .collect()
+}
+
+/// Whether `content` names `krate` as an item of a module, which `extern crate` makes it.
+fn used_as_item(content: &str, krate: &str) -> bool {
+    Regex::new(&format!(
+        r"\b(?:crate|self)\s*::\s*(?:r#)?{}\b",
+        regex::escape(krate)
+    ))
+    .unwrap()
+    .is_match(content)
+}
+
+/// `--fix` for redundant `extern crate` declarations: deletes their lines from the files
+/// the findings point at.
+pub fn fix(
+    project: &Project,
+    findings: &[Finding],
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut files: BTreeMap<PathBuf, BTreeSet<String>> = BTreeMap::new();
+    for finding in findings
+        .iter()
+        .filter(|f| f.kind == Kind::RedundantExternCrate)
+    {
+        let Some(file) = &finding.file else {
+            continue;
+        };
+        files
+            .entry(project.root().join(file))
+            .or_default()
+            .insert(finding.krate.replace('-', "_"));
+    }
+
+    let mut changes = Vec::new();
+    for (path, crates) in files {
+        let mut content = std::fs::read_to_string(&path)?;
+        let removed: Vec<Declaration> = redundant(&content)
+            .into_iter()
+            .filter(|declaration| crates.contains(&declaration.krate))
+            .collect();
+        for declaration in removed.iter().rev() {
+            content.replace_range(declaration.start..declaration.end, "");
+        }
+        if removed.is_empty() {
+            continue;
+        }
+        std::fs::write(&path, content)?;
+        for declaration in removed {
+            changes.push(format!(
+                "Removed `extern crate {};` from {}",
+                declaration.krate,
+                project.display_path(&path)
+            ));
+        }
+    }
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::fixture;
+
+    #[test]
+    fn fix_removes_only_plain_declarations() {
+        let lib = "#[macro_use]\n\
+                   extern crate log;\n\
+                   pub extern crate reexported;\n\
+                   extern crate renamed as r;\n\
+                   extern crate alloc;\n\
+                   extern crate plain;\n\
+                   extern crate pathed;\n\
+                   mod m {\n    extern crate selfed;\n    fn g() { self::selfed::go(); }\n}\n\
+                   fn f() { crate::pathed::go(); }\n";
+        let (dir, project) = fixture(&[
+            (
+                "Cargo.toml",
+                "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+            ),
+            ("src/lib.rs", lib),
+        ]);
+        let path = dir.path().join("src/lib.rs");
+        let findings: Vec<Finding> = [
+            "log",
+            "reexported",
+            "renamed",
+            "alloc",
+            "plain",
+            "pathed",
+            "selfed",
+        ]
+        .into_iter()
+        .map(|krate| Finding::new(Kind::RedundantExternCrate, krate).file(&project, &path))
+        .collect();
+
+        let changes = fix(&project, &findings).unwrap();
+        assert_eq!(changes, ["Removed `extern crate plain;` from src/lib.rs"]);
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            lib.replace("extern crate plain;\n", "")
+        );
+    }
+}
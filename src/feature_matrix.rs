@@ -0,0 +1,163 @@
+use crate::cli::Args;
+use crate::features;
+use crate::project::Project;
+use crate::report::{Finding, Kind};
+use cargo_metadata::{DependencyKind, Package};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// The crates referenced under feature gates, enabled or not: for each crate, the
+/// features of each gate (`"a, b"` for `all(feature = "a", feature = "b")`) and where it
+/// was first seen.
+pub type FeatureRefs = HashMap<String, BTreeMap<String, String>>;
+
+/// `feature-matrix`: for each feature of the analyzed packages, the dependencies code
+/// under it uses and the optional dependencies it enables.
+pub fn run(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let project = Project::load(args.manifest_path.as_deref())?;
+    for package in crate::selected_packages(args, &project)? {
+        let (_, refs) = crate::package_usage(args, &project, package)?;
+        println!("\nFeature requirements of {}:", package.name);
+        let width = package
+            .features
+            .keys()
+            .map(|f| f.chars().count())
+            .chain(["FEATURE".len()])
+            .max()
+            .unwrap_or(0);
+        println!("  {:<width$}  USES / ENABLES", "FEATURE", width = width);
+        let deps: HashSet<&str> = package
+            .dependencies
+            .iter()
+            .map(|dep| dep.name.as_str())
+            .collect();
+        for feature in package.features.keys().collect::<BTreeSet<_>>() {
+            let uses: BTreeSet<&str> = refs
+                .iter()
+                .filter(|(krate, _)| deps.contains(krate.as_str()))
+                .filter(|(_, gates)| {
+                    gates
+                        .keys()
+                        .any(|gate| gate_features(gate).any(|f| f == feature))
+                })
+                .map(|(krate, _)| krate.as_str())
+                .collect();
+            let enables: BTreeSet<&str> = optional_deps(package)
+                .filter(|dep| features::activating(package, dep).contains(feature))
+                .collect();
+            let list = |crates: &BTreeSet<&str>| {
+                if crates.is_empty() {
+                    "-".to_string()
+                } else {
+                    crates.iter().copied().collect::<Vec<_>>().join(", ")
+                }
+            };
+            println!(
+                "  {:<width$}  {} / {}",
+                feature,
+                list(&uses),
+                list(&enables),
+                width = width
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Mismatches between the features of `package` and the optional dependencies they
+/// enable: a feature enabling a dependency no code under it uses, and a dependency used
+/// under features that don't enable it. Crates in `reported` already have a finding
+/// saying the latter.
+pub fn findings(
+    project: &Project,
+    package: &Package,
+    refs: &FeatureRefs,
+    reported: &HashSet<String>,
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for dep in optional_deps(package) {
+        let Some(gates) = refs.get(dep) else {
+            // Unused altogether, or used without a feature gate: other findings cover it.
+            continue;
+        };
+        let activating = features::activating(package, dep);
+        // The implicit feature of an optional dependency enables it too.
+        let enables = |feature: &str| feature == dep || activating.contains(feature);
+
+        for feature in package
+            .features
+            .keys()
+            .filter(|f| directly_enables(package, f, dep))
+        {
+            let implied = features::enabled(package, std::slice::from_ref(feature), false, true);
+            if !gates
+                .keys()
+                .any(|gate| gate_features(gate).any(|f| implied.contains(f)))
+            {
+                findings.push(
+                    Finding::new(Kind::FeatureUnusedDependency, dep)
+                        .member(project, package)
+                        .note(format!(
+                            "Feature `{}` enables it, but no code under `{}` uses it",
+                            feature, feature
+                        ))
+                        .note(format!(
+                            "Used under: {}",
+                            gates.keys().cloned().collect::<Vec<_>>().join("; ")
+                        ))
+                        .note(format!(
+                            "Drop it from `{}`, or enable it from the features that use it",
+                            feature
+                        )),
+                );
+            }
+        }
+
+        if reported.contains(dep) {
+            continue;
+        }
+        for (gate, place) in gates {
+            if !gate_features(gate).any(enables) {
+                findings.push(
+                    Finding::new(Kind::FeatureMissingDependency, dep)
+                        .member(project, package)
+                        .note(format!(
+                            "Used under feature `{}` ({}), which doesn't enable it",
+                            gate, place
+                        ))
+                        .note(format!(
+                            "Add `dep:{}` to that feature, or the build with only it enabled \
+                             fails",
+                            dep
+                        )),
+                );
+            }
+        }
+    }
+    findings
+}
+
+fn gate_features(gate: &str) -> impl Iterator<Item = &str> {
+    gate.split(", ")
+}
+
+fn optional_deps(package: &Package) -> impl Iterator<Item = &str> {
+    package
+        .dependencies
+        .iter()
+        .filter(|dep| dep.optional && dep.kind == DependencyKind::Normal)
+        .map(|dep| dep.name.as_str())
+}
+
+/// Whether `feature` lists `dep` itself (`dep:name`, `name`, `name/feature`), rather than
+/// through another feature.
+fn directly_enables(package: &Package, feature: &str, dep: &str) -> bool {
+    package.features.get(feature).is_some_and(|values| {
+        values.iter().any(|value| {
+            !value.contains("?/")
+                && value
+                    .strip_prefix("dep:")
+                    .unwrap_or_else(|| value.split('/').next().unwrap_or(value))
+                    == dep
+        })
+    })
+}
@@ -0,0 +1,269 @@
+use crate::cache::{self, Cache};
+use crate::cargo_config::CargoConfig;
+use crate::registry::Registry;
+use cargo_metadata::semver::Version;
+use cargo_metadata::{Dependency, MetadataCommand, Package};
+use regex::Regex;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Explains a `cargo metadata` failure caused by a member's `[features]`: entries still
+/// referencing an optional dependency that was removed or renamed, which cargo reports
+/// one at a time without saying where, and forwards to a feature a dependency doesn't
+/// have (`"serde/nope"`, `"serde?/nope"`), which it only reports as a version it fails to
+/// select. `None` when no feature is to blame.
+pub fn explain_failure(
+    manifest_path: Option<&Path>,
+    config: &CargoConfig,
+    err: &cargo_metadata::Error,
+) -> Option<String> {
+    dangling(&err.to_string()).or_else(|| missing_features(manifest_path, config))
+}
+
+/// The references to undeclared dependencies in the manifest cargo failed to parse.
+fn dangling(err: &str) -> Option<String> {
+    let failed = Regex::new(r"failed to parse manifest at `([^`]+)`").unwrap();
+    let path = failed.captures(err)?.get(1)?.as_str();
+    let content = std::fs::read_to_string(path).ok()?;
+    let manifest: toml::Table = content.parse().ok()?;
+    let features = manifest.get("features")?.as_table()?;
+    // Every declared dependency by key, with the crate it names and whether it's optional.
+    let mut declared: Vec<(&str, &str, bool)> = Vec::new();
+    for table in crate::project::dependency_tables(&manifest) {
+        for (key, spec) in table.entries {
+            let package = spec.get("package").and_then(|p| p.as_str()).unwrap_or(key);
+            let optional = spec.get("optional").and_then(|o| o.as_bool()) == Some(true);
+            declared.push((key, package, optional));
+        }
+    }
+
+    let mut problems = Vec::new();
+    for (feature, values) in features {
+        for value in values
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|v| v.as_str())
+        {
+            let (name, explicit) = match value.strip_prefix("dep:") {
+                Some(name) => (name, true),
+                None => match value.split_once('/') {
+                    Some((name, _)) => (name.trim_end_matches('?'), true),
+                    None => (value, false),
+                },
+            };
+            let problem = match declared.iter().find(|(key, ..)| *key == name) {
+                Some((_, _, false)) if value.starts_with("dep:") => format!(
+                    "`{}` isn't optional; declare it with `optional = true` or drop the entry",
+                    name
+                ),
+                Some(_) => continue,
+                // A plain name may be another feature.
+                None if !explicit && features.contains_key(name) => continue,
+                None => match declared.iter().find(|(_, package, _)| *package == name) {
+                    Some((key, ..)) => format!(
+                        "`{}` is now declared as `{}` (package = \"{}\"); use `{}`",
+                        name,
+                        key,
+                        name,
+                        value.replacen(name, key, 1)
+                    ),
+                    None => format!(
+                        "no dependency `{}` is declared (removed?); drop the entry",
+                        name
+                    ),
+                },
+            };
+            let line = line_of(&content, feature, value);
+            let place = line.map(|line| format!(":{}", line)).unwrap_or_default();
+            problems.push((
+                line,
+                format!(
+                    "{}{}: `{}` includes \"{}\": {}",
+                    path, place, feature, value, problem
+                ),
+            ));
+        }
+    }
+    if problems.is_empty() {
+        return None;
+    }
+    // In the order the manifest lists them.
+    problems.sort();
+    let problems: Vec<String> = problems.into_iter().map(|(_, problem)| problem).collect();
+    Some(format!(
+        "Features referencing dependencies that aren't declared:\n  {}",
+        problems.join("\n  ")
+    ))
+}
+
+/// The line of `value` in the list of `feature`, under `[features]`.
+fn line_of(content: &str, feature: &str, value: &str) -> Option<usize> {
+    let quoted = format!("\"{}\"", value);
+    let mut in_features = false;
+    let mut in_feature = false;
+    for (index, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') && !trimmed.starts_with("[\"") {
+            in_features = trimmed == "[features]";
+            in_feature = false;
+            continue;
+        }
+        if !in_features {
+            continue;
+        }
+        // Values never contain `=`, so a line with one starts a feature's list.
+        if let Some((key, _)) = trimmed.split_once('=') {
+            in_feature = key.trim().trim_matches('"') == feature;
+        }
+        if in_feature && line.contains(&quoted) {
+            return Some(index + 1);
+        }
+    }
+    None
+}
+
+/// The forwards to features the dependencies don't have, once the members' manifests
+/// parse.
+fn missing_features(manifest_path: Option<&Path>, config: &CargoConfig) -> Option<String> {
+    // Without resolving, the members still load.
+    let mut command = MetadataCommand::new();
+    command.no_deps();
+    if let Some(manifest_path) = manifest_path {
+        command.manifest_path(manifest_path);
+    }
+    let metadata = command.exec().ok()?;
+    let lookup = Lookup {
+        config,
+        cache: Cache::new(cache::DEFAULT_TTL),
+    };
+
+    let mut problems = Vec::new();
+    for member in &metadata.packages {
+        for (feature, values) in &member.features {
+            for value in values {
+                let Some((name, forwarded)) = value.split_once('/') else {
+                    continue;
+                };
+                let name = name.trim_end_matches('?');
+                let Some(dep) = dependency(member, name) else {
+                    // Cargo reports forwards to what isn't a dependency itself.
+                    continue;
+                };
+                let Some(available) = lookup.features(dep) else {
+                    continue;
+                };
+                if !available.contains(forwarded) {
+                    problems.push(format!(
+                        "{}: `{} = [\"{}\"]`, but {} {} has no feature `{}` ({})",
+                        member.name,
+                        feature,
+                        value,
+                        dep.name,
+                        dep.req,
+                        forwarded,
+                        listing(&available)
+                    ));
+                }
+            }
+        }
+    }
+    if problems.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "Features forwarded to dependencies that don't have them:\n  {}",
+        problems.join("\n  ")
+    ))
+}
+
+/// The dependency a feature value names: by its key, which `package = "..."` renames.
+fn dependency<'a>(member: &'a Package, name: &str) -> Option<&'a Dependency> {
+    member
+        .dependencies
+        .iter()
+        .find(|dep| dep.rename.as_deref().unwrap_or(&dep.name) == name)
+}
+
+fn listing(available: &BTreeSet<String>) -> String {
+    if available.is_empty() {
+        return "it has no features".to_string();
+    }
+    let names: Vec<&str> = available.iter().map(String::as_str).collect();
+    format!("it has: {}", names.join(", "))
+}
+
+/// Where the feature lists of dependencies come from without a resolved graph.
+struct Lookup<'a> {
+    config: &'a CargoConfig,
+    cache: Cache,
+}
+
+impl Lookup<'_> {
+    /// The features `dep` can be asked for: its own, and the implicit ones of its
+    /// optional dependencies. For registry dependencies, those of any version the
+    /// requirement allows, as cargo would pick one having the feature. `None` when they
+    /// can't be known (git sources, offline, an unreachable registry).
+    fn features(&self, dep: &Dependency) -> Option<BTreeSet<String>> {
+        if let Some(path) = &dep.path {
+            let manifest = crate::project::read_toml(path.join("Cargo.toml").as_std_path()).ok()?;
+            let mut features: BTreeSet<String> = manifest
+                .get("features")
+                .and_then(|f| f.as_table())
+                .into_iter()
+                .flat_map(|table| table.keys().cloned())
+                .collect();
+            for table in crate::project::dependency_tables(&manifest) {
+                features.extend(
+                    table
+                        .entries
+                        .iter()
+                        .filter(|(_, spec)| {
+                            spec.get("optional").and_then(|o| o.as_bool()) == Some(true)
+                        })
+                        .map(|(name, _)| name.clone()),
+                );
+            }
+            return Some(features);
+        }
+
+        let source = dep.source.as_deref()?;
+        if self.config.offline()
+            || !(source.starts_with("registry+") || source.starts_with("sparse+"))
+        {
+            return None;
+        }
+        let registry = Registry::for_source(source, self.config, &self.cache).ok()??;
+        let versions = registry
+            .versions(&dep.name)
+            .map_err(|err| tracing::debug!("could not look up {}: {}", dep.name, err))
+            .ok()?;
+        let matching: Vec<_> = versions
+            .iter()
+            .filter(|entry| !entry.yanked)
+            .filter(|entry| {
+                entry
+                    .vers
+                    .parse::<Version>()
+                    .is_ok_and(|version| dep.req.matches(&version))
+            })
+            .collect();
+        if matching.is_empty() {
+            return None;
+        }
+        Some(
+            matching
+                .into_iter()
+                .flat_map(|entry| {
+                    let optional = entry.deps.iter().filter(|d| d.optional).map(|d| &d.name);
+                    entry
+                        .features
+                        .keys()
+                        .chain(entry.features2.keys())
+                        .chain(optional)
+                        .cloned()
+                })
+                .collect(),
+        )
+    }
+}
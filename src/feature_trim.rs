@@ -0,0 +1,299 @@
+use crate::cli::Args;
+use crate::fix::{self, ManifestEdit};
+use crate::project::Project;
+use crate::report::{Finding, Kind};
+use cargo_metadata::{Dependency, DependencyKind, Package};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use walkdir::WalkDir;
+
+/// A reduction of the features a member enables on a dependency.
+enum Trim {
+    /// `default-features = false`, dropping the listed default features.
+    DefaultFeatures(Vec<String>),
+    /// Removing one feature from the entry's `features` list.
+    Feature(String),
+}
+
+struct Candidate<'a> {
+    member: &'a Package,
+    dep: &'a Dependency,
+    trim: Trim,
+}
+
+impl Candidate<'_> {
+    fn describe(&self) -> String {
+        match &self.trim {
+            Trim::DefaultFeatures(defaults) => {
+                format!(
+                    "with `default-features = false`, dropping {}",
+                    defaults.join(", ")
+                )
+            }
+            Trim::Feature(feature) => format!("without the `{}` feature", feature),
+        }
+    }
+}
+
+/// `--verify-features`: tries turning off the default features of each registry
+/// dependency the members declare, and dropping each feature they enable on one, in a
+/// scratch copy of the workspace, and reports the changes `cargo check --all-targets`
+/// still builds. Optional dependencies are only tried with `--all-features`, and
+/// platform-specific ones not at all, as the check wouldn't build them.
+pub fn analyze(args: &Args, project: &Project) -> Result<Vec<Finding>, Box<dyn std::error::Error>> {
+    let mut candidates = candidates(args, project);
+    if candidates.is_empty() {
+        return Ok(Vec::new());
+    }
+    let dir = tempfile::Builder::new()
+        .prefix("cargo-dep-analysis-")
+        .tempdir()?;
+    let scratch = dir.path().join("workspace");
+    copy_workspace(project, &scratch)?;
+    // Entries inherited from the workspace can't turn off what it enables.
+    candidates.retain(|candidate| {
+        manifest(project, &scratch, candidate)
+            .and_then(|path| ManifestEdit::open(&path))
+            .is_ok_and(|mut manifest| apply(&mut manifest, candidate))
+    });
+    if candidates.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    tracing::info!("checking the unmodified workspace before trimming features");
+    if !check(args, project, &scratch)? {
+        tracing::warn!(
+            "the workspace doesn't build in a scratch copy (path dependencies outside it?); \
+             skipping --verify-features"
+        );
+        return Ok(Vec::new());
+    }
+
+    // Most trims usually build, so all of them are tried at once first.
+    tracing::info!("checking {} feature trims together", candidates.len());
+    let together = with_trims(
+        project,
+        &scratch,
+        &candidates.iter().collect::<Vec<_>>(),
+        || check(args, project, &scratch),
+    )?;
+    let mut proven = Vec::new();
+    for candidate in &candidates {
+        let builds = together || {
+            tracing::info!(
+                "checking {} of {} {}",
+                candidate.dep.name,
+                candidate.member.name,
+                candidate.describe()
+            );
+            with_trims(project, &scratch, &[candidate], || {
+                check(args, project, &scratch)
+            })?
+        };
+        if builds {
+            proven.push(finding(project, candidate));
+        }
+    }
+    Ok(proven)
+}
+
+fn finding(project: &Project, candidate: &Candidate) -> Finding {
+    let finding = Finding::new(Kind::FeatureTrim, &candidate.dep.name)
+        .member(project, candidate.member)
+        .note(format!(
+            "`cargo check --all-targets` passes {}",
+            candidate.describe()
+        ));
+    match &candidate.trim {
+        Trim::DefaultFeatures(_) => finding.note("set `default-features = false` on the entry"),
+        Trim::Feature(feature) => {
+            finding.note(format!("remove \"{}\" from the entry's features", feature))
+        }
+    }
+}
+
+/// The trims to try: default features where the dependency has any, and each feature
+/// enabled explicitly.
+fn candidates<'a>(args: &Args, project: &'a Project) -> Vec<Candidate<'a>> {
+    let members: Vec<&str> = project.members().map(|pkg| pkg.name.as_str()).collect();
+    let mut candidates = Vec::new();
+    for member in project.members() {
+        for dep in &member.dependencies {
+            let registry = dep.source.is_some() && dep.path.is_none();
+            if !registry
+                || members.contains(&dep.name.as_str())
+                || dep.target.is_some()
+                || (dep.optional && !args.all_features)
+            {
+                continue;
+            }
+            let defaults: Vec<String> = project
+                .metadata
+                .packages
+                .iter()
+                .find(|pkg| pkg.name == dep.name && dep.req.matches(&pkg.version))
+                .and_then(|pkg| pkg.features.get("default"))
+                .cloned()
+                .unwrap_or_default();
+            if dep.uses_default_features && !defaults.is_empty() {
+                candidates.push(Candidate {
+                    member,
+                    dep,
+                    trim: Trim::DefaultFeatures(defaults),
+                });
+            }
+            for feature in &dep.features {
+                candidates.push(Candidate {
+                    member,
+                    dep,
+                    trim: Trim::Feature(feature.clone()),
+                });
+            }
+        }
+    }
+    candidates
+}
+
+/// Copies the workspace to `to`, without its build output or hidden directories other
+/// than `.cargo`, whose configuration the build needs.
+pub fn copy_workspace(project: &Project, to: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let root = project.root();
+    let target = project.metadata.target_directory.as_std_path();
+    let entries = WalkDir::new(&root).into_iter().filter_entry(|entry| {
+        let name = entry.file_name().to_string_lossy();
+        let hidden = entry.depth() > 0 && name.starts_with('.') && name != ".cargo";
+        !hidden && entry.path() != target
+    });
+    for entry in entries {
+        let entry = entry?;
+        let dest = to.join(entry.path().strip_prefix(&root)?);
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&dest)?;
+        } else if entry.file_type().is_file() {
+            std::fs::copy(entry.path(), &dest)?;
+        }
+    }
+    Ok(())
+}
+
+/// The manifest of the candidate's member in the scratch copy.
+fn manifest(
+    project: &Project,
+    scratch: &Path,
+    candidate: &Candidate,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let path = candidate.member.manifest_path.as_std_path();
+    Ok(scratch.join(path.strip_prefix(project.root())?))
+}
+
+/// Runs `build` with `trims` applied to the scratch copy's manifests, restoring them
+/// afterwards.
+fn with_trims(
+    project: &Project,
+    scratch: &Path,
+    trims: &[&Candidate],
+    build: impl FnOnce() -> Result<bool, Box<dyn std::error::Error>>,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut originals: Vec<(PathBuf, String)> = Vec::new();
+    for candidate in trims {
+        let path = manifest(project, scratch, candidate)?;
+        let mut edit = ManifestEdit::open(&path)?;
+        if !originals.iter().any(|(saved, _)| *saved == path) {
+            originals.push((path.clone(), edit.contents()));
+        }
+        apply(&mut edit, candidate);
+        edit.save()?;
+    }
+    let builds = build();
+    for (path, contents) in originals {
+        std::fs::write(path, contents)?;
+    }
+    builds
+}
+
+/// Applies a trim to the dependency's entry. Whether it could be.
+fn apply(manifest: &mut ManifestEdit, candidate: &Candidate) -> bool {
+    let kind = match candidate.dep.kind {
+        DependencyKind::Development => "dev-dependencies",
+        DependencyKind::Build => "build-dependencies",
+        _ => "dependencies",
+    };
+    let Some(table) = manifest.dependency_table(None, kind) else {
+        return false;
+    };
+    let Some(key) = fix::entry_key(table, &candidate.dep.name) else {
+        return false;
+    };
+    let Some(item) = table.get_mut(&key) else {
+        return false;
+    };
+    if let Some(version) = item.as_str() {
+        let mut spec = toml_edit::InlineTable::new();
+        spec.insert("version", version.into());
+        *item = toml_edit::value(spec);
+    }
+    let Some(entry) = item.as_table_like_mut() else {
+        return false;
+    };
+    if entry.contains_key("workspace") {
+        // Inherited entries can't turn off what `[workspace.dependencies]` enables.
+        return false;
+    }
+    match &candidate.trim {
+        Trim::DefaultFeatures(_) => {
+            entry.remove("default_features");
+            entry.insert("default-features", toml_edit::value(false));
+        }
+        Trim::Feature(feature) => {
+            let Some(features) = entry.get_mut("features").and_then(|f| f.as_array_mut()) else {
+                return false;
+            };
+            features.retain(|f| f.as_str() != Some(feature));
+            if features.is_empty() {
+                entry.remove("features");
+            }
+        }
+    }
+    true
+}
+
+/// Whether `cargo check --workspace --all-targets` passes in the scratch copy.
+fn check(
+    args: &Args,
+    project: &Project,
+    scratch: &Path,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut command = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".into()));
+    command
+        .args(["check", "--workspace", "--all-targets", "--quiet"])
+        .arg("--manifest-path")
+        .arg(scratch.join("Cargo.toml"))
+        // The scratch copy shares the separate target directory, so dependencies are
+        // built once across the runs.
+        .arg("--target-dir")
+        .arg(
+            project
+                .metadata
+                .target_directory
+                .join("dep-analysis")
+                .as_std_path(),
+        )
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    if args.all_features {
+        command.arg("--all-features");
+    }
+    if args.no_default_features {
+        command.arg("--no-default-features");
+    }
+    if !args.features.is_empty() {
+        command.arg("--features").arg(args.features.join(","));
+    }
+    if let Some(target) = &args.target {
+        command.arg("--target").arg(target);
+    }
+    if args.offline || project.offline() {
+        command.arg("--offline");
+    }
+    Ok(command.status()?.success())
+}
@@ -0,0 +1,67 @@
+use cargo_metadata::Package;
+use std::collections::BTreeSet;
+
+/// The features of `package` that turn on its optional dependency `dep`, directly
+/// (`dep:name`, `name`, `name/feature`) or through other features.
+pub fn activating(package: &Package, dep: &str) -> BTreeSet<String> {
+    package
+        .features
+        .keys()
+        .filter(|feature| enables(package, feature, dep, &mut BTreeSet::new()))
+        .cloned()
+        .collect()
+}
+
+fn enables<'a>(
+    package: &'a Package,
+    feature: &'a str,
+    dep: &str,
+    seen: &mut BTreeSet<&'a str>,
+) -> bool {
+    if !seen.insert(feature) {
+        return false;
+    }
+    let Some(values) = package.features.get(feature) else {
+        return false;
+    };
+    values.iter().any(|value| {
+        let target = value
+            .strip_prefix("dep:")
+            .unwrap_or_else(|| value.split('/').next().unwrap_or(value));
+        // `name?/feature` only forwards a feature; it doesn't enable the dependency.
+        target == dep || (!value.contains("?/") && enables(package, target, dep, seen))
+    })
+}
+
+/// The features of `package` enabled by a `--features`/`--all-features`/
+/// `--no-default-features` selection, including the ones they enable in turn.
+pub fn enabled(
+    package: &Package,
+    requested: &[String],
+    all: bool,
+    no_default: bool,
+) -> BTreeSet<String> {
+    if all {
+        return package.features.keys().cloned().collect();
+    }
+    let mut pending: Vec<String> = requested
+        .iter()
+        .flat_map(|features| features.split_whitespace())
+        .map(str::to_string)
+        .collect();
+    if !no_default && package.features.contains_key("default") {
+        pending.push("default".to_string());
+    }
+    let mut enabled = BTreeSet::new();
+    while let Some(feature) = pending.pop() {
+        if !enabled.insert(feature.clone()) {
+            continue;
+        }
+        for value in package.features.get(&feature).into_iter().flatten() {
+            if !value.starts_with("dep:") && !value.contains('/') {
+                pending.push(value.clone());
+            }
+        }
+    }
+    enabled
+}
@@ -0,0 +1,113 @@
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item, Table, TableLike};
+
+/// A manifest opened for format-preserving edits by `--fix`.
+pub struct ManifestEdit {
+    path: PathBuf,
+    doc: DocumentMut,
+}
+
+impl ManifestEdit {
+    pub fn open(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        Self::parse(path, &std::fs::read_to_string(path)?)
+    }
+
+    /// The manifest at `path` with `content` instead of what is on disk, such as an
+    /// editor's unsaved buffer.
+    pub fn parse(path: &Path, content: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            doc: content.parse::<DocumentMut>()?,
+        })
+    }
+
+    /// The table at a dotted path such as `["target", "cfg(unix)", "dependencies"]`,
+    /// creating missing tables along the way. Intermediate tables are implicit so they
+    /// don't show up as empty headers.
+    pub fn table(&mut self, path: &[&str]) -> Option<&mut dyn TableLike> {
+        let mut item = self.doc.as_item_mut();
+        for (i, key) in path.iter().enumerate() {
+            let table = item.as_table_like_mut()?;
+            if !table.contains_key(key) {
+                let mut created = Table::new();
+                created.set_implicit(i + 1 < path.len());
+                table.insert(key, Item::Table(created));
+            }
+            item = table.get_mut(key)?;
+        }
+        item.as_table_like_mut()
+    }
+
+    /// The dependency table `kind`, under `[target.<target>]` when given.
+    pub fn dependency_table(
+        &mut self,
+        target: Option<&str>,
+        kind: &str,
+    ) -> Option<&mut dyn TableLike> {
+        match target {
+            Some(target) => self.table(&["target", target, kind]),
+            None => self.table(&[kind]),
+        }
+    }
+
+    /// Removes the declaration of `krate` from the first of `[dependencies]`,
+    /// `[build-dependencies]` and their platform-specific tables that has one. Whether
+    /// one was removed.
+    pub fn remove_dependency(&mut self, krate: &str) -> bool {
+        let targets: Vec<String> = self
+            .doc
+            .get("target")
+            .and_then(|t| t.as_table_like())
+            .map(|t| t.iter().map(|(key, _)| key.to_string()).collect())
+            .unwrap_or_default();
+        for kind in ["dependencies", "build-dependencies"] {
+            let tables = std::iter::once(None).chain(targets.iter().map(Some));
+            for target in tables {
+                let table = match target {
+                    Some(target) => self.doc.get_mut("target").and_then(|t| t.get_mut(target)),
+                    None => Some(self.doc.as_item_mut()),
+                }
+                .and_then(|item| item.get_mut(kind))
+                .and_then(|item| item.as_table_like_mut());
+                let Some(table) = table else {
+                    continue;
+                };
+                if let Some(key) = entry_key(table, krate) {
+                    table.remove(&key);
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(&self.path, self.contents())?;
+        Ok(())
+    }
+
+    pub fn contents(&self) -> String {
+        self.doc.to_string()
+    }
+}
+
+/// Converts a dependency spec read with `toml` into an editable value, collapsing
+/// `{ version = "x" }` to the plain `"x"` form.
+pub fn spec_value(spec: &toml::Table) -> toml_edit::Value {
+    if let (1, Some(version)) = (spec.len(), spec.get("version").and_then(|v| v.as_str())) {
+        return version.into();
+    }
+    toml::Value::Table(spec.clone())
+        .to_string()
+        .parse()
+        .expect("a serialized toml table is a valid inline table")
+}
+
+/// The key declaring `krate`, which differs from the crate name when renamed with
+/// `package = "..."`.
+pub fn entry_key(table: &dyn TableLike, krate: &str) -> Option<String> {
+    table
+        .iter()
+        .find(|(key, item)| item.get("package").and_then(|p| p.as_str()).unwrap_or(key) == krate)
+        .map(|(key, _)| key.to_string())
+}
@@ -0,0 +1,36 @@
+use crate::project::{read_toml, Project};
+use cargo_metadata::{MetadataCommand, Package};
+
+/// The cargo-fuzz crate of the workspace: `fuzz/Cargo.toml` marked with
+/// `[package.metadata] cargo-fuzz = true`. It is usually its own workspace, so unless it
+/// is a member it is read with a separate `cargo metadata --no-deps`.
+pub fn package(project: &Project) -> Result<Option<Package>, Box<dyn std::error::Error>> {
+    let manifest_path = project.root().join("fuzz").join("Cargo.toml");
+    let Ok(manifest) = read_toml(&manifest_path) else {
+        return Ok(None);
+    };
+    let is_fuzz = manifest
+        .get("package")
+        .and_then(|p| p.get("metadata"))
+        .and_then(|m| m.get("cargo-fuzz"))
+        .and_then(|f| f.as_bool())
+        .unwrap_or(false);
+    if !is_fuzz {
+        return Ok(None);
+    }
+
+    if let Some(member) = project
+        .members()
+        .find(|pkg| pkg.manifest_path.as_std_path() == manifest_path)
+    {
+        return Ok(Some(member.clone()));
+    }
+    let metadata = MetadataCommand::new()
+        .manifest_path(&manifest_path)
+        .no_deps()
+        .exec()?;
+    Ok(metadata
+        .packages
+        .into_iter()
+        .find(|pkg| pkg.manifest_path.as_std_path() == manifest_path))
+}
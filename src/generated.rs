@@ -0,0 +1,50 @@
+/// How much of a file is searched for a generator's marker; they sit in the header.
+const HEADER_LINES: usize = 20;
+
+/// Whether a source file looks machine-generated: an `@generated` marker (prost, tonic,
+/// many build scripts), a bindgen or rust-protobuf banner, or a "do not edit" warning
+/// next to the word "generated".
+pub fn is_generated(content: &str) -> bool {
+    let header: String = content
+        .lines()
+        .take(HEADER_LINES)
+        .collect::<Vec<_>>()
+        .join("\n")
+        .to_lowercase();
+    header.contains("@generated")
+        || header.contains("automatically generated by rust-bindgen")
+        || header.contains("generated by rust-protobuf")
+        || header.contains("auto-generated")
+        || header.contains("automatically generated")
+        || (header.contains("generated") && header.contains("do not edit"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generator_markers_are_recognized() {
+        assert!(is_generated(
+            "// @generated by prost-build\npub struct A;\n"
+        ));
+        assert!(is_generated(
+            "/* automatically generated by rust-bindgen 0.69.4 */\n"
+        ));
+        assert!(is_generated(
+            "// This file is generated by build.rs.\n// Do not edit it by hand.\n"
+        ));
+        assert!(is_generated("// Auto-Generated from schema.json\n"));
+    }
+
+    #[test]
+    fn ordinary_sources_are_not_generated() {
+        assert!(!is_generated(
+            "//! Generated reports.\npub fn report() {}\n"
+        ));
+        assert!(!is_generated("// Do not edit without reading the docs.\n"));
+
+        let late = format!("{}// @generated\n", "fn f() {}\n".repeat(HEADER_LINES));
+        assert!(!is_generated(&late));
+    }
+}
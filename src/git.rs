@@ -0,0 +1,153 @@
+use crate::project::Project;
+use crate::registry::Registry;
+use crate::report::{Finding, Kind};
+use cargo_metadata::Package;
+use std::process::Command;
+
+/// A git source as cargo records it: `git+<url>?<reference>#<resolved commit>`.
+struct GitSource<'a> {
+    url: &'a str,
+    reference: Option<(&'a str, &'a str)>,
+    commit: Option<&'a str>,
+}
+
+fn parse_source(repr: &str) -> Option<GitSource<'_>> {
+    let rest = repr.strip_prefix("git+")?;
+    let (rest, commit) = match rest.split_once('#') {
+        Some((rest, commit)) => (rest, Some(commit)),
+        None => (rest, None),
+    };
+    let (url, reference) = match rest.split_once('?') {
+        Some((url, query)) => (url, query.split_once('=')),
+        None => (rest, None),
+    };
+    Some(GitSource {
+        url,
+        reference,
+        commit,
+    })
+}
+
+/// Reviews the git dependencies of workspace members: unpinned references always, and
+/// (unless offline) how far the locked commit is from upstream and whether crates.io
+/// has caught up.
+pub fn analyze(project: &Project, offline: bool) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut seen = Vec::new();
+    let progress = project.progress.bar("Checking git sources", None);
+
+    for member in project.members() {
+        for dep in &member.dependencies {
+            progress.inc();
+            let Some(source) = dep.source.as_deref().filter(|s| s.starts_with("git+")) else {
+                continue;
+            };
+            if seen.contains(&(&dep.name, source)) {
+                continue;
+            }
+            seen.push((&dep.name, source));
+
+            let resolved = project.metadata.packages.iter().find(|pkg| {
+                pkg.name == dep.name
+                    && pkg
+                        .source
+                        .as_ref()
+                        .is_some_and(|s| s.repr.starts_with(source))
+            });
+            let Some(git) = resolved
+                .and_then(|pkg| pkg.source.as_ref())
+                .and_then(|s| parse_source(&s.repr))
+            else {
+                continue;
+            };
+
+            if !matches!(git.reference, Some(("rev", _)) | Some(("tag", _))) {
+                let tracking = match git.reference {
+                    Some((_, branch)) => format!("branch \"{}\"", branch),
+                    None => "the default branch".to_string(),
+                };
+                findings.push(
+                    Finding::new(Kind::UnpinnedGitDependency, &dep.name)
+                        .member(project, member)
+                        .note(format!(
+                            "{} tracks {} of {}",
+                            member.name, tracking, git.url
+                        ))
+                        .note("Only Cargo.lock pins the commit; `cargo update` or a fresh lockfile will silently move it")
+                        .note("Pin it with `rev = \"...\"` or `tag = \"...\"`"),
+                );
+            }
+
+            if !offline {
+                findings.extend(drift(project, &dep.name, &git).map(|f| f.member(project, member)));
+                findings.extend(
+                    released(project, resolved.unwrap()).map(|f| f.member(project, member)),
+                );
+            }
+        }
+    }
+
+    findings
+}
+
+/// Compares the locked commit with the head of the tracked branch (or the default branch
+/// for pinned revisions).
+fn drift(project: &Project, name: &str, git: &GitSource) -> Option<Finding> {
+    let reference = match git.reference {
+        Some(("branch", branch)) => format!("refs/heads/{}", branch),
+        _ => "HEAD".to_string(),
+    };
+    let stdout = project
+        .cache
+        .fetch(&["git", git.url, &reference], || {
+            tracing::debug!("running git ls-remote {} {}", git.url, reference);
+            let output = Command::new("git")
+                .args(["ls-remote", git.url, &reference])
+                .output()?;
+            if !output.status.success() {
+                return Err(String::from_utf8_lossy(&output.stderr).into());
+            }
+            Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+        })
+        .ok()?;
+    let upstream = stdout.split_whitespace().next()?;
+    let locked = git.commit?;
+    if upstream.starts_with(locked) || locked.starts_with(upstream) {
+        return None;
+    }
+
+    let branch = match git.reference {
+        Some(("branch", branch)) => branch.to_string(),
+        _ => "the default branch".to_string(),
+    };
+    Some(
+        Finding::new(Kind::GitDrift, name)
+            .note(format!("Locked at {} from {}", short(locked), git.url))
+            .note(format!("Upstream {} is at {}", branch, short(upstream))),
+    )
+}
+
+/// Suggests switching to crates.io when a release at least as new as the git version
+/// has been published.
+fn released(project: &Project, pkg: &Package) -> Option<Finding> {
+    let registry = Registry::crates_io(&project.cargo_config, &project.cache).ok()??;
+    let latest = registry.latest_release(&pkg.name).ok()??;
+    if latest < pkg.version {
+        return None;
+    }
+    Some(
+        Finding::new(Kind::GitReleaseAvailable, &pkg.name)
+            .note(format!(
+                "The git checkout is version {}; crates.io now has {}",
+                pkg.version, latest
+            ))
+            .note(format!(
+                "Consider depending on {} = \"{}\" from crates.io instead",
+                pkg.name, latest
+            )),
+    )
+}
+
+fn short(commit: &str) -> &str {
+    &commit[..commit.len().min(10)]
+}
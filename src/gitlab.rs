@@ -0,0 +1,64 @@
+use crate::project::Project;
+use crate::report::{Finding, Severity};
+use serde_json::{json, Value};
+
+/// `--format gitlab`: a Code Quality report, which GitLab shows in the merge request
+/// widget. Findings are located where their file declares the crate, or at the first line
+/// of the file (the workspace manifest by default).
+pub fn render(project: &Project, findings: &[Finding]) -> String {
+    let root_manifest = project.display_path(&project.root().join("Cargo.toml"));
+
+    let issues: Vec<Value> = findings
+        .iter()
+        .map(|finding| {
+            let path = finding.file.as_deref().unwrap_or(&root_manifest);
+            let (line, column) = finding
+                .declaration
+                .as_ref()
+                .map_or((1, 1), |span| (span.line, span.column));
+            json!({
+                "description": format!(
+                    "{} ({}): {}",
+                    finding.krate,
+                    finding.kind.label(),
+                    finding.notes.first().map(|n| n.trim()).unwrap_or_default()
+                ),
+                "check_name": finding.kind,
+                "fingerprint": fingerprint(finding),
+                "severity": severity(finding.severity),
+                "location": {
+                    "path": path,
+                    "positions": { "begin": { "line": line, "column": column } },
+                },
+            })
+        })
+        .collect();
+    serde_json::to_string_pretty(&issues).unwrap_or_default()
+}
+
+fn severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "major",
+        Severity::Warning => "minor",
+        Severity::Info | Severity::Help => "info",
+    }
+}
+/// GitLab matches issues across pipelines by fingerprint, so it must be stable between
+/// runs and Rust versions: FNV-1a over the finding's kind id, crate, member and file.
+/// Notes carry versions, spans and counts that change from run to run, so they're left out.
+fn fingerprint(finding: &Finding) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let parts = [
+        finding.kind.id(),
+        &finding.krate,
+        finding.member.as_deref().unwrap_or(""),
+        finding.file.as_deref().unwrap_or(""),
+    ];
+    for part in parts {
+        for byte in part.bytes().chain([0]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    format!("{:016x}", hash)
+}
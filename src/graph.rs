@@ -0,0 +1,167 @@
+use cargo_metadata::{DependencyKind, Metadata, Package, PackageId};
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::{Bfs, EdgeFiltered, EdgeRef};
+use petgraph::Direction;
+use std::collections::HashMap;
+
+/// A package of the resolved graph.
+#[derive(Debug, Clone)]
+pub struct Node {
+    pub id: PackageId,
+    pub name: String,
+    pub version: cargo_metadata::semver::Version,
+    /// Whether the package is a workspace member.
+    pub member: bool,
+    /// Whether it comes from a registry or git source rather than a path.
+    pub external: bool,
+}
+
+/// One declaration of a dependency: a package depending on another twice, say as a
+/// normal and as a build dependency, has an edge for each.
+#[derive(Debug, Clone)]
+pub struct Edge {
+    pub kind: DependencyKind,
+    /// Whether the declaration is `optional = true` (and was enabled by a feature).
+    pub optional: bool,
+    /// The `cfg(...)` expression or target triple of a `[target.'...'.dependencies]`
+    /// table; `None` for dependencies of every platform.
+    pub target: Option<String>,
+    /// The name the dependent uses for it, after any rename.
+    pub name: String,
+}
+
+/// The resolved dependency graph of a workspace, with edges pointing from a package to
+/// its dependencies.
+pub struct DepGraph {
+    pub graph: DiGraph<Node, Edge>,
+    index: HashMap<PackageId, NodeIndex>,
+}
+
+impl DepGraph {
+    /// Builds the graph from `cargo metadata` output; it needs the resolve, so the
+    /// metadata must not have been fetched with `--no-deps`.
+    pub fn from_metadata(metadata: &Metadata) -> Result<Self, Box<dyn std::error::Error>> {
+        let resolve = metadata
+            .resolve
+            .as_ref()
+            .ok_or("the metadata has no resolved graph (was it fetched with --no-deps?)")?;
+        let packages: HashMap<&PackageId, &Package> =
+            metadata.packages.iter().map(|pkg| (&pkg.id, pkg)).collect();
+
+        let mut graph = DiGraph::new();
+        let mut index = HashMap::new();
+        for node in &resolve.nodes {
+            let Some(pkg) = packages.get(&node.id) else {
+                continue;
+            };
+            let i = graph.add_node(Node {
+                id: pkg.id.clone(),
+                name: pkg.name.clone(),
+                version: pkg.version.clone(),
+                member: metadata.workspace_members.contains(&pkg.id),
+                external: pkg.source.is_some(),
+            });
+            index.insert(pkg.id.clone(), i);
+        }
+
+        for node in &resolve.nodes {
+            let (Some(&from), Some(pkg)) = (index.get(&node.id), packages.get(&node.id)) else {
+                continue;
+            };
+            for dep in &node.deps {
+                let (Some(&to), Some(target)) = (index.get(&dep.pkg), packages.get(&dep.pkg))
+                else {
+                    continue;
+                };
+                for info in &dep.dep_kinds {
+                    let optional = pkg.dependencies.iter().any(|declared| {
+                        declared.kind == info.kind
+                            && declared.target == info.target
+                            && declared.optional
+                            && match &declared.rename {
+                                Some(rename) => rename.replace('-', "_") == dep.name,
+                                None => declared.name == target.name,
+                            }
+                    });
+                    graph.add_edge(
+                        from,
+                        to,
+                        Edge {
+                            kind: info.kind,
+                            optional,
+                            target: info.target.as_ref().map(ToString::to_string),
+                            name: dep.name.clone(),
+                        },
+                    );
+                }
+            }
+        }
+        Ok(DepGraph { graph, index })
+    }
+
+    /// The node of a package, when it is part of the resolved graph.
+    pub fn node(&self, id: &PackageId) -> Option<NodeIndex> {
+        self.index.get(id).copied()
+    }
+
+    /// The nodes of the packages called `name`, one per resolved version.
+    pub fn find<'a>(&'a self, name: &'a str) -> impl Iterator<Item = NodeIndex> + 'a {
+        self.graph
+            .node_indices()
+            .filter(move |&i| self.graph[i].name == name)
+    }
+
+    /// The workspace members.
+    pub fn members(&self) -> impl Iterator<Item = NodeIndex> + '_ {
+        self.graph.node_indices().filter(|&i| self.graph[i].member)
+    }
+
+    /// The direct dependencies of `node`, with the edge of each declaration.
+    pub fn dependencies(&self, node: NodeIndex) -> impl Iterator<Item = (NodeIndex, &Edge)> {
+        self.graph
+            .edges_directed(node, Direction::Outgoing)
+            .map(|edge| (edge.target(), edge.weight()))
+    }
+
+    /// The packages depending directly on `node`, with the edge of each declaration.
+    pub fn dependents(&self, node: NodeIndex) -> impl Iterator<Item = (NodeIndex, &Edge)> {
+        self.graph
+            .edges_directed(node, Direction::Incoming)
+            .map(|edge| (edge.source(), edge.weight()))
+    }
+
+    /// Everything `from` depends on, transitively, through the edges `follow` accepts;
+    /// `from` itself included. `|edge| edge.kind == DependencyKind::Normal` gives what a
+    /// normal build links.
+    pub fn reachable(&self, from: NodeIndex, follow: impl Fn(&Edge) -> bool) -> Vec<NodeIndex> {
+        let filtered = EdgeFiltered::from_fn(&self.graph, |edge| follow(edge.weight()));
+        let mut bfs = Bfs::new(&filtered, from);
+        let mut nodes = Vec::new();
+        while let Some(node) = bfs.next(&filtered) {
+            nodes.push(node);
+        }
+        nodes
+    }
+
+    /// A shortest chain of dependencies from `from` to `to`, both ends included.
+    pub fn path(&self, from: NodeIndex, to: NodeIndex) -> Option<Vec<NodeIndex>> {
+        let mut parent: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+        let mut bfs = Bfs::new(&self.graph, from);
+        while let Some(node) = bfs.next(&self.graph) {
+            if node == to {
+                let mut path = vec![to];
+                while let Some(&previous) = parent.get(path.last()?) {
+                    path.push(previous);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            for next in self.graph.neighbors_directed(node, Direction::Outgoing) {
+                if next != from {
+                    parent.entry(next).or_insert(node);
+                }
+            }
+        }
+        None
+    }
+}
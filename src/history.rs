@@ -0,0 +1,164 @@
+use crate::project::Project;
+use crate::report::{Finding, Kind};
+use crate::xtask;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// One `--record`ed run.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub recorded: String,
+    pub commit: Option<String>,
+    pub direct_deps: usize,
+    pub total_crates: usize,
+    pub unused: usize,
+    pub duplicates: usize,
+    pub findings: usize,
+    /// The size of the Rust sources in the build, in KiB: the proxy for build time the
+    /// impact estimates use. Missing from snapshots recorded before it was.
+    #[serde(default)]
+    pub source_kib: Option<usize>,
+}
+
+impl Snapshot {
+    pub fn take(project: &Project, findings: &[Finding]) -> Snapshot {
+        // Build tooling (xtask) isn't part of the product.
+        let tooling = xtask::members(project);
+        let direct: BTreeSet<&str> = project
+            .members()
+            .filter(|member| !tooling.contains(&member.name))
+            .flat_map(|member| &member.dependencies)
+            .filter(|dep| dep.path.is_none())
+            .map(|dep| dep.name.as_str())
+            .collect();
+
+        let mut versions: BTreeMap<&str, BTreeSet<String>> = BTreeMap::new();
+        for pkg in &project.metadata.packages {
+            versions
+                .entry(&pkg.name)
+                .or_default()
+                .insert(pkg.version.to_string());
+        }
+
+        Snapshot {
+            recorded: humantime::format_rfc3339_seconds(std::time::SystemTime::now()).to_string(),
+            commit: head_commit(project),
+            direct_deps: direct.len(),
+            total_crates: project.metadata.packages.len(),
+            unused: findings.iter().filter(|f| f.kind == Kind::Unused).count(),
+            duplicates: versions.values().filter(|v| v.len() > 1).count(),
+            findings: findings.len(),
+            source_kib: Some(
+                (crate::impact::source_bytes(&project.metadata.packages.iter().collect::<Vec<_>>())
+                    / 1024) as usize,
+            ),
+        }
+    }
+}
+
+/// The history lives next to the workspace manifest so it can be committed and
+/// compared across releases.
+fn store(project: &Project) -> PathBuf {
+    project.root().join(".dep-analysis").join("history.jsonl")
+}
+
+fn head_commit(project: &Project) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .current_dir(project.root())
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+pub fn record(project: &Project, findings: &[Finding]) -> Result<(), Box<dyn std::error::Error>> {
+    let path = store(project);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let snapshot = Snapshot::take(project, findings);
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+    writeln!(file, "{}", serde_json::to_string(&snapshot)?)?;
+    tracing::info!("Recorded snapshot in {}", path.display());
+    Ok(())
+}
+
+/// `history`: prints every recorded snapshot with the change since the previous one.
+pub fn print(project: &Project) -> Result<(), Box<dyn std::error::Error>> {
+    let path = store(project);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(_) => {
+            println!("No history recorded yet; run with --record to take a snapshot");
+            return Ok(());
+        }
+    };
+    let snapshots: Vec<Snapshot> = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+
+    println!("\nDependency History ({}):", path.display());
+    println!("==========================");
+    println!(
+        "{:<22} {:<10} {:>12} {:>12} {:>12} {:>12} {:>12} {:>16}",
+        "Recorded", "Commit", "Direct", "Total", "Unused", "Duplicates", "Findings", "Source KiB"
+    );
+
+    let mut previous: Option<&Snapshot> = None;
+    for snapshot in &snapshots {
+        let column = |value: usize, before: Option<usize>| match before {
+            Some(before) if before != value => {
+                format!("{} ({:+})", value, value as i64 - before as i64)
+            }
+            _ => value.to_string(),
+        };
+        let source = match snapshot.source_kib {
+            Some(kib) => column(kib, previous.and_then(|p| p.source_kib)),
+            None => "-".to_string(),
+        };
+        println!(
+            "{:<22} {:<10} {:>12} {:>12} {:>12} {:>12} {:>12} {:>16}",
+            snapshot.recorded,
+            snapshot.commit.as_deref().unwrap_or("-"),
+            column(snapshot.direct_deps, previous.map(|p| p.direct_deps)),
+            column(snapshot.total_crates, previous.map(|p| p.total_crates)),
+            column(snapshot.unused, previous.map(|p| p.unused)),
+            column(snapshot.duplicates, previous.map(|p| p.duplicates)),
+            column(snapshot.findings, previous.map(|p| p.findings)),
+            source,
+        );
+        previous = Some(snapshot);
+    }
+
+    if let (Some(first), Some(last)) = (snapshots.first(), snapshots.last()) {
+        if snapshots.len() > 1 {
+            let source = match (first.source_kib, last.source_kib) {
+                (Some(first), Some(last)) => {
+                    format!(", source {:+} KiB", last as i64 - first as i64)
+                }
+                _ => String::new(),
+            };
+            println!(
+                "\nSince {}: direct deps {:+}, total crates {:+}, unused {:+}, duplicates {:+}{}",
+                first.recorded,
+                last.direct_deps as i64 - first.direct_deps as i64,
+                last.total_crates as i64 - first.total_crates as i64,
+                last.unused as i64 - first.unused as i64,
+                last.duplicates as i64 - first.duplicates as i64,
+                source
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,160 @@
+use crate::cli::Args;
+use crate::project::Project;
+use cargo_metadata::{DependencyKind, Node, Package, PackageId};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Which side of the build a crate is compiled for.
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    /// The code the members ship, their tests included.
+    Target,
+    /// Build scripts, proc-macros and everything they use, run on the build machine.
+    Host,
+}
+
+/// `host-target`: the crates compiled twice because both the target code and build-time
+/// code (build scripts and proc-macros) need them, how much of the build they account
+/// for, and the direct dependencies of the members that put them on the host side.
+pub fn run(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let project = Project::load(args.manifest_path.as_deref())?;
+    let nodes: BTreeMap<&PackageId, &Node> = project
+        .metadata
+        .resolve
+        .iter()
+        .flat_map(|resolve| &resolve.nodes)
+        .map(|node| (&node.id, node))
+        .collect();
+    let members: Vec<&PackageId> = project.metadata.workspace_members.iter().collect();
+
+    let (target, host) = sides(&project, &nodes, &members, false);
+    let duplicated: BTreeSet<&PackageId> = target.intersection(&host).copied().collect();
+    let packages = |ids: &BTreeSet<&PackageId>| -> Vec<&Package> {
+        ids.iter().filter_map(|id| project.package(id)).collect()
+    };
+    let units = target.len() + host.len();
+    let all: BTreeSet<&PackageId> = target.union(&host).copied().collect();
+    let bytes = crate::impact::source_bytes(&packages(&all));
+    let duplicated_bytes = crate::impact::source_bytes(&packages(&duplicated));
+
+    println!(
+        "\nCrates compiled for both the target and the host: {} of {} ({} compilations \
+         instead of {})",
+        duplicated.len(),
+        all.len(),
+        units,
+        all.len()
+    );
+    if let Some(share) = (duplicated_bytes * 100).checked_div(bytes) {
+        println!(
+            "They hold {}% of the sources built ({} of {} KiB), compiled a second time.",
+            share,
+            duplicated_bytes / 1024,
+            bytes / 1024
+        );
+    }
+    if duplicated.is_empty() {
+        return Ok(());
+    }
+    // Build scripts and proc-macros get the `build-override` profile, which differs from
+    // the target's (no debuginfo, and no optimization in release), so nothing is shared.
+    println!(
+        "Build-time code is compiled with `[profile.*.build-override]`, so cargo can't share \
+         these between the two sides{}.",
+        if args.target.is_some() {
+            ", nor across the --target boundary"
+        } else {
+            ""
+        }
+    );
+    for pkg in packages(&duplicated) {
+        println!("  {} {}", pkg.name, pkg.version);
+    }
+
+    println!("\nDirect dependencies putting them on the host:");
+    let mut causes: Vec<(String, Vec<&str>)> = Vec::new();
+    for member in &members {
+        let (Some(node), Some(pkg)) = (nodes.get(member), project.package(member)) else {
+            continue;
+        };
+        for dep in &node.deps {
+            let Some(dep_pkg) = project.package(&dep.pkg) else {
+                continue;
+            };
+            let build = dep
+                .dep_kinds
+                .iter()
+                .any(|k| k.kind == DependencyKind::Build);
+            let (_, host) = sides(&project, &nodes, &[&dep.pkg], build);
+            let caused: Vec<&str> = host
+                .intersection(&duplicated)
+                .filter_map(|id| project.package(id))
+                .map(|pkg| pkg.name.as_str())
+                .collect();
+            if caused.is_empty() {
+                continue;
+            }
+            let table = if build {
+                "build-dependency"
+            } else {
+                "dependency"
+            };
+            causes.push((
+                format!("{} ({} of {})", dep_pkg.name, table, pkg.name),
+                caused,
+            ));
+        }
+    }
+    causes.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(&b.0)));
+    for (cause, caused) in causes {
+        println!(
+            "  {}: {} crate{} ({})",
+            cause,
+            caused.len(),
+            if caused.len() == 1 { "" } else { "s" },
+            caused.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// The crates compiled for the target and for the host starting from `roots`, which are
+/// on the host side themselves when `host` is set. Members' dev-dependencies count;
+/// those of other packages aren't built.
+fn sides<'a>(
+    project: &'a Project,
+    nodes: &BTreeMap<&'a PackageId, &'a Node>,
+    roots: &[&'a PackageId],
+    host: bool,
+) -> (BTreeSet<&'a PackageId>, BTreeSet<&'a PackageId>) {
+    let mut target = BTreeSet::new();
+    let mut on_host = BTreeSet::new();
+    let side = if host { Side::Host } else { Side::Target };
+    let mut pending: Vec<(&PackageId, Side)> = roots.iter().map(|id| (*id, side)).collect();
+    while let Some((id, side)) = pending.pop() {
+        let proc_macro = project.package(id).is_some_and(|pkg| {
+            pkg.targets
+                .iter()
+                .any(|t| t.kind.iter().any(|k| k == "proc-macro"))
+        });
+        // A proc-macro runs in the compiler, wherever it's used from.
+        let side = if proc_macro { Side::Host } else { side };
+        let seen = match side {
+            Side::Target => &mut target,
+            Side::Host => &mut on_host,
+        };
+        if !seen.insert(id) {
+            continue;
+        }
+        let member = project.metadata.workspace_members.contains(id);
+        for dep in nodes.get(id).into_iter().flat_map(|node| &node.deps) {
+            for kind in &dep.dep_kinds {
+                match kind.kind {
+                    DependencyKind::Build => pending.push((&dep.pkg, Side::Host)),
+                    DependencyKind::Development if !member => {}
+                    _ => pending.push((&dep.pkg, side)),
+                }
+            }
+        }
+    }
+    (target, on_host)
+}
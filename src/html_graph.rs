@@ -0,0 +1,235 @@
+use crate::project::Project;
+use crate::report::{Finding, Kind};
+use crate::serve::escape;
+use cargo_dep_analysis::graph::DepGraph;
+use cargo_metadata::DependencyKind;
+use std::collections::{HashMap, HashSet};
+
+/// `--format html-graph`: a self-contained page drawing the resolved graph with a
+/// force-directed layout. It can be zoomed and panned, narrowed to what one member
+/// depends on, and highlights crates reported unused and crates resolved at several
+/// versions.
+pub fn render(
+    project: &Project,
+    findings: &[Finding],
+) -> Result<String, Box<dyn std::error::Error>> {
+    let graph = DepGraph::from_metadata(&project.metadata)?;
+    let unused: HashSet<&str> = findings
+        .iter()
+        .filter(|f| f.kind == Kind::Unused)
+        .map(|f| f.krate.as_str())
+        .collect();
+    let mut versions: HashMap<&str, usize> = HashMap::new();
+    for node in graph.graph.node_weights() {
+        *versions.entry(node.name.as_str()).or_default() += 1;
+    }
+
+    let nodes: Vec<_> = graph
+        .graph
+        .node_weights()
+        .map(|node| {
+            serde_json::json!({
+                "name": node.name,
+                "version": node.version.to_string(),
+                "member": node.member,
+                "unused": unused.contains(node.name.as_str()),
+                "duplicate": versions[node.name.as_str()] > 1,
+            })
+        })
+        .collect();
+    let edges: Vec<_> = graph
+        .graph
+        .edge_indices()
+        .filter_map(|edge| {
+            let (from, to) = graph.graph.edge_endpoints(edge)?;
+            let kind = match graph.graph[edge].kind {
+                DependencyKind::Development => "dev",
+                DependencyKind::Build => "build",
+                _ => "normal",
+            };
+            Some(serde_json::json!([from.index(), to.index(), kind]))
+        })
+        .collect();
+    let data = serde_json::json!({ "nodes": nodes, "edges": edges })
+        .to_string()
+        // Keeps a crate name from closing the script element.
+        .replace("</", "<\\/");
+    let title = format!(
+        "Dependency graph of {}",
+        project
+            .root()
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    );
+
+    Ok(format!(
+        r##"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ margin: 0; font-family: sans-serif; overflow: hidden; }}
+#controls {{ position: absolute; top: 0; left: 0; right: 0; padding: 8px; background: rgba(255, 255, 255, 0.9); border-bottom: 1px solid #ccc; }}
+#controls label {{ margin-left: 1em; }}
+#legend span {{ display: inline-block; width: 10px; height: 10px; border-radius: 5px; margin: 0 4px 0 1em; }}
+canvas {{ display: block; cursor: grab; }}
+</style>
+</head>
+<body>
+<div id="controls">
+<strong>{title}</strong>
+<label>Member <select id="member"><option value="">All</option></select></label>
+<label><input type="checkbox" id="dev"> Dev dependencies</label>
+<label><input type="checkbox" id="unused" checked> Highlight unused</label>
+<label><input type="checkbox" id="duplicate" checked> Highlight duplicates</label>
+<span id="legend"><span style="background: #36c"></span>member<span style="background: #d33"></span>unused<span style="background: #e90"></span>duplicate</span>
+<span id="count"></span>
+</div>
+<canvas id="graph"></canvas>
+<script>
+const data = {data};
+const canvas = document.getElementById("graph");
+const ctx = canvas.getContext("2d");
+const nodes = data.nodes.map((node, i) => ({{ ...node, x: Math.cos(i) * i * 2, y: Math.sin(i) * i * 2, vx: 0, vy: 0 }}));
+const edges = data.edges.map(([from, to, kind]) => ({{ from, to, kind }}));
+let visible = new Set();
+let view = {{ x: 0, y: 0, scale: 1 }};
+let heat = 1;
+let hovered = null;
+
+const memberSelect = document.getElementById("member");
+nodes.forEach((node, i) => {{
+  if (node.member) memberSelect.add(new Option(node.name, i));
+}});
+
+function shownEdges() {{
+  const dev = document.getElementById("dev").checked;
+  return edges.filter(e => (dev || e.kind !== "dev") && visible.has(e.from) && visible.has(e.to));
+}}
+
+function select() {{
+  const dev = document.getElementById("dev").checked;
+  const start = memberSelect.value === ""
+    ? [...nodes.keys()].filter(i => nodes[i].member)
+    : [Number(memberSelect.value)];
+  visible = new Set(start);
+  const pending = [...start];
+  while (pending.length) {{
+    const from = pending.pop();
+    for (const e of edges) {{
+      if (e.from !== from || visible.has(e.to)) continue;
+      // Dev dependencies only count for the members themselves, as with cargo.
+      if (e.kind === "dev" && (!dev || !start.includes(from))) continue;
+      visible.add(e.to);
+      pending.push(e.to);
+    }}
+  }}
+  document.getElementById("count").textContent = visible.size + " crates";
+  heat = 1;
+}}
+
+function tick() {{
+  const shown = [...visible].map(i => nodes[i]);
+  for (const a of shown) {{
+    for (const b of shown) {{
+      if (a === b) continue;
+      const dx = a.x - b.x, dy = a.y - b.y;
+      const distance2 = Math.max(dx * dx + dy * dy, 1);
+      a.vx += dx / distance2 * 60;
+      a.vy += dy / distance2 * 60;
+    }}
+  }}
+  for (const e of shownEdges()) {{
+    const a = nodes[e.from], b = nodes[e.to];
+    const dx = b.x - a.x, dy = b.y - a.y;
+    const distance = Math.max(Math.hypot(dx, dy), 1);
+    const pull = (distance - 60) * 0.02;
+    a.vx += dx / distance * pull; a.vy += dy / distance * pull;
+    b.vx -= dx / distance * pull; b.vy -= dy / distance * pull;
+  }}
+  for (const node of shown) {{
+    node.vx -= node.x * 0.002;
+    node.vy -= node.y * 0.002;
+    node.x += node.vx * heat;
+    node.y += node.vy * heat;
+    node.vx *= 0.5;
+    node.vy *= 0.5;
+  }}
+  heat *= 0.99;
+}}
+
+function color(node) {{
+  if (document.getElementById("unused").checked && node.unused) return "#d33";
+  if (document.getElementById("duplicate").checked && node.duplicate) return "#e90";
+  return node.member ? "#36c" : "#999";
+}}
+
+function draw() {{
+  canvas.width = window.innerWidth;
+  canvas.height = window.innerHeight;
+  ctx.setTransform(view.scale, 0, 0, view.scale, canvas.width / 2 + view.x, canvas.height / 2 + view.y);
+  ctx.lineWidth = 1 / view.scale;
+  for (const e of shownEdges()) {{
+    const a = nodes[e.from], b = nodes[e.to];
+    ctx.strokeStyle = e.kind === "normal" ? "#ccc" : "#e5e5ff";
+    ctx.beginPath(); ctx.moveTo(a.x, a.y); ctx.lineTo(b.x, b.y); ctx.stroke();
+  }}
+  for (const i of visible) {{
+    const node = nodes[i];
+    ctx.fillStyle = color(node);
+    ctx.beginPath(); ctx.arc(node.x, node.y, node.member ? 7 : 5, 0, 2 * Math.PI); ctx.fill();
+    if (node.member || node === hovered || view.scale > 1.5) {{
+      ctx.fillStyle = "#333";
+      ctx.font = 12 / view.scale + "px sans-serif";
+      ctx.fillText(node === hovered ? node.name + " " + node.version : node.name, node.x + 8, node.y + 4);
+    }}
+  }}
+}}
+
+function frame() {{
+  if (heat > 0.01) tick();
+  draw();
+  requestAnimationFrame(frame);
+}}
+
+function toGraph(event) {{
+  return {{
+    x: (event.clientX - canvas.width / 2 - view.x) / view.scale,
+    y: (event.clientY - canvas.height / 2 - view.y) / view.scale,
+  }};
+}}
+
+let dragging = null;
+canvas.addEventListener("mousedown", event => {{ dragging = {{ x: event.clientX, y: event.clientY }}; }});
+window.addEventListener("mouseup", () => {{ dragging = null; }});
+canvas.addEventListener("mousemove", event => {{
+  if (dragging) {{
+    view.x += event.clientX - dragging.x;
+    view.y += event.clientY - dragging.y;
+    dragging = {{ x: event.clientX, y: event.clientY }};
+  }}
+  const point = toGraph(event);
+  hovered = [...visible].map(i => nodes[i]).find(node => Math.hypot(node.x - point.x, node.y - point.y) < 8 / view.scale) || null;
+}});
+canvas.addEventListener("wheel", event => {{
+  event.preventDefault();
+  const before = toGraph(event);
+  view.scale *= Math.exp(-event.deltaY * 0.001);
+  const after = toGraph(event);
+  view.x += (after.x - before.x) * view.scale;
+  view.y += (after.y - before.y) * view.scale;
+}}, {{ passive: false }});
+for (const id of ["member", "dev"]) document.getElementById(id).addEventListener("change", select);
+
+select();
+frame();
+</script>
+</body>
+</html>
+"##,
+        title = escape(&title),
+        data = data
+    ))
+}
@@ -0,0 +1,33 @@
+/// A Rust identifier: Unicode ones (allowed since Rust 1.53) and raw ones (`r#async`)
+/// included.
+pub const PATTERN: &str = r"(?:r#)?[\p{XID_Start}_]\p{XID_Continue}*";
+
+/// The name an identifier refers to, without the `r#` of a raw identifier.
+pub fn unraw(ident: &str) -> &str {
+    ident.strip_prefix("r#").unwrap_or(ident)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+
+    #[test]
+    fn pattern_matches_whole_identifiers() {
+        let re = Regex::new(&format!("^{}$", PATTERN)).unwrap();
+        for ident in ["foo", "_x", "x1", "r#async", "größe", "Ωmega"] {
+            assert!(re.is_match(ident), "{}", ident);
+        }
+        for not_ident in ["", "1x", "r#", "a-b", "foo::bar", "#foo"] {
+            assert!(!re.is_match(not_ident), "{}", not_ident);
+        }
+    }
+
+    #[test]
+    fn unraw_strips_only_the_raw_prefix() {
+        assert_eq!(unraw("r#async"), "async");
+        assert_eq!(unraw("foo"), "foo");
+        assert_eq!(unraw("r#"), "");
+        assert_eq!(unraw("rust"), "rust");
+    }
+}
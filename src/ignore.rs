@@ -0,0 +1,120 @@
+use crate::project::Project;
+use cargo_metadata::{DependencyKind, Package};
+use std::collections::{BTreeMap, HashSet};
+
+/// The dependencies of a package never to report as unused, with the tool whose setting
+/// says so.
+pub struct Ignored {
+    /// cargo-udeps' lists, each of which only covers the dependency kind it's under.
+    udeps: HashSet<(DependencyKind, String)>,
+    /// The lists covering every kind.
+    any: BTreeMap<String, &'static str>,
+}
+
+impl Ignored {
+    /// The tool keeping `name` out of the report when it's declared with `kinds`: one
+    /// ignoring it whatever the kind, or cargo-udeps when it lists it under every one.
+    pub fn get(
+        &self,
+        name: &str,
+        kinds: impl IntoIterator<Item = DependencyKind>,
+    ) -> Option<&'static str> {
+        if let Some(tool) = self.any.get(name) {
+            return Some(tool);
+        }
+        let mut kinds = kinds.into_iter().peekable();
+        let listed = kinds.peek().is_some()
+            && kinds.all(|kind| self.udeps.contains(&(kind, name.to_string())));
+        listed.then_some("cargo-udeps")
+    }
+}
+
+/// The dependencies of `package` never to report as unused. Besides our own `ignore`
+/// (workspace-wide in the configuration, or in `[package.metadata.dep-analysis]` of the
+/// package), the lists of cargo-machete (`[package.metadata.cargo-machete] ignored`) and
+/// cargo-udeps (`[package.metadata.cargo-udeps.ignore] normal/development/build`) are
+/// honored, so teams coming from those tools keep their suppressions.
+pub fn ignored(project: &Project, package: &Package) -> Ignored {
+    let metadata = &package.metadata;
+    let names = |value: Option<&serde_json::Value>| -> Vec<String> {
+        value
+            .and_then(|v| v.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|name| name.as_str().map(str::to_string))
+            .collect()
+    };
+
+    let mut udeps = HashSet::new();
+    let lists = &metadata["cargo-udeps"]["ignore"];
+    for (list, kind) in [
+        ("normal", DependencyKind::Normal),
+        ("development", DependencyKind::Development),
+        ("build", DependencyKind::Build),
+    ] {
+        for name in names(lists.get(list)) {
+            udeps.insert((kind, name));
+        }
+    }
+    let mut any = BTreeMap::new();
+    for name in names(metadata["cargo-machete"].get("ignored")) {
+        any.insert(name, "cargo-machete");
+    }
+    for name in names(metadata["dep-analysis"].get("ignore"))
+        .into_iter()
+        .chain(project.config.ignore.iter().cloned())
+    {
+        any.insert(name, "dep-analysis");
+    }
+    Ignored { udeps, any }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::fixture;
+
+    #[test]
+    fn udeps_lists_only_cover_their_kind() {
+        let (_dir, project) = fixture(&[
+            (
+                "Cargo.toml",
+                r#"[package]
+name = "app"
+version = "0.1.0"
+edition = "2021"
+
+[package.metadata.cargo-udeps.ignore]
+normal = ["util"]
+development = ["helper"]
+
+[package.metadata.cargo-machete]
+ignored = ["shim"]
+"#,
+            ),
+            ("src/lib.rs", ""),
+        ]);
+        let app = project.members().next().unwrap();
+        let ignored = ignored(&project, app);
+
+        assert_eq!(
+            ignored.get("util", [DependencyKind::Normal]),
+            Some("cargo-udeps")
+        );
+        assert_eq!(ignored.get("util", [DependencyKind::Development]), None);
+        assert_eq!(
+            ignored.get("util", [DependencyKind::Normal, DependencyKind::Build]),
+            None
+        );
+        assert_eq!(ignored.get("helper", [DependencyKind::Normal]), None);
+        assert_eq!(
+            ignored.get("helper", [DependencyKind::Development]),
+            Some("cargo-udeps")
+        );
+        assert_eq!(
+            ignored.get("shim", [DependencyKind::Build]),
+            Some("cargo-machete")
+        );
+        assert_eq!(ignored.get("other", [DependencyKind::Normal]), None);
+    }
+}
@@ -0,0 +1,193 @@
+use crate::project::Project;
+use crate::report::{Finding, Kind};
+use cargo_metadata::{Package, PackageId};
+use std::collections::{BTreeSet, HashMap};
+
+/// The packages that would leave the dependency graph if `member` stopped depending on
+/// `dep`: reachable from the workspace members now, but not without that edge.
+pub fn exclusive_crates<'a>(project: &'a Project, member: &Package, dep: &str) -> Vec<&'a Package> {
+    let Some(target) = project
+        .metadata
+        .resolve
+        .iter()
+        .flat_map(|resolve| &resolve.nodes)
+        .find(|node| node.id == member.id)
+        .and_then(|node| {
+            node.deps
+                .iter()
+                .find(|d| name_of(project, &d.pkg) == Some(dep))
+        })
+        .map(|d| &d.pkg)
+    else {
+        return Vec::new();
+    };
+    dropped_without(project, &BTreeSet::from([(&member.id, target)]))
+}
+
+/// The packages that would leave the dependency graph without the `edges` (from, to):
+/// reachable from the workspace members now, but not without them.
+pub fn dropped_without<'a>(
+    project: &'a Project,
+    edges: &BTreeSet<(&PackageId, &PackageId)>,
+) -> Vec<&'a Package> {
+    let Some(resolve) = &project.metadata.resolve else {
+        return Vec::new();
+    };
+    let graph: HashMap<&PackageId, Vec<&PackageId>> = resolve
+        .nodes
+        .iter()
+        .map(|node| (&node.id, node.deps.iter().map(|d| &d.pkg).collect()))
+        .collect();
+
+    let reachable = |skip: &BTreeSet<(&PackageId, &PackageId)>| {
+        let mut seen = BTreeSet::new();
+        let mut pending: Vec<&PackageId> = project.metadata.workspace_members.iter().collect();
+        while let Some(id) = pending.pop() {
+            if !seen.insert(id) {
+                continue;
+            }
+            for next in graph.get(id).into_iter().flatten() {
+                if !skip.contains(&(id, *next)) {
+                    pending.push(next);
+                }
+            }
+        }
+        seen
+    };
+    let with = reachable(&BTreeSet::new());
+    let without = reachable(edges);
+    with.difference(&without)
+        .filter_map(|id| project.package(id))
+        .collect()
+}
+
+/// Adds to each unused-dependency finding what removing the dependency takes out of the
+/// build: the crates only it brings in, the build scripts among them and the native
+/// libraries they link.
+pub fn annotate(project: &Project, findings: &mut [Finding]) {
+    for finding in findings.iter_mut().filter(|f| f.kind == Kind::Unused) {
+        let Some(member) = project
+            .metadata
+            .packages
+            .iter()
+            .find(|pkg| pkg.source.is_none() && Some(&pkg.name) == finding.member.as_ref())
+        else {
+            continue;
+        };
+        let removed = exclusive_crates(project, member, &finding.krate);
+        if removed.is_empty() {
+            finding.notes.push(
+                "Removing it takes no crates out of the build: others depend on it too".into(),
+            );
+            continue;
+        }
+        let names = |packages: Vec<&Package>| {
+            packages
+                .iter()
+                .map(|pkg| format!("{} {}", pkg.name, pkg.version))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        finding.notes.push(format!(
+            "Removing it takes {} crate{} out of the build: {}",
+            removed.len(),
+            if removed.len() == 1 { "" } else { "s" },
+            names(removed.clone())
+        ));
+        let build_scripts: Vec<&Package> = removed
+            .iter()
+            .copied()
+            .filter(|pkg| {
+                pkg.targets
+                    .iter()
+                    .any(|t| t.kind.iter().any(|k| k == "custom-build"))
+            })
+            .collect();
+        if !build_scripts.is_empty() {
+            finding.notes.push(format!(
+                "Build scripts that would no longer run: {}",
+                names(build_scripts)
+            ));
+        }
+        let links: Vec<&str> = removed
+            .iter()
+            .filter_map(|pkg| pkg.links.as_deref())
+            .collect();
+        if !links.is_empty() {
+            finding.notes.push(format!(
+                "Native libraries that would no longer be linked: {}",
+                links.join(", ")
+            ));
+        }
+    }
+}
+
+/// The size of the Rust sources of `packages`, a rough proxy for the build time they
+/// cost; sources that aren't on disk yet count as nothing. Each package's directory is
+/// walked without what isn't its own source: nested packages (other members, vendored
+/// crates), which count on their own, the `target` directory and hidden directories.
+pub fn source_bytes(packages: &[&Package]) -> u64 {
+    packages
+        .iter()
+        .filter_map(|pkg| pkg.manifest_path.parent())
+        .flat_map(|dir| {
+            walkdir::WalkDir::new(dir)
+                .into_iter()
+                .filter_entry(|entry| {
+                    if entry.depth() == 0 || !entry.file_type().is_dir() {
+                        return true;
+                    }
+                    let name = entry.file_name().to_string_lossy();
+                    let skipped = name.starts_with('.')
+                        || (entry.depth() == 1 && name == "target")
+                        || entry.path().join("Cargo.toml").is_file();
+                    !skipped
+                })
+                .filter_map(Result::ok)
+        })
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "rs"))
+        .filter_map(|entry| entry.metadata().ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+fn name_of<'a>(project: &'a Project, id: &PackageId) -> Option<&'a str> {
+    project.package(id).map(|pkg| pkg.name.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::fixture;
+
+    #[test]
+    fn source_bytes_counts_each_package_once() {
+        let (_dir, project) = fixture(&[
+            (
+                "Cargo.toml",
+                "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n\
+                 [workspace]\nmembers = [\"util\"]\n",
+            ),
+            ("src/lib.rs", "0123456789"),
+            ("src/target/mod.rs", "01234"),
+            (
+                "util/Cargo.toml",
+                "[package]\nname = \"util\"\nversion = \"0.1.0\"\n",
+            ),
+            ("util/src/lib.rs", "0123456789012345678"),
+            // Not part of any package's sources.
+            ("target/debug/build/out/generated.rs", "0123456789"),
+            (
+                "vendor/dep/Cargo.toml",
+                "[package]\nname = \"dep\"\nversion = \"1.0.0\"\n",
+            ),
+            ("vendor/dep/src/lib.rs", "0123456789"),
+            (".git/hooks/check.rs", "0123456789"),
+        ]);
+        let app = project.members().find(|pkg| pkg.name == "app").unwrap();
+        let util = project.members().find(|pkg| pkg.name == "util").unwrap();
+        assert_eq!(source_bytes(&[app]), 15);
+        assert_eq!(source_bytes(&[util]), 19);
+        assert_eq!(source_bytes(&[app, util]), 34);
+    }
+}
@@ -0,0 +1,52 @@
+use crate::outdated;
+use crate::project::Project;
+use crate::report::{Finding, Kind};
+use cargo_metadata::semver::Version;
+use std::process::Command;
+
+/// Asks cargo's resolver which locked packages `cargo update` would move without
+/// touching any version requirement (a `--dry-run`, so the lockfile stays as it is), and
+/// reports each with the size of its update.
+pub fn analyze(project: &Project) -> Vec<Finding> {
+    let output = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".into()))
+        .args(["update", "--dry-run", "--manifest-path"])
+        .arg(project.root().join("Cargo.toml"))
+        .output();
+    let output = match output {
+        Ok(output) if output.status.success() => output,
+        Ok(output) => {
+            tracing::warn!(
+                "`cargo update --dry-run` failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            return Vec::new();
+        }
+        Err(err) => {
+            tracing::warn!("could not run `cargo update --dry-run`: {}", err);
+            return Vec::new();
+        }
+    };
+
+    let lockfile = project.root().join("Cargo.lock");
+    // Cargo reports the changes on stderr: `Updating serde v1.0.100 -> v1.0.152`.
+    String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .filter_map(|line| {
+            let rest = line.trim().strip_prefix("Updating ")?;
+            let (name, rest) = rest.split_once(" v")?;
+            let (from, to) = rest.split_once(" -> v")?;
+            let from: Version = from.trim().parse().ok()?;
+            let to: Version = to.split_whitespace().next()?.parse().ok()?;
+            let update = outdated::classify(&from, &to);
+            let mut finding = Finding::new(Kind::InRangeUpdate, name)
+                .file(project, &lockfile)
+                .note(format!(
+                    "Locked at {}; refreshing the lockfile would move it to {} ({} update)",
+                    from, to, update
+                ))
+                .note(format!("Run `cargo update -p {}@{}`", name, from));
+            finding.update = Some(update);
+            Some(finding)
+        })
+        .collect()
+}
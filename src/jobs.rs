@@ -0,0 +1,66 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// How many CPU-bound tasks run at once when `-j` isn't given: one per core.
+pub fn default_jobs() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Calls `f` on every item, at most `jobs` at a time, and returns the results in the
+/// order of `items`, one per item. With one job everything runs on the calling thread. A
+/// panic in `f` panics the caller, once every worker has stopped.
+pub fn map<T: Sync, R: Send>(jobs: usize, items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let jobs = jobs.clamp(1, items.len().max(1));
+    if jobs == 1 {
+        return items.iter().map(f).collect();
+    }
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new(items.iter().map(|_| None).collect());
+    std::thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(index) else {
+                    break;
+                };
+                let result = f(item);
+                results.lock().expect("a worker panicked")[index] = Some(result);
+            });
+        }
+    });
+    // The scope re-raises a worker's panic, so every slot is filled by now.
+    results
+        .into_inner()
+        .expect("a worker panicked")
+        .into_iter()
+        .map(|result| result.expect("every item was processed"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_the_order_of_the_items() {
+        let items: Vec<usize> = (0..100).collect();
+        assert_eq!(
+            map(4, &items, |n| n * 2),
+            (0..200).step_by(2).collect::<Vec<_>>()
+        );
+        assert_eq!(map(1, &items, |n| n * 2), map(8, &items, |n| n * 2));
+    }
+
+    #[test]
+    #[should_panic]
+    fn propagates_a_worker_panic() {
+        let items: Vec<usize> = (0..10).collect();
+        map(4, &items, |n| {
+            if *n == 7 {
+                panic!("task {} failed", n)
+            } else {
+                *n
+            }
+        });
+    }
+}
@@ -0,0 +1,198 @@
+use crate::project::Project;
+use crate::report::{Evidence, Finding, Kind, Severity, Span};
+use serde_json::{json, Value};
+
+/// `--format json-diagnostic`: a finding as cargo reports a compiler message, a
+/// `compiler-message` wrapping a diagnostic in rustc's JSON shape, so tools reading `cargo
+/// check --message-format json` can take findings unchanged. The primary span is the
+/// crate's declaration, usage sites are secondary spans, notes are child diagnostics, and
+/// removing an unused dependency is offered as a suggestion. One message per line.
+pub fn render(project: &Project, findings: &[Finding]) -> String {
+    findings
+        .iter()
+        .map(|finding| format!("{}\n", message(project, finding)))
+        .collect()
+}
+
+fn message(project: &Project, finding: &Finding) -> Value {
+    let package = finding
+        .member
+        .as_deref()
+        .and_then(|name| project.members().find(|pkg| pkg.name == name))
+        .or_else(|| project.metadata.root_package())
+        .or_else(|| project.members().next());
+    let target = package.and_then(|pkg| {
+        pkg.targets
+            .iter()
+            .find(|t| crate::targets::is_lib(t))
+            .or(pkg.targets.first())
+    });
+
+    let mut spans = Vec::new();
+    if let Some(declaration) = &finding.declaration {
+        spans.extend(span(project, declaration, None, true, None));
+    }
+    let ident = finding.krate.replace('-', "_");
+    for usage in &finding.usages {
+        let width = Some(ident.chars().count());
+        spans.extend(span(project, usage, width, false, Some("used here")));
+    }
+    let mut children: Vec<Value> = finding
+        .notes
+        .iter()
+        .map(|note| diagnostic(note.trim(), "note", None, Vec::new(), Vec::new()))
+        .collect();
+    if finding.kind == Kind::Unused {
+        // rustc saw the crate unused: removing it is safe to apply without review.
+        let applicability = match finding.evidence {
+            Some(Evidence::Compiler | Evidence::Both) => "MachineApplicable",
+            _ => "MaybeIncorrect",
+        };
+        let removal = finding.declaration.as_ref().and_then(|declaration| {
+            let mut removal = span(project, declaration, None, true, None)?;
+            // The whole line, with its line break.
+            let (start, end) = line_bytes(project, declaration)?;
+            removal["byte_start"] = json!(start);
+            removal["byte_end"] = json!(end);
+            removal["column_start"] = json!(1);
+            removal["line_end"] = json!(declaration.line + 1);
+            removal["column_end"] = json!(1);
+            removal["suggested_replacement"] = json!("");
+            removal["suggestion_applicability"] = json!(applicability);
+            Some(removal)
+        });
+        if let Some(removal) = removal {
+            children.push(diagnostic(
+                "remove the dependency",
+                "help",
+                None,
+                vec![removal],
+                Vec::new(),
+            ));
+        }
+    }
+
+    let level = match finding.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "note",
+        Severity::Help => "help",
+    };
+    let message = format!(
+        "{}: `{}`",
+        finding.kind.label().to_lowercase(),
+        finding.krate
+    );
+    let code = json!({
+        "code": format!("dep-analysis::{}", finding.kind.id()),
+        "explanation": crate::explain::text(finding.kind),
+    });
+    json!({
+        "reason": "compiler-message",
+        "package_id": package.map(|pkg| pkg.id.repr.clone()),
+        "manifest_path": package.map(|pkg| pkg.manifest_path.to_string()),
+        "target": target,
+        "message": diagnostic(&message, level, Some(code), spans, children),
+    })
+}
+
+fn diagnostic(
+    message: &str,
+    level: &str,
+    code: Option<Value>,
+    spans: Vec<Value>,
+    children: Vec<Value>,
+) -> Value {
+    let rendered = (code.is_some()).then(|| rendered(message, level, &spans, &children));
+    json!({
+        "$message_type": "diagnostic",
+        "message": message,
+        "code": code,
+        "level": level,
+        "spans": spans,
+        "children": children,
+        "rendered": rendered,
+    })
+}
+
+/// The text cargo prints for a diagnostic, which rustc gives the top-level one only.
+fn rendered(message: &str, level: &str, spans: &[Value], children: &[Value]) -> String {
+    let mut out = format!("{}: {}\n", level, message);
+    if let Some(primary) = spans.iter().find(|s| s["is_primary"] == true) {
+        out.push_str(&format!(
+            "  --> {}:{}:{}\n",
+            primary["file_name"].as_str().unwrap_or_default(),
+            primary["line_start"],
+            primary["column_start"]
+        ));
+    }
+    for child in children {
+        out.push_str(&format!(
+            "  = {}: {}\n",
+            child["level"].as_str().unwrap_or_default(),
+            child["message"].as_str().unwrap_or_default()
+        ));
+    }
+    out
+}
+
+/// A rustc span for `width` characters from `location` (the rest of its line when
+/// `None`), with the text of the line. `None` when the file can't be read or the location
+/// is past its end.
+fn span(
+    project: &Project,
+    location: &Span,
+    width: Option<usize>,
+    primary: bool,
+    label: Option<&str>,
+) -> Option<Value> {
+    let content = std::fs::read_to_string(project.root().join(&location.file)).ok()?;
+    let text = content.lines().nth(location.line - 1)?;
+    let (line_start, _) = line_bytes_in(&content, location.line)?;
+    let columns = text.chars().count();
+    let column_end = width.map_or(columns + 1, |width| location.column + width);
+    let bytes = |column: usize| -> usize {
+        line_start
+            + text
+                .chars()
+                .take(column - 1)
+                .map(char::len_utf8)
+                .sum::<usize>()
+    };
+    Some(json!({
+        "file_name": location.file,
+        "byte_start": bytes(location.column),
+        "byte_end": bytes(column_end),
+        "line_start": location.line,
+        "line_end": location.line,
+        "column_start": location.column,
+        "column_end": column_end,
+        "is_primary": primary,
+        "text": [{
+            "text": text,
+            "highlight_start": location.column,
+            "highlight_end": column_end,
+        }],
+        "label": label,
+        "suggested_replacement": null,
+        "suggestion_applicability": null,
+        "expansion": null,
+    }))
+}
+
+fn line_bytes(project: &Project, location: &Span) -> Option<(usize, usize)> {
+    let content = std::fs::read_to_string(project.root().join(&location.file)).ok()?;
+    line_bytes_in(&content, location.line)
+}
+
+/// The byte range of 1-based `line` in `content`, including its line break.
+fn line_bytes_in(content: &str, line: usize) -> Option<(usize, usize)> {
+    let mut start = 0;
+    for (index, text) in content.split_inclusive('\n').enumerate() {
+        if index + 1 == line {
+            return Some((start, start + text.len()));
+        }
+        start += text.len();
+    }
+    None
+}
@@ -0,0 +1,106 @@
+use crate::project::Project;
+use crate::report::{Finding, Kind};
+use cargo_metadata::{DependencyKind, Package, PackageId};
+use globset::{Glob, GlobMatcher};
+use std::collections::{HashMap, VecDeque};
+
+/// Enforces the `layers` rules from the config over the resolved graph, following
+/// normal and build edges (dev-dependencies don't ship, so they can't break layering).
+pub fn analyze(project: &Project) -> Result<Vec<Finding>, Box<dyn std::error::Error>> {
+    let mut findings = Vec::new();
+    let root = project.root();
+
+    for rule in &project.config.layers {
+        let members = Glob::new(&rule.members)?.compile_matcher();
+        let forbidden: Vec<GlobMatcher> = rule
+            .forbid
+            .iter()
+            .map(|pattern| Ok(Glob::new(pattern)?.compile_matcher()))
+            .collect::<Result<_, globset::Error>>()?;
+
+        for member in project.members() {
+            let dir = member.manifest_path.parent().unwrap().as_std_path();
+            let relative = dir.strip_prefix(&root).unwrap_or(dir);
+            if !members.is_match(&member.name) && !members.is_match(relative) {
+                continue;
+            }
+
+            for path in violations(project, member, &forbidden) {
+                let target = path.last().unwrap();
+                let mut finding = Finding::new(Kind::LayerViolation, target)
+                    .member(project, member)
+                    .note(format!(
+                        "{} matches layer \"{}\", which may not depend on {}",
+                        member.name,
+                        rule.members,
+                        rule.forbid.join(", ")
+                    ));
+                if let Some(reason) = &rule.reason {
+                    finding = finding.note(format!("Reason: {}", reason));
+                }
+                findings.push(finding.note(format!("Path: {}", path.join(" -> "))));
+            }
+        }
+    }
+
+    Ok(findings)
+}
+
+/// Breadth-first search from `member`, returning the shortest path to each forbidden
+/// package it reaches. The search doesn't continue past a forbidden package.
+fn violations(project: &Project, member: &Package, forbidden: &[GlobMatcher]) -> Vec<Vec<String>> {
+    let Some(resolve) = &project.metadata.resolve else {
+        return Vec::new();
+    };
+    let nodes: HashMap<&PackageId, _> = resolve.nodes.iter().map(|n| (&n.id, n)).collect();
+
+    let mut parents: HashMap<&PackageId, &PackageId> = HashMap::new();
+    let mut queue = VecDeque::from([&member.id]);
+    let mut found = Vec::new();
+
+    while let Some(id) = queue.pop_front() {
+        let Some(node) = nodes.get(id) else {
+            continue;
+        };
+        for dep in &node.deps {
+            let shipped = dep
+                .dep_kinds
+                .iter()
+                .any(|k| k.kind != DependencyKind::Development);
+            if !shipped || dep.pkg == member.id || parents.contains_key(&dep.pkg) {
+                continue;
+            }
+            parents.insert(&dep.pkg, id);
+
+            let Some(pkg) = project.package(&dep.pkg) else {
+                continue;
+            };
+            if forbidden.iter().any(|m| m.is_match(&pkg.name)) {
+                found.push(path_to(project, &parents, &member.id, &dep.pkg));
+            } else {
+                queue.push_back(&dep.pkg);
+            }
+        }
+    }
+
+    found
+}
+
+fn path_to(
+    project: &Project,
+    parents: &HashMap<&PackageId, &PackageId>,
+    start: &PackageId,
+    end: &PackageId,
+) -> Vec<String> {
+    let mut path = vec![end];
+    let mut current = end;
+    while current != start {
+        current = parents[current];
+        path.push(current);
+    }
+    path.iter()
+        .rev()
+        .filter_map(|id| project.package(id))
+        .map(|pkg| pkg.name.clone())
+        .collect()
+}
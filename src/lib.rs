@@ -0,0 +1,20 @@
+//! The library side of cargo-dep-analysis, for tools that want to run their own
+//! analyses on a workspace's dependencies.
+//!
+//! [`graph::DepGraph`] is the resolved dependency graph as a petgraph `DiGraph`, with
+//! typed edges (normal, dev or build; optional; platform-specific), so any petgraph
+//! algorithm applies to it:
+//!
+//! ```no_run
+//! use cargo_dep_analysis::graph::DepGraph;
+//!
+//! let metadata = cargo_metadata::MetadataCommand::new().exec()?;
+//! let graph = DepGraph::from_metadata(&metadata)?;
+//! for member in graph.members() {
+//!     let built = graph.reachable(member, |edge| edge.kind == cargo_metadata::DependencyKind::Normal);
+//!     println!("{} builds {} crates", graph.graph[member].name, built.len() - 1);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+pub mod graph;
@@ -0,0 +1,130 @@
+use crate::project::Project;
+use crate::report::{Finding, Kind, Severity};
+use cargo_metadata::{DependencyKind, Package, PackageId};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+/// Crates binding the same system library under different `links` keys, so cargo lets
+/// them into one graph although they define the same C symbols. By library, the crates
+/// known to provide it.
+const SAME_LIBRARY: [(&str, &[&str]); 5] = [
+    ("openssl", &["openssl-sys", "boring-sys"]),
+    ("zlib", &["libz-sys", "cloudflare-zlib-sys"]),
+    ("jemalloc", &["jemalloc-sys", "tikv-jemalloc-sys"]),
+    ("sqlite3", &["libsqlite3-sys", "sqlite3-src"]),
+    ("bzip2", &["bzip2-sys", "libbz2-rs-sys"]),
+];
+
+/// Flags native libraries linked by more than one crate in the graph: the same `links`
+/// key, which cargo refuses to build, and crates known to bind the same library under
+/// different keys (openssl-sys and boring-sys), whose symbols clash at link time. Each
+/// crate comes with the path from a member that brings it in.
+pub fn analyze(project: &Project) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    let mut by_key: BTreeMap<&str, Vec<&Package>> = BTreeMap::new();
+    for pkg in resolved(project) {
+        if let Some(links) = &pkg.links {
+            by_key.entry(links).or_default().push(pkg);
+        }
+    }
+    for (key, packages) in by_key.iter().filter(|(_, p)| p.len() > 1) {
+        let mut finding = Finding::new(Kind::LinksConflict, key).note(format!(
+            "{} crates declare `links = \"{}\"`; cargo allows one per graph, so the build fails",
+            packages.len(),
+            key
+        ));
+        finding.severity = Severity::Error;
+        findings.push(with_paths(project, finding, packages));
+    }
+
+    for (library, crates) in SAME_LIBRARY {
+        let packages: Vec<&Package> = resolved(project)
+            .filter(|pkg| crates.contains(&pkg.name.as_str()))
+            .collect();
+        let distinct = packages
+            .iter()
+            .map(|pkg| pkg.name.as_str())
+            .collect::<BTreeSet<_>>();
+        if distinct.len() < 2 {
+            continue;
+        }
+        let finding = Finding::new(Kind::LinksConflict, library).note(format!(
+            "{} bind the system library {} under different `links` keys: the final \
+             binary gets two copies of its symbols, and the linker either fails on \
+             duplicates or silently picks one for both",
+            distinct.into_iter().collect::<Vec<_>>().join(" and "),
+            library
+        ));
+        findings.push(with_paths(project, finding, &packages));
+    }
+    findings
+}
+
+fn resolved(project: &Project) -> impl Iterator<Item = &Package> {
+    project
+        .metadata
+        .resolve
+        .iter()
+        .flat_map(|resolve| &resolve.nodes)
+        .filter_map(|node| project.package(&node.id))
+}
+
+fn with_paths(project: &Project, mut finding: Finding, packages: &[&Package]) -> Finding {
+    for pkg in packages {
+        let path = path_to(project, &pkg.id)
+            .map(|path| path.join(" -> "))
+            .unwrap_or_else(|| "not reachable from a member".to_string());
+        let links = pkg
+            .links
+            .as_deref()
+            .map(|links| format!(" (links = \"{}\")", links))
+            .unwrap_or_default();
+        finding = finding.note(format!("{} {}{}: {}", pkg.name, pkg.version, links, path));
+    }
+    finding.note(
+        "Pick one implementation: turn off the features selecting the other (`vendored`, \
+         `native-tls` vs `rustls`, ...), or align the crates depending on them",
+    )
+}
+
+/// The shortest path from a member to `target`, by package name, not counting
+/// dev-dependencies of dependencies, which aren't built.
+fn path_to(project: &Project, target: &PackageId) -> Option<Vec<String>> {
+    let resolve = project.metadata.resolve.as_ref()?;
+    let nodes: HashMap<&PackageId, _> = resolve.nodes.iter().map(|n| (&n.id, n)).collect();
+    let members = &project.metadata.workspace_members;
+    let mut parents: HashMap<&PackageId, &PackageId> = HashMap::new();
+    let mut queue: VecDeque<&PackageId> = members.iter().collect();
+    while let Some(id) = queue.pop_front() {
+        if id == target {
+            let mut path = vec![id];
+            let mut current = id;
+            while let Some(parent) = parents.get(current) {
+                path.push(parent);
+                current = parent;
+            }
+            return Some(
+                path.iter()
+                    .rev()
+                    .filter_map(|id| project.package(id))
+                    .map(|pkg| pkg.name.clone())
+                    .collect(),
+            );
+        }
+        for dep in nodes.get(id).into_iter().flat_map(|n| &n.deps) {
+            let dev_only = dep
+                .dep_kinds
+                .iter()
+                .all(|k| k.kind == DependencyKind::Development);
+            if members.contains(&dep.pkg)
+                || parents.contains_key(&dep.pkg)
+                || dev_only && !members.contains(id)
+            {
+                continue;
+            }
+            parents.insert(&dep.pkg, id);
+            queue.push_back(&dep.pkg);
+        }
+    }
+    None
+}
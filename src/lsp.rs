@@ -0,0 +1,340 @@
+use crate::cli::Args;
+use crate::fix::ManifestEdit;
+use crate::report::{Finding, Kind, Severity};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// The editor's state of the session: the open documents and what was last published.
+struct Server {
+    /// The manifest the editor opened the workspace at, unless --manifest-path was given.
+    manifest_path: Option<PathBuf>,
+    /// The text of open documents, which may differ from what is saved.
+    documents: HashMap<PathBuf, String>,
+    /// Files diagnostics were published for, to clear them once their findings are gone.
+    published: BTreeSet<PathBuf>,
+}
+
+/// `lsp`: a minimal language server over stdin and stdout. It analyzes the workspace when
+/// the editor connects and again whenever a file is saved, publishes the findings as
+/// diagnostics on the manifests (an unused dependency on the line declaring it), and
+/// offers code actions to remove an unused dependency or ignore it.
+pub fn run(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let mut input = BufReader::new(std::io::stdin().lock());
+    let mut server = Server {
+        manifest_path: args.manifest_path.clone(),
+        documents: HashMap::new(),
+        published: BTreeSet::new(),
+    };
+    while let Some(message) = read_message(&mut input)? {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let result = match method {
+            "initialize" => {
+                if server.manifest_path.is_none() {
+                    server.manifest_path = params["rootUri"]
+                        .as_str()
+                        .and_then(uri_to_path)
+                        .map(|root| root.join("Cargo.toml"));
+                }
+                Some(json!({
+                    "capabilities": {
+                        "textDocumentSync": { "openClose": true, "change": 1, "save": true },
+                        "codeActionProvider": true,
+                    },
+                    "serverInfo": {
+                        "name": env!("CARGO_PKG_NAME"),
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                }))
+            }
+            "initialized" => {
+                server.analyze(args)?;
+                None
+            }
+            "textDocument/didOpen" | "textDocument/didChange" => {
+                let document = if method == "textDocument/didOpen" {
+                    params["textDocument"]["text"].as_str()
+                } else {
+                    // Full sync: the last change is the whole document.
+                    params["contentChanges"]
+                        .as_array()
+                        .and_then(|changes| changes.last())
+                        .and_then(|change| change["text"].as_str())
+                };
+                if let (Some(path), Some(text)) = (document_path(params), document) {
+                    server.documents.insert(path, text.to_string());
+                }
+                None
+            }
+            "textDocument/didClose" => {
+                if let Some(path) = document_path(params) {
+                    server.documents.remove(&path);
+                }
+                None
+            }
+            "textDocument/didSave" => {
+                server.analyze(args)?;
+                None
+            }
+            "textDocument/codeAction" => Some(server.code_actions(params)),
+            "shutdown" => Some(Value::Null),
+            "exit" => break,
+            _ => {
+                // Requests must be answered even when unsupported; notifications not.
+                if message.get("id").is_some() {
+                    write_message(&json!({
+                        "jsonrpc": "2.0",
+                        "id": message["id"],
+                        "error": { "code": -32601, "message": format!("unsupported: {}", method) },
+                    }))?;
+                }
+                None
+            }
+        };
+        if let (Some(result), Some(id)) = (result, message.get("id")) {
+            write_message(&json!({ "jsonrpc": "2.0", "id": id, "result": result }))?;
+        }
+    }
+    Ok(())
+}
+
+impl Server {
+    /// Runs the analysis and publishes its findings, clearing the diagnostics of files
+    /// that no longer have any.
+    fn analyze(&mut self, args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+        let (project, findings) = match crate::collect(args, self.manifest_path.as_deref()) {
+            Ok(analyzed) => analyzed,
+            Err(err) => {
+                tracing::warn!("analysis failed: {}", err);
+                return notify(
+                    "window/showMessage",
+                    json!({ "type": 1, "message": format!("dep-analysis: {}", err) }),
+                );
+            }
+        };
+        let mut by_file: BTreeMap<PathBuf, Vec<Value>> = BTreeMap::new();
+        for finding in &findings {
+            let path = project
+                .root()
+                .join(finding.file.as_deref().unwrap_or("Cargo.toml"));
+            let diagnostic = self.diagnostic(&project.root(), &path, finding);
+            by_file.entry(path).or_default().push(diagnostic);
+        }
+        for path in std::mem::take(&mut self.published) {
+            by_file.entry(path).or_default();
+        }
+        for (path, diagnostics) in by_file {
+            if !diagnostics.is_empty() {
+                self.published.insert(path.clone());
+            }
+            notify(
+                "textDocument/publishDiagnostics",
+                json!({ "uri": path_to_uri(&path), "diagnostics": diagnostics }),
+            )?;
+        }
+        Ok(())
+    }
+
+    fn diagnostic(&self, root: &Path, path: &Path, finding: &Finding) -> Value {
+        // The editor's buffer may have moved the declaration since the analysis ran.
+        let content = self.text(path).unwrap_or_default();
+        let (line, column) =
+            crate::spans::declaration(&content, path, &finding.krate).unwrap_or((1, 1));
+        let text = content.lines().nth(line - 1).unwrap_or_default();
+        let start: String = text.chars().take(column - 1).collect();
+        let (line, start) = (line - 1, start.encode_utf16().count());
+        let end = text.encode_utf16().count();
+        let mut message = format!("{}: {}", finding.kind.label(), finding.krate);
+        for note in &finding.notes {
+            message.push_str(&format!("\n{}", note.trim_end()));
+        }
+        json!({
+            "range": {
+                "start": { "line": line, "character": start },
+                "end": { "line": line, "character": end },
+            },
+            "severity": match finding.severity {
+                Severity::Error => 1,
+                Severity::Warning => 2,
+                Severity::Info => 3,
+                Severity::Help => 4,
+            },
+            "code": finding.kind.id(),
+            "source": "dep-analysis",
+            "message": message,
+            "data": { "crate": finding.krate },
+            "relatedInformation": finding.usages.iter().map(|usage| json!({
+                "location": {
+                    "uri": path_to_uri(&root.join(&usage.file)),
+                    "range": {
+                        "start": { "line": usage.line - 1, "character": usage.column - 1 },
+                        "end": { "line": usage.line - 1, "character": usage.column - 1 },
+                    },
+                },
+                "message": format!("{} used here", finding.krate),
+            })).collect::<Vec<_>>(),
+        })
+    }
+
+    /// Remove and ignore actions for the unused dependencies diagnosed in the requested
+    /// range.
+    fn code_actions(&self, params: &Value) -> Value {
+        let Some(path) = document_path(params) else {
+            return json!([]);
+        };
+        let Some(content) = self.text(&path) else {
+            return json!([]);
+        };
+        let uri = path_to_uri(&path);
+        let mut actions = Vec::new();
+        let diagnostics = params["context"]["diagnostics"].as_array();
+        for diagnostic in diagnostics.into_iter().flatten() {
+            if diagnostic["code"].as_str() != Some(Kind::Unused.id()) {
+                continue;
+            }
+            let Some(krate) = diagnostic["data"]["crate"].as_str() else {
+                continue;
+            };
+            let edits = [
+                (
+                    format!("Remove unused dependency `{}`", krate),
+                    remove(&path, &content, krate),
+                ),
+                (
+                    format!("Ignore `{}` in [package.metadata.dep-analysis]", krate),
+                    ignore(&path, &content, krate),
+                ),
+            ];
+            for (title, edited) in edits {
+                let Some(edited) = edited else {
+                    continue;
+                };
+                actions.push(json!({
+                    "title": title,
+                    "kind": "quickfix",
+                    "diagnostics": [diagnostic],
+                    "edit": { "changes": { &uri: [replace_all(&content, &edited)] } },
+                }));
+            }
+        }
+        Value::Array(actions)
+    }
+
+    /// A file's text as the editor has it, or as saved when it isn't open.
+    fn text(&self, path: &Path) -> Option<String> {
+        self.documents
+            .get(path)
+            .cloned()
+            .or_else(|| std::fs::read_to_string(path).ok())
+    }
+}
+
+fn remove(path: &Path, content: &str, krate: &str) -> Option<String> {
+    let mut manifest = ManifestEdit::parse(path, content).ok()?;
+    manifest
+        .remove_dependency(krate)
+        .then(|| manifest.contents())
+}
+
+fn ignore(path: &Path, content: &str, krate: &str) -> Option<String> {
+    let mut manifest = ManifestEdit::parse(path, content).ok()?;
+    manifest.table(&["package"])?;
+    let table = manifest.table(&["package", "metadata", "dep-analysis"])?;
+    let ignore = table
+        .entry("ignore")
+        .or_insert(toml_edit::value(toml_edit::Array::new()))
+        .as_array_mut()?;
+    if !ignore.iter().any(|name| name.as_str() == Some(krate)) {
+        ignore.push(krate);
+    }
+    Some(manifest.contents())
+}
+
+/// A text edit replacing the whole of `content` with `edited`.
+fn replace_all(content: &str, edited: &str) -> Value {
+    let lines = content.split('\n').count();
+    json!({
+        "range": {
+            "start": { "line": 0, "character": 0 },
+            "end": { "line": lines, "character": 0 },
+        },
+        "newText": edited,
+    })
+}
+
+fn document_path(params: &Value) -> Option<PathBuf> {
+    uri_to_path(params["textDocument"]["uri"].as_str()?)
+}
+
+/// The path of a `file://` URI, with percent-escapes decoded.
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?.as_bytes();
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut i = 0;
+    while i < encoded.len() {
+        let escaped = (encoded[i] == b'%')
+            .then(|| std::str::from_utf8(encoded.get(i + 1..i + 3)?).ok())
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                bytes.push(byte);
+                i += 3;
+            }
+            None => {
+                bytes.push(encoded[i]);
+                i += 1;
+            }
+        }
+    }
+    Some(PathBuf::from(String::from_utf8(bytes).ok()?))
+}
+
+fn path_to_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        if byte.is_ascii_alphanumeric() || b"/-_.~".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    uri
+}
+
+/// Reads one message, framed by a `Content-Length` header; `None` at the end of input.
+fn read_message(input: &mut impl BufRead) -> Result<Option<Value>, Box<dyn std::error::Error>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = Some(value.trim().parse::<usize>()?);
+            }
+        }
+    }
+    let mut body = vec![0; length.ok_or("message without a Content-Length header")?];
+    input.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+fn write_message(message: &Value) -> Result<(), Box<dyn std::error::Error>> {
+    let body = serde_json::to_string(message)?;
+    let mut stdout = std::io::stdout().lock();
+    write!(stdout, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+fn notify(method: &str, params: Value) -> Result<(), Box<dyn std::error::Error>> {
+    write_message(&json!({ "jsonrpc": "2.0", "method": method, "params": params }))
+}
@@ -0,0 +1,243 @@
+use crate::ident;
+use regex::Regex;
+use std::borrow::Cow;
+
+/// Rewrites the bodies of `macro_rules!` definitions so the usage patterns read them like
+/// ordinary code: metavariables such as `$name::new` are blanked so they aren't mistaken
+/// for crate names.
+pub fn normalize(content: &str) -> Cow<'_, str> {
+    let start = Regex::new(&format!(r"macro_rules!\s*{}\s*[{{(\[]", ident::PATTERN)).unwrap();
+    let metavariable = Regex::new(&format!(r"\${}", ident::PATTERN)).unwrap();
+
+    let mut out = String::new();
+    let mut copied = 0;
+    for m in start.find_iter(content) {
+        if m.start() < copied {
+            continue;
+        }
+        let body_start = m.end();
+        let body_end = matching_delimiter(content, m.end() - 1).unwrap_or(content.len());
+        out.push_str(&content[copied..body_start]);
+        out.push_str(
+            &metavariable.replace_all(&content[body_start..body_end], |cap: &regex::Captures| {
+                if &cap[0] == "$crate" { "crate" } else { "_" }.to_string()
+            }),
+        );
+        copied = body_end;
+    }
+    if copied == 0 {
+        return Cow::Borrowed(content);
+    }
+    out.push_str(&content[copied..]);
+    Cow::Owned(out)
+}
+
+/// The modules named along `$crate::…` paths. A macro reaches a dependency through the
+/// crate's own re-export (`$crate::__private::serde::Serialize`), so any of them may be one.
+pub fn crate_path_segments(content: &str) -> Vec<String> {
+    let re = Regex::new(&format!(r"\$crate((?:\s*::\s*{})+)", ident::PATTERN)).unwrap();
+    let mut segments = Vec::new();
+    for cap in re.captures_iter(content) {
+        let path: Vec<&str> = cap[1].split("::").map(str::trim).skip(1).collect();
+        // The last segment is the item itself.
+        segments.extend(
+            path[..path.len() - 1]
+                .iter()
+                .map(|s| ident::unraw(s).to_string()),
+        );
+    }
+    segments
+}
+
+/// The index of the delimiter closing the one at `open`, skipping comments and string,
+/// byte string, raw string and character literals.
+pub fn matching_delimiter(content: &str, open: usize) -> Option<usize> {
+    let bytes = content.as_bytes();
+    let mut depth = 0usize;
+    let mut i = open;
+    while i < bytes.len() {
+        if let Some(end) = skip_literal(content, i) {
+            i = end;
+            continue;
+        }
+        match bytes[i] {
+            b'{' | b'(' | b'[' => depth += 1,
+            b'}' | b')' | b']' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Past the comment or string, byte string, raw string or character literal starting at
+/// `i`, if one does; delimiters and quotes inside it mean nothing to the code around it.
+pub fn skip_literal(content: &str, i: usize) -> Option<usize> {
+    let bytes = content.as_bytes();
+    match bytes[i] {
+        b'/' if bytes.get(i + 1) == Some(&b'/') => {
+            Some(content[i..].find('\n').map_or(bytes.len(), |n| i + n))
+        }
+        b'/' if bytes.get(i + 1) == Some(&b'*') => Some(block_comment_end(bytes, i)),
+        b'"' => Some(string_end(bytes, i + 1)),
+        b'r' if raw_string_start(bytes, i) => Some(raw_string_end(bytes, i + 1)),
+        b'\'' => Some(char_end(content, i)),
+        _ => None,
+    }
+}
+
+fn is_ident_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_'
+}
+
+/// Past the `/* */` comment starting at `start`, which may nest.
+fn block_comment_end(bytes: &[u8], start: usize) -> usize {
+    let mut depth = 0usize;
+    let mut i = start;
+    while i + 1 < bytes.len() {
+        match (bytes[i], bytes[i + 1]) {
+            (b'/', b'*') => {
+                depth += 1;
+                i += 2;
+            }
+            (b'*', b'/') => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Past the closing quote of the (byte) string whose contents start at `start`.
+fn string_end(bytes: &[u8], start: usize) -> usize {
+    let mut i = start;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Whether the `r` at `i` starts a raw string (`r"…"`, `r#"…"#`, `br"…"`), rather than
+/// ending an identifier or starting a raw identifier (`r#type`).
+fn raw_string_start(bytes: &[u8], i: usize) -> bool {
+    let prefix_start = match i.checked_sub(1).map(|p| bytes[p]) {
+        Some(b'b' | b'c') => i - 1,
+        _ => i,
+    };
+    if prefix_start > 0 && is_ident_byte(bytes[prefix_start - 1]) {
+        return false;
+    }
+    let hashes = bytes[i + 1..].iter().take_while(|b| **b == b'#').count();
+    bytes.get(i + 1 + hashes) == Some(&b'"')
+}
+
+/// Past the raw string whose hashes (if any) start at `start`.
+fn raw_string_end(bytes: &[u8], start: usize) -> usize {
+    let hashes = bytes[start..].iter().take_while(|b| **b == b'#').count();
+    let mut i = start + hashes + 1;
+    while i < bytes.len() {
+        if bytes[i] == b'"'
+            && bytes[i + 1..]
+                .iter()
+                .take(hashes)
+                .filter(|b| **b == b'#')
+                .count()
+                == hashes
+        {
+            return i + 1 + hashes;
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+/// Past the character literal starting at `start` (`'a'`, `'\''`, `'\u{7b}'`), or just
+/// past the quote of a lifetime or label.
+fn char_end(content: &str, start: usize) -> usize {
+    let bytes = content.as_bytes();
+    if bytes.get(start + 1) == Some(&b'\\') {
+        return content[start + 3..]
+            .find('\'')
+            .map_or(bytes.len(), |n| start + 3 + n + 1);
+    }
+    let Some(c) = content[start + 1..].chars().next() else {
+        return bytes.len();
+    };
+    let after = start + 1 + c.len_utf8();
+    if bytes.get(after) == Some(&b'\'') {
+        after + 1
+    } else {
+        start + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The text from the opening delimiter (the first `{`) to its match.
+    fn block(content: &str) -> &str {
+        let open = content.find('{').unwrap();
+        &content[open..=matching_delimiter(content, open).expect("the block closes")]
+    }
+
+    #[test]
+    fn skips_comments() {
+        let content = "{\n    // a 3\" pipe }\n    /* } /* nested } */ } */\n}\nfn f() {}";
+        assert!(block(content).ends_with("*/\n}"));
+        let content = "{ /// doc with ' and \" }\n}";
+        assert_eq!(block(content), content);
+    }
+
+    #[test]
+    fn skips_strings() {
+        assert_eq!(block(r#"{ "}\"}" } }"#), r#"{ "}\"}" }"#);
+        assert_eq!(block(r#"{ b"}" } }"#), r#"{ b"}" }"#);
+        assert_eq!(block(r##"{ r#"a " } b"# } }"##), r##"{ r#"a " } b"# }"##);
+        assert_eq!(block(r#"{ br"}" r"\" } }"#), r#"{ br"}" r"\" }"#);
+        // A raw identifier isn't a string.
+        assert_eq!(block("{ r#type } }"), "{ r#type }");
+        // Nor is an identifier ending in `r`.
+        assert_eq!(block(r#"{ for"}" } }"#), r#"{ for"}" }"#);
+    }
+
+    #[test]
+    fn skips_char_literals() {
+        assert_eq!(block("{ '}' } }"), "{ '}' }");
+        assert_eq!(block(r"{ '\'' '}' } }"), r"{ '\'' '}' }");
+        assert_eq!(block(r"{ '\\' } }"), r"{ '\\' }");
+        assert_eq!(block(r"{ '\u{7d}' } }"), r"{ '\u{7d}' }");
+        assert_eq!(block("{ b'\"' \"}\" } }"), "{ b'\"' \"}\" }");
+        assert_eq!(block("{ 'é' } }"), "{ 'é' }");
+    }
+
+    #[test]
+    fn lifetimes_are_not_char_literals() {
+        let content = "{ fn f<'a>(x: &'a str) -> &'a str { x } } }";
+        assert_eq!(block(content), "{ fn f<'a>(x: &'a str) -> &'a str { x } }");
+        assert_eq!(
+            block("{ 'outer: loop { break 'outer; } } }"),
+            "{ 'outer: loop { break 'outer; } }"
+        );
+    }
+
+    #[test]
+    fn unbalanced_input_has_no_match() {
+        assert_eq!(matching_delimiter("{ ( }", 0), None);
+        assert_eq!(matching_delimiter("{ \"}", 0), None);
+    }
+}
@@ -1,71 +1,1072 @@
-use cargo_metadata::{Dependency, MetadataCommand};
+mod advisories;
+mod aliases;
+mod attest;
+mod badge;
+mod batch;
+mod benches;
+mod blame;
+mod budget;
+mod cache;
+mod cargo_config;
+mod cfg;
+mod cfg_attr;
+mod cfg_eval;
+mod check_features;
+mod checksums;
+mod cli;
+mod compare;
+mod completions;
+mod config;
+mod daemon;
+mod declarations;
+mod default_features;
+mod diff;
+mod doc_include;
+mod explain;
+mod extern_crate;
+mod feature_matrix;
+mod feature_refs;
+mod feature_trim;
+mod features;
+mod fix;
+mod fuzz;
+mod generated;
+mod git;
+mod gitlab;
+mod history;
+mod host_target;
+mod html_graph;
+mod ident;
+mod ignore;
+mod impact;
+mod in_range;
+mod jobs;
+mod json_diagnostic;
+mod layers;
+mod links;
+mod lsp;
+mod macros;
+mod member_graph;
+mod merge;
+mod metrics;
+mod minimal_versions;
+mod out_dir;
+mod outdated;
+mod overlap;
+mod overrides;
+mod patch;
+mod path_deps;
+mod plan;
+mod platform;
+mod pr_comment;
+mod proc_macro_gating;
+mod progress;
+mod project;
+mod publish;
+mod redundant_features;
+mod registry;
+mod remote;
+mod report;
+mod resolver;
+mod serve;
+mod shape;
+mod signing;
+mod skew;
+mod spans;
+mod stability;
+mod summary;
+mod target_usage;
+mod targets;
+mod template;
+mod test_only;
+mod undeclared;
+mod unknown_features;
+mod vendor;
+mod verify;
+mod workspace_deps;
+mod xtask;
+mod yanked;
+
+use cargo_metadata::{Dependency, DependencyKind, Edition, Package, Target};
+use cfg_eval::Cfg;
+use cli::{Args, Command, Format};
+use feature_matrix::FeatureRefs;
+use globset::{Glob, GlobSetBuilder};
+use project::Project;
 use regex::Regex;
-use std::collections::{HashMap, HashSet};
+use report::{Finding, Kind};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = Args::from_env();
+    init_logging(&args);
+    args.read_files_from()?;
+    match &args.command {
+        Some(Command::Remote { spec }) => remote::run(&args, spec),
+        Some(Command::Diff {
+            base,
+            head,
+            against,
+        }) => diff::run(
+            &args,
+            base.as_deref().or(against.as_deref()),
+            head.as_deref(),
+        ),
+        Some(Command::Badge { output }) => {
+            let (project, findings) = collect(&args, args.manifest_path.as_deref())?;
+            badge::write(&history::Snapshot::take(&project, &findings), output)
+        }
+        Some(Command::Serve { port }) => serve::run(&args, *port),
+        Some(Command::Completions { shell }) => {
+            completions::print(*shell, args.manifest_path.as_deref());
+            Ok(())
+        }
+        Some(Command::Explain { rule }) => explain::run(rule.as_deref()),
+        Some(Command::Plan) => {
+            let (project, findings) = collect(&args, args.manifest_path.as_deref())?;
+            plan::print(&project, &findings);
+            Ok(())
+        }
+        Some(Command::CheckFeatures { powerset }) => check_features::run(&args, *powerset),
+        Some(Command::FeatureMatrix) => feature_matrix::run(&args),
+        Some(Command::MinimalVersions) => minimal_versions::run(&args),
+        Some(Command::Targets) => target_usage::run(&args),
+        Some(Command::Overlap) => overlap::run(&args),
+        Some(Command::Members { dot }) => member_graph::run(&args, *dot),
+        Some(Command::DefaultFeatures) => default_features::run(&args),
+        Some(Command::HostTarget) => host_target::run(&args),
+        Some(Command::History) => history::print(&Project::load(args.manifest_path.as_deref())?),
+        Some(Command::Compare { other }) => compare::run(args.manifest_path.as_deref(), other),
+        Some(Command::Merge { reports, json }) => merge::run(reports, *json),
+        Some(Command::Lsp) => lsp::run(&args),
+        Some(Command::Daemon { socket }) => daemon::run(&args, socket.as_deref()),
+        Some(Command::Verify {
+            envelope,
+            key,
+            certificate_identity,
+            certificate_oidc_issuer,
+        }) => {
+            let trust = match (key, certificate_identity, certificate_oidc_issuer) {
+                (Some(key), _, _) => signing::Trust::Key(key),
+                (None, Some(identity), Some(issuer)) => {
+                    signing::Trust::Keyless { identity, issuer }
+                }
+                _ => {
+                    return Err(
+                        "pass --key, or --certificate-identity with --certificate-oidc-issuer"
+                            .into(),
+                    )
+                }
+            };
+            let report = signing::verify(envelope, trust)?;
+            tracing::info!("{}: signature verified", envelope.display());
+            print!("{}", report);
+            Ok(())
+        }
+        None => match &args.git {
+            Some(url) => remote::run_git(&args, url, args.rev.as_deref()),
+            None if !args.paths.is_empty() || args.recursive.is_some() => batch::run(&args),
+            None => {
+                if !analyze(&args, args.manifest_path.as_deref())? {
+                    std::process::exit(1);
+                }
+                Ok(())
+            }
+        },
+    }
+}
+
+/// Diagnostics go to stderr through `tracing`, so stdout carries only the report.
+fn init_logging(args: &Args) {
+    let level = match (args.verbose, args.quiet) {
+        (_, 2..) => "error",
+        (_, 1) => "warn",
+        (0 | 1, _) => "info",
+        (2, _) => "debug",
+        _ => "trace",
+    };
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+        // Other crates' debug output (TLS, HTTP) would drown ours.
+        let others = if level == "error" { "error" } else { "warn" };
+        tracing_subscriber::EnvFilter::new(format!("{},cargo_dep_analysis={}", others, level))
+    });
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .with_ansi(args.color.enabled(std::io::stderr().is_terminal()))
+        .with_target(false)
+        .without_time()
+        .init();
+}
+
+/// Runs every pass over the project at `manifest_path` (or the current directory), prints
+/// the report and returns whether the findings stay within the thresholds. The caller
+/// exits with a failure when they don't, once it has cleaned up.
+fn analyze(args: &Args, manifest_path: Option<&Path>) -> Result<bool, Box<dyn std::error::Error>> {
+    let (_, findings) = analyze_and_report(args, manifest_path)?;
+    if let Some(reason) = report::threshold_exceeded(&findings, args.max_warnings, &args.fail_on) {
+        tracing::error!("{}", reason);
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Like [`analyze`], returning the findings instead of checking them against the thresholds.
+fn analyze_and_report(
+    args: &Args,
+    manifest_path: Option<&Path>,
+) -> Result<(Project, Vec<Finding>), Box<dyn std::error::Error>> {
+    let (project, findings) = if args.format == Format::Jsonl {
+        collect_streaming(args, manifest_path, &mut |finding| {
+            if let Ok(line) = serde_json::to_string(finding) {
+                let mut stdout = std::io::stdout().lock();
+                let _ = writeln!(stdout, "{}", line);
+                let _ = stdout.flush();
+            }
+        })?
+    } else {
+        collect(args, manifest_path)?
+    };
+    // The formats rendered as a whole, which can be signed.
+    let rendered = if args.summary {
+        summary::print(&project, &findings);
+        None
+    } else if let Some(template) = &args.template {
+        Some(template::render(&project, &findings, template)?)
+    } else {
+        match args.format {
+            Format::Text => {
+                let color = args.color.enabled(std::io::stdout().is_terminal());
+                report::print(&findings, color, args.group_by, &xtask::members(&project));
+                None
+            }
+            Format::PrComment => {
+                let base = match &args.base {
+                    Some(base) => Some((base.as_str(), diff::findings_at(args, &project, base)?)),
+                    None => None,
+                };
+                Some(pr_comment::render(&findings, base, args.group_by))
+            }
+            Format::Metrics => Some(metrics::render(&project, &findings)),
+            Format::Gitlab => Some(format!("{}\n", gitlab::render(&project, &findings))),
+            Format::HtmlGraph => Some(html_graph::render(&project, &findings)?),
+            Format::Attest => Some(format!(
+                "{}\n",
+                attest::render(&project, &findings, &args.subject)?
+            )),
+            Format::JsonDiagnostic => Some(json_diagnostic::render(&project, &findings)),
+            // Already printed while collecting.
+            Format::Jsonl => None,
+        }
+    };
+    match (rendered, &args.sign) {
+        (Some(rendered), Some(key)) => {
+            let payload_type = match args.format {
+                Format::Attest if args.template.is_none() => "application/vnd.in-toto+json",
+                Format::Gitlab if args.template.is_none() => "application/json",
+                Format::JsonDiagnostic if args.template.is_none() => "application/x-ndjson",
+                _ => "text/plain",
+            };
+            println!("{}", signing::sign(&rendered, payload_type, key)?);
+        }
+        (Some(rendered), None) => print!("{}", rendered),
+        (None, Some(_)) => {
+            return Err(
+                "--sign needs a report rendered as a whole: use --format attest, \
+                        gitlab, json-diagnostic, metrics, pr-comment or html-graph, or --template"
+                    .into(),
+            )
+        }
+        (None, None) => {}
+    }
+    if args.record {
+        history::record(&project, &findings)?;
+    }
+
+    if args.fix {
+        let mut changes = workspace_deps::fix(&project)?;
+        changes.extend(resolver::fix(&project)?);
+        changes.extend(test_only::fix(&project, &findings)?);
+        changes.extend(platform::fix(&project, &findings)?);
+        changes.extend(extern_crate::fix(&project, &findings)?);
+        changes.extend(redundant_features::fix(&project, &findings)?);
+        changes.extend(undeclared::fix(&project, &findings)?);
+        report::print_fixes(&changes);
+    }
+
+    Ok((project, findings))
+}
+
+/// Runs every pass over the project at `manifest_path` (or the current directory).
+fn collect(
+    args: &Args,
+    manifest_path: Option<&Path>,
+) -> Result<(Project, Vec<Finding>), Box<dyn std::error::Error>> {
+    collect_streaming(args, manifest_path, &mut |_| {})
+}
+
+/// Like [`collect`], handing each finding to `on_finding` as soon as its pass is done.
+fn collect_streaming(
+    args: &Args,
+    manifest_path: Option<&Path>,
+    on_finding: &mut dyn FnMut(&Finding),
+) -> Result<(Project, Vec<Finding>), Box<dyn std::error::Error>> {
     // Get cargo metadata
-    let metadata = MetadataCommand::new().exec()?;
-    let package = metadata.root_package().unwrap();
+    let project = Project::load(manifest_path)?;
+    collect_project(args, project, on_finding)
+}
+
+/// Runs every pass over an already loaded project, handing each finding to `on_finding`
+/// as soon as its pass is done.
+fn collect_project(
+    args: &Args,
+    mut project: Project,
+    on_finding: &mut dyn FnMut(&Finding),
+) -> Result<(Project, Vec<Finding>), Box<dyn std::error::Error>> {
+    project.cache.refresh = args.refresh;
+    project.cargo_config.cacert = args.cacert.clone();
+    project.jobs = args.jobs.map(usize::from);
+    project.progress = progress::Progress::new(
+        args.format == Format::Text
+            && args.template.is_none()
+            && std::io::stdout().is_terminal()
+            && std::io::stderr().is_terminal(),
+    );
+    let offline = args.offline || project.offline();
+    if let Some(vendor) = &project.vendor {
+        let vendored = project
+            .metadata
+            .packages
+            .iter()
+            .filter(|pkg| vendor.contains(pkg.manifest_path.as_std_path()))
+            .count();
+        tracing::info!(
+            "{} packages resolved from vendored sources in {} ({}); network checks are skipped",
+            vendored,
+            vendor.directory.display(),
+            vendor.replaces.join(", ")
+        );
+    }
+
+    // Registry data is fetched in the background while the sources are scanned, and
+    // waited for before the first pass that needs it.
+    let prefetch = (!offline).then(|| registry::prefetch(&project));
+
+    let packages = selected_packages(args, &project)?;
+    let fuzz = fuzz::package(&project)?;
+    let mut scanned: Vec<&Package> = packages.clone();
+    if let Some(fuzz) = &fuzz {
+        tracing::info!("analyzing the cargo-fuzz crate {}", fuzz.name);
+        scanned.push(fuzz);
+    }
+    if let Some(depth) = args.follow_path_deps {
+        for dependency in path_deps::external(&project, depth) {
+            tracing::info!(
+                "analyzing the path dependency {} outside the workspace",
+                dependency.name
+            );
+            scanned.push(dependency);
+        }
+    }
+    let tooling = xtask::members(&project);
+    for member in project.members() {
+        if tooling.contains(&member.name) && packages.iter().all(|pkg| pkg.id != member.id) {
+            scanned.push(member);
+        }
+    }
+    let jobs = project.jobs.unwrap_or_else(jobs::default_jobs);
+    // Errors aren't `Send`; they cross back from the workers as messages.
+    let results = jobs::map(jobs, &scanned, |package| {
+        unused_dependencies(args, &project, package).map_err(|err| err.to_string())
+    });
+    let mut unused = Vec::new();
+    for (package, found) in scanned.iter().zip(results) {
+        let mut found = found?;
+        // `cargo check` runs take the build directory lock, so they stay one at a time.
+        if args.verify_with_rustc && packages.iter().any(|pkg| pkg.id == package.id) {
+            let confirmed = verify::unused_crates(args, &project, package)?;
+            verify::merge(&project, package, &mut found, &confirmed);
+        }
+        unused.extend(found);
+    }
+
+    impact::annotate(&project, &mut unused);
+
+    let mut findings = Vec::new();
+    let root_manifest = project.display_path(&project.root().join("Cargo.toml"));
+    // One step per `emit` below.
+    let checks = project.progress.bar(
+        "Running checks",
+        Some(if offline { 21 } else { 24 } + u64::from(args.verify_features)),
+    );
+    // Passes run one after another, so each one's duration is the time since the last.
+    let mut started = std::time::Instant::now();
+    let mut locator = spans::Locator::default();
+    let mut emit = |pass: &str, mut new: Vec<Finding>| {
+        checks.inc();
+        tracing::debug!(pass, findings = new.len(), elapsed = ?started.elapsed(), "pass finished");
+        started = std::time::Instant::now();
+        for finding in &mut new {
+            if let Some(severity) = project.config.severity.get(&finding.kind) {
+                finding.severity = *severity;
+            }
+            finding.file.get_or_insert_with(|| root_manifest.clone());
+            locator.annotate(args, &project, finding);
+        }
+        // Streamed findings can only be ordered within their pass.
+        report::sort(&mut new);
+        for finding in &new {
+            on_finding(finding);
+        }
+        findings.extend(new);
+    };
+    emit("unused", unused);
+    emit("undeclared", undeclared::analyze(args, &project));
+    emit("patch", patch::analyze(&project));
+    emit("overrides", overrides::analyze(&project));
+    emit("declarations", declarations::analyze(&project));
+    emit("redundant-features", redundant_features::analyze(&project));
+    emit(
+        "unknown-features",
+        unknown_features::analyze(args, &project),
+    );
+    emit(
+        "proc-macro-gating",
+        proc_macro_gating::analyze(args, &project),
+    );
+    emit("workspace-deps", workspace_deps::analyze(&project));
+    emit("inheritable", workspace_deps::analyze_inheritable(&project));
+    emit("skew", skew::analyze(&project));
+    emit("layers", layers::analyze(&project)?);
+    emit("resolver", resolver::analyze(&project));
+    emit("publish", publish::analyze(&project));
+    emit("benches", benches::analyze(&project));
+    emit("git", git::analyze(&project, offline));
+    if let Some(prefetch) = prefetch {
+        prefetch.wait();
+    }
+    emit("checksums", checksums::analyze(&project, offline));
+    emit("advisories", advisories::analyze(args, &project, offline));
+    emit("vendor", vendor::analyze(&project));
+    emit("links", links::analyze(&project));
+    emit("budget", budget::analyze(&project, args.base.as_deref()));
+    if args.verify_features {
+        emit("feature-trim", feature_trim::analyze(args, &project)?);
+    }
+    if !offline {
+        emit("yanked", yanked::analyze(&project));
+        emit("outdated", outdated::analyze(&project));
+        emit("in-range", in_range::analyze(&project));
+    }
+    report::sort(&mut findings);
+    Ok((project, findings))
+}
+
+/// The packages whose sources are scanned for usage: the members picked with -p, otherwise
+/// the root package, or every member of a virtual workspace.
+fn selected_packages<'a>(
+    args: &Args,
+    project: &'a Project,
+) -> Result<Vec<&'a Package>, Box<dyn std::error::Error>> {
+    if !args.package.is_empty() {
+        return args
+            .package
+            .iter()
+            .map(|name| {
+                project
+                    .members()
+                    .find(|member| &member.name == name)
+                    .ok_or_else(|| {
+                        format!("package `{}` is not a member of the workspace", name).into()
+                    })
+            })
+            .collect();
+    }
+    Ok(match project.metadata.root_package() {
+        Some(root) => vec![root],
+        None => project.members().collect(),
+    })
+}
+
+/// Scans a package's sources for the dependencies it uses, reporting the ones it doesn't
+/// and the ones only tests, examples, benches or disabled features use.
+fn unused_dependencies(
+    args: &Args,
+    project: &Project,
+    package: &Package,
+) -> Result<Vec<Finding>, Box<dyn std::error::Error>> {
+    Ok(package_usage(args, project, package)?.0)
+}
 
+/// [`unused_dependencies`], along with the feature gates each crate is referenced under.
+fn package_usage(
+    args: &Args,
+    project: &Project,
+    package: &Package,
+) -> Result<(Vec<Finding>, FeatureRefs), Box<dyn std::error::Error>> {
     // Collect all dependencies and their underscore variants
     let mut deps = HashMap::new();
     let mut name_mappings = HashMap::new();
+    // Non-optional [dependencies] entries, the ones tests alone can't justify.
+    let mut normal_deps = HashSet::new();
+    // The kinds each dependency is declared with, for ignore lists covering only some.
+    let mut kinds: HashMap<String, Vec<DependencyKind>> = HashMap::new();
+    let selection = args.targets();
     for dep in &package.dependencies {
+        let evaluated = match dep.kind {
+            DependencyKind::Development => selection.dev(),
+            _ => selection.normal(),
+        };
+        if !evaluated {
+            continue;
+        }
+        if dep.kind == DependencyKind::Normal && !dep.optional && dep.target.is_none() {
+            normal_deps.insert(dep.name.clone());
+        }
+        kinds.entry(dep.name.clone()).or_default().push(dep.kind);
         let underscore_name = dep.name.replace('-', "_");
         name_mappings.insert(underscore_name, dep.name.clone());
-        deps.insert(dep.name.clone(), analyze_dependency(&dep));
+        deps.insert(dep.name.clone(), analyze_dependency(dep));
     }
 
     // Scan source files for usage
     let mut used_crates = HashSet::new();
-    for entry in WalkDir::new("src") {
-        let entry = entry?;
-        if entry.path().extension().map_or(false, |ext| ext == "rs") {
-            let content = std::fs::read_to_string(entry.path())?;
-            scan_for_usage(&content, &mut used_crates, &name_mappings);
+    let package_root = package.manifest_path.parent().unwrap();
+    let scanning = project.progress.bar("Scanning sources", None);
+    // References from generated files don't count as usage, but are remembered so the
+    // report can point at them.
+    let mut generated_refs: HashMap<String, String> = HashMap::new();
+    let mut out_dir_includes = BTreeSet::new();
+    // Crates only used under features this analysis doesn't enable, with the features
+    // gating them and where each gate was first seen.
+    let mut gated: HashMap<String, BTreeMap<String, String>> = HashMap::new();
+    // Crates referenced from `#[cfg(test)]` code, with the first file doing so.
+    let mut test_refs: HashMap<String, String> = HashMap::new();
+    // Crates only referenced under platform predicates (`windows`, `target_os = ...`),
+    // with the predicates and the first file of each.
+    let mut platform_refs: HashMap<String, BTreeMap<String, String>> = HashMap::new();
+    let mut feature_refs = FeatureRefs::new();
+    let enabled = features::enabled(
+        package,
+        &args.features,
+        args.all_features,
+        args.no_default_features,
+    );
+    let feature_enabled = |features: &[String]| features.iter().any(|f| enabled.contains(f));
+    let target_cfg = args
+        .target
+        .as_deref()
+        .map(cfg_eval::TargetCfg::load)
+        .transpose()?;
+    let context = cfg_eval::Context {
+        features: Some(&enabled),
+        target: target_cfg.as_ref(),
+    };
+    let mut files = Vec::new();
+    for path in source_files(args, project, package)? {
+        let content = std::fs::read_to_string(&path)?;
+        files.push((path, content));
+    }
+    let file_gates = cfg::gated_files(&files);
+    let extern_crate = Regex::new(&format!(r"extern\s+crate\s+({})", ident::PATTERN)).unwrap();
+    let mut extern_crates = HashSet::new();
+    let edition_2015 = package.edition == Edition::E2015;
+    let mut redundant_externs = Vec::new();
+    for (path, content) in files {
+        scanning.inc();
+        out_dir_includes.extend(out_dir::includes(&content));
+        for name in extern_crate
+            .captures_iter(&content)
+            .map(|cap| ident::unraw(&cap[1]).to_string())
+        {
+            extern_crates.insert(name_mappings.get(&name).cloned().unwrap_or(name));
+        }
+        if !args.include_generated && generated::is_generated(&content) {
+            tracing::debug!("skipping generated file {}", path.display());
+            let mut referenced = HashSet::new();
+            scan_for_usage(&content, &mut referenced, &name_mappings);
+            for name in referenced {
+                generated_refs
+                    .entry(name)
+                    .or_insert_with(|| project.display_path(&path));
+            }
+            continue;
+        }
+        if !edition_2015 {
+            for declaration in extern_crate::redundant(&content) {
+                let Some(krate) = name_mappings.get(&declaration.krate) else {
+                    continue;
+                };
+                redundant_externs.push(
+                    Finding::new(Kind::RedundantExternCrate, krate)
+                        .member(project, package)
+                        .file(project, &path)
+                        .note(format!(
+                            "`extern crate {};` on line {} is unnecessary since edition 2018",
+                            declaration.krate, declaration.line
+                        ))
+                        .note("Delete it (run with --fix to apply)"),
+                );
+            }
+        }
+        for code in doc_include::code_blocks(&path, &content) {
+            scan_for_usage(&code, &mut used_crates, &name_mappings);
+        }
+        let display = project.display_path(&path);
+        let mut record_gated = |krate: String, features: &[String], attribute: &str| {
+            gated
+                .entry(krate)
+                .or_default()
+                .entry(features.join(", "))
+                .or_insert_with(|| format!("{} in {}", attribute, display));
+        };
+        let mut record_feature = |krate: &str, features: &[String], attribute: &str| {
+            feature_refs
+                .entry(krate.to_string())
+                .or_default()
+                .entry(features.join(", "))
+                .or_insert_with(|| format!("{} in {}", attribute, display));
+        };
+
+        let (content, attributes) = cfg_attr::extract(&content);
+        for attribute in attributes {
+            let krate = name_mappings
+                .get(&attribute.krate)
+                .cloned()
+                .unwrap_or(attribute.krate);
+            if !attribute.features.is_empty() {
+                record_feature(&krate, &attribute.features, "#[cfg_attr]");
+            }
+            if attribute.features.is_empty() || feature_enabled(&attribute.features) {
+                used_crates.insert(krate);
+            } else {
+                record_gated(krate, &attribute.features, "#[cfg_attr]");
+            }
+        }
+
+        let (unconditional, regions) = match file_gates.get(&path) {
+            Some(predicate) => (
+                String::new(),
+                vec![cfg::Region {
+                    predicate: predicate.clone(),
+                    text: content,
+                }],
+            ),
+            None => cfg::split(&content),
+        };
+        scan_for_usage(&unconditional, &mut used_crates, &name_mappings);
+        for region in regions {
+            let predicate = Cfg::parse(&region.predicate);
+            let active = predicate.as_ref().and_then(|p| p.eval(&context));
+            // Whether some feature selection would compile the region.
+            let reachable = predicate.as_ref().and_then(|p| {
+                p.eval(&cfg_eval::Context {
+                    features: None,
+                    ..context
+                })
+            }) != Some(false);
+            let features = predicate.as_ref().map(Cfg::features).unwrap_or_default();
+            let mut referenced = HashSet::new();
+            scan_for_usage(&region.text, &mut referenced, &name_mappings);
+            if !features.is_empty() {
+                for krate in &referenced {
+                    record_feature(krate, &features, "#[cfg]");
+                }
+            }
+            match active {
+                Some(true) => used_crates.extend(referenced),
+                _ if cfg::is_test(&region.predicate) => {
+                    for krate in referenced {
+                        test_refs.entry(krate).or_insert_with(|| display.clone());
+                    }
+                }
+                Some(false) if reachable && !features.is_empty() => {
+                    for krate in referenced {
+                        record_gated(krate, &features, "#[cfg]");
+                    }
+                }
+                _ if platform::is_platform(&region.predicate) => {
+                    for krate in referenced {
+                        platform_refs
+                            .entry(krate)
+                            .or_default()
+                            .entry(region.predicate.clone())
+                            .or_insert_with(|| display.clone());
+                    }
+                }
+                // Never compiled in this configuration, such as `not(unix)` for a Linux
+                // target.
+                Some(false) => {}
+                // Undecided without a target: count it, as the compiler might.
+                None => used_crates.extend(referenced),
+            }
         }
     }
 
-    // Compare and report
-    println!("\nDependency Analysis Report:");
-    println!("==========================");
+    // Files included from OUT_DIR are compiled into the crate, so whatever they reference
+    // counts as usage even though a build script generated them.
+    let target_dir = project.metadata.target_directory.as_std_path();
+    for file in out_dir_includes {
+        match out_dir::locate(target_dir, package, &file) {
+            Some(path) => {
+                tracing::debug!("scanning {} included from OUT_DIR", path.display());
+                let content = std::fs::read_to_string(&path)?;
+                scan_for_usage(&content, &mut used_crates, &name_mappings);
+            }
+            None => tracing::warn!(
+                "{} is included from OUT_DIR but hasn't been generated; build the package first so its references are seen",
+                file
+            ),
+        }
+    }
+
+    let mut example_refs = HashMap::new();
+    if selection.examples {
+        example_refs = target_refs(package, "example", &name_mappings)?;
+    }
+    let mut bench_refs = HashMap::new();
+    if selection.benches {
+        bench_refs = target_refs(package, "bench", &name_mappings)?;
+    }
+    if selection.tests {
+        for (krate, tests) in target_refs(package, "test", &name_mappings)? {
+            let tests = tests.into_iter().collect::<Vec<_>>().join(", ");
+            test_refs
+                .entry(krate)
+                .or_insert_with(|| format!("test target {}", tests));
+        }
+    }
+
+    // In edition 2015 a dependency is only reachable through an `extern crate` (plain, or
+    // `#[macro_use]` for its macros); without one, a `foo::...` path names a module at
+    // the crate root, not the dependency.
+    if edition_2015 {
+        used_crates.retain(|name| extern_crates.contains(name));
+        gated.retain(|name, _| extern_crates.contains(name));
+        test_refs.retain(|name, _| extern_crates.contains(name));
+        platform_refs.retain(|name, _| extern_crates.contains(name));
+        feature_refs.retain(|name, _| extern_crates.contains(name));
+    }
+
+    // libfuzzer-sys re-exports arbitrary, and fuzz crates declare arbitrary next to it to
+    // pick the version and features (such as `derive`) that re-export gets.
+    if used_crates.contains("libfuzzer-sys") {
+        used_crates.insert("arbitrary".to_string());
+    }
 
+    // Compare and report
+    let mut unused = Vec::new();
+    let ignored = ignore::ignored(project, package);
     for (name, info) in deps {
         let underscore_name = name.replace('-', "_");
-        let usage_status = if used_crates.contains(&name) || used_crates.contains(&underscore_name)
+        if used_crates.contains(&name) || used_crates.contains(&underscore_name) {
+            continue;
+        }
+        if let Some(tool) = ignored.get(&name, kinds[&name].iter().copied()) {
+            tracing::debug!(
+                "not reporting {}, ignored in {}'s {} metadata",
+                name,
+                package.name,
+                tool
+            );
+            continue;
+        }
+        if let Some(features) = gated.get(&name) {
+            // An optional dependency switched on by every feature gating its use is
+            // exactly what the attributes ask for.
+            let activating = features::activating(package, &name);
+            if info.optional
+                && features
+                    .keys()
+                    .all(|gate| gate.split(", ").any(|feature| activating.contains(feature)))
+            {
+                continue;
+            }
+            let mut finding = Finding::new(Kind::FeatureGated, &name).member(project, package);
+            for (feature, place) in features {
+                finding =
+                    finding.note(format!("Only used under feature `{}` ({})", feature, place));
+            }
+            finding = finding.note(format!(
+                "Not enabled in this analysis; pass --features {} to count that code",
+                features.keys().next().unwrap().replace(", ", ",")
+            ));
+            finding = finding.note(match (info.optional, activating.is_empty()) {
+                (false, _) => "Make it optional and enable it from those features so other \
+                               builds skip it"
+                    .to_string(),
+                (true, true) => "It is optional, but no feature enables it".to_string(),
+                (true, false) => format!(
+                    "It is optional, but those features don't enable it; it's enabled by: {}",
+                    activating.into_iter().collect::<Vec<_>>().join(", ")
+                ),
+            });
+            unused.push(finding);
+            continue;
+        }
+
+        if let Some(predicates) = platform_refs.get(&name) {
+            // Optional and already platform-specific entries are fine where they are.
+            if normal_deps.contains(&name) {
+                let target = platform::target(&predicates.keys().cloned().collect());
+                let mut finding = Finding::new(Kind::PlatformOnly, &name).member(project, package);
+                for (predicate, file) in predicates {
+                    finding = finding.note(format!(
+                        "Only used under #[cfg({})] (first in {})",
+                        predicate, file
+                    ));
+                }
+                unused.push(finding.note(platform::move_note(&target)));
+            }
+            continue;
+        }
+
+        if let Some(file) = test_refs.get(&name).filter(|_| normal_deps.contains(&name)) {
+            unused.push(
+                Finding::new(Kind::TestOnly, &name)
+                    .member(project, package)
+                    .note(format!("Only used by tests (first in {})", file))
+                    .note("Move it to [dev-dependencies] so dependents don't build it; --fix does"),
+            );
+            continue;
+        }
+
+        if let Some(benches) = bench_refs
+            .get(&name)
+            .filter(|_| normal_deps.contains(&name) && !example_refs.contains_key(&name))
         {
+            unused.push(
+                Finding::new(Kind::BenchOnly, &name)
+                    .member(project, package)
+                    .note(format!(
+                        "Only used by benches: {}",
+                        benches.iter().cloned().collect::<Vec<_>>().join(", ")
+                    ))
+                    .note("Move it to [dev-dependencies] so dependents don't build it"),
+            );
             continue;
+        }
+        if let Some(examples) = example_refs
+            .get(&name)
+            .filter(|_| normal_deps.contains(&name))
+        {
+            unused.push(
+                Finding::new(Kind::ExampleOnly, &name)
+                    .member(project, package)
+                    .note(format!(
+                        "Only used by examples: {}",
+                        examples.iter().cloned().collect::<Vec<_>>().join(", ")
+                    ))
+                    .note(
+                        "Move it to [dev-dependencies], or make it optional behind a feature \
+                         listed in those examples' required-features",
+                    ),
+            );
+            continue;
+        }
+
+        // Dev-dependencies are there for tests, examples and benches.
+        if example_refs.contains_key(&name)
+            || bench_refs.contains_key(&name)
+            || test_refs.contains_key(&name)
+        {
+            continue;
+        }
+
+        let scanned = if info.dev {
+            "the tests, examples or benches".to_string()
         } else {
-            "POTENTIALLY UNUSED"
+            format!(
+                "any source file under {}",
+                project.display_path(
+                    Some(package_root.join("src"))
+                        .filter(|src| src.is_dir())
+                        .as_deref()
+                        .unwrap_or(package_root)
+                        .as_std_path()
+                )
+            )
         };
+        let mut finding = Finding::new(Kind::Unused, &name)
+            .member(project, package)
+            .note(format!("Not referenced from {}", scanned))
+            .note(format!("Version: {}", info.version))
+            .note(format!("Feature flags: {:?}", info.features))
+            .note("⚠️  This dependency might be removable. Verify:")
+            .note("  1. Check for macro usage")
+            .note("  2. Look for #[derive(...)] usage")
+            .note("  3. Review build.rs dependencies")
+            .note("  4. Check conditional compilation flags");
+        if edition_2015 {
+            finding = finding.note(
+                "Edition 2015: it isn't declared with `extern crate`, so the package can't use it",
+            );
+        }
+        if let Some(file) = generated_refs
+            .get(&name)
+            .or_else(|| generated_refs.get(&underscore_name))
+        {
+            finding = finding.note(format!(
+                "Referenced from generated code ({}); pass --include-generated to count it",
+                file
+            ));
+        }
+        unused.push(finding);
+    }
+
+    if args.verbose > 0 {
+        blame::annotate(package, &mut unused);
+    }
+    unused.extend(redundant_externs);
+    let feature_gated: HashSet<String> = unused
+        .iter()
+        .filter(|f| f.kind == Kind::FeatureGated)
+        .map(|f| f.krate.clone())
+        .collect();
+    unused.extend(feature_matrix::findings(
+        project,
+        package,
+        &feature_refs,
+        &feature_gated,
+    ));
 
-        println!("\n{} ({})", name, usage_status);
-        println!("Version: {}", info.version);
-        println!("Feature flags: {:?}", info.features);
+    Ok((unused, feature_refs))
+}
 
-        if usage_status == "POTENTIALLY UNUSED" {
-            println!("⚠️  This dependency might be removable. Verify:");
-            println!("  1. Check for macro usage");
-            println!("  2. Look for #[derive(...)] usage");
-            println!("  3. Review build.rs dependencies");
-            println!("  4. Check conditional compilation flags");
+/// The crates referenced by the package's targets of `kind` (`example`, `bench`), with the
+/// targets referencing each.
+fn target_refs(
+    package: &Package,
+    kind: &str,
+    name_mappings: &HashMap<String, String>,
+) -> Result<HashMap<String, BTreeSet<String>>, Box<dyn std::error::Error>> {
+    let mut refs: HashMap<String, BTreeSet<String>> = HashMap::new();
+    for target in package
+        .targets
+        .iter()
+        .filter(|t| t.kind.iter().any(|k| k == kind))
+    {
+        let mut referenced = HashSet::new();
+        for file in targets::files(target) {
+            scan_for_usage(
+                &std::fs::read_to_string(&file)?,
+                &mut referenced,
+                name_mappings,
+            );
+        }
+        for krate in referenced {
+            refs.entry(krate).or_default().insert(target.name.clone());
         }
     }
+    Ok(refs)
+}
 
-    Ok(())
+/// The Rust files to scan for usage: those under `<package>/src` and of library or binary
+/// targets kept elsewhere (such as cargo-fuzz's `fuzz_targets`), or the ones given with
+/// --files-from that belong to the package, minus the --exclude and `exclude` globs. With
+/// only one of --lib and --bins, the entry points of the other targets are left out
+/// (modules they share are still scanned).
+fn source_files(
+    args: &Args,
+    project: &Project,
+    package: &Package,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let selection = args.targets();
+    // Without the library and binaries, src is still scanned for `#[cfg(test)]` code.
+    if !selection.normal() && !selection.tests {
+        return Ok(Vec::new());
+    }
+    let package_root = package.manifest_path.parent().unwrap().as_std_path();
+    let src = package_root.join("src");
+    let scanned = |target: &Target| {
+        if targets::is_lib(target) {
+            selection.lib || !selection.normal()
+        } else {
+            target.kind.iter().any(|k| k == "bin") && (selection.bins || !selection.normal())
+        }
+    };
+    let unselected: Vec<PathBuf> = package
+        .targets
+        .iter()
+        .filter(|t| t.kind.iter().any(|k| k == "bin") || targets::is_lib(t))
+        .filter(|t| !scanned(t))
+        .map(|t| {
+            let entry = t.src_path.as_std_path();
+            match entry.parent() {
+                // `src/bin/<name>/main.rs`: the directory is the binary's own.
+                Some(dir) if entry.ends_with("main.rs") && dir != src => dir.to_path_buf(),
+                _ => entry.to_path_buf(),
+            }
+        })
+        .collect();
+    let mut excludes = GlobSetBuilder::new();
+    for pattern in args.exclude.iter().chain(&project.config.exclude) {
+        excludes.add(Glob::new(pattern)?);
+    }
+    let excludes = excludes.build()?;
+    let included = |path: &Path| {
+        !excludes.is_match(path.strip_prefix(package_root).unwrap_or(path))
+            && !unselected.iter().any(|skipped| path.starts_with(skipped))
+    };
+    let is_rust = |path: &Path| path.extension().is_some_and(|ext| ext == "rs");
+
+    if let Some(files) = &args.files {
+        return Ok(files
+            .iter()
+            .filter(|path| is_rust(path) && path.starts_with(package_root) && path.is_file())
+            .filter(|path| included(path))
+            .cloned()
+            .collect());
+    }
+
+    let mut files: Vec<PathBuf> = package
+        .targets
+        .iter()
+        .filter(|t| scanned(t))
+        .filter(|t| !t.src_path.as_std_path().starts_with(&src))
+        .flat_map(targets::files)
+        .filter(|path| included(path))
+        .collect();
+    if !src.is_dir() {
+        return Ok(files);
+    }
+    // Pruning excluded directories keeps large generated trees from being walked at all.
+    for entry in WalkDir::new(&src)
+        .follow_links(args.follow_symlinks)
+        .into_iter()
+        .filter_entry(|entry| included(entry.path()))
+    {
+        let entry = match entry {
+            Err(err) if err.loop_ancestor().is_some() => {
+                tracing::warn!("skipping symlink loop: {}", err);
+                continue;
+            }
+            entry => entry?,
+        };
+        if is_rust(entry.path()) {
+            files.push(entry.into_path());
+        }
+    }
+    Ok(files)
 }
 
 #[derive(Debug)]
 struct DependencyInfo {
     version: String,
     features: Vec<String>,
+    optional: bool,
+    dev: bool,
 }
 
 fn analyze_dependency(dep: &Dependency) -> DependencyInfo {
     DependencyInfo {
         version: dep.req.to_string(),
         features: dep.features.clone(),
+        optional: dep.optional,
+        dev: dep.kind == DependencyKind::Development,
     }
 }
 
@@ -74,27 +1075,34 @@ fn scan_for_usage(
     used_crates: &mut HashSet<String>,
     name_mappings: &HashMap<String, String>,
 ) {
+    for segment in macros::crate_path_segments(content) {
+        let name = name_mappings.get(&segment).cloned().unwrap_or(segment);
+        used_crates.insert(name);
+    }
+    let content = &*macros::normalize(content);
+    let aliases = aliases::table(content);
+    let ident = ident::PATTERN;
     let patterns = [
         // Basic use statements
-        r#"use\s+([a-zA-Z_][a-zA-Z0-9_]*)\s*::"#,
+        format!(r"use\s+({ident})\s*::"),
         // Qualified use statements
-        r#"use\s+([a-zA-Z_][a-zA-Z0-9_]*)(?:\s+as\s+[a-zA-Z_][a-zA-Z0-9_]*)?\s*;"#,
+        format!(r"use\s+({ident})(?:\s+as\s+{ident})?\s*;"),
         // Extern crate statements
-        r#"extern\s+crate\s+([a-zA-Z_][a-zA-Z0-9_]*)"#,
+        format!(r"extern\s+crate\s+({ident})"),
         // Derive macros
-        r#"#\[derive\(([^)]*)\)\]"#,
+        r"#\[derive\(([^)]*)\)\]".to_string(),
         // Direct crate references
-        r#"([a-zA-Z_][a-zA-Z0-9_]*)::\w+"#,
+        format!(r"({ident})::\w+"),
         // Macro usage
-        r#"([a-zA-Z_][a-zA-Z0-9_]*)!\s*[({]"#,
+        format!(r"({ident})!\s*[({{]"),
         // Module declarations
-        r#"mod\s+([a-zA-Z_][a-zA-Z0-9_]*)\s*;"#,
+        format!(r"mod\s+({ident})\s*;"),
         // Type annotations
-        r#":\s*([a-zA-Z_][a-zA-Z0-9_]*)::"#,
+        format!(r":\s*({ident})::"),
     ];
 
     for pattern in patterns {
-        let re = Regex::new(pattern).unwrap();
+        let re = Regex::new(&pattern).unwrap();
         for cap in re.captures_iter(content) {
             if let Some(m) = cap.get(1) {
                 for name in m.as_str().split(',') {
@@ -103,7 +1111,12 @@ fn scan_for_usage(
                         .trim_start_matches("crate::")
                         .trim_start_matches("self::")
                         .trim_start_matches("::");
+                    let clean_name = ident::unraw(clean_name);
 
+                    let clean_name = aliases
+                        .get(clean_name)
+                        .map(String::as_str)
+                        .unwrap_or(clean_name);
                     if !clean_name.is_empty()
                         && !clean_name.starts_with("super")
                         && !clean_name.starts_with("crate")
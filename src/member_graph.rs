@@ -0,0 +1,147 @@
+use crate::cli::Args;
+use crate::project::Project;
+use cargo_metadata::DependencyKind;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// `members`: the workspace's internal graph, which members depend on which and how
+/// (normal, dev or build edges), layered so that each member sits above everything it
+/// depends on. `--dot` prints it for Graphviz instead.
+pub fn run(args: &Args, dot: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let project = Project::load(args.manifest_path.as_deref())?;
+    let edges = edges(&project);
+    let members: BTreeSet<&str> = project.members().map(|pkg| pkg.name.as_str()).collect();
+
+    if dot {
+        println!("digraph members {{");
+        println!("    rankdir=BT;");
+        for member in &members {
+            println!("    \"{}\";", member);
+        }
+        for ((from, to), kinds) in &edges {
+            for kind in kinds {
+                let style = match *kind {
+                    "dev" => " [style=dashed, label=\"dev\"]",
+                    "build" => " [style=dotted, label=\"build\"]",
+                    _ => "",
+                };
+                println!("    \"{}\" -> \"{}\"{};", from, to, style);
+            }
+        }
+        println!("}}");
+        return Ok(());
+    }
+
+    let count: usize = edges.values().map(BTreeSet::len).sum();
+    println!(
+        "\nWorkspace member graph ({} members, {} internal edges):",
+        members.len(),
+        count
+    );
+    let width = members.iter().map(|m| m.chars().count()).max().unwrap_or(0);
+    for member in &members {
+        let deps: Vec<String> = edges
+            .iter()
+            .filter(|((from, _), _)| from == member)
+            .map(|((_, to), kinds)| {
+                let kinds: Vec<&str> = kinds.iter().copied().filter(|k| *k != "normal").collect();
+                if kinds.is_empty() {
+                    to.to_string()
+                } else {
+                    format!("{} ({})", to, kinds.join(", "))
+                }
+            })
+            .collect();
+        let deps = if deps.is_empty() {
+            "-".to_string()
+        } else {
+            deps.join(", ")
+        };
+        println!("  {:<width$}  -> {}", member, deps, width = width);
+    }
+
+    println!("\nLayers (each member depends only on the layers below it):");
+    let layers = layers(&members, &edges);
+    for (index, layer) in layers.iter().enumerate() {
+        println!("  {}: {}", index, layer.join(", "));
+    }
+    // Dev-dependencies may point anywhere, as tests build after the library; those
+    // pointing at the same layer or above tie the layers together for testing.
+    let layer_of = |member: &str| layers.iter().position(|layer| layer.contains(&member));
+    let upward: Vec<String> = edges
+        .iter()
+        .filter(|(_, kinds)| kinds.contains("dev"))
+        .filter(|((from, to), _)| layer_of(to) >= layer_of(from))
+        .map(|((from, to), _)| format!("{} -> {}", from, to))
+        .collect();
+    if !upward.is_empty() {
+        println!("  dev edges pointing up the layers: {}", upward.join(", "));
+    }
+
+    let dependents: BTreeSet<&str> = edges.keys().map(|(_, to)| *to).collect();
+    let roots: Vec<&str> = members
+        .iter()
+        .copied()
+        .filter(|member| !dependents.contains(member))
+        .collect();
+    println!("\nMembers no other member depends on: {}", roots.join(", "));
+    Ok(())
+}
+
+/// The dependency edges between members, with their kinds (`normal`, `dev`, `build`),
+/// from the resolved graph.
+fn edges(project: &Project) -> BTreeMap<(&str, &str), BTreeSet<&'static str>> {
+    let mut edges: BTreeMap<(&str, &str), BTreeSet<&'static str>> = BTreeMap::new();
+    let nodes = project.metadata.resolve.iter().flat_map(|r| &r.nodes);
+    for node in nodes.filter(|node| project.metadata.workspace_members.contains(&node.id)) {
+        let Some(from) = project.package(&node.id) else {
+            continue;
+        };
+        for dep in &node.deps {
+            if !project.metadata.workspace_members.contains(&dep.pkg) {
+                continue;
+            }
+            let Some(to) = project.package(&dep.pkg) else {
+                continue;
+            };
+            let kinds = edges.entry((&from.name, &to.name)).or_default();
+            for kind in &dep.dep_kinds {
+                kinds.insert(match kind.kind {
+                    DependencyKind::Development => "dev",
+                    DependencyKind::Build => "build",
+                    _ => "normal",
+                });
+            }
+        }
+    }
+    edges
+}
+
+/// The members by layer, from those depending on no other member up, over normal and
+/// build edges (which cargo keeps acyclic).
+fn layers<'a>(
+    members: &BTreeSet<&'a str>,
+    edges: &BTreeMap<(&'a str, &'a str), BTreeSet<&'static str>>,
+) -> Vec<Vec<&'a str>> {
+    let mut placed: BTreeSet<&str> = BTreeSet::new();
+    let mut layers: Vec<Vec<&str>> = Vec::new();
+    loop {
+        let ready: Vec<&str> = members
+            .iter()
+            .copied()
+            .filter(|member| !placed.contains(member))
+            .filter(|member| {
+                edges
+                    .iter()
+                    .filter(|((from, _), kinds)| {
+                        from == member && kinds.iter().any(|k| *k != "dev")
+                    })
+                    .all(|((_, to), _)| placed.contains(to))
+            })
+            .collect();
+        if ready.is_empty() {
+            return layers;
+        }
+        placed.extend(&ready);
+        layers.push(ready);
+    }
+}
@@ -0,0 +1,299 @@
+use crate::report::{Kind, Severity};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// How many crates each ranking shows.
+const TOP: usize = 10;
+
+/// A finding as the machine formats serialize it, with the fields a rollup needs.
+#[derive(Deserialize)]
+struct Entry {
+    kind: Kind,
+    severity: Severity,
+    #[serde(rename = "crate")]
+    krate: String,
+}
+
+/// The versions of a crate resolved across the reports, and the reports depending on it.
+type Usage<'a> = (BTreeSet<&'a str>, BTreeSet<&'a str>);
+
+/// What one report contributes.
+struct Report {
+    name: String,
+    findings: Vec<Entry>,
+    /// The resolved packages (name, version), for reports that list them.
+    packages: Option<BTreeSet<(String, String)>>,
+}
+
+/// `merge`: combines reports produced earlier, across repositories, into one view for
+/// dependency governance: how many distinct crates the organization depends on, which
+/// are used most widely and resolved at the most versions, which crates are most often
+/// left unused, and which advisories (security advisories, yanked versions, checksum
+/// mismatches, other errors) affect several repositories.
+pub fn run(paths: &[PathBuf], as_json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let reports = paths
+        .iter()
+        .map(|path| read(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let rollup = rollup(&reports);
+    if as_json {
+        println!("{}", serde_json::to_string_pretty(&rollup)?);
+    } else {
+        print(&rollup);
+    }
+    Ok(())
+}
+
+/// Reads a report from `--format jsonl` or `--format attest` (signed or not), or the
+/// findings as the dashboard (a JSON array) or the daemon (a response to `analyze`)
+/// serve them.
+fn read(path: &Path) -> Result<Report, Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| format!("could not read {}: {}", path.display(), err))?;
+    let name = path.display().to_string();
+    let not_a_report = || {
+        format!(
+            "{} is not a report from --format jsonl or --format attest",
+            path.display()
+        )
+    };
+    let mut value = match serde_json::from_str::<Value>(&content) {
+        Ok(value) => value,
+        // Several values: one finding per line.
+        Err(_) => {
+            let findings = content
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(serde_json::from_str)
+                .collect::<Result<Vec<Entry>, _>>()
+                .map_err(|_| not_a_report())?;
+            return Ok(Report {
+                name,
+                findings,
+                packages: None,
+            });
+        }
+    };
+    // A signed report: the envelope's payload, which `verify` checks the signature of.
+    if let Some(payload) = value.get("payload").and_then(Value::as_str) {
+        value = serde_json::from_slice(&BASE64.decode(payload)?).map_err(|_| not_a_report())?;
+    }
+    if let Some(result) = value.get("result") {
+        value = result.clone();
+    }
+    if value["predicateType"] == crate::attest::PREDICATE_TYPE {
+        let predicate = &value["predicate"];
+        let findings = serde_json::from_value(predicate["findings"].clone())?;
+        let packages = predicate["packages"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            // The workspace's own packages aren't dependencies.
+            .filter(|pkg| pkg["member"] != true)
+            .filter_map(|pkg| {
+                Some((
+                    pkg["name"].as_str()?.to_string(),
+                    pkg["version"].as_str()?.to_string(),
+                ))
+            })
+            .collect();
+        return Ok(Report {
+            name,
+            findings,
+            packages: Some(packages),
+        });
+    }
+    let findings = match value {
+        Value::Array(_) => value,
+        Value::Object(ref object) if object.contains_key("findings") => value["findings"].clone(),
+        // A report of a single finding.
+        Value::Object(_) => Value::Array(vec![value]),
+        _ => return Err(not_a_report().into()),
+    };
+    Ok(Report {
+        name,
+        findings: serde_json::from_value(findings).map_err(|_| not_a_report())?,
+        packages: None,
+    })
+}
+
+/// Whether a finding is an advisory: a problem to act on wherever it appears.
+fn is_advisory(entry: &Entry) -> bool {
+    matches!(
+        entry.kind,
+        Kind::YankedVersion | Kind::Advisory | Kind::ChecksumMismatch
+    ) || entry.severity == Severity::Error
+}
+
+fn rollup(reports: &[Report]) -> Value {
+    let listing: Vec<&Report> = reports.iter().filter(|r| r.packages.is_some()).collect();
+    let mut crates: BTreeMap<&str, Usage> = BTreeMap::new();
+    for report in &listing {
+        for (name, version) in report.packages.iter().flatten() {
+            let (versions, users) = crates.entry(name).or_default();
+            versions.insert(version);
+            users.insert(&report.name);
+        }
+    }
+    let ranked = |key: &dyn Fn(&Usage) -> usize| -> Vec<Value> {
+        let mut ranked: Vec<_> = crates.iter().filter(|(_, c)| key(c) > 1).collect();
+        ranked.sort_by(|a, b| key(b.1).cmp(&key(a.1)).then(a.0.cmp(b.0)));
+        ranked
+            .into_iter()
+            .take(TOP)
+            .map(|(name, (versions, users))| {
+                json!({ "crate": name, "reports": users.len(), "versions": versions })
+            })
+            .collect()
+    };
+    let most_used = ranked(&|(_, users)| users.len());
+    let most_versions = ranked(&|(versions, _)| versions.len());
+
+    // For each (kind, crate), the reports it was found in.
+    let mut found: BTreeMap<(Kind, &str), BTreeSet<&str>> = BTreeMap::new();
+    let mut severities: BTreeMap<Severity, usize> = BTreeMap::new();
+    let mut kinds: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut advisory: BTreeSet<(Kind, &str)> = BTreeSet::new();
+    for report in reports {
+        for entry in &report.findings {
+            found
+                .entry((entry.kind, &entry.krate))
+                .or_default()
+                .insert(&report.name);
+            *severities.entry(entry.severity).or_default() += 1;
+            *kinds.entry(entry.kind.id()).or_default() += 1;
+            if is_advisory(entry) {
+                advisory.insert((entry.kind, &entry.krate));
+            }
+        }
+    }
+    let shared = |include: &dyn Fn(Kind, &str) -> bool, min: usize| -> Vec<Value> {
+        let mut shared: Vec<_> = found
+            .iter()
+            .filter(|((kind, krate), users)| include(*kind, krate) && users.len() >= min)
+            .collect();
+        shared.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(b.0)));
+        shared
+            .into_iter()
+            .map(|((kind, krate), users)| {
+                json!({ "kind": kind.id(), "crate": krate, "reports": users })
+            })
+            .collect()
+    };
+    let unused: Vec<Value> = shared(&|kind, _| kind == Kind::Unused, 1)
+        .into_iter()
+        .take(TOP)
+        .collect();
+    let advisories = shared(&|kind, krate| advisory.contains(&(kind, krate)), 2);
+
+    let unique_versions: usize = crates.values().map(|(versions, _)| versions.len()).sum();
+    json!({
+        "reports": reports.iter().map(|r| &r.name).collect::<Vec<_>>(),
+        "dependencies": {
+            "reports_listing_packages": listing.len(),
+            "unique_crates": crates.len(),
+            "unique_versions": unique_versions,
+            "most_used": most_used,
+            "most_versions": most_versions,
+        },
+        "findings": {
+            "total": severities.values().sum::<usize>(),
+            "by_severity": severities
+                .iter()
+                .map(|(severity, count)| (severity.to_string(), *count))
+                .collect::<BTreeMap<_, _>>(),
+            "by_kind": kinds,
+        },
+        "most_common_unused": unused,
+        "shared_advisories": advisories,
+    })
+}
+
+fn print(rollup: &Value) {
+    let reports = rollup["reports"].as_array().map_or(0, Vec::len);
+    let dependencies = &rollup["dependencies"];
+    println!("\nMerged Reports:");
+    println!("==========================");
+    for (label, value) in [
+        ("Reports", reports.to_string()),
+        ("Unique crates", dependencies["unique_crates"].to_string()),
+        (
+            "Unique versions",
+            dependencies["unique_versions"].to_string(),
+        ),
+        ("Findings", rollup["findings"]["total"].to_string()),
+    ] {
+        println!("{:<24} {:>6}", label, value);
+    }
+    let listing = dependencies["reports_listing_packages"]
+        .as_u64()
+        .unwrap_or(0);
+    if listing < reports as u64 {
+        println!(
+            "Only {} of {} reports list their packages (--format attest); the crate counts cover those.",
+            listing, reports
+        );
+    }
+
+    let crates = |title: &str, key: &str, entries: &Value| {
+        let Some(entries) = entries.as_array().filter(|e| !e.is_empty()) else {
+            return;
+        };
+        println!("\n{}:", title);
+        println!("==========================");
+        for entry in entries {
+            let versions: Vec<&str> = entry["versions"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .collect();
+            let count = if key == "reports" {
+                entry["reports"].as_u64().unwrap_or(0) as usize
+            } else {
+                versions.len()
+            };
+            println!(
+                "{:<24} {:>6}  {}",
+                entry["crate"].as_str().unwrap_or_default(),
+                count,
+                versions.join(", ")
+            );
+        }
+    };
+    crates("Most Widely Used", "reports", &dependencies["most_used"]);
+    crates(
+        "Most Versions Resolved",
+        "versions",
+        &dependencies["most_versions"],
+    );
+
+    let listed = |title: &str, entries: &Value, with_kind: bool| {
+        let Some(entries) = entries.as_array().filter(|e| !e.is_empty()) else {
+            return;
+        };
+        println!("\n{}:", title);
+        println!("==========================");
+        for entry in entries {
+            let users: Vec<&str> = entry["reports"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .collect();
+            let name = entry["crate"].as_str().unwrap_or_default();
+            let name = if with_kind {
+                format!("{} ({})", name, entry["kind"].as_str().unwrap_or_default())
+            } else {
+                name.to_string()
+            };
+            println!("{:<24} {:>6}  {}", name, users.len(), users.join(", "));
+        }
+    };
+    listed("Most Common Unused", &rollup["most_common_unused"], false);
+    listed("Shared Advisories", &rollup["shared_advisories"], true);
+}
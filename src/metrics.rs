@@ -0,0 +1,72 @@
+use crate::history::Snapshot;
+use crate::project::Project;
+use crate::report::{Finding, Kind};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// `--format metrics`: the report's counts as gauges in the Prometheus text exposition
+/// format, for scheduled runs to feed dashboards and alerts.
+pub fn render(project: &Project, findings: &[Finding]) -> String {
+    let snapshot = Snapshot::take(project, findings);
+    let count = |kind: Kind| findings.iter().filter(|f| f.kind == kind).count();
+
+    let mut out = String::new();
+    for (name, help, value) in [
+        (
+            "direct_dependencies",
+            "Distinct non-path dependencies declared by workspace members",
+            snapshot.direct_deps,
+        ),
+        (
+            "crates_total",
+            "Packages in the resolved dependency graph",
+            snapshot.total_crates,
+        ),
+        (
+            "unused_dependencies",
+            "Dependencies flagged as potentially unused",
+            snapshot.unused,
+        ),
+        (
+            "duplicate_crates",
+            "Crates resolved at more than one version",
+            snapshot.duplicates,
+        ),
+        (
+            "yanked_versions",
+            "Locked versions that have been yanked from their registry",
+            count(Kind::YankedVersion),
+        ),
+        (
+            "advisories",
+            "Locked packages covered by a RustSec advisory",
+            count(Kind::Advisory),
+        ),
+    ] {
+        gauge(&mut out, name, help, [(String::new(), value)]);
+    }
+
+    let mut by_kind: BTreeMap<Kind, usize> = BTreeMap::new();
+    for finding in findings {
+        *by_kind.entry(finding.kind).or_default() += 1;
+    }
+    let samples = by_kind
+        .into_iter()
+        .map(|(kind, count)| (format!("{{kind=\"{}\"}}", kind.id()), count));
+    gauge(&mut out, "findings", "Findings by kind", samples);
+
+    out
+}
+
+fn gauge(
+    out: &mut String,
+    name: &str,
+    help: &str,
+    samples: impl IntoIterator<Item = (String, usize)>,
+) {
+    let _ = writeln!(out, "# HELP dep_analysis_{} {}", name, help);
+    let _ = writeln!(out, "# TYPE dep_analysis_{} gauge", name);
+    for (labels, value) in samples {
+        let _ = writeln!(out, "dep_analysis_{}{} {}", name, labels, value);
+    }
+}
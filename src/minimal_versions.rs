@@ -0,0 +1,320 @@
+use crate::cli::Args;
+use crate::project::{read_toml, Project};
+use cargo_metadata::diagnostic::DiagnosticLevel;
+use cargo_metadata::{DependencyKind, Message, PackageId};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::BufReader;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// A direct registry dependency as the members declare it.
+struct Direct {
+    requirements: BTreeSet<String>,
+    members: BTreeSet<String>,
+}
+
+/// `minimal-versions`: resolves the direct dependencies to the lowest versions their
+/// requirements allow (cargo's `-Z direct-minimal-versions`, into a separate lockfile so
+/// the project's stays as it is), runs `cargo check` on a copy of the workspace, and
+/// reports the dependencies whose declared lower bounds are too low to build, blaming only
+/// the errors a check with the project's own lockfile doesn't have. Exits with 1 when one
+/// is.
+pub fn run(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let project = Project::load(args.manifest_path.as_deref())?;
+    let direct = direct_dependencies(&project);
+    let dir = tempfile::Builder::new()
+        .prefix("cargo-dep-analysis-")
+        .tempdir()?;
+    let lockfile = dir.path().join("Cargo.lock");
+
+    let update = update(args, &project, &lockfile, &["-Zdirect-minimal-versions"]).output()?;
+    if !update.status.success() {
+        let stderr = String::from_utf8_lossy(&update.stderr);
+        println!("\nThe lowest allowed versions don't resolve:");
+        for (name, version, requirement) in conflicts(&stderr) {
+            println!(
+                "  {} = \"{}\" allows {}, which other packages in the graph don't accept",
+                name, requirement, version
+            );
+        }
+        println!("\n{}", stderr.trim());
+        drop(dir);
+        std::process::exit(1);
+    }
+
+    let locked = read_toml(&lockfile)?;
+    let minimal: BTreeMap<&str, &str> = locked
+        .get("package")
+        .and_then(|p| p.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|pkg| Some((pkg.get("name")?.as_str()?, pkg.get("version")?.as_str()?)))
+        .filter(|(name, _)| direct.contains_key(*name))
+        .collect();
+    println!("\nDirect dependencies at their lowest allowed versions:");
+    for (name, dep) in &direct {
+        match minimal.get(name.as_str()) {
+            Some(version) => println!("  {} {} ({})", name, version, list(&dep.requirements)),
+            None => println!("  {} ({}): not in the build", name, list(&dep.requirements)),
+        }
+    }
+
+    // Errors the workspace already has with its own lockfile aren't the lower bounds'
+    // doing.
+    tracing::info!("checking the workspace with its own lockfile");
+    let (_, baseline) = check(args, &project, &project.root())?;
+    let known: BTreeSet<(&str, &str)> = baseline
+        .iter()
+        .filter_map(|error| Some((package_name(&project, &error.package)?, error.text.as_str())))
+        .collect();
+
+    // `-Zlockfile-path` would need `RUSTC_BOOTSTRAP` for the build too, which changes what
+    // build scripts detect, so the check runs in a copy using the minimal lockfile.
+    let scratch = dir.path().join("workspace");
+    crate::feature_trim::copy_workspace(&project, &scratch)?;
+    std::fs::copy(&lockfile, scratch.join("Cargo.lock"))?;
+    tracing::info!("checking the workspace with the lowest allowed versions");
+    let (built, errors) = check(args, &project, &scratch)?;
+
+    let members: BTreeSet<_> = project.members().map(|m| m.id.clone()).collect();
+    let mut too_low: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut other = Vec::new();
+    let mut new_errors = 0;
+    for error in &errors {
+        let pkg = project.package(&error.package);
+        if pkg.is_some_and(|pkg| known.contains(&(pkg.name.as_str(), error.text.as_str()))) {
+            continue;
+        }
+        new_errors += 1;
+        let blamed: Vec<&String> = match pkg {
+            // The dependency itself doesn't build at that version.
+            Some(pkg) if !members.contains(&pkg.id) => {
+                direct.keys().filter(|name| **name == pkg.name).collect()
+            }
+            // The member's code needs more than the lowest version offers: blame the
+            // dependencies the error mentions.
+            _ => direct
+                .keys()
+                .filter(|name| mentions(&error.rendered, name))
+                .collect(),
+        };
+        if blamed.is_empty() {
+            other.push(error.text.clone());
+        } else {
+            for name in blamed {
+                too_low
+                    .entry(name.clone())
+                    .or_default()
+                    .push(error.text.clone());
+            }
+        }
+    }
+
+    if !built && new_errors == 0 && !baseline.is_empty() {
+        println!(
+            "\nThe workspace doesn't build with its own lockfile either ({} error{}); none are \
+             new with the lowest allowed versions.",
+            baseline.len(),
+            if baseline.len() == 1 { "" } else { "s" }
+        );
+        return Ok(());
+    }
+    if built {
+        println!("\nThe workspace builds with the lowest allowed versions.");
+        return Ok(());
+    }
+    if !too_low.is_empty() {
+        println!("\nLower bounds too low to build:");
+    }
+    for (name, errors) in &too_low {
+        let dep = &direct[name];
+        println!(
+            "  {} {} in {}: raise the requirement to a version providing what the code uses",
+            name,
+            list(&dep.requirements),
+            list(&dep.members)
+        );
+        for error in errors {
+            println!("      {}", error);
+        }
+    }
+    if !other.is_empty() || too_low.is_empty() {
+        println!("\nErrors not attributed to a direct dependency:");
+        for error in &other {
+            println!("  {}", error);
+        }
+        if other.is_empty() {
+            println!("  the build failed without a compiler error (see `cargo check`)");
+        }
+    }
+    drop(dir);
+    std::process::exit(1);
+}
+
+/// The registry dependencies of the members, with the requirements they are declared
+/// with and the members declaring them.
+fn direct_dependencies(project: &Project) -> BTreeMap<String, Direct> {
+    let mut direct: BTreeMap<String, Direct> = BTreeMap::new();
+    for member in project.members() {
+        for dep in &member.dependencies {
+            if dep.path.is_some() || dep.source.is_none() {
+                continue;
+            }
+            let entry = direct.entry(dep.name.clone()).or_insert_with(|| Direct {
+                requirements: BTreeSet::new(),
+                members: BTreeSet::new(),
+            });
+            let kind = match dep.kind {
+                DependencyKind::Development => " (dev)",
+                DependencyKind::Build => " (build)",
+                _ => "",
+            };
+            entry.requirements.insert(format!("{}{}", dep.req, kind));
+            entry.members.insert(member.name.clone());
+        }
+    }
+    direct
+}
+
+/// An error `cargo check` reported.
+struct CompileError {
+    package: PackageId,
+    /// The message and the place it points at.
+    text: String,
+    /// The message as rustc renders it, with the code it's about.
+    rendered: String,
+}
+
+/// Runs `cargo check` on the workspace at `root` with the lockfile it has there, and
+/// returns whether it built, with the errors it reported.
+fn check(
+    args: &Args,
+    project: &Project,
+    root: &Path,
+) -> Result<(bool, Vec<CompileError>), Box<dyn std::error::Error>> {
+    let mut check = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".into()));
+    check
+        .args([
+            "check",
+            "--workspace",
+            "--all-targets",
+            "--message-format=json",
+        ])
+        .arg("--manifest-path")
+        .arg(root.join("Cargo.toml"))
+        // Both runs share the separate target directory, so what they have in common
+        // is built once.
+        .arg("--target-dir")
+        .arg(
+            project
+                .metadata
+                .target_directory
+                .join("dep-analysis")
+                .as_std_path(),
+        )
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    if args.all_features {
+        check.arg("--all-features");
+    }
+    if args.no_default_features {
+        check.arg("--no-default-features");
+    }
+    if !args.features.is_empty() {
+        check.arg("--features").arg(args.features.join(","));
+    }
+    if let Some(target) = &args.target {
+        check.arg("--target").arg(target);
+    }
+    if args.offline || project.offline() {
+        check.arg("--offline");
+    }
+    let mut child = check.spawn()?;
+
+    let mut errors = Vec::new();
+    let stdout = child.stdout.take().expect("stdout is piped");
+    for message in Message::parse_stream(BufReader::new(stdout)) {
+        let Message::CompilerMessage(msg) = message? else {
+            continue;
+        };
+        if msg.message.level != DiagnosticLevel::Error {
+            continue;
+        }
+        let place = msg
+            .message
+            .spans
+            .iter()
+            .find(|span| span.is_primary)
+            .map(|span| format!(" ({}:{})", span.file_name, span.line_start))
+            .unwrap_or_default();
+        // Members of the copy are identified by their path in it.
+        let package = PackageId {
+            repr: msg
+                .package_id
+                .repr
+                .replace(&*root.to_string_lossy(), &project.root().to_string_lossy()),
+        };
+        errors.push(CompileError {
+            package,
+            text: format!("{}{}", msg.message.message, place),
+            rendered: msg
+                .message
+                .rendered
+                .unwrap_or_else(|| msg.message.message.clone()),
+        });
+    }
+    Ok((child.wait()?.success(), errors))
+}
+
+fn package_name<'a>(project: &'a Project, id: &PackageId) -> Option<&'a str> {
+    project.package(id).map(|pkg| pkg.name.as_str())
+}
+
+/// `cargo update` with `update_args`, writing the lockfile to `lockfile`. The `-Z` flags
+/// need a nightly cargo; `RUSTC_BOOTSTRAP` lets a stable one accept them, and is set for
+/// this call alone since it builds nothing.
+fn update(args: &Args, project: &Project, lockfile: &Path, update_args: &[&str]) -> Command {
+    let mut command = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".into()));
+    command
+        .env("RUSTC_BOOTSTRAP", "1")
+        .arg("update")
+        .args(update_args)
+        // Cargo ignores `-Zlockfile-path` given before the subcommand.
+        .args(["-Zlockfile-path", "--config"])
+        .arg(format!(
+            "resolver.lockfile-path={}",
+            toml::Value::String(lockfile.to_string_lossy().into_owned())
+        ))
+        .arg("--manifest-path")
+        .arg(project.root().join("Cargo.toml"));
+    if args.offline || project.offline() {
+        command.arg("--offline");
+    }
+    command
+}
+
+/// The packages cargo selected at their lowest version before running into a conflict,
+/// with that version and the requirement it was selected for.
+fn conflicts(stderr: &str) -> BTreeSet<(String, String, String)> {
+    let re = Regex::new(
+        r#"previously selected package `([^ ]+) v([^`]+)`\s*\.\.\. which satisfies dependency `[^ ]+ = "([^"]+)"`"#,
+    )
+    .unwrap();
+    re.captures_iter(stderr)
+        .map(|c| (c[1].to_string(), c[2].to_string(), c[3].to_string()))
+        .collect()
+}
+
+/// Whether a compiler message mentions the crate `name`, by a path through it or the
+/// crate itself.
+fn mentions(text: &str, name: &str) -> bool {
+    let ident = name.replace('-', "_");
+    Regex::new(&format!(r"(?:^|[^\w]){}(?:::|`)", regex::escape(&ident)))
+        .map(|re| re.is_match(text))
+        .unwrap_or(false)
+}
+
+fn list(items: &BTreeSet<String>) -> String {
+    items.iter().cloned().collect::<Vec<_>>().join(", ")
+}
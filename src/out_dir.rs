@@ -0,0 +1,67 @@
+use cargo_metadata::Package;
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// The files a source includes from its build script's output, as in
+/// `include!(concat!(env!("OUT_DIR"), "/generated.rs"))`. Commented-out lines, doc
+/// comments showing the pattern included, don't count.
+pub fn includes(content: &str) -> Vec<String> {
+    let re = Regex::new(
+        r#"include!\s*\(\s*concat!\s*\(\s*env!\s*\(\s*"OUT_DIR"\s*\)\s*,\s*"([^"]+)"\s*\)\s*\)"#,
+    )
+    .unwrap();
+    let code: String = content
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("//"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    re.captures_iter(&code)
+        .map(|cap| cap[1].trim_start_matches('/').to_string())
+        .collect()
+}
+
+/// Finds `file` in the package's build-script output directories
+/// (`<target>/[<triple>/]<profile>/build/<package>-<hash>/out`), preferring the most
+/// recently written copy when several builds left one behind.
+pub fn locate(target_dir: &Path, package: &Package, file: &str) -> Option<PathBuf> {
+    let prefix = format!("{}-", package.name);
+    WalkDir::new(target_dir)
+        .max_depth(4)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry.file_type().is_dir()
+                && entry.file_name().to_string_lossy().starts_with(&prefix)
+                && entry
+                    .path()
+                    .parent()
+                    .is_some_and(|parent| parent.ends_with("build"))
+        })
+        .map(|entry| entry.path().join("out").join(file))
+        .filter(|path| path.is_file())
+        .max_by_key(|path| path.metadata().and_then(|m| m.modified()).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn includes_skip_comments() {
+        let content = r#"
+/// `include!(concat!(env!("$VAR"), "/documented.rs"))`
+// include!(concat!(env!("$VAR"), "/disabled.rs"));
+include!(concat!(env!("$VAR"), "/generated.rs"));
+mod bindings {
+    include!(concat!(
+        env!("$VAR"),
+        "/bindings.rs"
+    ));
+}
+"#
+        // Spelled out, the pattern would make this file include them too.
+        .replace("$VAR", "OUT_DIR");
+        assert_eq!(includes(&content), ["generated.rs", "bindings.rs"]);
+    }
+}
@@ -0,0 +1,208 @@
+use crate::project::Project;
+use crate::registry::Registry;
+use crate::report::{Finding, Kind, Update};
+use cargo_metadata::semver::Version;
+use cargo_metadata::Package;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// File names crates commonly keep their release notes under.
+const CHANGELOGS: [&str; 5] = [
+    "CHANGELOG.md",
+    "CHANGES.md",
+    "RELEASES.md",
+    "HISTORY.md",
+    "NEWS.md",
+];
+
+/// Reports registry dependencies of the members with a newer release out than their
+/// version requirement allows, with links to review the jump by.
+pub fn analyze(project: &Project) -> Vec<Finding> {
+    let mut registries: HashMap<&str, Option<Registry>> = HashMap::new();
+    let mut findings = Vec::new();
+    let progress = project.progress.bar("Checking for updates", None);
+
+    for member in project.members() {
+        for dep in &member.dependencies {
+            progress.inc();
+            let Some(source) = dep
+                .source
+                .as_deref()
+                .filter(|s| s.starts_with("registry+") || s.starts_with("sparse+"))
+            else {
+                continue;
+            };
+            let Some(resolved) = resolved(project, member, &dep.name, |version| {
+                dep.req.matches(version)
+            }) else {
+                continue;
+            };
+            let registry = registries.entry(source).or_insert_with(|| {
+                Registry::for_source(source, &project.cargo_config, &project.cache).unwrap_or_else(
+                    |err| {
+                        tracing::warn!("skipping registry {}: {}", source, err);
+                        None
+                    },
+                )
+            });
+            let Some(latest) = registry
+                .as_ref()
+                .and_then(|registry| registry.latest_release(&dep.name).ok().flatten())
+            else {
+                continue;
+            };
+            if latest <= resolved.version || dep.req.matches(&latest) {
+                continue;
+            }
+
+            let update = classify(&resolved.version, &latest);
+            let mut finding = Finding::new(Kind::Outdated, &dep.name)
+                .member(project, member)
+                .note(format!(
+                    "Requires {}, locked at {}; {} is out ({} update{})",
+                    dep.req,
+                    resolved.version,
+                    latest,
+                    update,
+                    if update == Update::Major {
+                        ", semver-incompatible"
+                    } else {
+                        ""
+                    }
+                ));
+            finding.update = Some(update);
+            for link in links(resolved, &latest) {
+                finding = finding.note(link);
+            }
+            findings.push(finding);
+        }
+    }
+    findings
+}
+
+/// The size of the jump from `from` to the newer `to`. Cargo treats the leftmost
+/// non-zero component as the major version, so 0.1 to 0.2 is a major update.
+pub fn classify(from: &Version, to: &Version) -> Update {
+    let compatible = match (from.major, from.minor) {
+        (0, 0) => to.major == 0 && to.minor == 0 && to.patch == from.patch,
+        (0, minor) => to.major == 0 && to.minor == minor,
+        (major, _) => to.major == major,
+    };
+    if !compatible {
+        Update::Major
+    } else if to.minor != from.minor {
+        Update::Minor
+    } else {
+        Update::Patch
+    }
+}
+
+/// The package `member` resolved `name` to, among those `matches` accepts.
+pub fn resolved<'a>(
+    project: &'a Project,
+    member: &Package,
+    name: &str,
+    matches: impl Fn(&Version) -> bool,
+) -> Option<&'a Package> {
+    project
+        .metadata
+        .resolve
+        .as_ref()?
+        .nodes
+        .iter()
+        .find(|node| node.id == member.id)?
+        .deps
+        .iter()
+        .filter_map(|dep| project.package(&dep.pkg))
+        .find(|pkg| pkg.name == name && matches(&pkg.version))
+}
+
+/// Where to read what changed between the locked version and `latest`: a diff of the
+/// published sources, the changelog the crate ships, and the repository's releases.
+fn links(pkg: &Package, latest: &Version) -> Vec<String> {
+    let mut links = vec![format!(
+        "Diff: https://diff.rs/{}/{}/{}",
+        pkg.name, pkg.version, latest
+    )];
+    let repository = pkg
+        .repository
+        .as_deref()
+        .map(|url| url.trim_end_matches('/').trim_end_matches(".git"));
+    let dir = pkg.manifest_path.parent().map(|dir| dir.as_std_path());
+    if let (Some(repository), Some(dir)) = (repository, dir) {
+        if let Some(changelog) = changelog(dir) {
+            let prefix = path_in_vcs(dir)
+                .filter(|path| !path.is_empty())
+                .map(|path| format!("{}/", path))
+                .unwrap_or_default();
+            if let Some(url) = blob_url(repository, &format!("{}{}", prefix, changelog)) {
+                links.push(format!("Changelog: {}", url));
+            }
+        }
+    }
+    if let Some(repository) = repository {
+        if repository.starts_with("https://github.com/") {
+            links.push(format!("Releases: {}/releases", repository));
+        } else if repository.starts_with("https://gitlab.com/") {
+            links.push(format!("Releases: {}/-/releases", repository));
+        }
+    }
+    links
+}
+
+/// The web view of `path` on the repository's default branch. Each forge lays these URLs
+/// out differently, so hosts without a known layout get no link at all.
+fn blob_url(repository: &str, path: &str) -> Option<String> {
+    let host = repository.strip_prefix("https://")?.split('/').next()?;
+    let layout = match host {
+        "github.com" => "blob/HEAD",
+        "gitlab.com" => "-/blob/HEAD",
+        "bitbucket.org" => "src/HEAD",
+        "git.sr.ht" => "tree/HEAD/item",
+        _ => return None,
+    };
+    Some(format!("{}/{}/{}", repository, layout, path))
+}
+
+/// The changelog file in a crate's unpacked sources, if it ships one.
+fn changelog(dir: &Path) -> Option<String> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .find(|name| CHANGELOGS.iter().any(|c| c.eq_ignore_ascii_case(name)))
+}
+
+/// Where the crate sits in its repository, as `cargo package` recorded it.
+fn path_in_vcs(dir: &Path) -> Option<String> {
+    let info = std::fs::read_to_string(dir.join(".cargo_vcs_info.json")).ok()?;
+    let info: serde_json::Value = serde_json::from_str(&info).ok()?;
+    Some(info.get("path_in_vcs")?.as_str()?.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blob_url_follows_the_host_layout() {
+        assert_eq!(
+            blob_url("https://github.com/o/r", "CHANGELOG.md").as_deref(),
+            Some("https://github.com/o/r/blob/HEAD/CHANGELOG.md")
+        );
+        assert_eq!(
+            blob_url("https://gitlab.com/o/r", "sub/CHANGELOG.md").as_deref(),
+            Some("https://gitlab.com/o/r/-/blob/HEAD/sub/CHANGELOG.md")
+        );
+        assert_eq!(
+            blob_url("https://bitbucket.org/o/r", "CHANGES.md").as_deref(),
+            Some("https://bitbucket.org/o/r/src/HEAD/CHANGES.md")
+        );
+        assert_eq!(
+            blob_url("https://git.sr.ht/~o/r", "NEWS.md").as_deref(),
+            Some("https://git.sr.ht/~o/r/tree/HEAD/item/NEWS.md")
+        );
+        assert_eq!(blob_url("https://codeberg.org/o/r", "CHANGELOG.md"), None);
+        assert_eq!(blob_url("https://example.com/git/r", "CHANGELOG.md"), None);
+    }
+}
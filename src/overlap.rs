@@ -0,0 +1,185 @@
+use crate::cli::Args;
+use crate::project::Project;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// How one member declares an external dependency.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Use {
+    reqs: BTreeSet<String>,
+    /// Features enabled explicitly, plus `default` where default features are kept.
+    features: BTreeSet<String>,
+}
+
+/// `overlap`: which external crates the workspace members share, and with which feature
+/// sets; the members declaring a shared crate differently from the others; the crates
+/// only one member uses; and how much of each member's dependency set it shares. Build
+/// tooling (xtask) members are left out, as they aren't part of the product.
+pub fn run(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let project = Project::load(args.manifest_path.as_deref())?;
+    let tooling = crate::xtask::members(&project);
+    let members: Vec<_> = project
+        .members()
+        .filter(|member| !tooling.contains(&member.name))
+        .collect();
+    if members.len() < 2 {
+        println!("\nThe workspace has a single member; there is nothing to overlap.");
+        return Ok(());
+    }
+    let names: BTreeSet<&str> = project.members().map(|pkg| pkg.name.as_str()).collect();
+
+    // For each external crate, how each member using it declares it.
+    let mut crates: BTreeMap<&str, BTreeMap<&str, Use>> = BTreeMap::new();
+    for member in &members {
+        for dep in &member.dependencies {
+            if names.contains(dep.name.as_str()) {
+                continue;
+            }
+            let usage = crates
+                .entry(&dep.name)
+                .or_default()
+                .entry(&member.name)
+                .or_insert_with(|| Use {
+                    reqs: BTreeSet::new(),
+                    features: BTreeSet::new(),
+                });
+            usage.reqs.insert(dep.req.to_string());
+            usage.features.extend(dep.features.iter().cloned());
+            if dep.uses_default_features {
+                usage.features.insert("default".to_string());
+            }
+        }
+    }
+    let width = crates.keys().map(|k| k.chars().count()).max().unwrap_or(0);
+
+    println!(
+        "\nExternal dependencies shared by several of the {} members:",
+        members.len()
+    );
+    for (krate, users) in crates.iter().filter(|(_, users)| users.len() > 1) {
+        // Members grouped by how they declare it, the most common way first.
+        let mut groups: BTreeMap<&Use, Vec<&str>> = BTreeMap::new();
+        for (member, usage) in users {
+            groups.entry(usage).or_default().push(member);
+        }
+        let mut groups: Vec<_> = groups.into_iter().collect();
+        groups.sort_by_key(|(_, members)| std::cmp::Reverse(members.len()));
+        let described: Vec<String> = groups
+            .iter()
+            .map(|(usage, members)| format!("{} ({})", members.join(", "), describe(usage)))
+            .collect();
+        println!(
+            "  {:<width$}  {} members: {}",
+            krate,
+            users.len(),
+            described.join("; "),
+            width = width
+        );
+    }
+
+    println!("\nMembers declaring a shared crate differently from the rest:");
+    let mut divergent = false;
+    for (krate, users) in crates.iter().filter(|(_, users)| users.len() > 1) {
+        let mut counts: BTreeMap<&Use, usize> = BTreeMap::new();
+        for usage in users.values() {
+            *counts.entry(usage).or_default() += 1;
+        }
+        let Some((&common, &count)) = counts.iter().max_by_key(|(_, count)| **count) else {
+            continue;
+        };
+        // Without a majority there is no "rest" to diverge from.
+        if counts.len() < 2 || count * 2 <= users.len() {
+            if counts.len() > 1 {
+                divergent = true;
+                println!(
+                    "  {:<width$}  declared {} different ways",
+                    krate,
+                    counts.len(),
+                    width = width
+                );
+            }
+            continue;
+        }
+        for (member, usage) in users.iter().filter(|(_, usage)| *usage != common) {
+            divergent = true;
+            println!(
+                "  {:<width$}  {}: {} (the others: {})",
+                krate,
+                member,
+                describe(usage),
+                describe(common),
+                width = width
+            );
+        }
+    }
+    if !divergent {
+        println!("  (none)");
+    }
+
+    println!("\nExternal dependencies used by a single member:");
+    let mut single = false;
+    for (krate, users) in crates.iter().filter(|(_, users)| users.len() == 1) {
+        single = true;
+        for member in users.keys() {
+            println!("  {:<width$}  {}", krate, member, width = width);
+        }
+    }
+    if !single {
+        println!("  (none)");
+    }
+
+    println!("\nShare of each member's external dependencies used by another member:");
+    let mut shares: Vec<(&str, usize, usize)> = members
+        .iter()
+        .map(|member| {
+            let own: Vec<_> = crates
+                .values()
+                .filter(|users| users.contains_key(member.name.as_str()))
+                .collect();
+            let shared = own.iter().filter(|users| users.len() > 1).count();
+            (member.name.as_str(), shared, own.len())
+        })
+        .collect();
+    // The members sharing least, those diverging most from the rest, first.
+    shares.sort_by(|a, b| {
+        let ratio = |(_, shared, total): &(&str, usize, usize)| {
+            if *total == 0 {
+                1.0
+            } else {
+                *shared as f64 / *total as f64
+            }
+        };
+        ratio(a).total_cmp(&ratio(b)).then(a.0.cmp(b.0))
+    });
+    let member_width = shares
+        .iter()
+        .map(|(name, ..)| name.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (member, shared, total) in shares {
+        if total == 0 {
+            println!(
+                "  {:<member_width$}     -  (no external dependencies)",
+                member
+            );
+            continue;
+        }
+        println!(
+            "  {:<member_width$}  {:>3}%  ({} of {})",
+            member,
+            shared * 100 / total,
+            shared,
+            total
+        );
+    }
+    Ok(())
+}
+
+fn describe(usage: &Use) -> String {
+    let reqs: Vec<&str> = usage.reqs.iter().map(String::as_str).collect();
+    let features: Vec<&str> = usage.features.iter().map(String::as_str).collect();
+    if features.is_empty() {
+        format!("{}, no default features", reqs.join(", "))
+    } else {
+        format!("{}, features: {}", reqs.join(", "), features.join(", "))
+    }
+}
@@ -0,0 +1,86 @@
+use crate::project::Project;
+use crate::report::{Finding, Kind};
+use cargo_metadata::Package;
+
+/// Audits legacy `[replace]` entries and `[profile.<name>.package.<spec>]` overrides
+/// against the resolved graph.
+pub fn analyze(project: &Project) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    if let Some(replace) = project.manifest.get("replace").and_then(|r| r.as_table()) {
+        for (spec, source) in replace {
+            findings.push(replace_entry(project, spec, source));
+        }
+    }
+
+    if let Some(profiles) = project.manifest.get("profile").and_then(|p| p.as_table()) {
+        for (profile, settings) in profiles {
+            let Some(packages) = settings.get("package").and_then(|p| p.as_table()) else {
+                continue;
+            };
+            for spec in packages.keys().filter(|spec| spec.as_str() != "*") {
+                if !in_graph(project, spec) {
+                    findings.push(
+                        Finding::new(Kind::StaleProfileOverride, spec_name(spec))
+                            .note(format!(
+                                "[profile.{}.package.\"{}\"] matches no package in the dependency graph",
+                                profile, spec
+                            ))
+                            .note("Remove the override; it no longer has any effect"),
+                    );
+                }
+            }
+        }
+    }
+
+    findings
+}
+
+fn replace_entry(project: &Project, spec: &str, source: &toml::Value) -> Finding {
+    let name = spec_name(spec);
+    if !in_graph(project, spec) {
+        return Finding::new(Kind::StaleReplace, name)
+            .note(format!(
+                "[replace] entry \"{}\" matches no package in the dependency graph",
+                spec
+            ))
+            .note("Remove the entry; it no longer has any effect");
+    }
+
+    Finding::new(Kind::LegacyReplace, name)
+        .note(format!(
+            "[replace] is deprecated; \"{}\" can be expressed as a [patch] instead:",
+            spec
+        ))
+        .note("  [patch.crates-io]")
+        .note(format!("  {} = {}", name, source))
+}
+
+/// Whether any package in the graph matches a package ID spec such as `foo`,
+/// `foo:1.2.3` or `foo@1.2`.
+fn in_graph(project: &Project, spec: &str) -> bool {
+    project
+        .metadata
+        .packages
+        .iter()
+        .any(|pkg| matches_spec(pkg, spec))
+}
+
+fn spec_name(spec: &str) -> &str {
+    spec.split([':', '@']).next().unwrap_or(spec)
+}
+
+fn matches_spec(pkg: &Package, spec: &str) -> bool {
+    let mut parts = spec.splitn(2, [':', '@']);
+    if parts.next() != Some(pkg.name.as_str()) {
+        return false;
+    }
+    let Some(version) = parts.next() else {
+        return true;
+    };
+    let actual = [pkg.version.major, pkg.version.minor, pkg.version.patch];
+    version
+        .split('.')
+        .zip(actual)
+        .all(|(wanted, actual)| wanted.parse::<u64>() == Ok(actual))
+}
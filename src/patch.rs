@@ -0,0 +1,164 @@
+use crate::project::Project;
+use crate::report::{Finding, Kind};
+use cargo_metadata::Package;
+use std::path::Path;
+
+/// Checks every entry of `[patch.<registry>]` against the resolved graph: patches that
+/// resolve to a package are reported with the nodes they affect, the rest as stale.
+pub fn analyze(project: &Project) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let Some(registries) = project.manifest.get("patch").and_then(|p| p.as_table()) else {
+        return findings;
+    };
+    let lockfile = project.lockfile();
+
+    for (registry, entries) in registries {
+        let Some(entries) = entries.as_table() else {
+            continue;
+        };
+        for (key, spec) in entries {
+            let name = spec.get("package").and_then(|p| p.as_str()).unwrap_or(key);
+            let patched = project
+                .metadata
+                .packages
+                .iter()
+                .find(|pkg| pkg.name == name && provided_by(project, pkg, spec));
+
+            findings.push(match patched {
+                Some(pkg) => active_patch(project, registry, pkg),
+                None => stale_patch(project, registry, name, spec, lockfile.as_ref()),
+            });
+        }
+    }
+
+    findings
+}
+
+/// Whether `pkg` comes from the source a patch entry points at.
+fn provided_by(project: &Project, pkg: &Package, spec: &toml::Value) -> bool {
+    if let Some(path) = spec.get("path").and_then(|p| p.as_str()) {
+        let patch_dir = project.root().join(path);
+        pkg.source.is_none()
+            && pkg
+                .manifest_path
+                .parent()
+                .is_some_and(|dir| same_dir(dir.as_std_path(), &patch_dir))
+    } else if let Some(git) = spec.get("git").and_then(|g| g.as_str()) {
+        let git = git.trim_end_matches('/').trim_end_matches(".git");
+        pkg.source
+            .as_ref()
+            .is_some_and(|s| s.repr.starts_with(&format!("git+{}", git)))
+    } else {
+        pkg.source.as_ref().is_some_and(|s| !s.is_crates_io())
+    }
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn active_patch(project: &Project, registry: &str, pkg: &Package) -> Finding {
+    let mut finding = Finding::new(Kind::ActivePatch, &pkg.name).note(format!(
+        "Patched in [patch.{}] with {} {}",
+        registry,
+        pkg.version,
+        source_description(project, pkg)
+    ));
+
+    let dependents: Vec<String> = project
+        .metadata
+        .resolve
+        .iter()
+        .flat_map(|resolve| &resolve.nodes)
+        .filter(|node| node.deps.iter().any(|dep| dep.pkg == pkg.id))
+        .filter_map(|node| project.metadata.packages.iter().find(|p| p.id == node.id))
+        .map(|p| format!("  - {} {}", p.name, p.version))
+        .collect();
+
+    if dependents.is_empty() {
+        finding = finding.note("No package in the graph depends on the patched crate");
+    } else {
+        finding = finding.note("Affects:");
+        for dependent in dependents {
+            finding = finding.note(dependent);
+        }
+    }
+    finding
+}
+
+fn stale_patch(
+    project: &Project,
+    registry: &str,
+    name: &str,
+    spec: &toml::Value,
+    lockfile: Option<&toml::Table>,
+) -> Finding {
+    let mut finding = Finding::new(Kind::StalePatch, name);
+    let patch_version =
+        unused_patch_version(lockfile, name).or_else(|| path_version(project, spec));
+
+    let resolved: Vec<&Package> = project
+        .metadata
+        .packages
+        .iter()
+        .filter(|pkg| pkg.name == name)
+        .collect();
+
+    if resolved.is_empty() {
+        finding = finding.note(format!("{} is not in the dependency graph", name));
+    } else {
+        let versions: Vec<String> = resolved.iter().map(|p| p.version.to_string()).collect();
+        finding = finding.note(format!(
+            "Patch provides {} but the graph resolves {} {}",
+            patch_version.as_deref().unwrap_or("an unknown version"),
+            name,
+            versions.join(", ")
+        ));
+        for pkg in &project.metadata.packages {
+            for dep in pkg.dependencies.iter().filter(|d| d.name == name) {
+                finding = finding.note(format!("  - required as {} by {}", dep.req, pkg.name));
+            }
+        }
+    }
+
+    finding.note(format!(
+        "Remove the entry from [patch.{}] or update it to a matching version",
+        registry
+    ))
+}
+
+/// Cargo records patches that did not apply as `[[patch.unused]]` in the lockfile.
+fn unused_patch_version(lockfile: Option<&toml::Table>, name: &str) -> Option<String> {
+    lockfile?
+        .get("patch")?
+        .get("unused")?
+        .as_array()?
+        .iter()
+        .find(|entry| entry.get("name").and_then(|n| n.as_str()) == Some(name))?
+        .get("version")?
+        .as_str()
+        .map(str::to_string)
+}
+
+fn path_version(project: &Project, spec: &toml::Value) -> Option<String> {
+    let path = spec.get("path")?.as_str()?;
+    let manifest = crate::project::read_toml(&project.root().join(path).join("Cargo.toml")).ok()?;
+    manifest
+        .get("package")?
+        .get("version")?
+        .as_str()
+        .map(str::to_string)
+}
+
+fn source_description(project: &Project, pkg: &Package) -> String {
+    match &pkg.source {
+        Some(source) => format!("from {}", source.repr),
+        None => match pkg.manifest_path.parent() {
+            Some(dir) => format!("from {}", project.display_path(dir.as_std_path())),
+            None => format!("from {}", pkg.manifest_path),
+        },
+    }
+}
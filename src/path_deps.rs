@@ -0,0 +1,38 @@
+use crate::project::Project;
+use cargo_metadata::Package;
+use std::collections::HashSet;
+
+/// The path dependencies of the workspace that aren't members of it, such as loose
+/// sibling crates of an umbrella repository: those the members depend on, then theirs, up
+/// to `depth` levels away from the members.
+pub fn external(project: &Project, depth: usize) -> Vec<&Package> {
+    let members: HashSet<_> = project.metadata.workspace_members.iter().collect();
+    let mut seen: HashSet<_> = members.clone();
+    let mut level: Vec<&Package> = project.members().collect();
+    let mut found = Vec::new();
+    for _ in 0..depth {
+        let mut next = Vec::new();
+        for package in level {
+            for dep in &package.dependencies {
+                let Some(path) = &dep.path else {
+                    continue;
+                };
+                let manifest_path = path.join("Cargo.toml");
+                let Some(target) = project
+                    .metadata
+                    .packages
+                    .iter()
+                    .find(|pkg| pkg.manifest_path == manifest_path)
+                else {
+                    continue;
+                };
+                if seen.insert(&target.id) {
+                    next.push(target);
+                }
+            }
+        }
+        found.extend(next.iter().copied());
+        level = next;
+    }
+    found
+}
@@ -0,0 +1,158 @@
+use crate::explain;
+use crate::impact;
+use crate::project::Project;
+use crate::report::{Evidence, Finding, Kind, Severity};
+
+/// How many of the crates a step takes out of the build are named.
+const SHOWN_CRATES: usize = 10;
+
+/// How sure we are that acting on a finding is safe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+struct Step<'a> {
+    finding: &'a Finding,
+    confidence: Confidence,
+    action: String,
+    /// The crates acting on it takes out of the build, and their source size.
+    removed: Vec<String>,
+    bytes: u64,
+}
+
+/// `plan`: the actionable findings as an ordered to-do list, surest and most rewarding
+/// first, so a large cleanup can be done a step at a time.
+pub fn print(project: &Project, findings: &[Finding]) {
+    let mut steps: Vec<Step> = findings
+        .iter()
+        .filter(|f| f.severity != Severity::Info)
+        .map(|finding| step(project, finding))
+        .collect();
+    steps.sort_by(|a, b| {
+        b.confidence
+            .cmp(&a.confidence)
+            .then_with(|| b.removed.len().cmp(&a.removed.len()))
+            .then_with(|| b.bytes.cmp(&a.bytes))
+            .then_with(|| b.finding.severity.cmp(&a.finding.severity))
+    });
+
+    println!("\nRemoval Plan:");
+    println!("=============");
+    if steps.is_empty() {
+        println!("Nothing to do");
+        return;
+    }
+    for (i, step) in steps.iter().enumerate() {
+        println!(
+            "\n{:>3}. [{}] {}",
+            i + 1,
+            format!("{:?}", step.confidence).to_lowercase(),
+            step.action
+        );
+        if !step.removed.is_empty() {
+            let mut names = step.removed[..step.removed.len().min(SHOWN_CRATES)].join(", ");
+            if step.removed.len() > SHOWN_CRATES {
+                names += &format!(" and {} more", step.removed.len() - SHOWN_CRATES);
+            }
+            println!(
+                "     Takes {} crate{} (~{} KB of source) out of the build: {}",
+                step.removed.len(),
+                if step.removed.len() == 1 { "" } else { "s" },
+                step.bytes.div_ceil(1024),
+                names
+            );
+        }
+        println!(
+            "     Why: {}{}",
+            step.finding.kind.label(),
+            step.finding
+                .notes
+                .first()
+                .map(|note| format!(": {}", note.trim()))
+                .unwrap_or_default()
+        );
+    }
+    let removed: usize = steps.iter().map(|s| s.removed.len()).sum();
+    println!(
+        "\n{} step{}; following them all takes up to {} crates out of the build.",
+        steps.len(),
+        if steps.len() == 1 { "" } else { "s" },
+        removed
+    );
+}
+
+fn step<'a>(project: &Project, finding: &'a Finding) -> Step<'a> {
+    let member = finding
+        .member
+        .as_ref()
+        .and_then(|name| project.members().find(|m| &m.name == name));
+    let place = finding
+        .file
+        .as_deref()
+        .map(|file| format!(" ({})", file))
+        .unwrap_or_default();
+    let (action, confidence) = match finding.kind {
+        Kind::Unused => (
+            format!("Remove `{}`{}", finding.krate, place),
+            match finding.evidence {
+                Some(Evidence::Both | Evidence::Compiler) => Confidence::High,
+                Some(Evidence::Heuristic) => Confidence::Low,
+                // The scan can't see every use; generated code is a known blind spot.
+                None if finding.notes.iter().any(|n| n.contains("generated code")) => {
+                    Confidence::Low
+                }
+                None => Confidence::Medium,
+            },
+        ),
+        Kind::TestOnly | Kind::ExampleOnly | Kind::BenchOnly => (
+            format!("Move `{}` to [dev-dependencies]{}", finding.krate, place),
+            Confidence::High,
+        ),
+        Kind::PlatformOnly => (
+            format!(
+                "Move `{}` to its platform's [target] table{}",
+                finding.krate, place
+            ),
+            Confidence::High,
+        ),
+        Kind::FeatureGated => (
+            format!(
+                "Make `{}` optional behind its feature{}",
+                finding.krate, place
+            ),
+            Confidence::Medium,
+        ),
+        kind => (
+            format!(
+                "{} `{}`{}: {}",
+                kind.label(),
+                finding.krate,
+                place,
+                explain::resolution(kind)
+            ),
+            if finding.severity == Severity::Error {
+                Confidence::High
+            } else {
+                Confidence::Medium
+            },
+        ),
+    };
+    let removes_crates = matches!(
+        finding.kind,
+        Kind::Unused | Kind::TestOnly | Kind::ExampleOnly | Kind::BenchOnly | Kind::FeatureGated
+    );
+    let removed = match member {
+        Some(member) if removes_crates => impact::exclusive_crates(project, member, &finding.krate),
+        _ => Vec::new(),
+    };
+    Step {
+        finding,
+        confidence,
+        action,
+        bytes: impact::source_bytes(&removed),
+        removed: removed.iter().map(|pkg| pkg.name.clone()).collect(),
+    }
+}
@@ -0,0 +1,139 @@
+use crate::fix::{self, ManifestEdit};
+use crate::project::Project;
+use crate::report::{Finding, Kind};
+use regex::Regex;
+use std::collections::BTreeSet;
+
+/// The note of a platform-only finding naming the table to move the dependency to; `--fix`
+/// reads the target back from it.
+const MOVE_TO: &str = "Move it to [target.'";
+
+/// Whether a `cfg` predicate only selects platforms (`windows`, `unix`,
+/// `target_os = "linux"`, ...), so it can gate a `[target.'cfg(...)'.dependencies]` table.
+pub fn is_platform(predicate: &str) -> bool {
+    let platform = Regex::new(r"\b(windows|unix|target_[a-z_]+)\b").unwrap();
+    let other = Regex::new(r"\b(feature|test|debug_assertions|doc|proc_macro|panic)\b").unwrap();
+    platform.is_match(predicate) && !other.is_match(predicate)
+}
+
+/// The cargo target spec covering every predicate a dependency is used under.
+pub fn target(predicates: &BTreeSet<String>) -> String {
+    let normalized: Vec<String> = predicates
+        .iter()
+        .map(|p| p.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    match normalized.as_slice() {
+        [single] => format!("cfg({})", single),
+        many => format!("cfg(any({}))", many.join(", ")),
+    }
+}
+
+/// The note suggesting the move to `target`'s dependency table.
+pub fn move_note(target: &str) -> String {
+    format!(
+        "{}{}'.dependencies] so other platforms don't build it; --fix does",
+        MOVE_TO, target
+    )
+}
+
+/// `--fix` for platform-only dependencies: moves each [dependencies] entry into the
+/// `[target.'cfg(...)'.dependencies]` table its finding suggests, keeping its formatting.
+pub fn fix(
+    project: &Project,
+    findings: &[Finding],
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut changes = Vec::new();
+    for finding in findings.iter().filter(|f| f.kind == Kind::PlatformOnly) {
+        let Some(package) = project
+            .members()
+            .find(|pkg| Some(&pkg.name) == finding.member.as_ref())
+        else {
+            continue;
+        };
+        let Some(target) = finding.notes.iter().find_map(|note| {
+            note.strip_prefix(MOVE_TO)
+                .and_then(|rest| rest.split_once("'.dependencies]"))
+                .map(|(target, _)| target.to_string())
+        }) else {
+            continue;
+        };
+        let mut manifest = ManifestEdit::open(package.manifest_path.as_std_path())?;
+        let dependencies = manifest
+            .dependency_table(None, "dependencies")
+            .ok_or("[dependencies] is not a table")?;
+        let Some(key) = fix::entry_key(dependencies, &finding.krate) else {
+            continue;
+        };
+        let item = dependencies.remove(&key).expect("the key was just found");
+        let platform = manifest
+            .dependency_table(Some(&target), "dependencies")
+            .ok_or("the target's [dependencies] is not a table")?;
+        if !platform.contains_key(&key) {
+            platform.insert(&key, item);
+        }
+        manifest.save()?;
+        changes.push(format!(
+            "Moved {} to [target.'{}'.dependencies] in {}",
+            key,
+            target,
+            project.display_path(package.manifest_path.as_std_path())
+        ));
+    }
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::fixture;
+
+    #[test]
+    fn fix_moves_the_entry_to_the_target_table() {
+        let (dir, project) = fixture(&[
+            (
+                "Cargo.toml",
+                r#"[package]
+name = "app"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+util = { path = "util" }
+"#,
+            ),
+            ("src/lib.rs", ""),
+            (
+                "util/Cargo.toml",
+                "[package]\nname = \"util\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+            ),
+            ("util/src/lib.rs", ""),
+        ]);
+        let app = project.members().find(|pkg| pkg.name == "app").unwrap();
+        let predicates = BTreeSet::from(["unix".to_string(), "windows".to_string()]);
+        let findings = [Finding::new(Kind::PlatformOnly, "util")
+            .member(&project, app)
+            .note(move_note(&target(&predicates)))];
+
+        let changes = fix(&project, &findings).unwrap();
+        assert_eq!(
+            changes,
+            ["Moved util to [target.'cfg(any(unix, windows))'.dependencies] in Cargo.toml"]
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("Cargo.toml")).unwrap(),
+            r#"[package]
+name = "app"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+
+[target."cfg(any(unix, windows))".dependencies]
+util = { path = "util" }
+"#
+        );
+        assert!(fix(&project, &findings).unwrap().is_empty());
+    }
+}
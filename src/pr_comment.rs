@@ -0,0 +1,107 @@
+use crate::cli::GroupBy;
+use crate::diff::{self, Status};
+use crate::report::{self, Finding, Kind};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Marks the comment so a CI bot can find and update it instead of posting a new one.
+const MARKER: &str = "<!-- cargo-dep-analysis -->";
+const TOP_FINDINGS: usize = 10;
+
+/// `--format pr-comment`: counts per kind, the changes against the base branch (when
+/// given), and the first few findings, with the details folded away.
+pub fn render(
+    findings: &[Finding],
+    base: Option<(&str, Vec<Finding>)>,
+    group_by: Option<GroupBy>,
+) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "{}", MARKER);
+    let _ = writeln!(
+        out,
+        "### Dependency analysis: {} finding{}\n",
+        findings.len(),
+        if findings.len() == 1 { "" } else { "s" }
+    );
+
+    let mut counts: BTreeMap<Kind, usize> = BTreeMap::new();
+    for finding in findings {
+        *counts.entry(finding.kind).or_default() += 1;
+    }
+    if !counts.is_empty() {
+        let _ = writeln!(out, "| Finding | Count |\n|---|---:|");
+        for (kind, count) in &counts {
+            let _ = writeln!(out, "| {} | {} |", kind.label(), count);
+        }
+        let _ = writeln!(out);
+    }
+
+    if let Some((base, before)) = base {
+        let changes = diff::compare(&before, findings);
+        let count = |status| changes.iter().filter(|c| c.status == status).count();
+        let _ = writeln!(
+            out,
+            "**Compared to `{}`:** {} new, {} resolved, {} changed\n",
+            base,
+            count(Status::Added),
+            count(Status::Removed),
+            count(Status::Changed)
+        );
+        if !changes.is_empty() {
+            let _ = writeln!(
+                out,
+                "<details><summary>Changes since <code>{}</code></summary>\n",
+                base
+            );
+            for change in &changes {
+                let marker = match change.status {
+                    Status::Added => "🆕",
+                    Status::Removed => "✅",
+                    Status::Changed => "✏️",
+                };
+                let _ = writeln!(
+                    out,
+                    "- {} `{}` ({})",
+                    marker,
+                    change.krate,
+                    change.kind.label()
+                );
+            }
+            let _ = writeln!(out, "\n</details>\n");
+        }
+    }
+
+    if !findings.is_empty() {
+        let shown = findings.len().min(TOP_FINDINGS);
+        let _ = writeln!(
+            out,
+            "<details><summary>Top findings ({} of {})</summary>\n",
+            shown,
+            findings.len()
+        );
+        let top = &findings[..shown];
+        let sections = match group_by {
+            Some(by) => report::group(top, by),
+            None => vec![(String::new(), top.iter().collect())],
+        };
+        for (title, findings) in sections {
+            if !title.is_empty() {
+                let _ = writeln!(out, "\n**{}**\n", title);
+            }
+            for finding in findings {
+                let _ = write!(out, "- `{}` ({})", finding.krate, finding.kind.label());
+                match finding.notes.first() {
+                    Some(note) => {
+                        let _ = writeln!(out, ": {}", note.trim());
+                    }
+                    None => {
+                        let _ = writeln!(out);
+                    }
+                }
+            }
+        }
+        let _ = writeln!(out, "\n</details>");
+    }
+
+    out
+}
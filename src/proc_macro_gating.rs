@@ -0,0 +1,417 @@
+use crate::cfg_eval::{Cfg, Context};
+use crate::cli::Args;
+use crate::project::Project;
+use crate::report::{Finding, Kind};
+use cargo_metadata::{DependencyKind, Node, Package, PackageId};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The derives of well-known proc-macro crates, by crate, so a bare `derive(Serialize)`
+/// counts as a use of serde_derive.
+const DERIVES: [(&str, &[&str]); 3] = [
+    ("serde_derive", &["Serialize", "Deserialize"]),
+    (
+        "strum_macros",
+        &[
+            "AsRefStr",
+            "Display",
+            "EnumCount",
+            "EnumDiscriminants",
+            "EnumIs",
+            "EnumIter",
+            "EnumMessage",
+            "EnumProperty",
+            "EnumString",
+            "EnumTryAs",
+            "FromRepr",
+            "IntoStaticStr",
+            "VariantNames",
+        ],
+    ),
+    (
+        "clap_derive",
+        &["Args", "Parser", "Subcommand", "ValueEnum"],
+    ),
+];
+
+/// The largest share of a library's source files, in percent, using a proc-macro for it
+/// to be worth putting behind a feature.
+const MAX_SHARE: usize = 25;
+
+/// A proc-macro a library member always builds: a dependency of its own, or one a
+/// feature it enables on a dependency turns on (`serde/derive`).
+struct MacroUse<'a> {
+    /// The crate the macros come from.
+    macro_crate: &'a Package,
+    /// The dependency entry of the member, and the feature on it enabling the macros.
+    entry: &'a str,
+    feature: Option<&'a str>,
+    /// The edge that goes away when consumers opt out.
+    edge: (&'a PackageId, &'a PackageId),
+}
+
+/// Suggests putting heavy proc-macros behind an optional feature when a library member
+/// uses them in only a few of its files: consumers that leave the feature off skip the
+/// macro crate and whatever only it needs (syn, quote, proc-macro2), whose source size
+/// stands in for the compile time saved.
+pub fn analyze(args: &Args, project: &Project) -> Vec<Finding> {
+    let nodes: BTreeMap<&PackageId, &Node> = project
+        .metadata
+        .resolve
+        .iter()
+        .flat_map(|resolve| &resolve.nodes)
+        .map(|node| (&node.id, node))
+        .collect();
+    let mut findings = Vec::new();
+    for member in project.members() {
+        // Binaries have no consumers to opt out, and proc-macro crates need their macros.
+        let library = member
+            .targets
+            .iter()
+            .any(|t| crate::targets::is_lib(t) && !t.kind.iter().any(|k| k == "proc-macro"));
+        if !library {
+            continue;
+        }
+        let Ok(files) = crate::source_files(args, project, member) else {
+            continue;
+        };
+        let contents: Vec<(String, String)> = files
+            .iter()
+            .filter_map(|path| {
+                let content = std::fs::read_to_string(path).ok()?;
+                Some((project.display_path(path), content))
+            })
+            .collect();
+        if contents.is_empty() {
+            continue;
+        }
+        for found in macro_uses(project, &nodes, member) {
+            let derives = DERIVES
+                .iter()
+                .find(|(name, _)| *name == found.macro_crate.name)
+                .map_or(&[][..], |(_, derives)| *derives);
+            // Without a known list of derives, a facade's macros can't be told apart from
+            // the rest of its API.
+            if found.feature.is_some() && derives.is_empty() {
+                continue;
+            }
+            let using: Vec<&str> = contents
+                .iter()
+                .filter(|(_, content)| uses(content, &found, derives))
+                .map(|(file, _)| file.as_str())
+                .collect();
+            if using.is_empty() || using.len() * 100 > contents.len() * MAX_SHARE {
+                continue;
+            }
+            if let Some(feature) = found.feature {
+                if let Some(other) = kept_on_by(project, &nodes, member, found.entry, feature) {
+                    tracing::debug!(
+                        "{}: {}/{} stays on for consumers through {}",
+                        member.name,
+                        found.entry,
+                        feature,
+                        other
+                    );
+                    continue;
+                }
+            }
+            let dropped = dropped_for_consumers(project, &nodes, &member.id, found.edge);
+            if dropped.is_empty() {
+                continue;
+            }
+            findings.push(finding(
+                project, member, &found, derives, &using, &contents, &dropped,
+            ));
+        }
+    }
+    findings
+}
+
+fn finding(
+    project: &Project,
+    member: &Package,
+    found: &MacroUse,
+    derives: &[&str],
+    using: &[&str],
+    contents: &[(String, String)],
+    dropped: &[&Package],
+) -> Finding {
+    let mut names: Vec<String> = dropped
+        .iter()
+        .map(|pkg| format!("{} {}", pkg.name, pkg.version))
+        .collect();
+    names.sort();
+    let shown: Vec<&str> = using.iter().take(5).copied().collect();
+    let more = using.len().saturating_sub(shown.len());
+    let derive = derives.first().copied().unwrap_or("...");
+    let (name, setup) = match found.feature {
+        Some(feature) => {
+            let name = format!("{}-{}", found.entry, feature);
+            let setup = format!(
+                "Suggested: drop \"{feature}\" from the {entry} entry, add a feature \
+                 `{name} = [\"{entry}/{feature}\"]`, and gate the derives: \
+                 `#[cfg_attr(feature = \"{name}\", derive({derive}))]`",
+                feature = feature,
+                entry = found.entry,
+                name = name,
+                derive = derive
+            );
+            (name, setup)
+        }
+        None => {
+            let name = found.entry.to_string();
+            let setup = format!(
+                "Suggested: make the {entry} entry `optional = true`, add a feature \
+                 `{entry} = [\"dep:{entry}\"]`, and gate its uses with \
+                 `#[cfg(feature = \"{entry}\")]` or `#[cfg_attr(feature = \"{entry}\", \
+                 derive({derive}))]`",
+                entry = found.entry,
+                derive = derive
+            );
+            (name, setup)
+        }
+    };
+    let via = found
+        .feature
+        .map(|feature| format!(" (through {}/{})", found.entry, feature))
+        .unwrap_or_default();
+    Finding::new(Kind::GateableProcMacro, &found.macro_crate.name)
+        .member(project, member)
+        .note(format!(
+            "Used in {} of {} source files{}: {}{}",
+            using.len(),
+            contents.len(),
+            via,
+            shown.join(", "),
+            if more > 0 {
+                format!(" and {} more", more)
+            } else {
+                String::new()
+            }
+        ))
+        .note(format!(
+            "Consumers leaving a `{}` feature off would skip {} crate{} (~{} KiB of \
+             source, compiled for the host before the member): {}",
+            name,
+            names.len(),
+            if names.len() == 1 { "" } else { "s" },
+            crate::impact::source_bytes(dropped) / 1024,
+            names.join(", ")
+        ))
+        .note(setup)
+}
+
+/// The proc-macros `member` always builds: its non-optional proc-macro dependencies, and
+/// those behind a feature it lists on a non-optional entry.
+fn macro_uses<'a>(
+    project: &'a Project,
+    nodes: &BTreeMap<&'a PackageId, &'a Node>,
+    member: &'a Package,
+) -> Vec<MacroUse<'a>> {
+    let Some(node) = nodes.get(&member.id) else {
+        return Vec::new();
+    };
+    let mut found = Vec::new();
+    for dep in member
+        .dependencies
+        .iter()
+        .filter(|d| d.kind == DependencyKind::Normal && !d.optional)
+    {
+        let Some(resolved) = node
+            .deps
+            .iter()
+            .filter_map(|d| project.package(&d.pkg))
+            .find(|pkg| pkg.name == dep.name && dep.req.matches(&pkg.version))
+        else {
+            continue;
+        };
+        if project.metadata.workspace_members.contains(&resolved.id) {
+            continue;
+        }
+        let entry = dep.rename.as_deref().unwrap_or(&dep.name);
+        if is_proc_macro(resolved) {
+            found.push(MacroUse {
+                macro_crate: resolved,
+                entry,
+                feature: None,
+                edge: (&member.id, &resolved.id),
+            });
+            continue;
+        }
+        let Some(resolved_node) = nodes.get(&resolved.id) else {
+            continue;
+        };
+        for feature in &dep.features {
+            for key in optional_deps(resolved, feature) {
+                let declared = resolved
+                    .dependencies
+                    .iter()
+                    .filter(|d| d.optional && d.rename.as_deref().unwrap_or(&d.name) == key);
+                for declared in declared {
+                    let macro_crate = resolved_node
+                        .deps
+                        .iter()
+                        .filter_map(|d| project.package(&d.pkg))
+                        .find(|pkg| {
+                            pkg.name == declared.name && declared.req.matches(&pkg.version)
+                        });
+                    let Some(macro_crate) = macro_crate.filter(|pkg| is_proc_macro(pkg)) else {
+                        continue;
+                    };
+                    found.push(MacroUse {
+                        macro_crate,
+                        entry,
+                        feature: Some(feature),
+                        edge: (&resolved.id, &macro_crate.id),
+                    });
+                }
+            }
+        }
+    }
+    found
+}
+
+fn is_proc_macro(pkg: &Package) -> bool {
+    pkg.targets
+        .iter()
+        .any(|t| t.kind.iter().any(|k| k == "proc-macro"))
+}
+
+/// The optional dependencies (by key) `feature` of `package` enables directly.
+fn optional_deps<'a>(package: &'a Package, feature: &str) -> Vec<&'a str> {
+    package
+        .features
+        .get(feature)
+        .into_iter()
+        .flatten()
+        .filter_map(|value| match value.strip_prefix("dep:") {
+            Some(key) => Some(key),
+            None => match value.split_once('/') {
+                Some((key, _)) if !key.ends_with('?') => Some(key),
+                Some(_) => None,
+                None => Some(value.as_str()),
+            },
+        })
+        .collect()
+}
+
+/// Whether a file uses the macros: a derive from the known list or qualified by the
+/// crate, or for a direct dependency any path through it.
+fn uses(content: &str, found: &MacroUse, derives: &[&str]) -> bool {
+    let lib = found.macro_crate.name.replace('-', "_");
+    let entry = found.entry.replace('-', "_");
+    let derive = Regex::new(r"derive\s*\(([^)]*)\)").unwrap();
+    let derived = derive.captures_iter(content).any(|cap| {
+        cap[1].split(',').map(str::trim).any(|name| {
+            let name = name.trim_start_matches("::");
+            match name.rsplit_once("::") {
+                Some((path, item)) => {
+                    let root = path.split("::").next().unwrap_or("");
+                    (root == lib || root == entry)
+                        && (derives.is_empty() || derives.contains(&item))
+                }
+                None => derives.contains(&name),
+            }
+        })
+    });
+    if derived || found.feature.is_some() {
+        return derived;
+    }
+    Regex::new(&format!(r"\b{}\s*::", regex::escape(&entry)))
+        .unwrap()
+        .is_match(content)
+}
+
+/// Another package consumers of `member` build that asks for `feature` of the `entry`
+/// dependency itself, on its own entry or forwarded from its enabled features, so it stays
+/// on whatever the member does.
+fn kept_on_by(
+    project: &Project,
+    nodes: &BTreeMap<&PackageId, &Node>,
+    member: &Package,
+    entry: &str,
+    feature: &str,
+) -> Option<String> {
+    let dep = member
+        .dependencies
+        .iter()
+        .find(|d| d.rename.as_deref().unwrap_or(&d.name) == entry)?;
+    for id in closure(nodes, &member.id, None) {
+        if id == &member.id {
+            continue;
+        }
+        let (Some(pkg), Some(node)) = (project.package(id), nodes.get(id)) else {
+            continue;
+        };
+        for theirs in pkg.dependencies.iter().filter(|d| d.name == dep.name) {
+            let key = theirs.rename.as_deref().unwrap_or(&theirs.name);
+            let forwarded = node.features.iter().any(|enabled| {
+                pkg.features
+                    .get(enabled.as_str())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|value| value.split_once('/'))
+                    .any(|(name, f)| name.trim_end_matches('?') == key && f == feature)
+            });
+            if forwarded || theirs.features.iter().any(|f| f == feature) {
+                return Some(format!("{} {}", pkg.name, pkg.version));
+            }
+        }
+    }
+    None
+}
+
+/// The crates a consumer of `member` would no longer build without `edge`.
+fn dropped_for_consumers<'a>(
+    project: &'a Project,
+    nodes: &BTreeMap<&'a PackageId, &'a Node>,
+    member: &'a PackageId,
+    edge: (&'a PackageId, &'a PackageId),
+) -> Vec<&'a Package> {
+    let with = closure(nodes, member, None);
+    let without = closure(nodes, member, Some(edge));
+    with.difference(&without)
+        .filter_map(|id| project.package(id))
+        .collect()
+}
+
+/// What building `root` as a dependency builds: its normal and build dependencies,
+/// recursively, leaving out `skip`. Entries under a `cfg` no platform satisfies, such as
+/// the `cfg(any())` crates use to pin their proc-macro's version, don't count.
+fn closure<'a>(
+    nodes: &BTreeMap<&'a PackageId, &'a Node>,
+    root: &'a PackageId,
+    skip: Option<(&'a PackageId, &'a PackageId)>,
+) -> BTreeSet<&'a PackageId> {
+    let mut seen = BTreeSet::new();
+    let mut pending = vec![root];
+    while let Some(id) = pending.pop() {
+        if !seen.insert(id) {
+            continue;
+        }
+        for dep in nodes.get(id).into_iter().flat_map(|node| &node.deps) {
+            let built = dep.dep_kinds.iter().any(|k| {
+                k.kind != DependencyKind::Development
+                    && !k.target.as_ref().is_some_and(|t| never(&t.to_string()))
+            });
+            if built && skip != Some((id, &dep.pkg)) {
+                pending.push(&dep.pkg);
+            }
+        }
+    }
+    seen
+}
+
+fn never(target: &str) -> bool {
+    let Some(predicate) = target
+        .strip_prefix("cfg(")
+        .and_then(|rest| rest.strip_suffix(')'))
+    else {
+        return false;
+    };
+    let context = Context {
+        features: None,
+        target: None,
+    };
+    Cfg::parse(predicate).and_then(|cfg| cfg.eval(&context)) == Some(false)
+}
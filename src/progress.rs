@@ -0,0 +1,59 @@
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+/// Progress bars on stderr for long analyses. Hidden unless enabled, so passes can report
+/// progress unconditionally.
+pub struct Progress {
+    multi: Option<MultiProgress>,
+}
+
+impl Progress {
+    pub fn hidden() -> Self {
+        Progress { multi: None }
+    }
+
+    /// Draws to stderr when `enabled`; indicatif itself stays quiet if stderr isn't a
+    /// terminal.
+    pub fn new(enabled: bool) -> Self {
+        Progress {
+            multi: enabled.then(|| MultiProgress::with_draw_target(ProgressDrawTarget::stderr())),
+        }
+    }
+
+    /// A bar for one stage of work; a spinner with a counter when the total is unknown.
+    /// It disappears when dropped.
+    pub fn bar(&self, message: &'static str, len: Option<u64>) -> Bar {
+        let Some(multi) = &self.multi else {
+            return Bar(None);
+        };
+        let bar = match len {
+            Some(len) => ProgressBar::new(len).with_style(
+                ProgressStyle::with_template("{msg:>24} [{bar:30}] {pos}/{len}")
+                    .unwrap_or_else(|_| ProgressStyle::default_bar())
+                    .progress_chars("=> "),
+            ),
+            None => ProgressBar::new_spinner().with_style(
+                ProgressStyle::with_template("{msg:>24} {spinner} {pos}")
+                    .unwrap_or_else(|_| ProgressStyle::default_spinner()),
+            ),
+        };
+        Bar(Some(multi.add(bar.with_message(message))))
+    }
+}
+
+pub struct Bar(Option<ProgressBar>);
+
+impl Bar {
+    pub fn inc(&self) {
+        if let Some(bar) = &self.0 {
+            bar.inc(1);
+        }
+    }
+}
+
+impl Drop for Bar {
+    fn drop(&mut self) {
+        if let Some(bar) = &self.0 {
+            bar.finish_and_clear();
+        }
+    }
+}
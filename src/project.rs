@@ -0,0 +1,192 @@
+use crate::cache::{self, Cache};
+use crate::cargo_config::CargoConfig;
+use crate::config::Config;
+use crate::progress::Progress;
+use crate::vendor::Vendor;
+use cargo_metadata::{Dependency, DependencyKind, Metadata, MetadataCommand, Package, PackageId};
+use std::path::{Path, PathBuf};
+
+/// The workspace being analyzed: cargo's resolved view plus the raw root manifest,
+/// which carries sections (`[patch]`, `[replace]`, ...) that `cargo metadata` drops.
+pub struct Project {
+    pub metadata: Metadata,
+    pub manifest: toml::Table,
+    pub config: Config,
+    /// Cargo's own configuration, as cargo sees it from the current directory.
+    pub cargo_config: CargoConfig,
+    /// Set when dependencies are vendored; cargo then resolves every replaced package
+    /// to a manifest inside the vendor directory, so nothing needs the network.
+    pub vendor: Option<Vendor>,
+    pub cache: Cache,
+    pub progress: Progress,
+    /// The bound on concurrent tasks from `-j`; each phase has its own default otherwise.
+    pub jobs: Option<usize>,
+}
+
+impl Project {
+    pub fn load(manifest_path: Option<&Path>) -> Result<Self, Box<dyn std::error::Error>> {
+        let cargo_config = CargoConfig::load(&std::env::current_dir()?);
+        let vendor = Vendor::detect(&cargo_config);
+        let mut command = MetadataCommand::new();
+        if let Some(manifest_path) = manifest_path {
+            command.manifest_path(manifest_path);
+        }
+        if vendor.is_some() {
+            command.other_options(vec!["--offline".to_string()]);
+        }
+        let metadata = command.exec().map_err(|err| {
+            if let Some(explained) =
+                crate::feature_refs::explain_failure(manifest_path, &cargo_config, &err)
+            {
+                return format!("{}\n\n{}", err, explained).into();
+            }
+            match &vendor {
+                Some(vendor) => vendor.explain_failure(manifest_path, err),
+                None => err.into(),
+            }
+        })?;
+        let manifest = read_toml(
+            &metadata
+                .workspace_root
+                .join("Cargo.toml")
+                .into_std_path_buf(),
+        )?;
+        let config = Config::load(&manifest)?;
+        let ttl = config
+            .cache_ttl
+            .map(std::time::Duration::from_secs)
+            .unwrap_or(cache::DEFAULT_TTL);
+        Ok(Self {
+            metadata,
+            manifest,
+            config,
+            cargo_config,
+            vendor,
+            cache: Cache::new(ttl),
+            progress: Progress::hidden(),
+            jobs: None,
+        })
+    }
+
+    /// Whether registry and git access is off: vendored sources or cargo's `net.offline`.
+    pub fn offline(&self) -> bool {
+        self.vendor.is_some() || self.cargo_config.offline()
+    }
+
+    pub fn root(&self) -> PathBuf {
+        self.metadata.workspace_root.clone().into_std_path_buf()
+    }
+
+    /// `path` relative to the workspace root when it lies inside it, so reports don't
+    /// depend on where the workspace is checked out.
+    pub fn display_path(&self, path: &Path) -> String {
+        let root = self.root();
+        match path.strip_prefix(&root) {
+            Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+            Ok(relative) => relative.display().to_string(),
+            Err(_) => path.display().to_string(),
+        }
+    }
+
+    pub fn members(&self) -> impl Iterator<Item = &Package> {
+        self.metadata
+            .packages
+            .iter()
+            .filter(|pkg| self.metadata.workspace_members.contains(&pkg.id))
+    }
+
+    pub fn package(&self, id: &PackageId) -> Option<&Package> {
+        self.metadata.packages.iter().find(|pkg| &pkg.id == id)
+    }
+
+    /// The raw manifest of a package, before cargo resolves `workspace = true` entries.
+    pub fn package_manifest(&self, pkg: &Package) -> Option<toml::Table> {
+        read_toml(pkg.manifest_path.as_std_path()).ok()
+    }
+
+    /// The parsed `Cargo.lock`, if the workspace has one.
+    pub fn lockfile(&self) -> Option<toml::Table> {
+        read_toml(&self.root().join("Cargo.lock")).ok()
+    }
+}
+
+pub fn read_toml(path: &Path) -> Result<toml::Table, Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(content.parse::<toml::Table>()?)
+}
+
+/// One dependency table of a manifest, e.g. `[dev-dependencies]` or
+/// `[target.'cfg(unix)'.dependencies]`.
+pub struct DepTable<'a> {
+    pub target: Option<&'a str>,
+    pub kind: &'static str,
+    pub entries: &'a toml::Table,
+}
+
+/// The dotted name of a dependency table, as it appears in a manifest header.
+pub fn table_name(target: Option<&str>, kind: &str) -> String {
+    match target {
+        Some(target) => format!("target.'{}'.{}", target, kind),
+        None => kind.to_string(),
+    }
+}
+
+/// The manifest table a dependency kind is declared in.
+pub fn kind_table(kind: DependencyKind) -> &'static str {
+    match kind {
+        DependencyKind::Development => "dev-dependencies",
+        DependencyKind::Build => "build-dependencies",
+        _ => "dependencies",
+    }
+}
+
+/// The full name of the table `dep` was declared in, target included.
+pub fn declared_in(dep: &Dependency) -> String {
+    let target = dep.target.as_ref().map(|platform| platform.to_string());
+    table_name(target.as_deref(), kind_table(dep.kind))
+}
+
+/// Every dependency table of a manifest, including target-specific ones.
+pub fn dependency_tables(manifest: &toml::Table) -> Vec<DepTable<'_>> {
+    const KINDS: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+    let mut tables = Vec::new();
+
+    for kind in KINDS {
+        if let Some(entries) = manifest.get(kind).and_then(|t| t.as_table()) {
+            tables.push(DepTable {
+                target: None,
+                kind,
+                entries,
+            });
+        }
+    }
+    if let Some(targets) = manifest.get("target").and_then(|t| t.as_table()) {
+        for (target, tables_for_target) in targets {
+            for kind in KINDS {
+                if let Some(entries) = tables_for_target.get(kind).and_then(|t| t.as_table()) {
+                    tables.push(DepTable {
+                        target: Some(target),
+                        kind,
+                        entries,
+                    });
+                }
+            }
+        }
+    }
+
+    tables
+}
+
+/// A throwaway workspace made of `files` (paths relative to its root), loaded as a
+/// project. Keep the directory alive as long as the project is used.
+#[cfg(test)]
+pub fn fixture(files: &[(&str, &str)]) -> (tempfile::TempDir, Project) {
+    let dir = tempfile::tempdir().expect("a temporary directory");
+    for (path, content) in files {
+        let path = dir.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    let project = Project::load(Some(&dir.path().join("Cargo.toml"))).expect("the fixture loads");
+    (dir, project)
+}
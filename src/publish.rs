@@ -0,0 +1,98 @@
+use crate::project::{declared_in, Project};
+use crate::report::{Finding, Kind};
+use cargo_metadata::semver::VersionReq;
+use cargo_metadata::DependencyKind;
+
+/// Checks the path dependencies of publishable members: without a version requirement
+/// `cargo publish` refuses them, and paths outside the workspace usually mean the
+/// published crate was never tested against what consumers will download.
+pub fn analyze(project: &Project) -> Vec<Finding> {
+    let root = project.root();
+    let mut findings = Vec::new();
+
+    for member in project.members() {
+        // `publish = false` is reported by cargo metadata as an empty registry list.
+        if member
+            .publish
+            .as_ref()
+            .is_some_and(|registries| registries.is_empty())
+        {
+            continue;
+        }
+
+        for dep in &member.dependencies {
+            let Some(path) = &dep.path else {
+                continue;
+            };
+            // Cargo strips version-less path dev-dependencies when publishing.
+            if dep.kind == DependencyKind::Development {
+                continue;
+            }
+
+            if dep.req == VersionReq::STAR {
+                findings.push(
+                    Finding::new(Kind::UnpublishablePathDependency, &dep.name)
+                        .member(project, member)
+                        .note(format!(
+                            "{} depends on it by path ({}) in [{}] without a version requirement",
+                            member.name,
+                            project.display_path(path.as_std_path()),
+                            declared_in(dep)
+                        ))
+                        .note(format!(
+                            "`cargo publish -p {}` will fail; add `version = \"...\"` next to `path`",
+                            member.name
+                        )),
+                );
+            }
+            if !path.as_std_path().starts_with(&root) {
+                findings.push(
+                    Finding::new(Kind::ExternalPathDependency, &dep.name)
+                        .member(project, member)
+                        .note(format!(
+                            "{} depends on {} in [{}], which is outside the workspace",
+                            member.name,
+                            path,
+                            declared_in(dep)
+                        ))
+                        .note("The published crate will use the registry release instead of this checkout"),
+                );
+            }
+        }
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::fixture;
+
+    #[test]
+    fn each_table_is_named_in_its_finding() {
+        let (_dir, project) = fixture(&[
+            (
+                "Cargo.toml",
+                "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n\
+                 [dependencies]\nutil = { path = \"util\" }\n\n\
+                 [build-dependencies]\nutil = { path = \"util\" }\n\n\
+                 [workspace]\nmembers = [\"util\"]\n",
+            ),
+            ("src/lib.rs", ""),
+            (
+                "util/Cargo.toml",
+                "[package]\nname = \"util\"\nversion = \"0.1.0\"\n",
+            ),
+            ("util/src/lib.rs", ""),
+        ]);
+        let notes: Vec<String> = analyze(&project)
+            .into_iter()
+            .filter(|finding| finding.kind == Kind::UnpublishablePathDependency)
+            .map(|finding| finding.notes[0].clone())
+            .collect();
+        assert_eq!(notes.len(), 2);
+        assert!(notes[0].contains("in [dependencies]"), "{:?}", notes);
+        assert!(notes[1].contains("in [build-dependencies]"), "{:?}", notes);
+    }
+}
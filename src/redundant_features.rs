@@ -0,0 +1,206 @@
+use crate::fix::ManifestEdit;
+use crate::project::{read_toml, Project};
+use crate::report::{Finding, Kind};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+/// Flags `[features]` entries listing something already enabled: a value listed twice,
+/// or one another value of the same feature enables, directly or through other features
+/// (`x = ["y", "z"]` where `y = ["z"]`). Each finding carries the minimized list.
+pub fn analyze(project: &Project) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for member in project.members() {
+        let Ok(manifest) = read_toml(member.manifest_path.as_std_path()) else {
+            continue;
+        };
+        let Some(features) = manifest.get("features").and_then(|f| f.as_table()) else {
+            continue;
+        };
+        for (feature, values) in features {
+            let values = strings(values);
+            let redundant = redundant(features, &values);
+            if redundant.is_empty() {
+                continue;
+            }
+            let mut finding = Finding::new(Kind::RedundantFeature, feature).member(project, member);
+            for reason in redundant.values() {
+                finding = finding.note(reason.clone());
+            }
+            let kept: Vec<String> = values
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !redundant.contains_key(index))
+                .map(|(_, value)| format!("\"{}\"", value))
+                .collect();
+            findings.push(finding.note(format!("Minimized: {} = [{}]", feature, kept.join(", "))));
+        }
+    }
+    findings
+}
+
+/// Rewrites the feature lists with redundant entries to their minimized form.
+pub fn fix(
+    project: &Project,
+    findings: &[Finding],
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut manifests: BTreeMap<PathBuf, BTreeSet<&str>> = BTreeMap::new();
+    for finding in findings.iter().filter(|f| f.kind == Kind::RedundantFeature) {
+        if let Some(file) = &finding.file {
+            manifests
+                .entry(project.root().join(file))
+                .or_default()
+                .insert(&finding.krate);
+        }
+    }
+
+    let mut changes = Vec::new();
+    for (path, names) in manifests {
+        let features = read_toml(&path)?
+            .get("features")
+            .and_then(|f| f.as_table())
+            .cloned()
+            .unwrap_or_default();
+        let mut manifest = ManifestEdit::open(&path)?;
+        let table = manifest
+            .table(&["features"])
+            .ok_or("[features] is not a table")?;
+        for name in names {
+            let Some(values) = features.get(name) else {
+                continue;
+            };
+            let values = strings(values);
+            let redundant = redundant(&features, &values);
+            let Some(array) = table.get_mut(name).and_then(|item| item.as_array_mut()) else {
+                continue;
+            };
+            // The first value's leading whitespace (none on a single line) stays first.
+            let prefix = array.get(0).and_then(|v| v.decor().prefix()).cloned();
+            // From the end, so the earlier indices stay valid.
+            for index in redundant.keys().rev() {
+                array.remove(*index);
+            }
+            if let (Some(first), Some(prefix)) = (array.get_mut(0), prefix) {
+                first.decor_mut().set_prefix(prefix);
+            }
+            let removed: Vec<String> = redundant
+                .keys()
+                .map(|index| format!("\"{}\"", values[*index]))
+                .collect();
+            changes.push(format!(
+                "Removed {} from feature `{}` in {}",
+                removed.join(", "),
+                name,
+                project.display_path(&path)
+            ));
+        }
+        manifest.save()?;
+    }
+    Ok(changes)
+}
+
+fn strings(values: &toml::Value) -> Vec<&str> {
+    values
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|value| value.as_str())
+        .collect()
+}
+
+/// The redundant values of a feature's list, by index, with why each is.
+fn redundant(features: &toml::Table, values: &[&str]) -> BTreeMap<usize, String> {
+    let implied: Vec<BTreeSet<String>> = values.iter().map(|v| implied(features, v)).collect();
+    let mut redundant = BTreeMap::new();
+    for (index, value) in values.iter().enumerate() {
+        if values[..index].contains(value) {
+            redundant.insert(index, format!("\"{}\" is listed twice", value));
+            continue;
+        }
+        // Values enabling each other (a cycle) are left alone.
+        let by = (0..values.len()).find(|other| {
+            values[*other] != *value
+                && implied[*other].contains(*value)
+                && !implied[index].contains(values[*other])
+        });
+        if let Some(other) = by {
+            redundant.insert(
+                index,
+                format!("\"{}\" is already enabled by \"{}\"", value, values[other]),
+            );
+        }
+    }
+    redundant
+}
+
+/// Everything enabling `value` enables in turn: the lists of the features it leads to,
+/// and for a forward (`dep/feature`) the dependency and the weak form of the forward.
+fn implied(features: &toml::Table, value: &str) -> BTreeSet<String> {
+    let mut implied = BTreeSet::new();
+    let mut pending = vec![value.to_string()];
+    while let Some(value) = pending.pop() {
+        if let Some((dep, feature)) = value.split_once('/') {
+            if !dep.ends_with('?') {
+                implied.insert(format!("dep:{}", dep));
+                implied.insert(format!("{}?/{}", dep, feature));
+            }
+            continue;
+        }
+        for next in features.get(&value).map(strings).into_iter().flatten() {
+            if implied.insert(next.to_string()) {
+                pending.push(next.to_string());
+            }
+        }
+    }
+    implied
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::fixture;
+
+    #[test]
+    fn fix_writes_the_minimized_lists() {
+        let (dir, project) = fixture(&[
+            (
+                "Cargo.toml",
+                r#"[package]
+name = "app"
+version = "0.1.0"
+edition = "2021"
+
+[features]
+default = ["std", "alloc", "std"]
+std = ["alloc"]
+alloc = []
+full = [
+    "alloc",
+    "std",
+]
+"#,
+            ),
+            ("src/lib.rs", ""),
+        ]);
+        let findings = analyze(&project);
+        assert_eq!(findings.len(), 2);
+
+        fix(&project, &findings).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("Cargo.toml")).unwrap(),
+            r#"[package]
+name = "app"
+version = "0.1.0"
+edition = "2021"
+
+[features]
+default = ["std"]
+std = ["alloc"]
+alloc = []
+full = [
+    "std",
+]
+"#
+        );
+        assert!(analyze(&project).is_empty());
+    }
+}
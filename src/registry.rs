@@ -0,0 +1,588 @@
+use crate::cache::Cache;
+use crate::cargo_config::{bypasses_proxy, cargo_home, CargoConfig};
+use crate::project::Project;
+use cargo_metadata::semver::Version;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Command, Stdio};
+
+const CRATES_IO_INDEX: &str = "https://index.crates.io";
+const CRATES_IO_SOURCE: &str = "registry+https://github.com/rust-lang/crates.io-index";
+/// Requests fetched at once by `prefetch` unless `-j` says otherwise.
+const PARALLEL_FETCHES: usize = 16;
+/// Retries of a transiently failed request when `net.retry` isn't set; cargo's default.
+const DEFAULT_RETRIES: u32 = 3;
+/// The wait before the first retry, doubling with each one.
+const RETRY_BACKOFF: std::time::Duration = std::time::Duration::from_millis(250);
+/// The longest `Retry-After`, in seconds, that is waited out.
+const MAX_RETRY_AFTER: u64 = 30;
+
+/// One published version of a crate, as listed in a sparse registry index.
+#[derive(Debug, Deserialize)]
+pub struct IndexEntry {
+    pub vers: String,
+    #[serde(default)]
+    pub yanked: bool,
+    /// The SHA-256 of the `.crate` file, as lockfiles record it.
+    #[serde(default)]
+    pub cksum: String,
+    #[serde(default)]
+    pub features: BTreeMap<String, Vec<String>>,
+    /// Features using `dep:` or `?/` syntax, which older cargo can't read from `features`.
+    #[serde(default)]
+    pub features2: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    pub deps: Vec<IndexDependency>,
+}
+
+/// A dependency of a published version, as the index lists it.
+#[derive(Debug, Deserialize)]
+pub struct IndexDependency {
+    /// The name the version depends on it by, renamed or not.
+    pub name: String,
+    #[serde(default)]
+    pub optional: bool,
+}
+
+/// A registry with a sparse index that can be queried over HTTP.
+pub struct Registry {
+    pub name: String,
+    index: String,
+    http: Http,
+    token: Option<String>,
+    cache: Cache,
+}
+
+impl Registry {
+    /// crates.io, or whatever `[source.crates-io]` replaces it with. `Ok(None)` when the
+    /// replacement can't be queried (vendored or local sources, git indexes).
+    pub fn crates_io(
+        config: &CargoConfig,
+        cache: &Cache,
+    ) -> Result<Option<Registry>, Box<dyn std::error::Error>> {
+        Registry::open(
+            "crates-io",
+            &format!("sparse+{}/", CRATES_IO_INDEX),
+            config,
+            cache,
+        )
+    }
+
+    /// The registry a package source (`registry+<url>` or `sparse+<url>`) refers to.
+    /// Alternative registries are looked up in `[registries]` of the cargo config to
+    /// find their name, source replacement and credentials.
+    pub fn for_source(
+        source: &str,
+        config: &CargoConfig,
+        cache: &Cache,
+    ) -> Result<Option<Registry>, Box<dyn std::error::Error>> {
+        if source == CRATES_IO_SOURCE || source.starts_with(&format!("sparse+{}", CRATES_IO_INDEX))
+        {
+            return Registry::crates_io(config, cache);
+        }
+
+        let index = source.strip_prefix("registry+").unwrap_or(source);
+        let name = config
+            .get(&["registries"])
+            .and_then(|r| r.as_table())
+            .and_then(|registries| {
+                registries.iter().find_map(|(name, registry)| {
+                    let configured = registry.get("index")?.as_str()?;
+                    (same_index(configured, index)).then(|| name.clone())
+                })
+            })
+            .unwrap_or_else(|| index.to_string());
+        Registry::open(&name, index, config, cache)
+    }
+
+    /// Follows `[source.<name>] replace-with` chains the way cargo does before
+    /// connecting to the resulting index.
+    fn open(
+        name: &str,
+        index: &str,
+        config: &CargoConfig,
+        cache: &Cache,
+    ) -> Result<Option<Registry>, Box<dyn std::error::Error>> {
+        let mut name = name.to_string();
+        let mut index = index.to_string();
+        // Cargo rejects replacement cycles; the bound just keeps a broken config from
+        // looping forever here.
+        for _ in 0..16 {
+            let Some(next) = config.get_str(&["source", &name, "replace-with"]) else {
+                break;
+            };
+            name = next.to_string();
+            if config.get(&["source", &name, "directory"]).is_some()
+                || config.get(&["source", &name, "local-registry"]).is_some()
+            {
+                return Ok(None);
+            }
+            if let Some(replacement) = config
+                .get_str(&["source", &name, "registry"])
+                .or_else(|| config.get_str(&["registries", &name, "index"]))
+            {
+                index = replacement.to_string();
+            }
+        }
+
+        let Some(index) = index.strip_prefix("sparse+") else {
+            return Ok(None);
+        };
+        let mut registry = Registry {
+            name,
+            index: index.trim_end_matches('/').to_string(),
+            http: Http::new(config)?,
+            token: None,
+            cache: cache.clone(),
+        };
+        if registry.auth_required()? {
+            registry.token = token(&registry, config)?;
+            if registry.token.is_none() {
+                return Err(format!(
+                    "registry `{}` requires authentication but no token was found",
+                    registry.name
+                )
+                .into());
+            }
+        }
+        Ok(Some(registry))
+    }
+
+    fn get(&self, path: &str) -> Result<ureq::Response, Box<ureq::Error>> {
+        let url = format!("{}/{}", self.index, path);
+        tracing::debug!(%url, "fetching from registry");
+        self.http.get(&url, self.token.as_deref())
+    }
+
+    /// Whether the index's `config.json` asks for authenticated requests.
+    fn auth_required(&self) -> Result<bool, Box<dyn std::error::Error>> {
+        #[derive(Deserialize)]
+        struct IndexConfig {
+            #[serde(default, rename = "auth-required")]
+            auth_required: bool,
+        }
+        let required = self
+            .cache
+            .fetch(&["registry", &self.index, "auth-required"], || {
+                let required = match self.get("config.json") {
+                    Ok(response) => {
+                        serde_json::from_str::<IndexConfig>(&response.into_string()?)?.auth_required
+                    }
+                    // Authenticated registries answer the unauthenticated probe with 401.
+                    Err(err) if matches!(*err, ureq::Error::Status(401, _)) => true,
+                    Err(err) => return Err(err),
+                };
+                Ok(required.to_string())
+            })?;
+        Ok(required == "true")
+    }
+
+    /// Downloads the `.crate` tarball of `name` `version`, using the `dl` template from
+    /// the index's `config.json`, and checks it against the index's checksum.
+    pub fn download(
+        &self,
+        name: &str,
+        version: &str,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        #[derive(Deserialize)]
+        struct IndexConfig {
+            dl: String,
+        }
+        let config = self
+            .cache
+            .fetch(&["registry", &self.index, "config.json"], || {
+                Ok(self.get("config.json")?.into_string()?)
+            })?;
+        let dl = serde_json::from_str::<IndexConfig>(&config)?.dl;
+        let cksum = self
+            .versions(name)?
+            .into_iter()
+            .find(|entry| entry.vers == version)
+            .map(|entry| entry.cksum)
+            .ok_or_else(|| format!("{} {} is not in the index of {}", name, version, self.name))?;
+
+        let markers = [
+            "{crate}",
+            "{version}",
+            "{prefix}",
+            "{lowerprefix}",
+            "{sha256-checksum}",
+        ];
+        let url = if markers.iter().any(|marker| dl.contains(marker)) {
+            dl.replace("{crate}", name)
+                .replace("{version}", version)
+                .replace("{prefix}", &prefix(name))
+                .replace("{lowerprefix}", &prefix(&name.to_lowercase()))
+                .replace("{sha256-checksum}", &cksum)
+        } else {
+            format!("{}/{}/{}/download", dl.trim_end_matches('/'), name, version)
+        };
+
+        let response = self.http.get(&url, self.token.as_deref())?;
+        let mut bytes = Vec::new();
+        response.into_reader().read_to_end(&mut bytes)?;
+        let actual = crate::checksums::sha256_hex(&bytes);
+        if actual != cksum {
+            return Err(format!(
+                "{} {} from {} doesn't match the index's checksum (expected {}, got {})",
+                name, version, url, cksum, actual
+            )
+            .into());
+        }
+        Ok(bytes)
+    }
+
+    /// All published versions of `name`.
+    pub fn versions(&self, name: &str) -> Result<Vec<IndexEntry>, Box<dyn std::error::Error>> {
+        let path = index_path(name);
+        let body = self.cache.fetch(&["registry", &self.index, &path], || {
+            Ok(self.get(&path)?.into_string()?)
+        })?;
+        body.lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| Ok(serde_json::from_str(line)?))
+            .collect()
+    }
+
+    /// The newest non-yanked, non-prerelease version of `name`.
+    pub fn latest_release(
+        &self,
+        name: &str,
+    ) -> Result<Option<Version>, Box<dyn std::error::Error>> {
+        Ok(self
+            .versions(name)?
+            .into_iter()
+            .filter(|entry| !entry.yanked)
+            .filter_map(|entry| entry.vers.parse::<Version>().ok())
+            .filter(|version| version.pre.is_empty())
+            .max())
+    }
+}
+
+/// A [`prefetch`] running in the background.
+pub struct Prefetch(Option<std::thread::JoinHandle<()>>);
+
+impl Prefetch {
+    /// Blocks until every index entry has been fetched.
+    pub fn wait(self) {
+        if let Some(handle) = self.0 {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Starts fetching the index entries of every registry package in the graph in the
+/// background, `-j` (or `PARALLEL_FETCHES`) at a time, so the passes that look them up
+/// one by one afterwards find them in the cache instead of waiting on a request each.
+pub fn prefetch(project: &Project) -> Prefetch {
+    let mut names: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for pkg in &project.metadata.packages {
+        let Some(source) = pkg.source.as_ref().map(|s| s.repr.as_str()) else {
+            continue;
+        };
+        if source.starts_with("registry+") || source.starts_with("sparse+") {
+            names.entry(source).or_default().insert(&pkg.name);
+        }
+    }
+    let registries: Vec<(Registry, BTreeSet<&str>)> = names
+        .into_iter()
+        .filter_map(|(source, names)| {
+            // The passes warn about registries they can't open.
+            match Registry::for_source(source, &project.cargo_config, &project.cache) {
+                Ok(registry) => Some((registry?, names)),
+                Err(err) => {
+                    tracing::debug!("not prefetching from {}: {}", source, err);
+                    None
+                }
+            }
+        })
+        .collect();
+    let names: Vec<(usize, String)> = registries
+        .iter()
+        .enumerate()
+        .flat_map(|(i, (_, names))| names.iter().map(move |name| (i, name.to_string())))
+        .collect();
+    let registries: Vec<Registry> = registries
+        .into_iter()
+        .map(|(registry, _)| registry)
+        .collect();
+    let progress = project
+        .progress
+        .bar("Fetching registry data", Some(names.len() as u64));
+    let jobs = project.jobs.unwrap_or(PARALLEL_FETCHES);
+    let handle = std::thread::spawn(move || {
+        crate::jobs::map(jobs, &names, |(registry, name)| {
+            let registry = &registries[*registry];
+            if let Err(err) = registry.versions(name) {
+                tracing::debug!(
+                    "could not prefetch {} from {}: {}",
+                    name,
+                    registry.name,
+                    err
+                );
+            }
+            progress.inc();
+        });
+    });
+    Prefetch(Some(handle))
+}
+
+/// HTTP agents honoring cargo's `[http]` settings (`proxy`, `timeout`, `cainfo`) and the
+/// proxy variables: one through the proxy, one for the hosts `NO_PROXY` exempts.
+struct Http {
+    proxied: ureq::Agent,
+    direct: ureq::Agent,
+    /// How often to retry a request that failed transiently, cargo's `net.retry`.
+    retries: u32,
+}
+
+impl Http {
+    fn new(config: &CargoConfig) -> Result<Http, Box<dyn std::error::Error>> {
+        let tls = config
+            .cainfo()
+            .map(|cainfo| tls_config(&cainfo))
+            .transpose()?;
+        let builder = || {
+            let mut builder = ureq::AgentBuilder::new();
+            if let Some(timeout) = config
+                .get(&["http", "timeout"])
+                .and_then(|t| t.as_integer())
+            {
+                builder = builder.timeout(std::time::Duration::from_secs(timeout.max(0) as u64));
+            }
+            if let Some(tls) = &tls {
+                builder = builder.tls_config(tls.clone());
+            }
+            builder
+        };
+        let direct = builder().build();
+        let proxied = match config.proxy() {
+            Some(proxy) => {
+                tracing::debug!(%proxy, "sending registry requests through a proxy");
+                builder().proxy(ureq::Proxy::new(proxy)?).build()
+            }
+            None => direct.clone(),
+        };
+        let retries = config
+            .get(&["net", "retry"])
+            .and_then(|r| r.as_integer())
+            .map_or(DEFAULT_RETRIES, |r| r.clamp(0, 10) as u32);
+        Ok(Http {
+            proxied,
+            direct,
+            retries,
+        })
+    }
+
+    /// GETs `url`, retrying connection failures, rate limiting and server errors with
+    /// exponential backoff (or as long as `Retry-After` asks).
+    fn get(&self, url: &str, token: Option<&str>) -> Result<ureq::Response, Box<ureq::Error>> {
+        let host = url
+            .split_once("://")
+            .map_or(url, |(_, rest)| rest)
+            .split(['/', ':'])
+            .next()
+            .unwrap_or_default();
+        let agent = if bypasses_proxy(host) {
+            &self.direct
+        } else {
+            &self.proxied
+        };
+        let mut attempt = 0;
+        loop {
+            let request = agent.get(url);
+            let result = match token {
+                Some(token) => request.set("Authorization", token).call(),
+                None => request.call(),
+            };
+            let delay = match &result {
+                Err(ureq::Error::Transport(_)) => None,
+                Err(ureq::Error::Status(429 | 500..=599, response)) => response
+                    .header("Retry-After")
+                    .and_then(|secs| secs.parse::<u64>().ok())
+                    .map(|secs| std::time::Duration::from_secs(secs.min(MAX_RETRY_AFTER))),
+                _ => return result.map_err(Box::new),
+            };
+            if attempt >= self.retries {
+                return result.map_err(Box::new);
+            }
+            let delay = delay.unwrap_or(RETRY_BACKOFF * 2u32.pow(attempt));
+            tracing::debug!(%url, ?delay, attempt, "retrying failed request");
+            std::thread::sleep(delay);
+            attempt += 1;
+        }
+    }
+}
+
+/// TLS trusting the system's certificates plus those in the PEM bundle at `cainfo`, such
+/// as the CA of a proxy that intercepts TLS.
+fn tls_config(
+    cainfo: &std::path::Path,
+) -> Result<std::sync::Arc<rustls::ClientConfig>, Box<dyn std::error::Error>> {
+    let mut roots = rustls::RootCertStore::empty();
+    for cert in rustls_native_certs::load_native_certs()? {
+        // Like ureq, skip system certificates rustls can't parse.
+        let _ = roots.add(cert);
+    }
+    let pem = std::fs::read(cainfo)
+        .map_err(|err| format!("could not read CA bundle {}: {}", cainfo.display(), err))?;
+    let mut added = 0;
+    for cert in rustls_pemfile::certs(&mut pem.as_slice()) {
+        roots.add(cert?)?;
+        added += 1;
+    }
+    if added == 0 {
+        return Err(format!("{} contains no PEM certificates", cainfo.display()).into());
+    }
+    let provider = std::sync::Arc::new(rustls::crypto::ring::default_provider());
+    Ok(std::sync::Arc::new(
+        rustls::ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()?
+            .with_root_certificates(roots)
+            .with_no_client_auth(),
+    ))
+}
+
+fn same_index(a: &str, b: &str) -> bool {
+    let normalize = |index: &str| {
+        index
+            .trim_start_matches("registry+")
+            .trim_end_matches('/')
+            .to_string()
+    };
+    normalize(a) == normalize(b)
+}
+
+/// Finds a token for an authenticated registry the way cargo does: the
+/// `CARGO_REGISTRIES_<NAME>_TOKEN` variable, then the configured credential provider
+/// (`cargo:token` reads `credentials.toml`; anything else is run as an external provider).
+fn token(
+    registry: &Registry,
+    config: &CargoConfig,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let env_name = format!(
+        "CARGO_REGISTRIES_{}_TOKEN",
+        registry.name.to_uppercase().replace('-', "_")
+    );
+    if let Ok(token) = std::env::var(env_name) {
+        return Ok(Some(token));
+    }
+
+    let provider = config
+        .get(&["registries", &registry.name, "credential-provider"])
+        .or_else(|| {
+            config
+                .get(&["registry", "global-credential-providers"])?
+                .as_array()?
+                .last()
+        });
+    let command: Vec<String> = match provider {
+        Some(toml::Value::String(command)) => {
+            command.split_whitespace().map(str::to_string).collect()
+        }
+        Some(toml::Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|p| p.as_str())
+            .map(str::to_string)
+            .collect(),
+        _ => vec!["cargo:token".to_string()],
+    };
+
+    match command.first().map(String::as_str) {
+        None | Some("cargo:token") => {
+            let from_config = config
+                .get_str(&["registries", &registry.name, "token"])
+                .map(str::to_string);
+            if from_config.is_some() {
+                return Ok(from_config);
+            }
+            let credentials = ["credentials.toml", "credentials"]
+                .iter()
+                .find_map(|file| crate::project::read_toml(&cargo_home().join(file)).ok());
+            Ok(credentials.and_then(|c| {
+                c.get("registries")?
+                    .get(&registry.name)?
+                    .get("token")?
+                    .as_str()
+                    .map(str::to_string)
+            }))
+        }
+        Some(builtin) if builtin.starts_with("cargo:") => {
+            Err(format!("credential provider `{}` is not supported", builtin).into())
+        }
+        Some(_) => external_provider(registry, &command),
+    }
+}
+
+/// Asks an external credential provider for a read token using cargo's credential
+/// provider protocol (version 1) over stdin/stdout.
+fn external_provider(
+    registry: &Registry,
+    command: &[String],
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let mut child = Command::new(&command[0])
+        .args(&command[1..])
+        .arg("--cargo-plugin")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().ok_or("provider stdin unavailable")?;
+    let mut stdout = BufReader::new(child.stdout.take().ok_or("provider stdout unavailable")?);
+
+    let mut hello = String::new();
+    stdout.read_line(&mut hello)?;
+
+    let request = serde_json::json!({
+        "v": 1,
+        "registry": { "index-url": format!("sparse+{}/", registry.index), "name": registry.name },
+        "kind": "get",
+        "operation": "read",
+        "args": command[1..],
+    });
+    writeln!(stdin, "{}", request)?;
+
+    let mut response = String::new();
+    stdout.read_line(&mut response)?;
+    drop(stdin);
+    child.wait()?;
+
+    let response: serde_json::Value = serde_json::from_str(&response)?;
+    Ok(response
+        .get("Ok")
+        .and_then(|ok| ok.get("token"))
+        .and_then(|token| token.as_str())
+        .map(str::to_string))
+}
+
+/// The location of a crate's file within an index, e.g. `se/rd/serde`.
+fn index_path(name: &str) -> String {
+    let name = name.to_lowercase();
+    format!("{}/{}", prefix(&name), name)
+}
+
+/// The directories an index files `name` under, as the `{prefix}` of a `dl` template:
+/// `1`, `2`, `3/s` or `se/rd`, in the name's own case.
+fn prefix(name: &str) -> String {
+    match name.len() {
+        1 => "1".to_string(),
+        2 => "2".to_string(),
+        3 => format!("3/{}", &name[..1]),
+        _ => format!("{}/{}", &name[..2], &name[2..4]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixes_keep_the_case() {
+        assert_eq!(prefix("a"), "1");
+        assert_eq!(prefix("ab"), "2");
+        assert_eq!(prefix("Abc"), "3/A");
+        assert_eq!(prefix("Serde_Json"), "Se/rd");
+        assert_eq!(index_path("Serde_Json"), "se/rd/serde_json");
+        assert_eq!(index_path("Abc"), "3/a/abc");
+    }
+}
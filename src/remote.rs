@@ -0,0 +1,117 @@
+use crate::cache::{self, Cache};
+use crate::cargo_config::CargoConfig;
+use crate::cli::Args;
+use crate::registry::Registry;
+use cargo_metadata::semver::Version;
+use flate2::read::GzDecoder;
+use std::path::Path;
+use std::process::Command;
+
+/// `remote <name>[@<version>]`: downloads a published crate into a temporary directory
+/// and runs the full analysis on it, to review a dependency's hygiene before adopting it.
+pub fn run(args: &Args, spec: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if args.offline {
+        return Err("`remote` needs network access and can't run with --offline".into());
+    }
+
+    // Only cargo's configuration is needed here; the project is the downloaded crate.
+    let mut cargo_config = CargoConfig::load(&std::env::current_dir()?);
+    cargo_config.cacert = args.cacert.clone();
+    let mut cache = Cache::new(cache::DEFAULT_TTL);
+    cache.refresh = args.refresh;
+    let registry = Registry::crates_io(&cargo_config, &cache)?
+        .ok_or("crates.io is replaced by a source that can't be downloaded from")?;
+
+    let (name, version) = match spec.split_once('@') {
+        Some((name, version)) => (name, version.parse::<Version>()?),
+        None => (
+            spec,
+            registry
+                .latest_release(spec)?
+                .ok_or_else(|| format!("no releases of `{}` found", spec))?,
+        ),
+    };
+
+    let tarball = registry.download(name, &version.to_string())?;
+    let dir = tempfile::Builder::new()
+        .prefix("cargo-dep-analysis-")
+        .tempdir()?;
+    tar::Archive::new(GzDecoder::new(tarball.as_slice())).unpack(dir.path())?;
+
+    let manifest = dir
+        .path()
+        .join(format!("{}-{}", name, version))
+        .join("Cargo.toml");
+    if !manifest.is_file() {
+        return Err(format!(
+            "{}@{} did not unpack to {}",
+            name,
+            version,
+            manifest.display()
+        )
+        .into());
+    }
+
+    tracing::info!("Analyzing {} {} from {}", name, version, registry.name);
+    let passed = crate::analyze(args, Some(&manifest))?;
+    // Exiting skips destructors, so remove the download first.
+    drop(dir);
+    if !passed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// `--git <url> [--rev <rev>]`: fetches a single commit of a repository into a temporary
+/// directory and analyzes it, so third-party projects can be audited without cloning
+/// them by hand.
+pub fn run_git(
+    args: &Args,
+    url: &str,
+    rev: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if args.offline {
+        return Err("--git needs network access and can't run with --offline".into());
+    }
+
+    let dir = tempfile::Builder::new()
+        .prefix("cargo-dep-analysis-")
+        .tempdir()?;
+    let rev = rev.unwrap_or("HEAD");
+    // `clone --depth 1` only accepts branch and tag names; fetching the revision
+    // directly also works for commit hashes.
+    git(dir.path(), &["init", "--quiet"])?;
+    git(dir.path(), &["fetch", "--quiet", "--depth", "1", url, rev])?;
+    git(dir.path(), &["checkout", "--quiet", "FETCH_HEAD"])?;
+
+    let manifest = match &args.manifest_path {
+        Some(path) => dir.path().join(path),
+        None => dir.path().join("Cargo.toml"),
+    };
+    if !manifest.is_file() {
+        return Err(format!(
+            "{} has no {}",
+            url,
+            manifest.strip_prefix(dir.path())?.display()
+        )
+        .into());
+    }
+
+    tracing::info!("Analyzing {} at {}", url, rev);
+    let passed = crate::analyze(args, Some(&manifest))?;
+    // Exiting skips destructors, so remove the checkout first.
+    drop(dir);
+    if !passed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+pub fn git(dir: &Path, args: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+    tracing::debug!("running git {}", args.join(" "));
+    let status = Command::new("git").args(args).current_dir(dir).status()?;
+    if !status.success() {
+        return Err(format!("`git {}` failed", args.join(" ")).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,560 @@
+use crate::cli::GroupBy;
+use crate::project::Project;
+use cargo_metadata::Package;
+use console::Style;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::Path;
+
+/// How much a finding matters; configurable per kind with the `severity` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Severity {
+    Help,
+    Info,
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Severity::Help => "help",
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+    Unused,
+    UndeclaredDependency,
+    FeatureGated,
+    FeatureUnusedDependency,
+    FeatureMissingDependency,
+    UnknownFeature,
+    FeatureTrim,
+    RedundantFeature,
+    GateableProcMacro,
+    PlatformOnly,
+    TestOnly,
+    ExampleOnly,
+    BenchOnly,
+    BenchHarness,
+    RedundantExternCrate,
+    StalePatch,
+    ActivePatch,
+    StaleReplace,
+    LegacyReplace,
+    StaleProfileOverride,
+    DuplicateDeclaration,
+    UnusedWorkspaceDependency,
+    InheritableDependency,
+    VersionSkew,
+    LayerViolation,
+    ResolverV1,
+    UnpublishablePathDependency,
+    ExternalPathDependency,
+    UnpinnedGitDependency,
+    GitDrift,
+    GitReleaseAvailable,
+    Outdated,
+    InRangeUpdate,
+    YankedVersion,
+    Advisory,
+    ChecksumMismatch,
+    VendorDrift,
+    LinksConflict,
+    BudgetExceeded,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 39] = [
+        Kind::Unused,
+        Kind::UndeclaredDependency,
+        Kind::FeatureGated,
+        Kind::FeatureUnusedDependency,
+        Kind::FeatureMissingDependency,
+        Kind::UnknownFeature,
+        Kind::FeatureTrim,
+        Kind::RedundantFeature,
+        Kind::GateableProcMacro,
+        Kind::PlatformOnly,
+        Kind::TestOnly,
+        Kind::ExampleOnly,
+        Kind::BenchOnly,
+        Kind::BenchHarness,
+        Kind::RedundantExternCrate,
+        Kind::StalePatch,
+        Kind::ActivePatch,
+        Kind::StaleReplace,
+        Kind::LegacyReplace,
+        Kind::StaleProfileOverride,
+        Kind::DuplicateDeclaration,
+        Kind::UnusedWorkspaceDependency,
+        Kind::InheritableDependency,
+        Kind::VersionSkew,
+        Kind::LayerViolation,
+        Kind::ResolverV1,
+        Kind::UnpublishablePathDependency,
+        Kind::ExternalPathDependency,
+        Kind::UnpinnedGitDependency,
+        Kind::GitDrift,
+        Kind::GitReleaseAvailable,
+        Kind::Outdated,
+        Kind::InRangeUpdate,
+        Kind::YankedVersion,
+        Kind::Advisory,
+        Kind::ChecksumMismatch,
+        Kind::VendorDrift,
+        Kind::LinksConflict,
+        Kind::BudgetExceeded,
+    ];
+
+    /// The rule id used by `explain`, the configuration and machine-readable formats.
+    pub fn id(self) -> &'static str {
+        match self {
+            Kind::Unused => "unused",
+            Kind::UndeclaredDependency => "undeclared-dependency",
+            Kind::FeatureGated => "feature-gated",
+            Kind::FeatureUnusedDependency => "feature-unused-dependency",
+            Kind::FeatureMissingDependency => "feature-missing-dependency",
+            Kind::UnknownFeature => "unknown-feature",
+            Kind::FeatureTrim => "feature-trim",
+            Kind::RedundantFeature => "redundant-feature",
+            Kind::GateableProcMacro => "gateable-proc-macro",
+            Kind::PlatformOnly => "platform-only",
+            Kind::TestOnly => "test-only",
+            Kind::ExampleOnly => "example-only",
+            Kind::BenchOnly => "bench-only",
+            Kind::BenchHarness => "bench-harness",
+            Kind::RedundantExternCrate => "redundant-extern-crate",
+            Kind::StalePatch => "stale-patch",
+            Kind::ActivePatch => "active-patch",
+            Kind::StaleReplace => "stale-replace",
+            Kind::LegacyReplace => "legacy-replace",
+            Kind::StaleProfileOverride => "stale-profile-override",
+            Kind::DuplicateDeclaration => "duplicate-declaration",
+            Kind::UnusedWorkspaceDependency => "unused-workspace-dependency",
+            Kind::InheritableDependency => "inheritable-dependency",
+            Kind::VersionSkew => "version-skew",
+            Kind::LayerViolation => "layer-violation",
+            Kind::ResolverV1 => "resolver-v1",
+            Kind::UnpublishablePathDependency => "unpublishable-path-dependency",
+            Kind::ExternalPathDependency => "external-path-dependency",
+            Kind::UnpinnedGitDependency => "unpinned-git-dependency",
+            Kind::GitDrift => "git-drift",
+            Kind::GitReleaseAvailable => "git-release-available",
+            Kind::Outdated => "outdated",
+            Kind::InRangeUpdate => "in-range-update",
+            Kind::YankedVersion => "yanked-version",
+            Kind::Advisory => "advisory",
+            Kind::ChecksumMismatch => "checksum-mismatch",
+            Kind::VendorDrift => "vendor-drift",
+            Kind::LinksConflict => "links-conflict",
+            Kind::BudgetExceeded => "budget-exceeded",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Kind::Unused => "POTENTIALLY UNUSED",
+            Kind::UndeclaredDependency => "UNDECLARED DEPENDENCY",
+            Kind::FeatureGated => "USED UNDER FEATURE",
+            Kind::FeatureUnusedDependency => "FEATURE ENABLES UNUSED",
+            Kind::FeatureMissingDependency => "FEATURE DOESN'T ENABLE",
+            Kind::UnknownFeature => "UNKNOWN FEATURE",
+            Kind::FeatureTrim => "FEATURES NOT NEEDED",
+            Kind::RedundantFeature => "REDUNDANT FEATURE ENTRY",
+            Kind::GateableProcMacro => "PROC-MACRO WORTH GATING",
+            Kind::PlatformOnly => "PLATFORM ONLY",
+            Kind::TestOnly => "TEST ONLY",
+            Kind::ExampleOnly => "EXAMPLES ONLY",
+            Kind::BenchOnly => "BENCHES ONLY",
+            Kind::BenchHarness => "BENCH HARNESS",
+            Kind::RedundantExternCrate => "REDUNDANT EXTERN CRATE",
+            Kind::StalePatch => "STALE PATCH",
+            Kind::ActivePatch => "PATCH ACTIVE",
+            Kind::StaleReplace => "STALE REPLACE",
+            Kind::LegacyReplace => "LEGACY REPLACE",
+            Kind::StaleProfileOverride => "STALE PROFILE OVERRIDE",
+            Kind::DuplicateDeclaration => "DUPLICATE DECLARATION",
+            Kind::UnusedWorkspaceDependency => "UNUSED WORKSPACE DEPENDENCY",
+            Kind::InheritableDependency => "INHERITABLE BY WORKSPACE",
+            Kind::VersionSkew => "VERSION SKEW",
+            Kind::LayerViolation => "LAYERING VIOLATION",
+            Kind::ResolverV1 => "RESOLVER V1",
+            Kind::UnpublishablePathDependency => "PUBLISH BLOCKER",
+            Kind::ExternalPathDependency => "PATH OUTSIDE WORKSPACE",
+            Kind::UnpinnedGitDependency => "UNPINNED GIT DEPENDENCY",
+            Kind::GitDrift => "GIT DRIFT",
+            Kind::GitReleaseAvailable => "RELEASED ON CRATES.IO",
+            Kind::Outdated => "OUTDATED",
+            Kind::InRangeUpdate => "UPDATE IN RANGE",
+            Kind::YankedVersion => "YANKED",
+            Kind::Advisory => "SECURITY ADVISORY",
+            Kind::ChecksumMismatch => "CHECKSUM MISMATCH",
+            Kind::VendorDrift => "VENDOR DRIFT",
+            Kind::LinksConflict => "NATIVE LIBRARY CONFLICT",
+            Kind::BudgetExceeded => "OVER BUDGET",
+        }
+    }
+
+    /// Errors break an explicit rule or the build of dependents; warnings are likely
+    /// mistakes; info is context; help is an optional improvement.
+    pub fn default_severity(self) -> Severity {
+        match self {
+            Kind::LayerViolation
+            | Kind::YankedVersion
+            | Kind::Advisory
+            | Kind::ChecksumMismatch
+            | Kind::VendorDrift
+            | Kind::BudgetExceeded => Severity::Error,
+            Kind::Unused
+            | Kind::UndeclaredDependency
+            | Kind::FeatureUnusedDependency
+            | Kind::FeatureMissingDependency
+            | Kind::UnknownFeature
+            | Kind::PlatformOnly
+            | Kind::TestOnly
+            | Kind::ExampleOnly
+            | Kind::BenchOnly
+            | Kind::BenchHarness
+            | Kind::StalePatch
+            | Kind::StaleReplace
+            | Kind::StaleProfileOverride
+            | Kind::DuplicateDeclaration
+            | Kind::UnusedWorkspaceDependency
+            | Kind::ResolverV1
+            | Kind::UnpublishablePathDependency
+            | Kind::UnpinnedGitDependency
+            | Kind::LinksConflict => Severity::Warning,
+            Kind::FeatureGated
+            | Kind::ActivePatch
+            | Kind::VersionSkew
+            | Kind::ExternalPathDependency
+            | Kind::GitDrift
+            | Kind::GitReleaseAvailable
+            | Kind::Outdated
+            | Kind::InRangeUpdate => Severity::Info,
+            Kind::FeatureTrim
+            | Kind::RedundantFeature
+            | Kind::GateableProcMacro
+            | Kind::RedundantExternCrate
+            | Kind::LegacyReplace
+            | Kind::InheritableDependency => Severity::Help,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    pub kind: Kind,
+    pub severity: Severity,
+    #[serde(rename = "crate")]
+    pub krate: String,
+    /// The workspace member the finding is about, when it concerns a single one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub member: Option<String>,
+    /// The file to change, relative to the workspace root; the root manifest by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    pub notes: Vec<String>,
+    /// What backs a usage finding when `--verify-with-rustc` is given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub evidence: Option<Evidence>,
+    /// How far the update an `outdated` or `in-range-update` finding suggests jumps.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub update: Option<Update>,
+    /// Where `file` declares the crate: its manifest entry, or its lockfile entry.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub declaration: Option<Span>,
+    /// Where the member's sources reference the crate, for findings about how it's used.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub usages: Vec<Span>,
+}
+
+/// A location in a file, relative to the workspace root, with a 1-based line and column
+/// (counted in characters).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Span {
+    pub file: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Where the evidence for a usage finding comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Evidence {
+    /// The textual scan only; rustc saw the dependency used.
+    Heuristic,
+    /// rustc's `unused_crate_dependencies` lint only.
+    Compiler,
+    /// The scan and rustc agree.
+    Both,
+}
+
+/// The size of an update by semver: a major update is incompatible with the locked
+/// version (for 0.x crates, a new minor; for 0.0.x ones, a new patch).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Update {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl fmt::Display for Update {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Update::Patch => "patch",
+            Update::Minor => "minor",
+            Update::Major => "major",
+        })
+    }
+}
+
+impl Finding {
+    pub fn new(kind: Kind, krate: &str) -> Self {
+        Finding {
+            kind,
+            severity: kind.default_severity(),
+            krate: krate.to_string(),
+            member: None,
+            file: None,
+            notes: Vec::new(),
+            evidence: None,
+            update: None,
+            declaration: None,
+            usages: Vec::new(),
+        }
+    }
+
+    pub fn note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Attributes the finding to a workspace member and its manifest.
+    pub fn member(mut self, project: &Project, member: &Package) -> Self {
+        self.member = Some(member.name.clone());
+        self.file = Some(project.display_path(member.manifest_path.as_std_path()));
+        self
+    }
+
+    pub fn file(mut self, project: &Project, path: &Path) -> Self {
+        self.file = Some(project.display_path(path));
+        self
+    }
+
+    /// Whether a `--fail-on` rule selects the finding: its kind's id, or for updates the
+    /// id and the size of the update, as in `outdated-major`.
+    pub fn matches_rule(&self, rule: &str) -> bool {
+        rule == self.kind.id()
+            || self
+                .update
+                .is_some_and(|update| rule == format!("{}-{}", self.kind.id(), update))
+    }
+}
+
+impl Severity {
+    fn style(self) -> Style {
+        match self {
+            Severity::Error => Style::new().red().bold(),
+            Severity::Warning => Style::new().yellow().bold(),
+            Severity::Info => Style::new().cyan(),
+            Severity::Help => Style::new().green(),
+        }
+    }
+}
+
+/// Splits sorted findings into titled sections. Severity sections keep the findings'
+/// order (most severe first); the others are sorted by title.
+pub fn group(findings: &[Finding], by: GroupBy) -> Vec<(String, Vec<&Finding>)> {
+    let mut groups: Vec<(String, Vec<&Finding>)> = Vec::new();
+    for finding in findings {
+        let title = match by {
+            GroupBy::Crate => finding.krate.clone(),
+            GroupBy::File => finding
+                .file
+                .clone()
+                .unwrap_or_else(|| "Cargo.toml".to_string()),
+            GroupBy::Severity => finding.severity.to_string(),
+            GroupBy::Rule => finding.kind.id().to_string(),
+            GroupBy::Member => finding
+                .member
+                .clone()
+                .unwrap_or_else(|| "(workspace)".to_string()),
+        };
+        match groups.iter_mut().find(|(t, _)| *t == title) {
+            Some((_, members)) => members.push(finding),
+            None => groups.push((title, vec![finding])),
+        }
+    }
+    if by != GroupBy::Severity {
+        groups.sort_by(|a, b| a.0.cmp(&b.0));
+    }
+    groups
+}
+
+/// The text report: one row per finding with its severity, crate and kind aligned in
+/// columns, and its notes stacked in the last one; in sections with `group_by`. Findings
+/// about the `tooling` members (xtask) come last, in a section of their own.
+pub fn print(
+    findings: &[Finding],
+    color: bool,
+    group_by: Option<GroupBy>,
+    tooling: &BTreeSet<String>,
+) {
+    let bold = Style::new().bold().force_styling(color);
+    println!("\n{}", bold.apply_to("Dependency Analysis Report:"));
+    println!("==========================");
+
+    let width = |column: fn(&Finding) -> usize, header: &str| {
+        findings
+            .iter()
+            .map(column)
+            .max()
+            .unwrap_or(0)
+            .max(header.len())
+    };
+    let crate_width = width(|f| f.krate.chars().count(), "CRATE");
+    let kind_width = width(|f| f.kind.label().len(), "FINDING");
+    let indent = " ".repeat(8 + 2 + crate_width + 2 + kind_width + 2);
+
+    let is_tooling = |f: &Finding| f.member.as_ref().is_some_and(|m| tooling.contains(m));
+    let (tooling_findings, product): (Vec<Finding>, Vec<Finding>) =
+        findings.iter().cloned().partition(is_tooling);
+    let mut sections = match group_by {
+        Some(by) => group(&product, by),
+        None => vec![(String::new(), product.iter().collect())],
+    };
+    if !tooling_findings.is_empty() {
+        let names: Vec<&str> = tooling.iter().map(String::as_str).collect();
+        sections.push((
+            format!("Build tooling: {}", names.join(", ")),
+            tooling_findings.iter().collect(),
+        ));
+    }
+    for (title, findings) in sections.iter().filter(|(_, f)| !f.is_empty()) {
+        if !title.is_empty() {
+            let heading = format!("{} ({})", title, findings.len());
+            println!("\n{}", bold.apply_to(&heading));
+            println!("{}", "-".repeat(heading.chars().count()));
+        }
+        println!(
+            "\n{}",
+            bold.apply_to(format!(
+                "{:<8}  {:<crate_width$}  {:<kind_width$}  DETAILS",
+                "SEVERITY", "CRATE", "FINDING"
+            ))
+        );
+        for (i, finding) in findings.iter().enumerate() {
+            if i > 0 {
+                println!();
+            }
+            let severity = finding.severity.style().force_styling(color);
+            let mut notes = finding.notes.iter();
+            println!(
+                "{}  {}  {:<kind_width$}  {}",
+                severity.apply_to(format!("{:<8}", finding.severity)),
+                bold.apply_to(format!("{:<crate_width$}", finding.krate)),
+                finding.kind.label(),
+                notes.next().map(String::as_str).unwrap_or_default()
+            );
+            for note in notes {
+                println!("{}{}", indent, note);
+            }
+        }
+    }
+
+    let count = |severity: Severity| {
+        let count = findings.iter().filter(|f| f.severity == severity).count();
+        let plural = count != 1 && severity >= Severity::Warning;
+        let text = format!("{} {}{}", count, severity, if plural { "s" } else { "" });
+        if count == 0 {
+            text
+        } else {
+            severity
+                .style()
+                .force_styling(color)
+                .apply_to(text)
+                .to_string()
+        }
+    };
+    println!(
+        "\nSummary: {}, {}, {}, {}",
+        count(Severity::Error),
+        count(Severity::Warning),
+        count(Severity::Info),
+        count(Severity::Help)
+    );
+    if !findings.is_empty() {
+        println!("Run `cargo dep-analysis explain <finding>` for details on a kind of finding");
+    }
+}
+
+/// Errors first, then by crate, kind and details, so that every format lists findings in
+/// the same order from run to run.
+pub fn sort(findings: &mut [Finding]) {
+    findings.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+            .then_with(|| a.krate.cmp(&b.krate))
+            .then_with(|| a.kind.cmp(&b.kind))
+            .then_with(|| a.notes.cmp(&b.notes))
+    });
+}
+
+/// Why the run should fail, if it should: any error, or more warnings than allowed.
+pub fn threshold_exceeded(
+    findings: &[Finding],
+    max_warnings: Option<usize>,
+    fail_on: &[String],
+) -> Option<String> {
+    let selected: BTreeSet<&str> = fail_on
+        .iter()
+        .filter(|rule| findings.iter().any(|f| f.matches_rule(rule)))
+        .map(String::as_str)
+        .collect();
+    if !selected.is_empty() {
+        return Some(format!(
+            "findings matching --fail-on {}",
+            selected.into_iter().collect::<Vec<_>>().join(", ")
+        ));
+    }
+    let errors = findings
+        .iter()
+        .filter(|f| f.severity == Severity::Error)
+        .count();
+    let warnings = findings
+        .iter()
+        .filter(|f| f.severity == Severity::Warning)
+        .count();
+    if errors > 0 {
+        return Some(format!("{} error-level finding(s)", errors));
+    }
+    match max_warnings {
+        Some(max) if warnings > max => Some(format!(
+            "{} warning(s) exceed the maximum of {}",
+            warnings, max
+        )),
+        _ => None,
+    }
+}
+
+pub fn print_fixes(changes: &[String]) {
+    println!("\nApplied Fixes:");
+    println!("==============");
+    if changes.is_empty() {
+        println!("Nothing to fix");
+    }
+    for change in changes {
+        println!("  - {}", change);
+    }
+}
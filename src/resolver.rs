@@ -0,0 +1,146 @@
+use crate::fix::ManifestEdit;
+use crate::project::Project;
+use crate::report::{Finding, Kind};
+use cargo_metadata::{Dependency, DependencyKind, Package};
+use std::collections::BTreeSet;
+
+/// The resolver version cargo uses for the workspace and whether it was set explicitly.
+pub fn effective_resolver(project: &Project) -> (String, bool) {
+    let explicit = ["workspace", "package"].iter().find_map(|section| {
+        project
+            .manifest
+            .get(*section)?
+            .get("resolver")?
+            .as_str()
+            .map(str::to_string)
+    });
+    if let Some(resolver) = explicit {
+        return (resolver, true);
+    }
+
+    // Virtual workspaces always default to v1; otherwise the root package's edition decides.
+    let edition = project
+        .manifest
+        .get("package")
+        .and_then(|p| p.get("edition"))
+        .and_then(|e| e.as_str());
+    let resolver = match edition {
+        Some("2024") => "3",
+        Some("2021") => "2",
+        _ => "1",
+    };
+    (resolver.to_string(), false)
+}
+
+/// Flags workspaces resolving features with v1 and lists the feature unification the
+/// v2 resolver would undo in the current graph.
+pub fn analyze(project: &Project) -> Vec<Finding> {
+    let (resolver, explicit) = effective_resolver(project);
+    if resolver != "1" {
+        return Vec::new();
+    }
+
+    let mut finding = Finding::new(Kind::ResolverV1, "workspace");
+    finding = if explicit {
+        finding.note("The workspace sets resolver = \"1\"")
+    } else {
+        finding.note("No resolver is set, so cargo falls back to resolver = \"1\"")
+    };
+
+    let leaks = unification_leaks(project);
+    if leaks.is_empty() {
+        finding = finding.note(
+            "No dev/build/target-only feature leaks were observed in the current graph; \
+             switching is still recommended to match current cargo defaults",
+        );
+    } else {
+        finding = finding.note("Features that v1 unifies into normal builds:");
+        for leak in leaks {
+            finding = finding.note(format!("  - {}", leak));
+        }
+    }
+    vec![finding.note("Set resolver = \"2\" (run with --fix to apply)")]
+}
+
+/// Features of normally-built packages that are only requested through dev, build or
+/// target-specific edges, which resolver v1 nevertheless enables everywhere.
+fn unification_leaks(project: &Project) -> Vec<String> {
+    let mut leaks = BTreeSet::new();
+
+    for pkg in &project.metadata.packages {
+        let requests: Vec<(&Package, &Dependency)> = project
+            .metadata
+            .packages
+            .iter()
+            .flat_map(|from| from.dependencies.iter().map(move |dep| (from, dep)))
+            .filter(|(from, dep)| {
+                dep.name == pkg.name
+                    && dep.req.matches(&pkg.version)
+                    && (dep.kind != DependencyKind::Development
+                        || project.metadata.workspace_members.contains(&from.id))
+            })
+            .collect();
+        let unconditional =
+            |dep: &Dependency| dep.kind == DependencyKind::Normal && dep.target.is_none();
+        if !requests.iter().any(|(_, dep)| unconditional(dep)) {
+            continue;
+        }
+
+        let normal: BTreeSet<&str> = requests
+            .iter()
+            .filter(|(_, dep)| unconditional(dep))
+            .flat_map(|(_, dep)| requested_features(dep))
+            .collect();
+        for (from, dep) in requests.iter().filter(|(_, dep)| !unconditional(dep)) {
+            for feature in requested_features(dep) {
+                if !normal.contains(feature) {
+                    leaks.insert(format!(
+                        "{} feature \"{}\" (requested by {} as a {})",
+                        pkg.name,
+                        feature,
+                        from.name,
+                        edge_description(dep)
+                    ));
+                }
+            }
+        }
+    }
+
+    leaks.into_iter().collect()
+}
+
+fn requested_features(dep: &Dependency) -> impl Iterator<Item = &str> {
+    let default = dep.uses_default_features.then_some("default");
+    dep.features.iter().map(String::as_str).chain(default)
+}
+
+fn edge_description(dep: &Dependency) -> String {
+    match (dep.kind, &dep.target) {
+        (DependencyKind::Development, _) => "dev-dependency".to_string(),
+        (DependencyKind::Build, _) => "build-dependency".to_string(),
+        (_, Some(target)) => format!("dependency for {}", target),
+        _ => "dependency".to_string(),
+    }
+}
+
+/// Sets `resolver = "2"` in `[workspace]`, or in `[package]` when there is no
+/// workspace table.
+pub fn fix(project: &Project) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    if effective_resolver(project).0 != "1" {
+        return Ok(Vec::new());
+    }
+
+    let section = if project.manifest.contains_key("workspace") {
+        "workspace"
+    } else {
+        "package"
+    };
+    let mut manifest = ManifestEdit::open(&project.root().join("Cargo.toml"))?;
+    manifest
+        .table(&[section])
+        .ok_or("manifest section is not a table")?
+        .insert("resolver", toml_edit::value("2"));
+    manifest.save()?;
+
+    Ok(vec![format!("Set resolver = \"2\" in [{}]", section)])
+}
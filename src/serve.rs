@@ -0,0 +1,404 @@
+use crate::cli::Args;
+use crate::project::Project;
+use crate::report::Finding;
+use cargo_metadata::{DependencyKind, PackageId};
+use std::collections::{BTreeSet, HashSet};
+use std::fmt::Write as _;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use walkdir::WalkDir;
+
+/// How often the sources are checked for changes, and how often the page asks whether
+/// a new analysis is available.
+const POLL: Duration = Duration::from_millis(1000);
+
+struct Dashboard {
+    project: Project,
+    findings: Vec<Finding>,
+    /// Bumped on every re-analysis; the page reloads when it sees a new value.
+    generation: u64,
+    fingerprint: Fingerprint,
+}
+
+/// `serve`: analyzes the project, hosts a dashboard for it on localhost and analyzes it
+/// again whenever a manifest, the lockfile, a Rust source or cargo's configuration changes.
+pub fn run(args: &Args, port: u16) -> Result<(), Box<dyn std::error::Error>> {
+    let (project, findings) = crate::collect(args, args.manifest_path.as_deref())?;
+    let sources = fingerprint(&project.root());
+    let mut dashboard = Dashboard {
+        project,
+        findings,
+        generation: 1,
+        fingerprint: sources,
+    };
+
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    listener.set_nonblocking(true)?;
+    tracing::info!("Serving the dashboard on http://127.0.0.1:{}/", port);
+
+    let mut checked = SystemTime::now();
+    loop {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                if let Err(err) = respond(&dashboard, stream) {
+                    tracing::warn!("failed to answer a request: {}", err);
+                }
+            }
+            Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                std::thread::sleep(Duration::from_millis(50))
+            }
+            Err(err) => return Err(err.into()),
+        }
+
+        if checked.elapsed().unwrap_or_default() < POLL {
+            continue;
+        }
+        checked = SystemTime::now();
+        let sources = fingerprint(&dashboard.project.root());
+        if sources != dashboard.fingerprint {
+            tracing::info!("Sources changed, analyzing again");
+            match crate::collect(args, args.manifest_path.as_deref()) {
+                Ok((project, findings)) => {
+                    dashboard.project = project;
+                    dashboard.findings = findings;
+                    dashboard.generation += 1;
+                }
+                Err(err) => tracing::warn!("analysis failed: {}", err),
+            }
+            dashboard.fingerprint = sources;
+        }
+    }
+}
+
+/// The files the analysis reads, each with its modification time: the manifests, the
+/// lockfile and the Rust sources under `root`, and cargo's configuration. Comparing whole
+/// fingerprints catches deletions and renames too, which leave the newest time alone.
+pub type Fingerprint = Vec<(PathBuf, SystemTime)>;
+
+pub fn fingerprint(root: &Path) -> Fingerprint {
+    let sources = WalkDir::new(root)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            !(entry.depth() > 0 && (name.starts_with('.') || name == "target"))
+        })
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            name == "Cargo.toml" || name == "Cargo.lock" || name.ends_with(".rs")
+        })
+        .map(|entry| entry.into_path());
+    // Where `CargoConfig::load` looks.
+    let configs = root
+        .ancestors()
+        .map(|ancestor| ancestor.join(".cargo"))
+        .chain([crate::cargo_config::cargo_home()])
+        .flat_map(|dir| [dir.join("config.toml"), dir.join("config")]);
+    sources
+        .chain(configs)
+        .filter_map(|path| {
+            let modified = path.metadata().ok()?.modified().ok()?;
+            Some((path, modified))
+        })
+        .collect()
+}
+
+fn respond(dashboard: &Dashboard, mut stream: TcpStream) -> std::io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut request = [0; 8192];
+    let len = stream.read(&mut request)?;
+    let request = String::from_utf8_lossy(&request[..len]);
+    let path = request
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("GET "))
+        .and_then(|rest| rest.split_whitespace().next())
+        .unwrap_or("");
+
+    let (status, content_type, body) = match path {
+        "/" => ("200 OK", "text/html", index(dashboard)),
+        "/api/findings" => (
+            "200 OK",
+            "application/json",
+            serde_json::to_string(&dashboard.findings).unwrap_or_default(),
+        ),
+        "/api/generation" => ("200 OK", "text/plain", dashboard.generation.to_string()),
+        _ => match path.strip_prefix("/dep/") {
+            Some(name) if !name.is_empty() => match detail(dashboard, name) {
+                Some(page) => ("200 OK", "text/html", page),
+                None => (
+                    "404 Not Found",
+                    "text/plain",
+                    format!("{} is not in the graph", name),
+                ),
+            },
+            _ => ("404 Not Found", "text/plain", "Not found".to_string()),
+        },
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+fn index(dashboard: &Dashboard) -> String {
+    let mut body = String::new();
+    let _ = writeln!(
+        body,
+        "<h1>Dependency analysis</h1>\n<p>{} findings in {} packages</p>",
+        dashboard.findings.len(),
+        dashboard.project.metadata.packages.len()
+    );
+
+    let kinds: BTreeSet<&str> = dashboard.findings.iter().map(|f| f.kind.label()).collect();
+    let _ = writeln!(
+        body,
+        "<h2>Findings</h2>\n<input id=\"filter\" placeholder=\"Filter\" oninput=\"filter()\">\n<select id=\"kind\" onchange=\"filter()\"><option value=\"\">All kinds</option>"
+    );
+    for kind in &kinds {
+        let _ = writeln!(body, "<option>{}</option>", escape(kind));
+    }
+    let _ = writeln!(
+        body,
+        "</select>\n<table id=\"findings\"><tr><th>Crate</th><th>Kind</th><th>Details</th></tr>"
+    );
+    for finding in &dashboard.findings {
+        let _ = writeln!(
+            body,
+            "<tr data-kind=\"{kind}\"><td><a href=\"/dep/{krate}\">{krate}</a></td><td>{kind}</td><td>{notes}</td></tr>",
+            krate = escape(&finding.krate),
+            kind = escape(finding.kind.label()),
+            notes = finding
+                .notes
+                .iter()
+                .map(|note| escape(note))
+                .collect::<Vec<_>>()
+                .join("<br>")
+        );
+    }
+    let _ = writeln!(body, "</table>\n<h2>Graph</h2>");
+    for member in dashboard.project.members() {
+        let mut shown = HashSet::new();
+        tree(dashboard, &member.id, &mut shown, &mut body);
+    }
+
+    page("Dependency analysis", &body)
+}
+
+/// A collapsible tree of the normal and build dependencies below `id`; packages already
+/// expanded elsewhere in the tree are listed without their children.
+fn tree(dashboard: &Dashboard, id: &PackageId, shown: &mut HashSet<PackageId>, out: &mut String) {
+    let project = &dashboard.project;
+    let Some(pkg) = project.package(id) else {
+        return;
+    };
+    let label = format!(
+        "<a href=\"/dep/{name}\">{name}</a> {version}",
+        name = escape(&pkg.name),
+        version = pkg.version
+    );
+    let deps: Vec<&PackageId> = project
+        .metadata
+        .resolve
+        .iter()
+        .flat_map(|resolve| &resolve.nodes)
+        .filter(|node| &node.id == id)
+        .flat_map(|node| &node.deps)
+        .filter(|dep| {
+            dep.dep_kinds
+                .iter()
+                .any(|k| k.kind != DependencyKind::Development)
+        })
+        .map(|dep| &dep.pkg)
+        .collect();
+
+    if deps.is_empty() || !shown.insert(id.clone()) {
+        let _ = writeln!(out, "<div class=\"leaf\">{}</div>", label);
+        return;
+    }
+    let _ = writeln!(out, "<details><summary>{}</summary>", label);
+    for dep in deps {
+        tree(dashboard, dep, shown, out);
+    }
+    let _ = writeln!(out, "</details>");
+}
+
+/// Every resolved version of `name`, what it depends on, what depends on it, and the
+/// findings about it.
+fn detail(dashboard: &Dashboard, name: &str) -> Option<String> {
+    let project = &dashboard.project;
+    let packages: Vec<_> = project
+        .metadata
+        .packages
+        .iter()
+        .filter(|pkg| pkg.name == name)
+        .collect();
+    let findings: Vec<_> = dashboard
+        .findings
+        .iter()
+        .filter(|f| f.krate == name)
+        .collect();
+    if packages.is_empty() && findings.is_empty() {
+        return None;
+    }
+
+    let mut body = format!(
+        "<p><a href=\"/\">&larr; Dashboard</a></p>\n<h1>{}</h1>\n",
+        escape(name)
+    );
+    let nodes = project.metadata.resolve.iter().flat_map(|r| &r.nodes);
+    let link = |id: &PackageId| {
+        project
+            .package(id)
+            .map(|pkg| {
+                format!(
+                    "<li><a href=\"/dep/{name}\">{name}</a> {}</li>",
+                    pkg.version,
+                    name = escape(&pkg.name)
+                )
+            })
+            .unwrap_or_default()
+    };
+
+    for pkg in packages {
+        let _ = writeln!(
+            body,
+            "<h2>{} {}</h2>\n<p>Source: {}</p>",
+            escape(&pkg.name),
+            pkg.version,
+            escape(
+                &pkg.source
+                    .as_ref()
+                    .map(|s| s.repr.clone())
+                    .unwrap_or_else(|| project.display_path(pkg.manifest_path.as_std_path()))
+            )
+        );
+        let _ = writeln!(body, "<h3>Depends on</h3>\n<ul>");
+        for node in nodes.clone().filter(|node| node.id == pkg.id) {
+            for dep in &node.deps {
+                body.push_str(&link(&dep.pkg));
+            }
+        }
+        let _ = writeln!(body, "</ul>\n<h3>Used by</h3>\n<ul>");
+        for node in nodes
+            .clone()
+            .filter(|node| node.deps.iter().any(|dep| dep.pkg == pkg.id))
+        {
+            body.push_str(&link(&node.id));
+        }
+        let _ = writeln!(body, "</ul>");
+    }
+
+    if !findings.is_empty() {
+        let _ = writeln!(body, "<h2>Findings</h2>");
+        for finding in findings {
+            let _ = writeln!(
+                body,
+                "<h3>{}</h3>\n<pre>{}</pre>",
+                escape(finding.kind.label()),
+                escape(&finding.notes.join("\n"))
+            );
+        }
+    }
+
+    Some(page(name, &body))
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        r##"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; }}
+table {{ border-collapse: collapse; width: 100%; }}
+td, th {{ border: 1px solid #ccc; padding: 4px 8px; text-align: left; vertical-align: top; }}
+details, .leaf {{ margin-left: 1.5em; }}
+</style>
+</head>
+<body>
+{body}
+<script>
+function filter() {{
+  const text = document.getElementById("filter").value.toLowerCase();
+  const kind = document.getElementById("kind").value;
+  for (const row of document.querySelectorAll("#findings tr[data-kind]")) {{
+    const shown = (!kind || row.dataset.kind === kind) && row.textContent.toLowerCase().includes(text);
+    row.style.display = shown ? "" : "none";
+  }}
+}}
+let generation = null;
+setInterval(async () => {{
+  const current = await fetch("/api/generation").then(r => r.text()).catch(() => generation);
+  if (generation !== null && current !== generation) location.reload();
+  generation = current;
+}}, {poll});
+</script>
+</body>
+</html>
+"##,
+        title = escape(title),
+        body = body,
+        poll = POLL.as_millis()
+    )
+}
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_sees_deletions_renames_and_cargo_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("target/debug")).unwrap();
+        std::fs::write(root.join("Cargo.toml"), "").unwrap();
+        std::fs::write(root.join("src/lib.rs"), "").unwrap();
+        std::fs::write(root.join("src/old.rs"), "").unwrap();
+        std::fs::write(root.join("target/debug/build.rs"), "").unwrap();
+        std::fs::write(root.join("README.md"), "").unwrap();
+        let paths = |fingerprint: &Fingerprint| -> Vec<PathBuf> {
+            fingerprint
+                .iter()
+                .filter_map(|(path, _)| Some(path.strip_prefix(root).ok()?.to_path_buf()))
+                .collect()
+        };
+
+        let original = fingerprint(root);
+        assert_eq!(
+            paths(&original),
+            ["Cargo.toml", "src/lib.rs", "src/old.rs"].map(PathBuf::from)
+        );
+
+        std::fs::rename(root.join("src/old.rs"), root.join("src/new.rs")).unwrap();
+        let renamed = fingerprint(root);
+        assert_ne!(renamed, original);
+
+        std::fs::remove_file(root.join("src/new.rs")).unwrap();
+        let deleted = fingerprint(root);
+        assert_ne!(deleted, renamed);
+
+        std::fs::create_dir(root.join(".cargo")).unwrap();
+        std::fs::write(root.join(".cargo/config.toml"), "").unwrap();
+        assert!(paths(&fingerprint(root)).contains(&PathBuf::from(".cargo/config.toml")));
+    }
+}
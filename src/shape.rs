@@ -0,0 +1,72 @@
+use crate::project::Project;
+use cargo_metadata::PackageId;
+use std::collections::{HashMap, VecDeque};
+
+/// The structure of the resolved dependency graph, for `--summary`.
+pub struct Shape {
+    /// The longest of the shortest paths from a workspace member to a package, in edges.
+    pub depth: usize,
+    /// Dependency edges per package.
+    pub fan_out: f64,
+    /// The most packages at the same distance from the members.
+    pub widest_layer: usize,
+    pub proc_macros: usize,
+    pub build_scripts: usize,
+}
+
+impl Shape {
+    pub fn of(project: &Project) -> Shape {
+        let nodes = project
+            .metadata
+            .resolve
+            .as_ref()
+            .map(|resolve| resolve.nodes.as_slice())
+            .unwrap_or_default();
+        let edges: HashMap<&PackageId, Vec<&PackageId>> = nodes
+            .iter()
+            .map(|node| (&node.id, node.deps.iter().map(|d| &d.pkg).collect()))
+            .collect();
+
+        // Breadth-first from the members, so each package lands in the layer of its
+        // shortest path.
+        let mut layer: HashMap<&PackageId, usize> = HashMap::new();
+        let mut pending: VecDeque<&PackageId> = VecDeque::new();
+        for member in &project.metadata.workspace_members {
+            layer.insert(member, 0);
+            pending.push_back(member);
+        }
+        while let Some(id) = pending.pop_front() {
+            let next_layer = layer[id] + 1;
+            for next in edges.get(id).into_iter().flatten() {
+                if !layer.contains_key(next) {
+                    layer.insert(next, next_layer);
+                    pending.push_back(next);
+                }
+            }
+        }
+        let mut widths: HashMap<usize, usize> = HashMap::new();
+        for depth in layer.values() {
+            *widths.entry(*depth).or_default() += 1;
+        }
+
+        let has_target = |kind: &str| {
+            nodes
+                .iter()
+                .filter_map(|node| project.package(&node.id))
+                .filter(|pkg| pkg.targets.iter().any(|t| t.kind.iter().any(|k| k == kind)))
+                .count()
+        };
+        let edge_count: usize = edges.values().map(Vec::len).sum();
+        Shape {
+            depth: layer.values().copied().max().unwrap_or(0),
+            fan_out: if nodes.is_empty() {
+                0.0
+            } else {
+                edge_count as f64 / nodes.len() as f64
+            },
+            widest_layer: widths.values().copied().max().unwrap_or(0),
+            proc_macros: has_target("proc-macro"),
+            build_scripts: has_target("custom-build"),
+        }
+    }
+}
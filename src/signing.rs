@@ -0,0 +1,278 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use ed25519_dalek::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::process::Command;
+
+/// `--sign keyless`: sign with a short-lived sigstore certificate through `cosign`.
+pub const KEYLESS: &str = "keyless";
+
+/// A [DSSE](https://github.com/secure-systems-lab/dsse) envelope, the format in-toto
+/// attestations are signed in: the report as its payload, signed over the pre-auth
+/// encoding of payload type and payload.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Envelope {
+    pub payload_type: String,
+    /// The report, base64-encoded.
+    pub payload: String,
+    pub signatures: Vec<EnvelopeSignature>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EnvelopeSignature {
+    /// The SHA-256 of the public key, for keys; `sigstore` for keyless signatures.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub keyid: String,
+    pub sig: String,
+    /// For keyless signatures, the sigstore bundle `cosign` produced: the signing
+    /// certificate and the transparency log entry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle: Option<serde_json::Value>,
+}
+
+/// Signs `report` with the Ed25519 private key at `key` (PKCS#8 PEM, as `openssl genpkey
+/// -algorithm ed25519` writes it), or keyless through sigstore when `key` is `keyless`,
+/// and returns the envelope as JSON.
+pub fn sign(
+    report: &str,
+    payload_type: &str,
+    key: &Path,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let message = pae(payload_type, report.as_bytes());
+    let signature = if key == Path::new(KEYLESS) {
+        sign_keyless(&message)?
+    } else {
+        let pem = std::fs::read_to_string(key)
+            .map_err(|err| format!("could not read signing key {}: {}", key.display(), err))?;
+        let signing_key = SigningKey::from_pkcs8_pem(&pem)
+            .map_err(|err| format!("{} is not an Ed25519 private key: {}", key.display(), err))?;
+        EnvelopeSignature {
+            keyid: keyid(&signing_key.verifying_key()),
+            sig: BASE64.encode(signing_key.sign(&message).to_bytes()),
+            bundle: None,
+        }
+    };
+    let envelope = Envelope {
+        payload_type: payload_type.to_string(),
+        payload: BASE64.encode(report),
+        signatures: vec![signature],
+    };
+    Ok(serde_json::to_string_pretty(&envelope)?)
+}
+
+/// How `verify` checks an envelope's signatures.
+pub enum Trust<'a> {
+    /// The Ed25519 public key at this path (SPKI PEM).
+    Key(&'a Path),
+    /// A sigstore certificate issued to this identity by this OIDC issuer.
+    Keyless { identity: &'a str, issuer: &'a str },
+}
+
+/// `verify`: checks that the envelope at `path` carries a valid signature by `trust` and
+/// returns its payload, the report as it was signed.
+pub fn verify(path: &Path, trust: Trust) -> Result<String, Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(path)?;
+    let envelope: Envelope = serde_json::from_str(&content)
+        .map_err(|err| format!("{} is not a signed report: {}", path.display(), err))?;
+    let payload = BASE64.decode(&envelope.payload)?;
+    let message = pae(&envelope.payload_type, &payload);
+
+    let verified = match trust {
+        Trust::Key(key) => {
+            let pem = std::fs::read_to_string(key)
+                .map_err(|err| format!("could not read public key {}: {}", key.display(), err))?;
+            let verifying_key = VerifyingKey::from_public_key_pem(&pem).map_err(|err| {
+                format!("{} is not an Ed25519 public key: {}", key.display(), err)
+            })?;
+            let keyid = keyid(&verifying_key);
+            envelope
+                .signatures
+                .iter()
+                .filter(|s| s.keyid.is_empty() || s.keyid == keyid)
+                .any(|s| {
+                    let Ok(Ok(signature)) = BASE64
+                        .decode(&s.sig)
+                        .map(|bytes| Signature::from_slice(&bytes))
+                    else {
+                        return false;
+                    };
+                    verifying_key.verify(&message, &signature).is_ok()
+                })
+        }
+        Trust::Keyless { identity, issuer } => {
+            let mut verified = false;
+            for signature in &envelope.signatures {
+                let Some(bundle) = &signature.bundle else {
+                    continue;
+                };
+                if verify_keyless(&message, bundle, identity, issuer)? {
+                    verified = true;
+                    break;
+                }
+            }
+            verified
+        }
+    };
+    if !verified {
+        return Err(format!(
+            "{} has no valid signature by the given signer",
+            path.display()
+        )
+        .into());
+    }
+    Ok(String::from_utf8(payload)?)
+}
+
+/// DSSE's pre-authentication encoding, what is actually signed: binding the payload type
+/// to the payload keeps a signature from being replayed under another type.
+fn pae(payload_type: &str, payload: &[u8]) -> Vec<u8> {
+    let mut message = format!(
+        "DSSEv1 {} {} {} ",
+        payload_type.len(),
+        payload_type,
+        payload.len()
+    )
+    .into_bytes();
+    message.extend_from_slice(payload);
+    message
+}
+
+fn keyid(key: &VerifyingKey) -> String {
+    Sha256::digest(key.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Signs `message` with `cosign sign-blob`, which gets a certificate for the signer's
+/// OIDC identity from Fulcio and records the signature in Rekor.
+fn sign_keyless(message: &[u8]) -> Result<EnvelopeSignature, Box<dyn std::error::Error>> {
+    let dir = tempfile::Builder::new()
+        .prefix("cargo-dep-analysis-")
+        .tempdir()?;
+    let blob = dir.path().join("message");
+    let bundle = dir.path().join("bundle.json");
+    std::fs::write(&blob, message)?;
+    let status = Command::new("cosign")
+        .args(["sign-blob", "--yes", "--new-bundle-format", "--bundle"])
+        .arg(&bundle)
+        .arg(&blob)
+        // cosign prints the signature on stdout; the report goes there.
+        .stdout(std::process::Stdio::null())
+        .status()
+        .map_err(|err| format!("keyless signing needs `cosign` on the PATH: {}", err))?;
+    if !status.success() {
+        return Err("`cosign sign-blob` failed".into());
+    }
+    let bundle: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&bundle)?)?;
+    let sig = bundle
+        .pointer("/messageSignature/signature")
+        .and_then(|s| s.as_str())
+        .ok_or("cosign's bundle has no message signature")?
+        .to_string();
+    Ok(EnvelopeSignature {
+        keyid: "sigstore".to_string(),
+        sig,
+        bundle: Some(bundle),
+    })
+}
+
+/// Checks a keyless signature with `cosign verify-blob`: the certificate must have been
+/// issued to `identity` by `issuer` and the signature be in the transparency log.
+fn verify_keyless(
+    message: &[u8],
+    bundle: &serde_json::Value,
+    identity: &str,
+    issuer: &str,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let dir = tempfile::Builder::new()
+        .prefix("cargo-dep-analysis-")
+        .tempdir()?;
+    let blob = dir.path().join("message");
+    let bundle_path = dir.path().join("bundle.json");
+    std::fs::write(&blob, message)?;
+    std::fs::write(&bundle_path, serde_json::to_string(bundle)?)?;
+    let output = Command::new("cosign")
+        .args(["verify-blob", "--new-bundle-format", "--bundle"])
+        .arg(&bundle_path)
+        .args(["--certificate-identity", identity])
+        .args(["--certificate-oidc-issuer", issuer])
+        .arg(&blob)
+        .output()
+        .map_err(|err| {
+            format!(
+                "verifying keyless signatures needs `cosign` on the PATH: {}",
+                err
+            )
+        })?;
+    if !output.status.success() {
+        tracing::debug!(
+            "cosign rejected a signature: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::pkcs8::spki::der::pem::LineEnding;
+    use ed25519_dalek::pkcs8::{EncodePrivateKey, EncodePublicKey};
+
+    const REPORT: &str = "{\"findings\":[]}";
+    const PAYLOAD_TYPE: &str = "application/json";
+
+    /// Writes the PEM files for a key made from `seed` and returns their paths.
+    fn keypair(dir: &Path, seed: u8) -> (std::path::PathBuf, std::path::PathBuf) {
+        let signing_key = SigningKey::from_bytes(&[seed; 32]);
+        let private = dir.join(format!("key-{}.pem", seed));
+        let public = dir.join(format!("key-{}.pub.pem", seed));
+        let pem = signing_key.to_pkcs8_pem(LineEnding::LF).unwrap();
+        std::fs::write(&private, pem.as_bytes()).unwrap();
+        let pem = signing_key
+            .verifying_key()
+            .to_public_key_pem(LineEnding::LF)
+            .unwrap();
+        std::fs::write(&public, pem).unwrap();
+        (private, public)
+    }
+
+    #[test]
+    fn signed_reports_verify_with_the_public_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let (private, public) = keypair(dir.path(), 1);
+        let envelope = dir.path().join("report.dsse.json");
+        std::fs::write(&envelope, sign(REPORT, PAYLOAD_TYPE, &private).unwrap()).unwrap();
+
+        assert_eq!(verify(&envelope, Trust::Key(&public)).unwrap(), REPORT);
+    }
+
+    #[test]
+    fn tampered_payloads_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let (private, public) = keypair(dir.path(), 1);
+        let signed = sign(REPORT, PAYLOAD_TYPE, &private).unwrap();
+        let mut envelope: Envelope = serde_json::from_str(&signed).unwrap();
+        envelope.payload = BASE64.encode("{\"findings\":[{}]}");
+        let path = dir.path().join("report.dsse.json");
+        std::fs::write(&path, serde_json::to_string(&envelope).unwrap()).unwrap();
+
+        assert!(verify(&path, Trust::Key(&public)).is_err());
+    }
+
+    #[test]
+    fn signatures_by_another_key_are_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let (private, _) = keypair(dir.path(), 1);
+        let (_, other) = keypair(dir.path(), 2);
+        let envelope = dir.path().join("report.dsse.json");
+        std::fs::write(&envelope, sign(REPORT, PAYLOAD_TYPE, &private).unwrap()).unwrap();
+
+        assert!(verify(&envelope, Trust::Key(&other)).is_err());
+    }
+}
@@ -0,0 +1,66 @@
+use crate::project::Project;
+use crate::report::{Finding, Kind};
+use std::collections::BTreeMap;
+
+/// Reports registry/git dependencies that workspace members request with different
+/// version requirements, and whether that leaves several copies in the build.
+pub fn analyze(project: &Project) -> Vec<Finding> {
+    let mut requests: BTreeMap<&str, Vec<(&str, String)>> = BTreeMap::new();
+    for member in project.members() {
+        for dep in member.dependencies.iter().filter(|d| d.path.is_none()) {
+            let request = (member.name.as_str(), dep.req.to_string());
+            let entry = requests.entry(&dep.name).or_default();
+            if !entry.contains(&request) {
+                entry.push(request);
+            }
+        }
+    }
+
+    let mut findings = Vec::new();
+    for (name, requests) in requests {
+        let mut reqs: Vec<&str> = requests.iter().map(|(_, req)| req.as_str()).collect();
+        reqs.sort();
+        reqs.dedup();
+        if reqs.len() < 2 {
+            continue;
+        }
+
+        let mut finding = Finding::new(Kind::VersionSkew, name).note(format!(
+            "Members disagree on the version requirement of {}:",
+            name
+        ));
+        for req in &reqs {
+            let members: Vec<&str> = requests
+                .iter()
+                .filter(|(_, r)| r == req)
+                .map(|(member, _)| *member)
+                .collect();
+            finding = finding.note(format!("  {}: {}", req, members.join(", ")));
+        }
+
+        let mut versions: Vec<String> = project
+            .metadata
+            .packages
+            .iter()
+            .filter(|pkg| pkg.name == name)
+            .map(|pkg| pkg.version.to_string())
+            .collect();
+        versions.sort();
+        versions.dedup();
+        finding = if versions.len() > 1 {
+            finding.note(format!(
+                "The skew compiles {} copies: {}",
+                versions.len(),
+                versions.join(", ")
+            ))
+        } else {
+            finding.note(format!(
+                "All requirements resolve to {}; align them to keep it that way",
+                versions.join(", ")
+            ))
+        };
+        findings.push(finding);
+    }
+
+    findings
+}
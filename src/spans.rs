@@ -0,0 +1,149 @@
+use crate::cli::Args;
+use crate::project::Project;
+use crate::report::{Finding, Kind, Span};
+use regex::Regex;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+/// The kinds of finding about where a crate is used, which list its usage sites.
+const USAGE_KINDS: [Kind; 6] = [
+    Kind::UndeclaredDependency,
+    Kind::FeatureGated,
+    Kind::PlatformOnly,
+    Kind::TestOnly,
+    Kind::ExampleOnly,
+    Kind::BenchOnly,
+];
+
+/// Fills in the `declaration` and `usages` spans of findings, reading each file and
+/// scanning each member's sources once however many findings point into them.
+#[derive(Default)]
+pub struct Locator {
+    files: HashMap<PathBuf, Option<String>>,
+    /// Usage sites per member, by the crate name as written in the sources.
+    usages: HashMap<String, HashMap<String, Vec<Span>>>,
+}
+
+impl Locator {
+    pub fn annotate(&mut self, args: &Args, project: &Project, finding: &mut Finding) {
+        if let Some(file) = &finding.file {
+            let path = project.root().join(file);
+            if let Some(content) = self.read(&path) {
+                finding.declaration =
+                    declaration(content, &path, &finding.krate).map(|(line, column)| Span {
+                        file: file.clone(),
+                        line,
+                        column,
+                    });
+            }
+        }
+        if !USAGE_KINDS.contains(&finding.kind) {
+            return;
+        }
+        let Some(member) = &finding.member else {
+            return;
+        };
+        if !self.usages.contains_key(member) {
+            let sites = project
+                .members()
+                .find(|pkg| pkg.name == *member)
+                .map(|pkg| usage_sites(args, project, pkg))
+                .unwrap_or_default();
+            self.usages.insert(member.clone(), sites);
+        }
+        finding.usages = self.usages[member]
+            .get(&finding.krate.replace('-', "_"))
+            .cloned()
+            .unwrap_or_default();
+    }
+
+    fn read(&mut self, path: &Path) -> Option<&str> {
+        self.files
+            .entry(path.to_path_buf())
+            .or_insert_with(|| std::fs::read_to_string(path).ok())
+            .as_deref()
+    }
+}
+
+/// The 1-based line and column at which `path` declares `krate`: the key of its manifest
+/// entry, the `name` of its lockfile entry, or an `extern crate` in a source file.
+pub fn declaration(content: &str, path: &Path, krate: &str) -> Option<(usize, usize)> {
+    let column = |line: &str, token: &str| line.find(token).map(|i| line[..i].chars().count() + 1);
+    let extension = path.extension().and_then(|e| e.to_str());
+    if path.file_name().is_some_and(|name| name == "Cargo.lock") {
+        let entry = format!("name = \"{}\"", krate);
+        return content.lines().enumerate().find_map(|(index, line)| {
+            (line.trim() == entry).then(|| Some((index + 1, column(line, &entry)?)))?
+        });
+    }
+    if extension == Some("toml") {
+        let line = crate::blame::declaration_line(content, krate)?;
+        let text = content.lines().nth(line - 1)?;
+        return Some((line, column(text, krate)?));
+    }
+    if extension == Some("rs") {
+        let pattern = Regex::new(&format!(
+            r"extern\s+crate\s+{}\b",
+            regex::escape(&krate.replace('-', "_"))
+        ))
+        .ok()?;
+        return content.lines().enumerate().find_map(|(index, line)| {
+            let found = pattern.find(line)?;
+            Some((index + 1, line[..found.start()].chars().count() + 1))
+        });
+    }
+    None
+}
+
+/// Where the sources of `package` (its library, binaries, tests, examples and benches)
+/// refer to a crate by path, macro, `use` or `extern crate`, by crate name.
+pub fn usage_sites(
+    args: &Args,
+    project: &Project,
+    package: &cargo_metadata::Package,
+) -> HashMap<String, Vec<Span>> {
+    let mut files: BTreeSet<PathBuf> = crate::source_files(args, project, package)
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+    for target in &package.targets {
+        if target
+            .kind
+            .iter()
+            .any(|kind| matches!(kind.as_str(), "test" | "example" | "bench"))
+        {
+            files.extend(crate::targets::files(target));
+        }
+    }
+    let ident = crate::ident::PATTERN;
+    let pattern = Regex::new(&format!(
+        r"(?:\buse\s+|\bextern\s+crate\s+|(?:^|[^\w:])(?:::)?)({ident})\s*(?:::|!)|\buse\s+({ident})\s*;|\bextern\s+crate\s+({ident})"
+    ))
+    .expect("usage pattern is valid");
+    let mut sites: HashMap<String, Vec<Span>> = HashMap::new();
+    for path in files {
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let file = project.display_path(&path);
+        for (index, line) in content.lines().enumerate() {
+            if line.trim_start().starts_with("//") {
+                continue;
+            }
+            for captures in pattern.captures_iter(line) {
+                let Some(name) = captures.get(1).or(captures.get(2)).or(captures.get(3)) else {
+                    continue;
+                };
+                sites
+                    .entry(crate::ident::unraw(name.as_str()).to_string())
+                    .or_default()
+                    .push(Span {
+                        file: file.clone(),
+                        line: index + 1,
+                        column: line[..name.start()].chars().count() + 1,
+                    });
+            }
+        }
+    }
+    sites
+}
@@ -0,0 +1,67 @@
+use crate::project::Project;
+use cargo_dep_analysis::graph::DepGraph;
+use cargo_metadata::semver::Version;
+use std::collections::BTreeMap;
+
+/// How much of the graph has promised a stable API: crates at 1.0 or later may only
+/// break it in a major release, while for 0.x crates every minor release (and for 0.0.x
+/// every release) counts as breaking.
+pub struct Stability {
+    /// External crates at 1.0 or later.
+    pub stable: usize,
+    /// External crates below 1.0.
+    pub unstable: usize,
+    /// The direct dependencies below 1.0, those most likely to break on update first.
+    pub unstable_direct: Vec<(String, Version)>,
+}
+
+impl Stability {
+    pub fn of(project: &Project) -> Stability {
+        let Ok(graph) = DepGraph::from_metadata(&project.metadata) else {
+            return Stability {
+                stable: 0,
+                unstable: 0,
+                unstable_direct: Vec::new(),
+            };
+        };
+        let external = || graph.graph.node_weights().filter(|node| node.external);
+        let unstable = external().filter(|node| node.version.major == 0).count();
+
+        let mut direct = BTreeMap::new();
+        for member in graph.members() {
+            for (dep, _) in graph.dependencies(member) {
+                let node = &graph.graph[dep];
+                if node.external && node.version.major == 0 {
+                    direct.insert((node.name.clone(), node.version.to_string()), node);
+                }
+            }
+        }
+        let mut unstable_direct: Vec<(String, Version)> = direct
+            .into_values()
+            .map(|node| (node.name.clone(), node.version.clone()))
+            .collect();
+        // 0.0.x crates break with any release; among the rest, the earlier in their 0.x
+        // series the more breaking releases are likely to come.
+        unstable_direct.sort_by_key(|(name, version)| {
+            (
+                version.minor != 0,
+                version.minor,
+                version.patch,
+                name.clone(),
+            )
+        });
+
+        Stability {
+            stable: external().count() - unstable,
+            unstable,
+            unstable_direct,
+        }
+    }
+
+    /// The share of external crates at 1.0 or later, in percent.
+    pub fn percent_stable(&self) -> usize {
+        (self.stable * 100)
+            .checked_div(self.stable + self.unstable)
+            .unwrap_or(100)
+    }
+}
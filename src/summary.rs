@@ -0,0 +1,106 @@
+use crate::history::Snapshot;
+use crate::impact;
+use crate::project::Project;
+use crate::report::{Finding, Kind, Severity};
+use crate::shape::Shape;
+use crate::stability::Stability;
+use crate::xtask;
+use cargo_metadata::{Package, PackageId};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// `--summary`: the headline numbers only, for quick health checks. Build time is
+/// estimated by source size, as in the impact notes.
+pub fn print(project: &Project, findings: &[Finding]) {
+    let snapshot = Snapshot::take(project, findings);
+    let members = project.members().count();
+    let tooling = xtask::members(project);
+    let direct = |tooling_members: bool| -> BTreeSet<&str> {
+        project
+            .members()
+            .filter(|member| tooling.contains(&member.name) == tooling_members)
+            .flat_map(|member| &member.dependencies)
+            .filter(|dep| dep.path.is_none())
+            .map(|dep| dep.name.as_str())
+            .collect()
+    };
+    // Dependencies only the build tooling has; the product's count as direct.
+    let tooling_deps = direct(true).difference(&direct(false)).count();
+    let count = |kind: Kind| findings.iter().filter(|f| f.kind == kind).count();
+    let severity = |severity: Severity| findings.iter().filter(|f| f.severity == severity).count();
+    // What the unused dependencies alone bring into the build, each crate counted once.
+    let mut unused_crates: BTreeMap<&PackageId, &Package> = BTreeMap::new();
+    for finding in findings.iter().filter(|f| f.kind == Kind::Unused) {
+        let Some(member) = project
+            .members()
+            .find(|member| Some(&member.name) == finding.member.as_ref())
+        else {
+            continue;
+        };
+        for pkg in impact::exclusive_crates(project, member, &finding.krate) {
+            unused_crates.insert(&pkg.id, pkg);
+        }
+    }
+    let unused_kib = impact::source_bytes(&unused_crates.into_values().collect::<Vec<_>>()) / 1024;
+
+    println!("\nDependency Summary:");
+    println!("==========================");
+    for (label, value) in [
+        ("Direct dependencies", snapshot.direct_deps),
+        (
+            "Transitive dependencies",
+            snapshot
+                .total_crates
+                .saturating_sub(members + snapshot.direct_deps + tooling_deps),
+        ),
+        ("Build tooling (xtask)", tooling_deps),
+        ("Potentially unused", snapshot.unused),
+        ("Duplicate crates", snapshot.duplicates),
+        ("Yanked versions", count(Kind::YankedVersion)),
+        ("Security advisories", count(Kind::Advisory)),
+        ("Errors", severity(Severity::Error)),
+        ("Warnings", severity(Severity::Warning)),
+        (
+            "Source to build (KiB)",
+            snapshot.source_kib.unwrap_or_default(),
+        ),
+        ("Unused deps' share (KiB)", unused_kib as usize),
+    ] {
+        println!("{:<24} {:>6}", label, value);
+    }
+
+    let shape = Shape::of(project);
+    println!("\nGraph Shape:");
+    println!("==========================");
+    for (label, value) in [
+        ("Depth", shape.depth.to_string()),
+        ("Average fan-out", format!("{:.2}", shape.fan_out)),
+        ("Widest layer", shape.widest_layer.to_string()),
+        ("Proc-macro crates", shape.proc_macros.to_string()),
+        ("Build scripts", shape.build_scripts.to_string()),
+    ] {
+        println!("{:<24} {:>6}", label, value);
+    }
+
+    let stability = Stability::of(project);
+    println!("\nStability:");
+    println!("==========================");
+    for (label, value) in [
+        (
+            "Crates at 1.0 or later",
+            format!("{}%", stability.percent_stable()),
+        ),
+        ("Pre-1.0 crates", stability.unstable.to_string()),
+        (
+            "Pre-1.0 direct deps",
+            stability.unstable_direct.len().to_string(),
+        ),
+    ] {
+        println!("{:<24} {:>6}", label, value);
+    }
+    if !stability.unstable_direct.is_empty() {
+        println!("Most likely to break on update:");
+        for (name, version) in stability.unstable_direct.iter().take(5) {
+            println!("  {} {}", name, version);
+        }
+    }
+}
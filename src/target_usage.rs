@@ -0,0 +1,83 @@
+use crate::cli::Args;
+use crate::project::Project;
+use crate::targets;
+use cargo_metadata::Package;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// `targets`: for each dependency of the analyzed packages, the targets whose sources use
+/// it (the library, each binary, test, example and bench), so dependencies serving a
+/// single binary stand out.
+pub fn run(args: &Args) -> Result<(), Box<dyn std::error::Error>> {
+    let project = Project::load(args.manifest_path.as_deref())?;
+    for package in crate::selected_packages(args, &project)? {
+        let usage = by_target(args, package)?;
+        let bins = package
+            .targets
+            .iter()
+            .filter(|t| t.kind.iter().any(|k| k == "bin"))
+            .count();
+        println!("\nDependency usage by target in {}:", package.name);
+        let width = usage.keys().map(|k| k.chars().count()).max().unwrap_or(0);
+        for (krate, targets) in &usage {
+            let mut line = if targets.is_empty() {
+                "-".to_string()
+            } else {
+                targets.iter().cloned().collect::<Vec<_>>().join(", ")
+            };
+            if bins > 1 && targets.len() == 1 && targets.iter().all(|t| t.starts_with("bin ")) {
+                line.push_str(" (only this binary)");
+            }
+            println!("  {:<width$}  {}", krate, line, width = width);
+        }
+    }
+    Ok(())
+}
+
+/// Every dependency of `package`, mapped to the targets using it, labeled like `lib`,
+/// `bin cli` or `test it`. Only the targets selected with --lib, --bins, ... are scanned.
+pub fn by_target(
+    args: &Args,
+    package: &Package,
+) -> Result<BTreeMap<String, BTreeSet<String>>, Box<dyn std::error::Error>> {
+    let selection = args.targets();
+    let name_mappings: HashMap<String, String> = package
+        .dependencies
+        .iter()
+        .map(|dep| (dep.name.replace('-', "_"), dep.name.clone()))
+        .collect();
+    let mut usage: BTreeMap<String, BTreeSet<String>> = package
+        .dependencies
+        .iter()
+        .map(|dep| (dep.name.clone(), BTreeSet::new()))
+        .collect();
+    for target in &package.targets {
+        let label = if targets::is_lib(target) && selection.lib {
+            "lib".to_string()
+        } else {
+            let Some(kind) = target.kind.iter().find(|kind| match kind.as_str() {
+                "bin" => selection.bins,
+                "test" => selection.tests,
+                "bench" => selection.benches,
+                "example" => selection.examples,
+                _ => false,
+            }) else {
+                continue;
+            };
+            format!("{} {}", kind, target.name)
+        };
+        let mut referenced = HashSet::new();
+        for file in targets::files(target) {
+            crate::scan_for_usage(
+                &std::fs::read_to_string(&file)?,
+                &mut referenced,
+                &name_mappings,
+            );
+        }
+        for krate in referenced {
+            if let Some(targets) = usage.get_mut(&krate) {
+                targets.insert(label.clone());
+            }
+        }
+    }
+    Ok(usage)
+}
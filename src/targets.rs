@@ -0,0 +1,60 @@
+use crate::cfg;
+use cargo_metadata::Target;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// The source files of a target: its entry point and the out-of-line modules it declares,
+/// or the whole directory when the entry point is a `main.rs` of its own (as in
+/// `examples/<name>/main.rs` or `benches/<name>/main.rs`, unlike `src/main.rs`).
+pub fn files(target: &Target) -> Vec<PathBuf> {
+    let entry = target.src_path.as_std_path();
+    match (entry.file_name(), entry.parent()) {
+        (Some(name), Some(dir)) if name == "main.rs" && !dir.ends_with("src") => WalkDir::new(dir)
+            .into_iter()
+            .filter_map(Result::ok)
+            .map(|entry| entry.into_path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "rs"))
+            .collect(),
+        _ => with_modules(entry),
+    }
+}
+
+/// Whether a target is a library of any crate type (`lib`, `proc-macro`, `cdylib`, ...).
+pub fn is_lib(target: &Target) -> bool {
+    target.kind.iter().any(|kind| {
+        !matches!(
+            kind.as_str(),
+            "bin" | "test" | "bench" | "example" | "custom-build"
+        )
+    })
+}
+
+/// A crate root and the modules it declares with `mod name;`, recursively: `mod common;`
+/// in `tests/it.rs` reads `tests/common.rs` or `tests/common/mod.rs`.
+fn with_modules(entry: &Path) -> Vec<PathBuf> {
+    let mut files = vec![entry.to_path_buf()];
+    let mut next = 0;
+    while next < files.len() {
+        let file = files[next].clone();
+        next += 1;
+        let Ok(content) = std::fs::read_to_string(&file) else {
+            continue;
+        };
+        for name in cfg::modules(&content) {
+            let candidates = match file.parent() {
+                // Modules of a crate root live next to it, whatever its name.
+                Some(dir) if file == entry => [
+                    dir.join(format!("{}.rs", name)),
+                    dir.join(&name).join("mod.rs"),
+                ],
+                _ => cfg::module_files(&file, &name),
+            };
+            for candidate in candidates {
+                if candidate.is_file() && !files.contains(&candidate) {
+                    files.push(candidate);
+                }
+            }
+        }
+    }
+    files
+}
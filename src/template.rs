@@ -0,0 +1,52 @@
+use crate::history::Snapshot;
+use crate::project::Project;
+use crate::report::Finding;
+use handlebars::Handlebars;
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// `--template <file.hbs>`: renders the report with a user-supplied Handlebars template.
+/// Output is HTML-escaped only for `*.html.hbs` templates.
+pub fn render(
+    project: &Project,
+    findings: &[Finding],
+    template: &Path,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let source = std::fs::read_to_string(template)
+        .map_err(|err| format!("failed to read {}: {}", template.display(), err))?;
+    let mut handlebars = Handlebars::new();
+    if !template.to_string_lossy().ends_with(".html.hbs") {
+        handlebars.register_escape_fn(handlebars::no_escape);
+    }
+    handlebars.register_template_string("report", source)?;
+    Ok(handlebars.render("report", &model(project, findings))?)
+}
+
+/// Everything a template can use; documented in the README.
+fn model(project: &Project, findings: &[Finding]) -> Value {
+    let package = |pkg: &cargo_metadata::Package| {
+        json!({
+            "name": pkg.name,
+            "version": pkg.version.to_string(),
+            "source": pkg.source.as_ref().map(|s| s.repr.clone()),
+        })
+    };
+    json!({
+        "workspace_root": project.root().display().to_string(),
+        "members": project.members().map(package).collect::<Vec<_>>(),
+        "packages": project.metadata.packages.iter().map(package).collect::<Vec<_>>(),
+        "metrics": Snapshot::take(project, findings),
+        "findings": findings
+            .iter()
+            .map(|finding| json!({
+                "kind": finding.kind,
+                "label": finding.kind.label(),
+                "crate": finding.krate,
+                "notes": finding.notes,
+                "file": finding.file,
+                "declaration": finding.declaration,
+                "usages": finding.usages,
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
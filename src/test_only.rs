@@ -0,0 +1,90 @@
+use crate::fix::{self, ManifestEdit};
+use crate::project::Project;
+use crate::report::{Finding, Kind};
+
+/// `--fix` for test-only dependencies: moves each [dependencies] entry to
+/// [dev-dependencies], keeping its formatting. An entry already present there is kept and
+/// the normal one dropped.
+pub fn fix(
+    project: &Project,
+    findings: &[Finding],
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut changes = Vec::new();
+    for finding in findings.iter().filter(|f| f.kind == Kind::TestOnly) {
+        let Some(package) = project
+            .members()
+            .find(|pkg| Some(&pkg.name) == finding.member.as_ref())
+        else {
+            continue;
+        };
+        let mut manifest = ManifestEdit::open(package.manifest_path.as_std_path())?;
+        let dependencies = manifest
+            .dependency_table(None, "dependencies")
+            .ok_or("[dependencies] is not a table")?;
+        let Some(key) = fix::entry_key(dependencies, &finding.krate) else {
+            continue;
+        };
+        let item = dependencies.remove(&key).expect("the key was just found");
+        let dev = manifest
+            .dependency_table(None, "dev-dependencies")
+            .ok_or("[dev-dependencies] is not a table")?;
+        if !dev.contains_key(&key) {
+            dev.insert(&key, item);
+        }
+        manifest.save()?;
+        changes.push(format!(
+            "Moved {} to [dev-dependencies] in {}",
+            key,
+            project.display_path(package.manifest_path.as_std_path())
+        ));
+    }
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::fixture;
+
+    #[test]
+    fn fix_moves_the_entry_to_dev_dependencies() {
+        let (dir, project) = fixture(&[
+            (
+                "Cargo.toml",
+                r#"[package]
+name = "app"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+util = { path = "util" } # only the tests use it
+"#,
+            ),
+            ("src/lib.rs", ""),
+            (
+                "util/Cargo.toml",
+                "[package]\nname = \"util\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+            ),
+            ("util/src/lib.rs", ""),
+        ]);
+        let app = project.members().find(|pkg| pkg.name == "app").unwrap();
+        let findings = [Finding::new(Kind::TestOnly, "util").member(&project, app)];
+
+        let changes = fix(&project, &findings).unwrap();
+        assert_eq!(changes, ["Moved util to [dev-dependencies] in Cargo.toml"]);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("Cargo.toml")).unwrap(),
+            r#"[package]
+name = "app"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+
+[dev-dependencies]
+util = { path = "util" } # only the tests use it
+"#
+        );
+        assert!(fix(&project, &findings).unwrap().is_empty());
+    }
+}
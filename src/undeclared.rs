@@ -0,0 +1,382 @@
+use crate::cli::Args;
+use crate::fix::ManifestEdit;
+use crate::project::Project;
+use crate::report::{Finding, Kind, Span};
+use cargo_metadata::{Node, Package, PackageId};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+
+/// The start of the note giving the declaration to add, which `--fix` reads back.
+const DECLARE_IN: &str = "Declare it in [";
+
+/// Names paths start with that never name a dependency.
+const RESERVED: [&str; 8] = [
+    "crate",
+    "self",
+    "super",
+    "Self",
+    "std",
+    "core",
+    "alloc",
+    "proc_macro",
+];
+
+/// Flags crates the members' sources refer to without declaring them, which only resolve
+/// because they are somewhere in the graph: a dependency of a dependency, or another
+/// member. The code builds only as long as whatever declares them keeps doing so (and
+/// code behind a feature or a platform nobody builds doesn't build at all). Each finding
+/// lists where the crate is referenced and the declaration to add.
+pub fn analyze(args: &Args, project: &Project) -> Vec<Finding> {
+    let nodes: BTreeMap<&PackageId, &Node> = project
+        .metadata
+        .resolve
+        .iter()
+        .flat_map(|resolve| &resolve.nodes)
+        .map(|node| (&node.id, node))
+        .collect();
+    // Every package in the graph with a library, by the name code refers to it with.
+    let mut libraries: HashMap<String, Vec<&Package>> = HashMap::new();
+    for id in nodes.keys() {
+        let Some(pkg) = project.package(id) else {
+            continue;
+        };
+        if let Some(lib) = pkg.targets.iter().find(|t| crate::targets::is_lib(t)) {
+            libraries
+                .entry(lib.name.replace('-', "_"))
+                .or_default()
+                .push(pkg);
+        }
+    }
+
+    let mut findings = Vec::new();
+    for member in project.members() {
+        let mut declared: HashSet<String> = member
+            .dependencies
+            .iter()
+            .map(|dep| dep.rename.as_deref().unwrap_or(&dep.name).replace('-', "_"))
+            .collect();
+        declared.extend(
+            nodes
+                .get(&member.id)
+                .into_iter()
+                .flat_map(|node| &node.deps)
+                .map(|dep| dep.name.clone()),
+        );
+        // The package's own library, which its binaries, tests and examples use by name.
+        declared.extend(
+            member
+                .targets
+                .iter()
+                .filter(|t| crate::targets::is_lib(t))
+                .map(|t| t.name.replace('-', "_")),
+        );
+
+        let mut files: HashMap<String, Option<String>> = HashMap::new();
+        let mut read = |file: &str| -> Option<String> {
+            files
+                .entry(file.to_string())
+                .or_insert_with(|| std::fs::read_to_string(project.root().join(file)).ok())
+                .clone()
+        };
+        let sites = crate::spans::usage_sites(args, project, member);
+        let mut local: HashSet<String> = HashSet::new();
+        for file in sites.values().flatten().map(|span| &span.file) {
+            if let Some(content) = read(file) {
+                local.extend(local_names(&content));
+            }
+        }
+
+        let mut undeclared: Vec<(&String, Vec<&Span>)> = Vec::new();
+        for (name, spans) in &sites {
+            if declared.contains(name)
+                || local.contains(name)
+                || RESERVED.contains(&name.as_str())
+                || !libraries.contains_key(name)
+            {
+                continue;
+            }
+            // `name!` is a macro in scope, not a path through a crate.
+            let spans: Vec<&Span> = spans
+                .iter()
+                .filter(|span| !is_macro_call(read(&span.file).as_deref(), span, name))
+                .collect();
+            if !spans.is_empty() {
+                undeclared.push((name, spans));
+            }
+        }
+        undeclared.sort();
+
+        let dev_files = dev_files(project, member);
+        for (name, spans) in undeclared {
+            let candidates = &libraries[name];
+            let Some(pkg) = candidates
+                .iter()
+                .filter(|pkg| pkg.id != member.id)
+                .max_by(|a, b| a.version.cmp(&b.version))
+            else {
+                continue;
+            };
+            let mut places: Vec<String> = spans
+                .iter()
+                .map(|span| format!("{}:{}:{}", span.file, span.line, span.column))
+                .collect();
+            places.dedup();
+            let shown = places.len().min(5);
+            let more = places.len() - shown;
+            let kind = if spans.iter().all(|span| dev_files.contains(&span.file)) {
+                "dev-dependencies"
+            } else {
+                "dependencies"
+            };
+            let mut finding = Finding::new(Kind::UndeclaredDependency, &pkg.name)
+                .member(project, member)
+                .note(format!(
+                    "Referenced at {}{}",
+                    places[..shown].join(", "),
+                    if more > 0 {
+                        format!(" and {} more", more)
+                    } else {
+                        String::new()
+                    }
+                ))
+                .note(format!(
+                    "{} {} {}, not declared by {}",
+                    pkg.name,
+                    pkg.version,
+                    provenance(project, &nodes, pkg),
+                    member.name
+                ));
+            if let Some(spec) = spec(project, member, pkg) {
+                finding = finding.note(format!("{}{}]: `{}`; --fix does", DECLARE_IN, kind, spec));
+            }
+            findings.push(finding);
+        }
+    }
+    findings
+}
+
+/// `--fix` for undeclared dependencies: adds the declaration each finding gives.
+pub fn fix(
+    project: &Project,
+    findings: &[Finding],
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut changes = Vec::new();
+    for finding in findings
+        .iter()
+        .filter(|f| f.kind == Kind::UndeclaredDependency)
+    {
+        let Some(package) = project
+            .members()
+            .find(|pkg| Some(&pkg.name) == finding.member.as_ref())
+        else {
+            continue;
+        };
+        let Some((kind, declaration)) = finding.notes.iter().find_map(|note| {
+            let (kind, rest) = note.strip_prefix(DECLARE_IN)?.split_once("]: `")?;
+            Some((kind, rest.split_once('`')?.0))
+        }) else {
+            continue;
+        };
+        let parsed: toml_edit::DocumentMut = declaration.parse()?;
+        let Some((key, item)) = parsed.iter().next() else {
+            continue;
+        };
+        let mut manifest = ManifestEdit::open(package.manifest_path.as_std_path())?;
+        let table = manifest
+            .dependency_table(None, kind)
+            .ok_or_else(|| format!("[{}] is not a table", kind))?;
+        if table.contains_key(key) {
+            continue;
+        }
+        table.insert(key, item.clone());
+        manifest.save()?;
+        changes.push(format!(
+            "Declared {} in [{}] of {}",
+            key,
+            kind,
+            project.display_path(package.manifest_path.as_std_path())
+        ));
+    }
+    Ok(changes)
+}
+
+/// The names a file binds itself that paths could start with: modules it declares and
+/// names it imports (`use a::b;`, `use a::{b, c as d};`, `extern crate a as b;`).
+fn local_names(content: &str) -> HashSet<String> {
+    let ident = crate::ident::PATTERN;
+    let module = Regex::new(&format!(r"\bmod\s+({ident})")).unwrap();
+    let import = Regex::new(r"\buse\s+([^;]+);").unwrap();
+    let renamed = Regex::new(&format!(r"\bas\s+({ident})")).unwrap();
+    // The last segment of each imported path, the name it's bound to.
+    let last = Regex::new(&format!(r"({ident})\s*[,}};]")).unwrap();
+
+    let mut names: HashSet<String> = module
+        .captures_iter(content)
+        .chain(renamed.captures_iter(content))
+        .map(|cap| crate::ident::unraw(&cap[1]).to_string())
+        .collect();
+    for cap in import.captures_iter(content) {
+        // `use name;` brings a crate in, it doesn't bind a local one.
+        if !cap[1].contains("::") {
+            continue;
+        }
+        let tree = format!("{};", &cap[1]);
+        for found in last.captures_iter(&tree) {
+            names.insert(crate::ident::unraw(&found[1]).to_string());
+        }
+    }
+    names
+}
+
+fn is_macro_call(content: Option<&str>, span: &Span, name: &str) -> bool {
+    let Some(line) = content.and_then(|c| c.lines().nth(span.line - 1)) else {
+        return false;
+    };
+    let start: usize = line.chars().take(span.column - 1).map(char::len_utf8).sum();
+    line.get(start + name.len()..)
+        .is_some_and(|rest| rest.trim_start().starts_with('!'))
+}
+
+/// The files only tests, examples and benches are built from, as displayed.
+fn dev_files(project: &Project, member: &Package) -> HashSet<String> {
+    member
+        .targets
+        .iter()
+        .filter(|t| {
+            t.kind
+                .iter()
+                .any(|kind| matches!(kind.as_str(), "test" | "example" | "bench"))
+        })
+        .flat_map(crate::targets::files)
+        .map(|path| project.display_path(&path))
+        .collect()
+}
+
+/// How `pkg` got into the graph: another member, or the packages depending on it.
+fn provenance(project: &Project, nodes: &BTreeMap<&PackageId, &Node>, pkg: &Package) -> String {
+    if project.metadata.workspace_members.contains(&pkg.id) {
+        return "is another workspace member".to_string();
+    }
+    let dependents: BTreeSet<String> = nodes
+        .values()
+        .filter(|node| node.deps.iter().any(|dep| dep.pkg == pkg.id))
+        .filter_map(|node| project.package(&node.id))
+        .map(|dependent| dependent.name.to_string())
+        .collect();
+    format!(
+        "is only in the graph as a dependency of {}",
+        dependents.into_iter().collect::<Vec<_>>().join(", ")
+    )
+}
+
+/// The declaration making `pkg` available: inherited when the workspace declares
+/// it, a path for members and local packages, its version for registry packages, and the
+/// repository for git ones.
+fn spec(project: &Project, member: &Package, pkg: &Package) -> Option<String> {
+    // Declared by its package name, a library named otherwise is still in scope by its own.
+    let key = pkg.name.to_string();
+    let inherited = project
+        .manifest
+        .get("workspace")
+        .and_then(|w| w.get("dependencies"))
+        .and_then(|d| d.as_table())
+        .is_some_and(|shared| shared.contains_key(&key));
+    if inherited {
+        return Some(format!("{} = {{ workspace = true }}", key));
+    }
+    let Some(source) = &pkg.source else {
+        let from = member.manifest_path.parent()?.as_std_path();
+        let to = pkg.manifest_path.parent()?.as_std_path();
+        return Some(format!(
+            "{} = {{ path = \"{}\" }}",
+            key,
+            relative(from, to).to_string_lossy().replace('\\', "/")
+        ));
+    };
+    let source = source.repr.as_str();
+    if let Some(git) = source.strip_prefix("git+") {
+        let url = git.split(['?', '#']).next().unwrap_or(git);
+        return Some(format!("{} = {{ git = \"{}\" }}", key, url));
+    }
+    Some(format!("{} = \"{}\"", key, pkg.version))
+}
+
+/// `to` relative to `from`, both absolute.
+fn relative(from: &Path, to: &Path) -> PathBuf {
+    let from: Vec<Component> = from.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut path: PathBuf = std::iter::repeat_n(Component::ParentDir, from.len() - common)
+        .map(|c| c.as_os_str())
+        .collect();
+    path.extend(to[common..].iter().map(|c| c.as_os_str()));
+    if path.as_os_str().is_empty() {
+        path.push(".");
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::fixture;
+
+    #[test]
+    fn fix_adds_the_declaration() {
+        let (dir, project) = fixture(&[
+            (
+                "Cargo.toml",
+                r#"[workspace]
+members = ["app", "util"]
+"#,
+            ),
+            (
+                "app/Cargo.toml",
+                r#"[package]
+name = "app"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+"#,
+            ),
+            ("app/src/lib.rs", "pub use util::helper;\n"),
+            (
+                "util/Cargo.toml",
+                "[package]\nname = \"util\"\nversion = \"0.1.0\"\nedition = \"2021\"\n",
+            ),
+            ("util/src/lib.rs", "pub fn helper() {}\n"),
+        ]);
+        let app = project.members().find(|pkg| pkg.name == "app").unwrap();
+        let util = project.members().find(|pkg| pkg.name == "util").unwrap();
+        let declaration = spec(&project, app, util).unwrap();
+        assert_eq!(declaration, r#"util = { path = "../util" }"#);
+        let findings = [Finding::new(Kind::UndeclaredDependency, "util")
+            .member(&project, app)
+            .note(format!(
+                "{}dev-dependencies]: `{}`; --fix does",
+                DECLARE_IN, declaration
+            ))];
+
+        let changes = fix(&project, &findings).unwrap();
+        assert_eq!(
+            changes,
+            ["Declared util in [dev-dependencies] of app/Cargo.toml"]
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("app/Cargo.toml")).unwrap(),
+            r#"[package]
+name = "app"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+
+[dev-dependencies]
+util = { path = "../util" }
+"#
+        );
+        assert!(fix(&project, &findings).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,172 @@
+use crate::cli::Args;
+use crate::project::Project;
+use crate::report::{Finding, Kind};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+/// Flags `cfg(feature = "...")` predicates naming a feature the package doesn't have, in
+/// `#[cfg]`, `#[cfg_attr]` (`doc(cfg(...))` included) and `cfg!`: a typo compiles fine
+/// and silently gates the code off for good. Each finding lists where the name is used
+/// and the closest existing feature.
+pub fn analyze(args: &Args, project: &Project) -> Vec<Finding> {
+    let start = Regex::new(r"#!?\[\s*cfg(?:_attr)?\s*\(|\bcfg!\s*\(").unwrap();
+    let feature = Regex::new(r#"\bfeature\s*=\s*"([^"]*)""#).unwrap();
+    let mut findings = Vec::new();
+    for member in project.members() {
+        let known: BTreeSet<&str> = member.features.keys().map(String::as_str).collect();
+        let mut files: BTreeSet<PathBuf> = crate::source_files(args, project, member)
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        for target in &member.targets {
+            if target
+                .kind
+                .iter()
+                .any(|kind| matches!(kind.as_str(), "test" | "example" | "bench"))
+            {
+                files.extend(crate::targets::files(target));
+            }
+        }
+
+        // Each unknown name with where it's used.
+        let mut unknown: BTreeMap<&str, Vec<(PathBuf, usize, usize)>> = BTreeMap::new();
+        let mut contents = Vec::new();
+        for path in files {
+            if let Ok(content) = std::fs::read_to_string(&path) {
+                contents.push((path, content));
+            }
+        }
+        for (path, content) in &contents {
+            for m in start.find_iter(content) {
+                if in_comment(content, m.start()) {
+                    continue;
+                }
+                let open = m.end() - 1;
+                let close = crate::macros::matching_delimiter(content, open).unwrap_or(open);
+                for cap in feature.captures_iter(&content[open..close]) {
+                    let name = cap.get(1).expect("the name is captured");
+                    if known.contains(name.as_str()) {
+                        continue;
+                    }
+                    let offset = open + name.start();
+                    let line = content[..offset].matches('\n').count() + 1;
+                    let line_start = content[..offset].rfind('\n').map_or(0, |i| i + 1);
+                    let column = content[line_start..offset].chars().count() + 1;
+                    unknown
+                        .entry(name.as_str())
+                        .or_default()
+                        .push((path.clone(), line, column));
+                }
+            }
+        }
+
+        for (name, places) in unknown {
+            let shown: Vec<String> = places
+                .iter()
+                .take(5)
+                .map(|(path, line, column)| {
+                    format!("{}:{}:{}", project.display_path(path), line, column)
+                })
+                .collect();
+            let more = places.len() - shown.len();
+            let mut finding = Finding::new(Kind::UnknownFeature, name)
+                .member(project, member)
+                .file(project, &places[0].0)
+                .note(format!(
+                    "`feature = \"{}\"` at {}{}, but {} has no such feature, so the code under \
+                     it is never compiled",
+                    name,
+                    shown.join(", "),
+                    if more > 0 {
+                        format!(" and {} more", more)
+                    } else {
+                        String::new()
+                    },
+                    member.name
+                ));
+            finding = match closest(name, &known) {
+                Some(closest) => finding.note(format!("Did you mean `{}`?", closest)),
+                None if known.is_empty() => finding.note(format!(
+                    "{} declares no features; add `{}` to [features] or remove the gate",
+                    member.name, name
+                )),
+                None => finding.note(format!(
+                    "Add `{}` to [features] or fix the name (features: {})",
+                    name,
+                    known.iter().copied().collect::<Vec<_>>().join(", ")
+                )),
+            };
+            findings.push(finding);
+        }
+    }
+    findings
+}
+
+/// Whether `offset` is on a line commented out with `//`.
+fn in_comment(content: &str, offset: usize) -> bool {
+    let line_start = content[..offset].rfind('\n').map_or(0, |i| i + 1);
+    content[line_start..offset].contains("//")
+}
+
+/// The known feature closest to `name`, when it's a plausible typo of it: at most half
+/// of its characters differ, or they only differ in case and `-` and `_`.
+fn closest<'a>(name: &str, known: &BTreeSet<&'a str>) -> Option<&'a str> {
+    let normalized = |s: &str| s.to_lowercase().replace('-', "_");
+    if let Some(same) = known.iter().find(|k| normalized(k) == normalized(name)) {
+        return Some(same);
+    }
+    known
+        .iter()
+        .map(|k| (distance(name, k), *k))
+        .filter(|(d, k)| *d * 2 <= name.chars().count().max(k.chars().count()))
+        .min()
+        .map(|(_, k)| k)
+}
+
+/// The Levenshtein distance between two strings, by character.
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn in_comment_looks_at_the_offset_line_only() {
+        let content = "let a = 1; // feature = \"x\"\nlet b = \"y\";\n";
+        assert!(in_comment(content, content.find("\"x\"").unwrap()));
+        assert!(!in_comment(content, content.find("1;").unwrap()));
+        assert!(!in_comment(content, content.find("\"y\"").unwrap()));
+    }
+
+    #[test]
+    fn closest_suggests_plausible_typos_only() {
+        let known: BTreeSet<&str> = ["serde", "std", "async-std", "tokio"].into();
+        assert_eq!(closest("Serde", &known), Some("serde"));
+        assert_eq!(closest("async_std", &known), Some("async-std"));
+        assert_eq!(closest("serd", &known), Some("serde"));
+        assert_eq!(closest("tokoi", &known), Some("tokio"));
+        assert_eq!(closest("xyz", &known), None);
+        assert_eq!(closest("alloc", &known), None);
+    }
+
+    #[test]
+    fn distance_counts_characters() {
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("", "abc"), 3);
+        assert_eq!(distance("abc", "abc"), 0);
+        assert_eq!(distance("é", "e"), 1);
+    }
+}
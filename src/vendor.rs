@@ -0,0 +1,210 @@
+use crate::cargo_config::CargoConfig;
+use crate::project::Project;
+use crate::report::{Finding, Kind, Severity};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+/// A directory source that replaces a registry, as written by `cargo vendor`:
+///
+/// ```toml
+/// [source.crates-io]
+/// replace-with = "vendored-sources"
+///
+/// [source.vendored-sources]
+/// directory = "vendor"
+/// ```
+#[derive(Debug)]
+pub struct Vendor {
+    pub directory: PathBuf,
+    pub replaces: Vec<String>,
+    /// How Cargo.lock records the replaced sources (`registry+<url>`, `git+<url>`), as
+    /// prefixes of its `source` fields.
+    pub sources: Vec<String>,
+}
+
+/// Where the vendor directory and Cargo.lock disagree.
+#[derive(Debug, Default)]
+pub struct Drift {
+    /// Locked packages from a replaced source that aren't vendored: `--offline` builds
+    /// fail to resolve them.
+    pub missing: BTreeSet<(String, String)>,
+    /// Vendored packages the lockfile no longer has, with their directories.
+    pub stale: BTreeSet<(String, String, PathBuf)>,
+}
+
+impl Vendor {
+    pub fn detect(config: &CargoConfig) -> Option<Vendor> {
+        let sources = config.get(&["source"])?.as_table()?;
+        sources.iter().find_map(|(name, source)| {
+            let directory = source.get("directory")?.as_str()?;
+            let replaced: Vec<(&String, &toml::Value)> = sources
+                .iter()
+                .filter(|(_, other)| {
+                    other.get("replace-with").and_then(|r| r.as_str()) == Some(name)
+                })
+                .collect();
+            if replaced.is_empty() {
+                return None;
+            }
+            Some(Vendor {
+                directory: PathBuf::from(directory),
+                replaces: replaced.iter().map(|(name, _)| name.to_string()).collect(),
+                sources: replaced
+                    .iter()
+                    .flat_map(|(name, source)| lock_sources(name, source))
+                    .collect(),
+            })
+        })
+    }
+
+    pub fn contains(&self, manifest: &Path) -> bool {
+        manifest.starts_with(&self.directory)
+    }
+
+    /// Cargo can't load a project whose lockfile needs packages the vendor directory
+    /// lacks; names them alongside cargo's error when that is why `err` happened.
+    pub fn explain_failure(
+        &self,
+        manifest_path: Option<&Path>,
+        err: cargo_metadata::Error,
+    ) -> Box<dyn std::error::Error> {
+        let start = match manifest_path.and_then(Path::parent) {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => std::env::current_dir().unwrap_or_default(),
+        };
+        // The workspace root isn't known yet: take the nearest lockfile.
+        let Some(lockfile) = start
+            .ancestors()
+            .map(|dir| dir.join("Cargo.lock"))
+            .find(|lockfile| lockfile.is_file())
+        else {
+            return err.into();
+        };
+        let missing = crate::project::read_toml(&lockfile)
+            .map(|lockfile| self.audit(&lockfile).missing)
+            .unwrap_or_default();
+        if missing.is_empty() {
+            return err.into();
+        }
+        let missing: Vec<String> = missing
+            .iter()
+            .map(|(name, version)| format!("{} {}", name, version))
+            .collect();
+        format!(
+            "{}\n\n{} lacks packages Cargo.lock locks: {}; run `cargo vendor` again",
+            err,
+            self.directory.display(),
+            missing.join(", ")
+        )
+        .into()
+    }
+
+    /// Compares the vendored packages against `lockfile`.
+    pub fn audit(&self, lockfile: &toml::Table) -> Drift {
+        let mut vendored = BTreeSet::new();
+        let mut drift = Drift::default();
+        for entry in std::fs::read_dir(&self.directory)
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+        {
+            let Ok(manifest) = crate::project::read_toml(&entry.path().join("Cargo.toml")) else {
+                continue;
+            };
+            let field = |key: &str| {
+                manifest
+                    .get("package")
+                    .and_then(|p| p.get(key))
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+            };
+            if let (Some(name), Some(version)) = (field("name"), field("version")) {
+                vendored.insert((name, version, entry.path()));
+            }
+        }
+
+        let locked: Vec<(String, String, &str)> = lockfile
+            .get("package")
+            .and_then(|p| p.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|pkg| {
+                Some((
+                    pkg.get("name")?.as_str()?.to_string(),
+                    pkg.get("version")?.as_str()?.to_string(),
+                    pkg.get("source").and_then(|s| s.as_str()).unwrap_or(""),
+                ))
+            })
+            .collect();
+        for (name, version, source) in &locked {
+            let replaced = self.sources.iter().any(|s| source.starts_with(s.as_str()));
+            let present = vendored.iter().any(|(n, v, _)| n == name && v == version);
+            if replaced && !present {
+                drift.missing.insert((name.clone(), version.clone()));
+            }
+        }
+        for (name, version, dir) in vendored {
+            if !locked.iter().any(|(n, v, _)| *n == name && *v == version) {
+                drift.stale.insert((name, version, dir));
+            }
+        }
+        drift
+    }
+}
+
+/// Reports where the vendor directory has drifted from Cargo.lock: locked packages it
+/// lacks, and vendored packages no longer locked. Modified vendored files are
+/// `checksum-mismatch` findings.
+pub fn analyze(project: &Project) -> Vec<Finding> {
+    let (Some(vendor), Some(lockfile)) = (&project.vendor, project.lockfile()) else {
+        return Vec::new();
+    };
+    let drift = vendor.audit(&lockfile);
+    let lockfile_path = project.root().join("Cargo.lock");
+    let directory = project.display_path(&vendor.directory);
+    let mut findings = Vec::new();
+    for (name, version) in drift.missing {
+        findings.push(
+            Finding::new(Kind::VendorDrift, &name)
+                .file(project, &lockfile_path)
+                .note(format!(
+                    "Cargo.lock locks {} {}, but {} has no copy of it",
+                    name, version, directory
+                ))
+                .note("`--offline` builds fail to resolve it; run `cargo vendor` again"),
+        );
+    }
+    for (name, version, dir) in drift.stale {
+        let mut finding = Finding::new(Kind::VendorDrift, &name)
+            .file(project, &dir.join("Cargo.toml"))
+            .note(format!(
+                "{} {} is vendored, but Cargo.lock no longer locks it",
+                name, version
+            ))
+            .note(format!(
+                "Remove {}, or run `cargo vendor` again",
+                project.display_path(&dir)
+            ));
+        // Unused sources don't break the build.
+        finding.severity = Severity::Warning;
+        findings.push(finding);
+    }
+    findings
+}
+
+/// The `source` prefixes Cargo.lock uses for packages from the source `name`.
+fn lock_sources(name: &str, source: &toml::Value) -> Vec<String> {
+    if name == "crates-io" {
+        return vec![
+            "registry+https://github.com/rust-lang/crates.io-index".to_string(),
+            "sparse+https://index.crates.io/".to_string(),
+        ];
+    }
+    let url = |key: &str| source.get(key).and_then(|v| v.as_str());
+    match (url("registry"), url("git")) {
+        (Some(registry), _) if registry.starts_with("sparse+") => vec![registry.to_string()],
+        (Some(registry), _) => vec![format!("registry+{}", registry)],
+        (None, Some(git)) => vec![format!("git+{}", git)],
+        (None, None) => Vec::new(),
+    }
+}
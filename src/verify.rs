@@ -0,0 +1,162 @@
+use crate::cli::Args;
+use crate::ignore;
+use crate::project::Project;
+use crate::report::{Evidence, Finding, Kind, Severity};
+use cargo_metadata::{Message, Package};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::BufReader;
+use std::process::{Command, Stdio};
+
+/// `--verify-with-rustc`: runs `cargo check` on `package` with the
+/// `unused_crate_dependencies` lint and returns the crates (by crate name, with
+/// underscores) that every library and binary target leaves unused. A binary that
+/// doesn't use one of its library's dependencies doesn't make it unused.
+pub fn unused_crates(
+    args: &Args,
+    project: &Project,
+    package: &Package,
+) -> Result<BTreeSet<String>, Box<dyn std::error::Error>> {
+    let rustflags = std::env::var("RUSTFLAGS").unwrap_or_default();
+    let mut command = Command::new(std::env::var("CARGO").unwrap_or_else(|_| "cargo".into()));
+    command
+        .args([
+            "check",
+            "--message-format=json",
+            "--lib",
+            "--bins",
+            "--package",
+        ])
+        .arg(format!("{}@{}", package.name, package.version))
+        .arg("--manifest-path")
+        .arg(package.manifest_path.as_std_path())
+        // A separate target directory keeps the changed flags from invalidating the
+        // user's build cache.
+        .arg("--target-dir")
+        .arg(
+            project
+                .metadata
+                .target_directory
+                .join("dep-analysis")
+                .as_std_path(),
+        )
+        .env(
+            "RUSTFLAGS",
+            format!("{} -W unused-crate-dependencies", rustflags).trim(),
+        )
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    if args.all_features {
+        command.arg("--all-features");
+    }
+    if args.no_default_features {
+        command.arg("--no-default-features");
+    }
+    if !args.features.is_empty() {
+        command.arg("--features").arg(args.features.join(","));
+    }
+    if let Some(target) = &args.target {
+        command.arg("--target").arg(target);
+    }
+    if args.offline || project.offline() {
+        command.arg("--offline");
+    }
+    tracing::info!(
+        "verifying unused dependencies of {} with rustc",
+        package.name
+    );
+    let mut child = command.spawn()?;
+
+    let mut targets = BTreeSet::new();
+    let mut unused: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let stdout = child.stdout.take().expect("stdout is piped");
+    for message in Message::parse_stream(BufReader::new(stdout)) {
+        match message? {
+            Message::CompilerArtifact(artifact) if artifact.package_id == package.id => {
+                targets.insert(artifact.target.name);
+            }
+            Message::CompilerMessage(msg) if msg.package_id == package.id => {
+                let lint = msg.message.code.as_ref().map(|c| c.code.as_str());
+                if lint != Some("unused_crate_dependencies") {
+                    continue;
+                }
+                if let Some(krate) = msg.message.message.split('`').nth(1) {
+                    unused
+                        .entry(krate.to_string())
+                        .or_default()
+                        .insert(msg.target.name);
+                }
+            }
+            _ => {}
+        }
+    }
+    if !child.wait()?.success() {
+        return Err(format!(
+            "`cargo check` failed for {}; --verify-with-rustc needs a package that builds",
+            package.name
+        )
+        .into());
+    }
+    Ok(unused
+        .into_iter()
+        .filter(|(_, warned)| *warned == targets)
+        .map(|(krate, _)| krate)
+        .collect())
+}
+
+/// Merges the compiler's verdict into the heuristic findings about `package`, labeling
+/// each with where its evidence comes from. Heuristic findings rustc contradicts drop to
+/// info; dependencies only rustc reports are added.
+pub fn merge(
+    project: &Project,
+    package: &Package,
+    findings: &mut Vec<Finding>,
+    unused: &BTreeSet<String>,
+) {
+    const USAGE_KINDS: [Kind; 6] = [
+        Kind::Unused,
+        Kind::FeatureGated,
+        Kind::PlatformOnly,
+        Kind::TestOnly,
+        Kind::ExampleOnly,
+        Kind::BenchOnly,
+    ];
+    let crate_name = |name: &str| name.replace('-', "_");
+    let ignored = ignore::ignored(project, package);
+    let mut reported = BTreeSet::new();
+    for finding in findings.iter_mut().filter(|f| {
+        USAGE_KINDS.contains(&f.kind) && f.member.as_deref() == Some(package.name.as_str())
+    }) {
+        reported.insert(crate_name(&finding.krate));
+        if unused.contains(&crate_name(&finding.krate)) {
+            finding.evidence = Some(Evidence::Both);
+            finding
+                .notes
+                .push("Evidence: the scan and rustc's unused_crate_dependencies lint".into());
+        } else {
+            finding.evidence = Some(Evidence::Heuristic);
+            finding.notes.push(
+                "Evidence: the scan only; rustc's unused_crate_dependencies lint saw it used"
+                    .into(),
+            );
+            if finding.kind == Kind::Unused {
+                finding.severity = Severity::Info;
+            }
+        }
+    }
+
+    for dep in &package.dependencies {
+        let name = crate_name(dep.rename.as_deref().unwrap_or(&dep.name));
+        if unused.contains(&name)
+            && !reported.contains(&crate_name(&dep.name))
+            && ignored.get(&dep.name, [dep.kind]).is_none()
+        {
+            let mut finding = Finding::new(Kind::Unused, &dep.name)
+                .member(project, package)
+                .note("Reported by rustc's unused_crate_dependencies lint, though the scan found a reference (in a doc test, comment or string)")
+                .note("Evidence: rustc only");
+            finding.evidence = Some(Evidence::Compiler);
+            findings.push(finding);
+            reported.insert(name);
+        }
+    }
+}
@@ -0,0 +1,344 @@
+use crate::fix::{self, ManifestEdit};
+use crate::project::{dependency_tables, table_name, Project};
+use crate::report::{Finding, Kind};
+use std::path::{Path, PathBuf};
+
+/// Reports `[workspace.dependencies]` entries that no member inherits with
+/// `workspace = true`.
+pub fn analyze(project: &Project) -> Vec<Finding> {
+    let Some(shared) = project
+        .manifest
+        .get("workspace")
+        .and_then(|w| w.get("dependencies"))
+        .and_then(|d| d.as_table())
+    else {
+        return Vec::new();
+    };
+
+    let mut inherited = Vec::new();
+    for member in project.members() {
+        let Some(manifest) = project.package_manifest(member) else {
+            continue;
+        };
+        for table in dependency_tables(&manifest) {
+            for (key, spec) in table.entries {
+                if spec.get("workspace").and_then(|w| w.as_bool()) == Some(true) {
+                    inherited.push(key.clone());
+                }
+            }
+        }
+    }
+
+    shared
+        .keys()
+        .filter(|key| !inherited.contains(key))
+        .map(|key| {
+            Finding::new(Kind::UnusedWorkspaceDependency, key)
+                .note("Declared in [workspace.dependencies] but no member uses `workspace = true` for it")
+                .note("Remove the entry from the workspace manifest")
+        })
+        .collect()
+}
+
+/// A dependency declared with the same spec by several members that could be
+/// inherited from `[workspace.dependencies]` instead.
+pub struct Inheritable {
+    pub key: String,
+    pub spec: toml::Table,
+    pub sites: Vec<Site>,
+    /// Set when `[workspace.dependencies]` already has this key with a different spec.
+    pub conflict: Option<toml::Value>,
+    /// Whether `[workspace.dependencies]` already has this key with the same spec.
+    pub shared: bool,
+}
+
+pub struct Site {
+    pub member: String,
+    pub manifest: PathBuf,
+    pub target: Option<String>,
+    pub kind: &'static str,
+    pub optional: bool,
+}
+
+/// Finds dependencies declared identically (ignoring `optional`) by at least two members,
+/// or by any member when `[workspace.dependencies]` already declares them that way.
+pub fn inheritable(project: &Project) -> Vec<Inheritable> {
+    let root = project.root();
+    let shared = project
+        .manifest
+        .get("workspace")
+        .and_then(|w| w.get("dependencies"))
+        .and_then(|d| d.as_table());
+
+    let mut groups: Vec<Inheritable> = Vec::new();
+    for member in project.members() {
+        let Some(manifest) = project.package_manifest(member) else {
+            continue;
+        };
+        let member_dir = member.manifest_path.parent().unwrap().as_std_path();
+        for table in dependency_tables(&manifest) {
+            for (key, spec) in table.entries {
+                if spec.get("workspace").is_some() {
+                    continue;
+                }
+                let Some(normalized) = normalize(spec, member_dir, &root) else {
+                    continue;
+                };
+                let site = Site {
+                    member: member.name.clone(),
+                    manifest: member.manifest_path.clone().into_std_path_buf(),
+                    target: table.target.map(str::to_string),
+                    kind: table.kind,
+                    optional: spec.get("optional").and_then(|o| o.as_bool()) == Some(true),
+                };
+                match groups
+                    .iter_mut()
+                    .find(|g| g.key == *key && g.spec == normalized)
+                {
+                    Some(group) => group.sites.push(site),
+                    None => groups.push(Inheritable {
+                        key: key.clone(),
+                        spec: normalized,
+                        sites: vec![site],
+                        conflict: None,
+                        shared: false,
+                    }),
+                }
+            }
+        }
+    }
+
+    for group in &mut groups {
+        if let Some(existing) = shared.and_then(|s| s.get(&group.key)) {
+            let existing_spec = normalize(existing, &root, &root);
+            if existing_spec.as_ref() == Some(&group.spec) {
+                group.shared = true;
+            } else {
+                group.conflict = Some(existing.clone());
+            }
+        }
+    }
+
+    groups.retain(|group| {
+        let mut members: Vec<&str> = group.sites.iter().map(|s| s.member.as_str()).collect();
+        members.dedup();
+        members.len() >= 2 || group.shared
+    });
+    // Only one spec per key can live in the workspace table; keep the one already there,
+    // or else the most common one.
+    groups.sort_by_key(|group| std::cmp::Reverse((group.shared, group.sites.len())));
+    let mut seen = Vec::new();
+    groups.retain(|group| {
+        let first = !seen.contains(&group.key);
+        seen.push(group.key.clone());
+        first
+    });
+    groups
+}
+
+pub fn analyze_inheritable(project: &Project) -> Vec<Finding> {
+    inheritable(project)
+        .into_iter()
+        .map(|group| {
+            let members: Vec<String> = group
+                .sites
+                .iter()
+                .map(|site| format!("{} [{}]", site.member, site_table(site)))
+                .collect();
+            let finding = Finding::new(Kind::InheritableDependency, &group.key)
+                .note(format!(
+                    "Declared identically as {} by: {}",
+                    fix::spec_value(&group.spec),
+                    members.join(", ")
+                ));
+            match &group.conflict {
+                Some(existing) => finding.note(format!(
+                    "[workspace.dependencies] already declares it as {}; align the specs first",
+                    existing
+                )),
+                None if group.shared => finding.note(
+                    "[workspace.dependencies] already declares it; run with --fix to inherit it with `workspace = true`",
+                ),
+                None => finding.note(
+                    "Run with --fix to move it into [workspace.dependencies] and inherit it with `workspace = true`",
+                ),
+            }
+        })
+        .collect()
+}
+
+/// Moves every conflict-free group into `[workspace.dependencies]` and rewrites the
+/// member declarations to inherit it. Returns a line per change made.
+pub fn fix(project: &Project) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let groups: Vec<Inheritable> = inheritable(project)
+        .into_iter()
+        .filter(|group| group.conflict.is_none())
+        .collect();
+    if groups.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut changes = Vec::new();
+    let mut root = ManifestEdit::open(&project.root().join("Cargo.toml"))?;
+    for group in &groups {
+        let table = root
+            .table(&["workspace", "dependencies"])
+            .ok_or("[workspace] is not a table")?;
+        if !table.contains_key(&group.key) {
+            table.insert(&group.key, toml_edit::value(fix::spec_value(&group.spec)));
+            changes.push(format!("Added {} to [workspace.dependencies]", group.key));
+        }
+    }
+    root.save()?;
+
+    for group in &groups {
+        for site in &group.sites {
+            // The root package shares its manifest with the workspace, so reopen every
+            // time rather than holding edits to the same file twice.
+            let mut manifest = ManifestEdit::open(&site.manifest)?;
+            let table = manifest
+                .dependency_table(site.target.as_deref(), site.kind)
+                .ok_or("dependency table is not a table")?;
+            let mut inherited = toml_edit::InlineTable::new();
+            inherited.insert("workspace", true.into());
+            if site.optional {
+                inherited.insert("optional", true.into());
+            }
+            table.insert(&group.key, toml_edit::value(inherited));
+            manifest.save()?;
+            changes.push(format!(
+                "{}: [{}] {} now uses `workspace = true`",
+                site.member,
+                site_table(site),
+                group.key
+            ));
+        }
+    }
+
+    Ok(changes)
+}
+
+fn site_table(site: &Site) -> String {
+    table_name(site.target.as_deref(), site.kind)
+}
+
+/// A comparable form of a dependency spec: plain version strings become tables,
+/// `optional` is dropped (members keep it), and paths are made relative to the
+/// workspace root. Returns `None` for specs that can't be shared, such as paths
+/// outside the workspace.
+fn normalize(spec: &toml::Value, dir: &Path, root: &Path) -> Option<toml::Table> {
+    let mut table = match spec {
+        toml::Value::String(version) => {
+            let mut table = toml::Table::new();
+            table.insert("version".to_string(), version.clone().into());
+            table
+        }
+        toml::Value::Table(table) => table.clone(),
+        _ => return None,
+    };
+    table.remove("optional");
+
+    if let Some(path) = table.get("path").and_then(|p| p.as_str()) {
+        let absolute = dir.join(path).canonicalize().ok()?;
+        let relative = absolute.strip_prefix(root.canonicalize().ok()?).ok()?;
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        table.insert("path".to_string(), relative.into());
+    }
+    Some(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::fixture;
+
+    const ROOT: &str = r#"[workspace]
+members = ["a", "b"]
+"#;
+
+    fn package(name: &str, dependencies: &str) -> String {
+        format!(
+            "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\n{}",
+            name, dependencies
+        )
+    }
+
+    #[test]
+    fn fix_moves_shared_specs_into_the_workspace() {
+        let a = package("a", "util = { path = \"../util\" }\n");
+        let b = package("b", "util = { path = \"../util\", optional = true }\n");
+        let (dir, project) = fixture(&[
+            ("Cargo.toml", ROOT),
+            ("a/Cargo.toml", &a),
+            ("a/src/lib.rs", ""),
+            ("b/Cargo.toml", &b),
+            ("b/src/lib.rs", ""),
+            ("util/Cargo.toml", &package("util", "")),
+            ("util/src/lib.rs", ""),
+        ]);
+
+        let changes = fix(&project).unwrap();
+        assert_eq!(changes.len(), 3, "{:?}", changes);
+        let read = |path: &str| std::fs::read_to_string(dir.path().join(path)).unwrap();
+        assert_eq!(
+            read("Cargo.toml"),
+            format!(
+                "{}\n[workspace.dependencies]\nutil = {{ path = \"util\" }}\n",
+                ROOT
+            )
+        );
+        assert_eq!(
+            read("a/Cargo.toml"),
+            package("a", "util = { workspace = true }\n")
+        );
+        assert_eq!(
+            read("b/Cargo.toml"),
+            package("b", "util = { workspace = true, optional = true }\n")
+        );
+    }
+
+    #[test]
+    fn a_single_member_inherits_what_the_workspace_declares() {
+        let root = format!(
+            "{}\n[workspace.dependencies]\nutil = {{ path = \"util\" }}\n",
+            ROOT
+        );
+        let a = package("a", "util = { path = \"../util\" }\n");
+        let (dir, project) = fixture(&[
+            ("Cargo.toml", &root),
+            ("a/Cargo.toml", &a),
+            ("a/src/lib.rs", ""),
+            ("b/Cargo.toml", &package("b", "")),
+            ("b/src/lib.rs", ""),
+            ("util/Cargo.toml", &package("util", "")),
+            ("util/src/lib.rs", ""),
+        ]);
+
+        let findings = analyze_inheritable(&project);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].krate, "util");
+
+        fix(&project).unwrap();
+        let read = |path: &str| std::fs::read_to_string(dir.path().join(path)).unwrap();
+        assert_eq!(read("Cargo.toml"), root);
+        assert_eq!(
+            read("a/Cargo.toml"),
+            package("a", "util = { workspace = true }\n")
+        );
+    }
+
+    #[test]
+    fn a_single_member_alone_is_not_inheritable() {
+        let a = package("a", "util = { path = \"../util\" }\n");
+        let (_dir, project) = fixture(&[
+            ("Cargo.toml", ROOT),
+            ("a/Cargo.toml", &a),
+            ("a/src/lib.rs", ""),
+            ("b/Cargo.toml", &package("b", "")),
+            ("b/src/lib.rs", ""),
+            ("util/Cargo.toml", &package("util", "")),
+            ("util/src/lib.rs", ""),
+        ]);
+        assert!(inheritable(&project).is_empty());
+    }
+}
@@ -0,0 +1,33 @@
+use crate::cargo_config::CargoConfig;
+use crate::project::Project;
+use std::collections::BTreeSet;
+
+/// The workspace members that are build tooling rather than product (the xtask pattern):
+/// a member named `xtask`, or the one the `xtask` cargo alias runs, as in
+/// `[alias] xtask = "run --package tools --"` in `.cargo/config.toml`.
+pub fn members(project: &Project) -> BTreeSet<String> {
+    let config = CargoConfig::load(&project.root());
+    let alias: Vec<String> = match config.get(&["alias", "xtask"]) {
+        Some(toml::Value::String(command)) => {
+            command.split_whitespace().map(str::to_string).collect()
+        }
+        Some(toml::Value::Array(args)) => args
+            .iter()
+            .filter_map(|arg| arg.as_str().map(str::to_string))
+            .collect(),
+        _ => Vec::new(),
+    };
+    let aliased = alias
+        .iter()
+        .enumerate()
+        .find_map(|(i, arg)| match arg.as_str() {
+            "-p" | "--package" => alias.get(i + 1).cloned(),
+            _ => arg.strip_prefix("--package=").map(str::to_string),
+        });
+
+    project
+        .members()
+        .filter(|member| member.name == "xtask" || Some(&member.name) == aliased.as_ref())
+        .map(|member| member.name.clone())
+        .collect()
+}
@@ -0,0 +1,59 @@
+use crate::project::Project;
+use crate::registry::Registry;
+use crate::report::{Finding, Kind};
+use std::collections::HashMap;
+
+/// Reports locked packages whose exact version has been yanked from the registry they
+/// were resolved from, whether that's crates.io or an alternative sparse registry.
+pub fn analyze(project: &Project) -> Vec<Finding> {
+    let mut registries: HashMap<&str, Option<Registry>> = HashMap::new();
+    let mut findings = Vec::new();
+    let progress = project.progress.bar(
+        "Checking registries",
+        Some(project.metadata.packages.len() as u64),
+    );
+
+    for pkg in &project.metadata.packages {
+        progress.inc();
+        let Some(source) = pkg.source.as_ref().map(|s| s.repr.as_str()) else {
+            continue;
+        };
+        if !source.starts_with("registry+") && !source.starts_with("sparse+") {
+            continue;
+        }
+        let registry = registries.entry(source).or_insert_with(|| {
+            Registry::for_source(source, &project.cargo_config, &project.cache).unwrap_or_else(
+                |err| {
+                    tracing::warn!("skipping registry {}: {}", source, err);
+                    None
+                },
+            )
+        });
+        let Some(registry) = registry else {
+            continue;
+        };
+
+        let Ok(versions) = registry.versions(&pkg.name) else {
+            continue;
+        };
+        let yanked = versions
+            .iter()
+            .any(|entry| entry.yanked && entry.vers == pkg.version.to_string());
+        if yanked {
+            findings.push(
+                Finding::new(Kind::YankedVersion, &pkg.name)
+                    .file(project, &project.root().join("Cargo.lock"))
+                    .note(format!(
+                        "{} {} is locked but has been yanked from {}",
+                        pkg.name, pkg.version, registry.name
+                    ))
+                    .note(format!(
+                        "Run `cargo update -p {}@{}` to move to a published version",
+                        pkg.name, pkg.version
+                    )),
+            );
+        }
+    }
+
+    findings
+}